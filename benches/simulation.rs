@@ -0,0 +1,77 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use qasmsim::grammar::ast::OpenQasmProgram;
+use qasmsim::{parse_and_link, simulate, simulate_with_shots};
+
+/// Quantum Fourier transform on `width` qubits, written with the controlled
+/// phase gates of `qelib1.inc`.
+fn qft(width: usize) -> String {
+    let mut source = format!(
+        "OPENQASM 2.0;\ninclude \"qelib1.inc\";\nqreg q[{0}];\ncreg c[{0}];\nx q[0];\n",
+        width
+    );
+    for target in 0..width {
+        source.push_str(&format!("h q[{}];\n", target));
+        for control in target + 1..width {
+            source.push_str(&format!(
+                "cu1(pi/{}) q[{}], q[{}];\n",
+                1 << (control - target),
+                control,
+                target
+            ));
+        }
+    }
+    source.push_str("measure q -> c;\n");
+    source
+}
+
+/// Layers of Toffoli and Fredkin gates, which `qelib1.inc` defines in terms
+/// of several levels of nested macros.
+fn toffoli_layers(width: usize, depth: usize) -> String {
+    let mut source = format!(
+        "OPENQASM 2.0;\ninclude \"qelib1.inc\";\nqreg q[{0}];\ncreg c[{0}];\nh q;\n",
+        width
+    );
+    for layer in 0..depth {
+        for first in 0..width - 2 {
+            let (a, b, c) = (first, first + 1, first + 2);
+            if layer % 2 == 0 {
+                source.push_str(&format!("ccx q[{}], q[{}], q[{}];\n", a, b, c));
+            } else {
+                source.push_str(&format!("cswap q[{}], q[{}], q[{}];\n", c, b, a));
+            }
+        }
+    }
+    source.push_str("measure q -> c;\n");
+    source
+}
+
+fn program(source: &str) -> OpenQasmProgram {
+    parse_and_link(source).expect("the benchmark programs are valid")
+}
+
+fn bench_simulate(c: &mut Criterion) {
+    let mut group = c.benchmark_group("simulate");
+    for (name, source) in &[("qft", qft(10)), ("toffoli", toffoli_layers(8, 20))] {
+        let program = program(source);
+        group.bench_with_input(BenchmarkId::from_parameter(name), &program, |b, program| {
+            b.iter(|| simulate(program).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn bench_simulate_with_shots(c: &mut Criterion) {
+    let mut group = c.benchmark_group("simulate_with_shots");
+    group.sample_size(10);
+    for (name, source) in &[("qft", qft(6)), ("toffoli", toffoli_layers(6, 20))] {
+        let program = program(source);
+        group.bench_with_input(BenchmarkId::from_parameter(name), &program, |b, program| {
+            b.iter(|| simulate_with_shots(program, 100).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_simulate, bench_simulate_with_shots);
+criterion_main!(benches);
@@ -1,24 +1,35 @@
 use std::collections::HashMap;
 use std::iter::FromIterator;
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
 use crate::error::QasmSimError;
-use crate::grammar::{ast, parse_program};
+use crate::grammar::lexer::{tokenize, Location};
+use crate::grammar::{
+    ast, format_program, is_open_qasm2, parse_program_with_options, LanguageOptions, LoweringError,
+};
 use crate::interpreter;
-use crate::interpreter::runtime::RuntimeError;
+use crate::interpreter::equivalence::{self, Equivalence};
+use crate::interpreter::runtime::{RuntimeError, UnitarySimulator};
+use crate::interpreter::{Basis, Stats, Unitary};
 use crate::linker::Linker;
 use crate::qe;
-use crate::semantics;
+use crate::semantics::{self, MacroDefinition, OpaqueDefinition, RegisterEntry, RegisterType};
+use crate::statevector::StateVector;
 
 pub type Result<'src, T> = std::result::Result<T, QasmSimError<'src>>;
 
-/// Return the default linker which includes the [`qelib1.inc`] library.
+/// Return the default linker which includes the [`qelib1.inc`] and
+/// [`stdgates.inc`] libraries.
 ///
 /// [`qelib1.inc`]: https://github.com/Qiskit/openqasm/blob/master/examples/generic/qelib1.inc
-fn default_linker() -> Linker {
-    Linker::with_embedded(HashMap::from_iter(vec![(
-        "qelib1.inc".to_owned(),
-        qe::QELIB1.to_owned(),
-    )]))
+/// [`stdgates.inc`]: https://github.com/openqasm/openqasm/blob/main/examples/stdgates.inc
+pub(crate) fn default_linker() -> Linker {
+    Linker::with_embedded(HashMap::from_iter(vec![
+        ("qelib1.inc".to_owned(), qe::QELIB1.to_owned()),
+        ("stdgates.inc".to_owned(), qe::STDGATES.to_owned()),
+    ]))
 }
 
 /// Return the AST of `input` and link external sources with `linker`.
@@ -48,17 +59,55 @@ fn default_linker() -> Linker {
 /// # Ok::<(), qasmsim::QasmSimError>(())
 /// ```
 pub fn parse_and_link(input: &str) -> Result<'_, ast::OpenQasmProgram> {
+    parse_and_link_with_options(input, &LanguageOptions::new())
+}
+
+/// Return the AST of `input`, accepting the extensions to OPENQASM 2.0
+/// enabled in `options`, and link external sources with `linker`.
+///
+/// # Errors
+///
+/// Besides the errors of [`parse_and_link()`], the function fails if the
+/// program uses an extension not enabled in `options`, returning an `Err`
+/// variant wrapping [`QasmSimError::DisabledExtension`].
+///
+/// [`parse_and_link()`]: ./fn.parse_and_link.html
+/// [`QasmSimError::DisabledExtension`]: ./error/enum.QasmSimError.html#variant.DisabledExtension
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use qasmsim::{parse_and_link_with_options, Extension, LanguageOptions};
+///
+/// let options = LanguageOptions::new().allow(Extension::ExtendedConditionals);
+/// let ast = parse_and_link_with_options(r#"
+///     OPENQASM 2.0;
+///     include "qelib1.inc";
+///     qreg q[1];
+///     creg c[1];
+///     if (c[0]==0) x q[0];
+/// "#, &options)?;
+/// # use qasmsim::QasmSimError;
+/// # Ok::<(), qasmsim::QasmSimError>(())
+/// ```
+pub fn parse_and_link_with_options<'src>(
+    input: &'src str,
+    options: &LanguageOptions,
+) -> Result<'src, ast::OpenQasmProgram> {
     let linker = default_linker();
-    let program = parse_program(input)?;
+    let program = parse_program_with_options(input, options)?;
     linker
-        .link(program)
+        .link(program, options)
         .map_err(|err| QasmSimError::from((input, err)))
 }
 
 type GateSignature = (String, Vec<String>, Vec<String>);
 
 /// Return the signature and documentation of the gate `gate_name` if it is
-/// defined in the source code `input`.
+/// defined in the source code `input`, accepting the extensions to
+/// OPENQASM 2.0 enabled in `options`.
 ///
 /// # Errors
 ///
@@ -75,13 +124,13 @@ type GateSignature = (String, Vec<String>, Vec<String>);
 /// Basic usage:
 ///
 /// ```
-/// use qasmsim::get_gate_info;
+/// use qasmsim::{get_gate_info, LanguageOptions};
 ///
 /// let (docstring, (name, real_params, quantum_params)) = get_gate_info(r#"
 ///     OPENQASM 2.0;
 ///     // 3-parameter 2-pulse single qubit gate
 ///     gate u3(theta,phi,lambda) q { U(theta,phi,lambda) q; }
-/// "#, "u3")?;
+/// "#, "u3", &LanguageOptions::new())?;
 ///
 /// assert_eq!(
 ///     docstring,
@@ -114,41 +163,598 @@ type GateSignature = (String, Vec<String>, Vec<String>);
 pub fn get_gate_info<'src>(
     input: &'src str,
     gate_name: &str,
+    options: &LanguageOptions,
 ) -> Result<'src, (String, GateSignature)> {
-    let linked = parse_and_link(input)?;
+    let (docstring, macro_def) = find_gate(input, gate_name, options)?;
+    Ok((docstring, (macro_def.0, macro_def.1, macro_def.2)))
+}
+
+/// Return the definition of the gate `gate_name` in the source code `input`,
+/// accepting the extensions to OPENQASM 2.0 enabled in `options`.
+///
+/// The definition is written as [`to_qasm()`] does, without the
+/// documentation of the gate, which [`get_gate_info()`] returns. The real
+/// parameters of the body are simplified too.
+///
+/// [`to_qasm()`]: ./fn.to_qasm.html
+/// [`get_gate_info()`]: ./fn.get_gate_info.html
+///
+/// # Errors
+///
+/// The function fails as [`get_gate_info()`] does.
+///
+/// # Examples
+///
+/// ```
+/// use qasmsim::{get_gate_definition, LanguageOptions};
+///
+/// let definition = get_gate_definition(r#"
+///     OPENQASM 2.0;
+///     // Quarter turn around the Z axis.
+///     gate quarter q { U(0, 0, 2 * pi + tau / 4) q; }
+/// "#, "quarter", &LanguageOptions::new())?;
+///
+/// assert_eq!(definition, "gate quarter q {\n  U(0, 0, pi / 2) q;\n}");
+/// # Ok::<(), qasmsim::QasmSimError>(())
+/// ```
+pub fn get_gate_definition<'src>(
+    input: &'src str,
+    gate_name: &str,
+    options: &LanguageOptions,
+) -> Result<'src, String> {
+    // The body of the definition is simplified when extracting the semantics.
+    let (_, MacroDefinition(name, real_args, args, body, ..)) =
+        find_gate(input, gate_name, options)?;
+    let declaration = ast::Statement::GateDecl {
+        signature: (name, real_args, args, body),
+        docstring: None,
+    };
+    Ok(declaration.to_string())
+}
+
+/// Return the docstring and definition of the gate `gate_name` in `input`.
+fn find_gate<'src>(
+    input: &'src str,
+    gate_name: &str,
+    options: &LanguageOptions,
+) -> Result<'src, (String, MacroDefinition)> {
+    let linked = parse_and_link_with_options(input, options)?;
     // TODO: Implement conversion from SemanticError to QasmSimError directly
     // without converting to RuntimeError first.
-    let semantics = semantics::extract_semantics(&linked)
+    let mut semantics = semantics::extract_semantics(&linked, options)
         .map_err(|err| QasmSimError::from((input, RuntimeError::from(err))))?;
+    let undefined = || QasmSimError::UndefinedGate {
+        source: "",
+        lineno: 0,
+        symbol_name: String::from(gate_name),
+    };
+    let docstring = semantics
+        .symbol_docstrings
+        .remove(gate_name)
+        .ok_or_else(undefined)?;
+    let macro_def = semantics
+        .macro_definitions
+        .remove(gate_name)
+        .ok_or_else(undefined)?;
+    Ok((docstring, macro_def))
+}
+
+/// The kind of a symbol declared in a program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SymbolKind {
+    /// A quantum register.
+    QuantumRegister,
+    /// A classical register.
+    ClassicalRegister,
+    /// A gate defined in OPENQASM.
+    Gate,
+    /// An opaque gate.
+    OpaqueGate,
+}
 
-    let docstring =
-        semantics
-            .symbol_docstrings
-            .get(gate_name)
-            .ok_or(QasmSimError::UndefinedGate {
-                source: "",
-                lineno: 0,
-                symbol_name: String::from(gate_name),
-            })?;
-
-    let macro_def =
-        semantics
-            .macro_definitions
-            .get(gate_name)
-            .ok_or(QasmSimError::UndefinedGate {
-                source: "",
-                lineno: 0,
-                symbol_name: String::from(gate_name),
-            })?;
-
-    Ok((
-        docstring.to_string(),
-        (
-            macro_def.0.clone(),
-            macro_def.1.clone(),
-            macro_def.2.clone(),
-        ),
-    ))
+/// A register or a gate declared in a program or in the libraries it
+/// includes.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Symbol {
+    /// Name of the symbol.
+    pub name: String,
+    /// Kind of the symbol.
+    pub kind: SymbolKind,
+    /// Declaration of the symbol without its body, such as `qreg q[2]` or
+    /// `gate u1(lambda) q`.
+    pub signature: String,
+    /// Documentation of the gate, taken from the comments right before its
+    /// definition.
+    pub docstring: Option<String>,
+    /// Boundaries of the declaration. For the gates of a library, these are
+    /// the boundaries of the `include` directive.
+    pub boundaries: (Location, Location),
+    /// Path of the library declaring the gate, if any.
+    pub library: Option<String>,
+}
+
+/// Return the registers and gates declared in the source code `input`,
+/// including those of the included libraries, accepting the extensions to
+/// OPENQASM 2.0 enabled in `options`. The symbols are sorted by the position
+/// of their declarations.
+///
+/// Semantic errors, such as redeclaring a register, do not prevent returning
+/// the symbols. When a symbol is declared twice, the first declaration wins.
+///
+/// # Errors
+///
+/// The function fails if the program cannot be parsed or linked, returning
+/// an `Err` variant with a value of [`QasmSimError`].
+///
+/// [`QasmSimError`]: ./error/enum.QasmSimError.html
+///
+/// # Examples
+///
+/// ```
+/// use qasmsim::{get_symbols, LanguageOptions, SymbolKind};
+///
+/// let symbols = get_symbols(r#"
+/// OPENQASM 2.0;
+/// // Apply a rotation.
+/// gate rot(theta) a { U(theta, 0, 0) a; }
+/// qreg q[2];
+/// "#, &LanguageOptions::new())?;
+///
+/// assert_eq!(symbols[0].kind, SymbolKind::Gate);
+/// assert_eq!(symbols[0].signature, "gate rot(theta) a");
+/// assert_eq!(symbols[0].docstring.as_deref(), Some(" Apply a rotation.\n"));
+/// assert_eq!(symbols[1].signature, "qreg q[2]");
+/// # Ok::<(), qasmsim::QasmSimError>(())
+/// ```
+pub fn get_symbols<'src>(input: &'src str, options: &LanguageOptions) -> Result<'src, Vec<Symbol>> {
+    let program = parse_program_with_options(input, options)?;
+    let libraries: HashMap<Location, String> = program
+        .program
+        .iter()
+        .filter_map(|span| match &*span.node {
+            ast::Statement::Include(libpath) => Some((span.boundaries.0, libpath.clone())),
+            _ => None,
+        })
+        .collect();
+    let linked = default_linker()
+        .link(program, options)
+        .map_err(|err| QasmSimError::from((input, err)))?;
+    let boundaries: HashMap<Location, (Location, Location)> = linked
+        .program
+        .iter()
+        .map(|span| (span.boundaries.0, span.boundaries))
+        .collect();
+    let (semantics, _) = semantics::extract_semantics_and_errors(&linked, options);
+
+    let symbol = |name: &str, kind, signature, location: &Location| Symbol {
+        name: name.into(),
+        kind,
+        signature,
+        docstring: semantics.symbol_docstrings.get(name).cloned(),
+        boundaries: boundaries
+            .get(location)
+            .copied()
+            .unwrap_or((*location, *location)),
+        library: libraries.get(location).cloned(),
+    };
+    let gate_signature = |keyword: &str, name: &str, real_args: &[String], args: &[String]| {
+        format!(
+            "{} {}{} {}",
+            keyword,
+            name,
+            match real_args.len() {
+                0 => String::from(""),
+                _ => format!("({})", real_args.join(", ")),
+            },
+            args.join(", ")
+        )
+    };
+
+    let mut symbols: Vec<Symbol> = semantics
+        .register_table
+        .values()
+        .map(|RegisterEntry(name, register_type, size, location)| {
+            let (kind, keyword) = match register_type {
+                RegisterType::Q => (SymbolKind::QuantumRegister, "qreg"),
+                RegisterType::C => (SymbolKind::ClassicalRegister, "creg"),
+            };
+            let signature = format!("{} {}[{}]", keyword, name, size);
+            symbol(name, kind, signature, location)
+        })
+        .chain(semantics.macro_definitions.values().map(
+            |MacroDefinition(name, real_args, args, _, location, _)| {
+                let signature = gate_signature("gate", name, real_args, args);
+                symbol(name, SymbolKind::Gate, signature, location)
+            },
+        ))
+        .chain(semantics.opaque_definitions.values().map(
+            |OpaqueDefinition(name, real_args, args, location)| {
+                let signature = gate_signature("opaque", name, real_args, args);
+                symbol(name, SymbolKind::OpaqueGate, signature, location)
+            },
+        ))
+        .collect();
+    symbols.sort_by(|a, b| (a.boundaries, &a.name).cmp(&(b.boundaries, &b.name)));
+    Ok(symbols)
+}
+
+/// Return the metrics of the circuit described by the source code `input`,
+/// accepting the extensions to OPENQASM 2.0 enabled in `options`. The
+/// program is compiled but not simulated, so opaque gates need no
+/// implementation.
+///
+/// # Errors
+///
+/// The function fails if the program cannot be parsed, linked or compiled,
+/// returning an `Err` variant with a value of [`QasmSimError`].
+///
+/// [`QasmSimError`]: ./error/enum.QasmSimError.html
+///
+/// # Examples
+///
+/// ```
+/// use qasmsim::{get_stats, LanguageOptions};
+///
+/// let stats = get_stats(r#"
+/// OPENQASM 2.0;
+/// include "qelib1.inc";
+/// qreg q[2];
+/// creg c[2];
+/// h q[0];
+/// cx q[0], q[1];
+/// measure q -> c;
+/// "#, &LanguageOptions::new())?;
+///
+/// assert_eq!(stats.qubits, 2);
+/// assert_eq!(stats.gate_counts["CX"], 1);
+/// assert_eq!(stats.depth, 3);
+/// assert_eq!(stats.measurements, 2);
+/// assert!(!stats.dynamic);
+/// # Ok::<(), qasmsim::QasmSimError>(())
+/// ```
+pub fn get_stats<'src>(input: &'src str, options: &LanguageOptions) -> Result<'src, Stats> {
+    let linked = parse_and_link_with_options(input, options)?;
+    let semantics = semantics::extract_semantics(&linked, options)
+        .map_err(|err| QasmSimError::from((input, RuntimeError::from(err))))?;
+    interpreter::collect_stats(&linked, &semantics).map_err(|err| QasmSimError::from((input, err)))
+}
+
+/// Return the OPENQASM 2.0 program `input` formatted in a canonical layout,
+/// accepting the extensions enabled in `options`.
+///
+/// The formatted program has one statement per line, gate bodies indented
+/// with two spaces and normalized spacing inside statements. Comments are
+/// kept: those following a statement in the same line stay there, and the
+/// rest go in their own lines before the next statement. Runs of blank
+/// lines are collapsed into one. The real parameters of gate calls are
+/// simplified as [`to_qasm()`] does. Formatting does not change the AST of
+/// the program otherwise, apart from the locations of its statements.
+///
+/// # Errors
+///
+/// The function fails if the program cannot be parsed, returning an `Err`
+/// variant with a value of [`QasmSimError`]. OPENQASM 3 programs are not
+/// supported.
+///
+/// [`QasmSimError`]: ./error/enum.QasmSimError.html
+/// [`to_qasm()`]: ./fn.to_qasm.html
+///
+/// # Examples
+///
+/// ```
+/// use qasmsim::{format_source, LanguageOptions};
+///
+/// let formatted = format_source(r#"OPENQASM 2.0;
+/// // Two qubits.
+/// qreg q [2]; CX q[0],q[1];
+/// "#, &LanguageOptions::new())?;
+///
+/// assert_eq!(formatted, "OPENQASM 2.0;\n// Two qubits.\nqreg q[2];\nCX q[0], q[1];\n");
+/// # Ok::<(), qasmsim::QasmSimError>(())
+/// ```
+pub fn format_source<'src>(input: &'src str, options: &LanguageOptions) -> Result<'src, String> {
+    let program = parse_program_with_options(input, options)?;
+    reject_open_qasm3(input, &program, "formatting")?;
+    Ok(format_program(input, &program.simplify()))
+}
+
+/// Fail with an unsupported feature error if `program`, the AST of `input`,
+/// is not an OPENQASM 2.0 program. The `action` describes what the
+/// feature is.
+fn reject_open_qasm3<'src>(
+    input: &'src str,
+    program: &ast::OpenQasmProgram,
+    action: &str,
+) -> Result<'src, ()> {
+    if is_open_qasm2(program) {
+        return Ok(());
+    }
+    let location = tokenize(input).first().map_or(Location(0), |token| token.0);
+    Err(QasmSimError::from((
+        input,
+        LoweringError::UnsupportedFeature {
+            location,
+            feature: format!("{} OPENQASM 3 programs", action),
+        },
+    )))
+}
+
+/// Return the OPENQASM source of the AST `program`, one statement per line.
+///
+/// Unlike [`format_source()`], which formats source code, the function
+/// writes out ASTs built or transformed programmatically. Comments other
+/// than the docstrings of gates are not part of the AST so they are lost.
+/// The real parameters of gate calls are simplified: constant subexpressions
+/// are folded, fractions of pi are written exactly and the `phi` and
+/// `lambda` angles of `U` are normalized to `(-pi, pi]`. Parsing the
+/// returned source produces the same AST, apart from the locations of its
+/// statements and the simplified parameters.
+///
+/// [`format_source()`]: ./fn.format_source.html
+///
+/// # Examples
+///
+/// ```
+/// use qasmsim::grammar::ast::{Argument, Span, Statement, QuantumOperation, UnitaryOperation};
+/// use qasmsim::grammar::lexer::Location;
+/// use qasmsim::{parse_and_link, to_qasm};
+///
+/// let mut program = parse_and_link("OPENQASM 2.0; qreg q[1];")?;
+/// program.program = vec![
+///     Span::new((Location(0), Location(0)), Statement::QRegDecl("r".into(), 2)),
+///     Span::new(
+///         (Location(0), Location(0)),
+///         Statement::QuantumOperation(QuantumOperation::Unitary(UnitaryOperation(
+///             "CX".into(),
+///             vec![],
+///             vec![Argument::Item("r".into(), 0), Argument::Item("r".into(), 1)],
+///         ))),
+///     ),
+/// ];
+///
+/// assert_eq!(to_qasm(&program), "OPENQASM 2.0;\nqreg r[2];\nCX r[0], r[1];\n");
+/// # Ok::<(), qasmsim::QasmSimError>(())
+/// ```
+pub fn to_qasm(program: &ast::OpenQasmProgram) -> String {
+    program.simplify().to_string()
+}
+
+/// Return the OPENQASM 2.0 program `input` flattened and written in `basis`.
+///
+/// Flattening inlines all the gate calls, substitutes the parameters by
+/// their values and expands the operations on whole registers into one
+/// statement per qubit. Gates under modifiers are decomposed into one-qubit
+/// gates and `CX` gates. The result declares the same registers, so it can
+/// replace the original program, and each unitary gate is written in the
+/// gates of `basis`, up to a global phase.
+///
+/// # Errors
+///
+/// The function fails if the program does not compile, or if it calls
+/// opaque gates since they cannot be inlined. OPENQASM 3 programs are not
+/// supported.
+///
+/// # Examples
+///
+/// ```
+/// use qasmsim::{flatten, Basis, LanguageOptions};
+///
+/// let flattened = flatten(r#"
+///     OPENQASM 2.0;
+///     include "qelib1.inc";
+///     qreg q[2];
+///     h q;
+///     cx q[0], q[1];
+/// "#, Basis::U3Cx, &LanguageOptions::new())?;
+///
+/// assert_eq!(flattened, concat!(
+///     "OPENQASM 2.0;\n",
+///     "include \"qelib1.inc\";\n",
+///     "qreg q[2];\n",
+///     "u3(pi / 2, 0, pi) q[0];\n",
+///     "u3(pi / 2, 0, pi) q[1];\n",
+///     "cx q[0], q[1];\n",
+/// ));
+/// # Ok::<(), qasmsim::QasmSimError>(())
+/// ```
+pub fn flatten<'src>(
+    input: &'src str,
+    basis: Basis,
+    options: &LanguageOptions,
+) -> Result<'src, String> {
+    let program = parse_program_with_options(input, options)?;
+    reject_open_qasm3(input, &program, "flattening")?;
+    let linked = default_linker()
+        .link(program, options)
+        .map_err(|err| QasmSimError::from((input, err)))?;
+    let semantics = semantics::extract_semantics(&linked, options)
+        .map_err(|err| QasmSimError::from((input, RuntimeError::from(err))))?;
+    let flattened = interpreter::flatten(&linked, &semantics, basis)
+        .map_err(|err| QasmSimError::from((input, err)))?;
+    Ok(to_qasm(&flattened))
+}
+
+/// Return the text circuit diagram of the OPENQASM 2.0 program `input`,
+/// wrapped at `width` columns if given.
+///
+/// The diagram has one wire per qubit and classical bit, labelled with the
+/// names of the registers. By default, it shows the gates called in the
+/// program; with `expand`, it shows the primitive gates they expand into.
+/// Long diagrams are split in chunks of at most `width` columns, unless a
+/// single operation is wider.
+///
+/// # Errors
+///
+/// The function fails if the program does not compile. OPENQASM 3 programs
+/// are not supported.
+///
+/// # Examples
+///
+/// ```
+/// use qasmsim::{draw, LanguageOptions};
+///
+/// let diagram = draw(r#"
+///     OPENQASM 2.0;
+///     include "qelib1.inc";
+///     qreg q[2];
+///     creg c[1];
+///     h q[0];
+///     cx q[0], q[1];
+///     measure q[1] -> c[0];
+/// "#, false, None, &LanguageOptions::new())?;
+///
+/// assert_eq!(diagram, concat!(
+///     "q[0]: --[h]---*--------\n",
+///     "              |\n",
+///     "q[1]: -------(+)--[M]--\n",
+///     "                   |\n",
+///     "c[0]: =============v===\n",
+/// ));
+/// # Ok::<(), qasmsim::QasmSimError>(())
+/// ```
+pub fn draw<'src>(
+    input: &'src str,
+    expand: bool,
+    width: Option<usize>,
+    options: &LanguageOptions,
+) -> Result<'src, String> {
+    let program = parse_program_with_options(input, options)?;
+    reject_open_qasm3(input, &program, "drawing")?;
+    let linked = default_linker()
+        .link(program, options)
+        .map_err(|err| QasmSimError::from((input, err)))?;
+    let semantics = semantics::extract_semantics(&linked, options)
+        .map_err(|err| QasmSimError::from((input, RuntimeError::from(err))))?;
+    interpreter::draw(&linked, &semantics, expand, width)
+        .map_err(|err| QasmSimError::from((input, err)))
+}
+
+/// Check if the programs `left` and `right` implement the same unitary up to
+/// a global phase.
+///
+/// The check runs both programs on random product states and, for programs
+/// acting on a few qubits, compares the whole unitaries column by column.
+/// If the programs differ, the result contains a witness input state for
+/// which the outputs differ, and the fidelity between both outputs.
+///
+/// # Errors
+///
+/// The function fails if any of the programs cannot be parsed or simulated,
+/// or if any of them contains non-unitary operations such as `measure`,
+/// `reset` or conditionals. In that case, it will return an `Err` variant
+/// with a value of [`QasmSimError`] referring to the source of the faulty
+/// program.
+///
+/// [`QasmSimError`]: ./error/enum.QasmSimError.html
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use qasmsim::{check_equivalence, LanguageOptions};
+///
+/// let equivalence = check_equivalence(r#"
+///     OPENQASM 2.0;
+///     include "qelib1.inc";
+///     qreg q[2];
+///     cx q[0], q[1];
+/// "#, r#"
+///     OPENQASM 2.0;
+///     include "qelib1.inc";
+///     qreg q[2];
+///     h q;
+///     cx q[1], q[0];
+///     h q;
+/// "#, &LanguageOptions::new())?;
+///
+/// assert!(equivalence.is_equivalent());
+/// # use qasmsim::QasmSimError;
+/// # Ok::<(), qasmsim::QasmSimError>(())
+/// ```
+pub fn check_equivalence<'src>(
+    left: &'src str,
+    right: &'src str,
+    options: &LanguageOptions,
+) -> Result<'src, Equivalence> {
+    let left_program = parse_and_link_with_options(left, options)?;
+    let right_program = parse_and_link_with_options(right, options)?;
+    let mut left_simulator = UnitarySimulator::new(&left_program, options)
+        .map_err(|err| QasmSimError::from((left, err)))?;
+    let mut right_simulator = UnitarySimulator::new(&right_program, options)
+        .map_err(|err| QasmSimError::from((right, err)))?;
+
+    let qubit_width = left_simulator.qubit_width();
+    if qubit_width != right_simulator.qubit_width() {
+        return Ok(Equivalence::DifferentWidths(
+            qubit_width,
+            right_simulator.qubit_width(),
+        ));
+    }
+
+    equivalence::compare(
+        qubit_width,
+        |input| {
+            left_simulator
+                .apply(input)
+                .map_err(|err| QasmSimError::from((left, err)))
+        },
+        |input| {
+            right_simulator
+                .apply(input)
+                .map_err(|err| QasmSimError::from((right, err)))
+        },
+    )
+}
+
+/// Return the unitary matrix implemented by the program `input`, accepting
+/// the extensions to OPENQASM 2.0 enabled in `options`.
+///
+/// The unitary is computed by running the program on each of the
+/// computational basis states, so it has dimension 2 to the power of the
+/// number of qubits in the program.
+///
+/// # Errors
+///
+/// The function fails if the program cannot be parsed or simulated, or if it
+/// contains non-unitary operations such as `measure`, `reset` or
+/// conditionals. In that case, it will return an `Err` variant with a value
+/// of [`QasmSimError`].
+///
+/// [`QasmSimError`]: ./error/enum.QasmSimError.html
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use qasmsim::{extract_unitary, LanguageOptions};
+/// use qasmsim::statevector::Complex;
+///
+/// let unitary = extract_unitary(r#"
+///     OPENQASM 2.0;
+///     include "qelib1.inc";
+///     qreg q[1];
+///     x q[0];
+/// "#, &LanguageOptions::new())?;
+///
+/// assert_eq!(unitary.dimension(), 2);
+/// assert!((unitary.get(1, 0) - Complex::new(1.0, 0.0)).norm() < 1e-10);
+/// # use qasmsim::QasmSimError;
+/// # Ok::<(), qasmsim::QasmSimError>(())
+/// ```
+pub fn extract_unitary<'src>(input: &'src str, options: &LanguageOptions) -> Result<'src, Unitary> {
+    let program = parse_and_link_with_options(input, options)?;
+    let mut simulator =
+        UnitarySimulator::new(&program, options).map_err(|err| QasmSimError::from((input, err)))?;
+    let qubit_width = simulator.qubit_width();
+    let columns = (0..(1 << qubit_width))
+        .map(|index| simulator.apply(StateVector::from_basis_state(qubit_width, index)))
+        .collect::<std::result::Result<Vec<StateVector>, RuntimeError>>()
+        .map_err(|err| QasmSimError::from((input, err)))?;
+    Ok(Unitary::from_columns(&columns))
 }
 
 pub use interpreter::runtime::simulate;
@@ -6,15 +6,30 @@ use std::convert;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use crate::{api, statevector::StateVector};
+use crate::api;
+use crate::statevector::{QuantumRegister, StateVector};
 
 use crate::error::QasmSimError;
-use crate::interpreter::{Computation, Histogram};
+use crate::grammar::{parse_program_with_options, LanguageOptions};
+use crate::interpreter::{runtime, validation};
+use crate::interpreter::{Computation, Histogram, OpaqueGates};
+use crate::lint::Warning;
 
+pub use api::check_equivalence;
+pub use api::draw;
+pub use api::extract_unitary;
+pub use api::flatten;
+pub use api::format_source;
+pub use api::get_gate_definition;
 pub use api::get_gate_info;
+pub use api::get_stats;
+pub use api::get_symbols;
 pub use api::parse_and_link;
+pub use api::parse_and_link_with_options;
 pub use api::simulate;
 pub use api::simulate_with_shots;
+pub use api::to_qasm;
+pub use api::{Symbol, SymbolKind};
 
 macro_rules! measure {
     ($block:expr) => {{
@@ -86,6 +101,8 @@ pub struct Execution {
     memory: HashMap<String, u64>,
     histogram: Option<Histogram>,
     times: ExecutionTimes,
+    #[cfg_attr(feature = "serde", serde(default))]
+    quantum_registers: Vec<QuantumRegister>,
 }
 
 impl Execution {
@@ -103,9 +120,16 @@ impl Execution {
             memory,
             histogram,
             times,
+            quantum_registers: Vec::new(),
         }
     }
 
+    /// Set the layout of the quantum registers in the state-vector.
+    pub fn with_quantum_registers(mut self, quantum_registers: Vec<QuantumRegister>) -> Self {
+        self.quantum_registers = quantum_registers;
+        self
+    }
+
     /// Return the statevector of the quantum system.
     pub fn statevector(&self) -> &StateVector {
         &self.statevector
@@ -130,6 +154,12 @@ impl Execution {
     pub fn times(&self) -> &ExecutionTimes {
         &self.times
     }
+
+    /// Return the quantum registers sorted by their position in the
+    /// state-vector.
+    pub fn quantum_registers(&self) -> &[QuantumRegister] {
+        &self.quantum_registers
+    }
 }
 
 impl convert::From<(Computation, u128, u128)> for Execution {
@@ -144,6 +174,7 @@ impl convert::From<(Computation, u128, u128)> for Execution {
                 parsing_time,
                 simulation_time,
             },
+            quantum_registers: computation.quantum_registers().to_vec(),
         }
     }
 }
@@ -184,3 +215,216 @@ pub fn run(input: &str, shots: Option<usize>) -> api::Result<'_, Execution> {
     let out = out.map_err(|err| QasmSimError::from((input, err)));
     Ok(Execution::from((out?, parsing_time, simulation_time)))
 }
+
+/// Parse and simulate the `input` OPENQASM program with optional `shots`,
+/// using `opaque_gates` as the implementations of the opaque gates declared
+/// in the program.
+///
+/// # Errors
+///
+/// Besides the errors of [`run()`], the function fails if the program calls
+/// an opaque gate with no implementation in `opaque_gates`, returning an
+/// `Err` variant wrapping [`QasmSimError::UnboundOpaqueGate`].
+///
+/// [`run()`]: ./fn.run.html
+/// [`QasmSimError::UnboundOpaqueGate`]: ./error/enum.QasmSimError.html#variant.UnboundOpaqueGate
+///
+/// # Examples
+///
+/// Bind a matrix to an opaque gate:
+///
+/// ```
+/// use qasmsim::{run_with_opaque_gates, MatrixGate, OpaqueGates};
+/// use qasmsim::statevector::Complex;
+///
+/// let zero = Complex::from(0.0);
+/// let one = Complex::from(1.0);
+/// let mut opaque_gates = OpaqueGates::new();
+/// opaque_gates.bind("not", MatrixGate::new(vec![
+///     vec![zero, one],
+///     vec![one, zero],
+/// ])?);
+///
+/// let execution = run_with_opaque_gates(r#"
+/// OPENQASM 2.0;
+/// opaque not q;
+/// qreg q[1];
+/// creg c[1];
+/// not q[0];
+/// measure q -> c;
+/// "#, None, &opaque_gates)?;
+///
+/// assert_eq!(execution.memory()["c"], 1);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+///
+/// Or a closure operating on the state-vector:
+///
+/// ```
+/// use qasmsim::{run_with_opaque_gates, OpaqueGates};
+/// use qasmsim::statevector::StateVector;
+///
+/// let mut opaque_gates = OpaqueGates::new();
+/// opaque_gates.bind(
+///     "ry",
+///     |statevector: &mut StateVector, real_args: &[f64], qubits: &[usize]| {
+///         statevector.u(real_args[0], 0.0, 0.0, qubits[0]);
+///     },
+/// );
+///
+/// let execution = run_with_opaque_gates(r#"
+/// OPENQASM 2.0;
+/// opaque ry(theta) q;
+/// qreg q[1];
+/// ry(pi) q[0];
+/// "#, None, &opaque_gates)?;
+///
+/// assert!(execution.probabilities()[1] > 0.99);
+/// # use qasmsim::QasmSimError;
+/// # Ok::<(), QasmSimError>(())
+/// ```
+pub fn run_with_opaque_gates<'src>(
+    input: &'src str,
+    shots: Option<usize>,
+    opaque_gates: &OpaqueGates,
+) -> api::Result<'src, Execution> {
+    run_with_options(input, shots, &LanguageOptions::new(), opaque_gates)
+}
+
+/// Parse and simulate the `input` OPENQASM program with optional `shots`,
+/// accepting the extensions to OPENQASM 2.0 enabled in `options` and using
+/// `opaque_gates` as the implementations of the opaque gates declared in the
+/// program.
+///
+/// # Errors
+///
+/// Besides the errors of [`run_with_opaque_gates()`], the function fails if
+/// the program uses an extension not enabled in `options`, returning an
+/// `Err` variant wrapping [`QasmSimError::DisabledExtension`].
+///
+/// [`run_with_opaque_gates()`]: ./fn.run_with_opaque_gates.html
+/// [`QasmSimError::DisabledExtension`]: ./error/enum.QasmSimError.html#variant.DisabledExtension
+///
+/// # Examples
+///
+/// Condition an operation on a single bit:
+///
+/// ```
+/// use qasmsim::{run_with_options, Extension, LanguageOptions, OpaqueGates};
+///
+/// let options = LanguageOptions::new().allow(Extension::ExtendedConditionals);
+/// let execution = run_with_options(r#"
+/// OPENQASM 2.0;
+/// include "qelib1.inc";
+/// qreg q[2];
+/// creg c[2];
+/// x q[0];
+/// measure q[0] -> c[0];
+/// if (c[0]==1) x q[1];
+/// measure q[1] -> c[1];
+/// "#, None, &options, &OpaqueGates::new())?;
+///
+/// assert_eq!(execution.memory()["c"], 0b11);
+/// # use qasmsim::QasmSimError;
+/// # Ok::<(), QasmSimError>(())
+/// ```
+pub fn run_with_options<'src>(
+    input: &'src str,
+    shots: Option<usize>,
+    options: &LanguageOptions,
+    opaque_gates: &OpaqueGates,
+) -> api::Result<'src, Execution> {
+    let (linked, parsing_time) = measure!({ api::parse_and_link_with_options(input, options) });
+    let (out, simulation_time) =
+        measure!({ runtime::simulate_with_options(&linked?, shots, options, opaque_gates) });
+    let out = out.map_err(|err| QasmSimError::from((input, err)));
+    Ok(Execution::from((out?, parsing_time, simulation_time)))
+}
+
+/// Check the `input` OPENQASM program without simulating it, accepting the
+/// extensions to OPENQASM 2.0 enabled in `options` and the opaque gates
+/// implemented in `opaque_gates`. Return all the errors found, or an empty
+/// list if the program is valid.
+///
+/// Unlike [`run_with_options()`], the check does not stop at the first error,
+/// and finds errors inside conditionals whose branches never run and inside
+/// gates that are never called. If the program cannot be parsed, the parsing
+/// error is the only error returned.
+///
+/// [`run_with_options()`]: ./fn.run_with_options.html
+///
+/// # Examples
+///
+/// ```
+/// use qasmsim::{check, LanguageOptions, OpaqueGates};
+///
+/// let errors = check(r#"
+/// OPENQASM 2.0;
+/// include "qelib1.inc";
+/// qreg q[2];
+/// creg c[2];
+/// h q[2];
+/// if (c==1) cx q[0];
+/// "#, &LanguageOptions::new(), &OpaqueGates::new());
+///
+/// assert_eq!(errors.len(), 2);
+/// ```
+pub fn check<'src>(
+    input: &'src str,
+    options: &LanguageOptions,
+    opaque_gates: &OpaqueGates,
+) -> Vec<QasmSimError<'src>> {
+    match api::parse_and_link_with_options(input, options) {
+        Err(error) => vec![error],
+        Ok(linked) => validation::validate(&linked, options, Some(opaque_gates))
+            .into_iter()
+            .map(|error| QasmSimError::from((input, error)))
+            .collect(),
+    }
+}
+
+/// Return the warnings about suspicious constructions in the `input`
+/// OPENQASM program, accepting the extensions to OPENQASM 2.0 enabled in
+/// `options`. Warnings do not prevent the program from running: unused
+/// registers and gates, measurements overwritten before being read,
+/// unconditional gates applied after the final measurement of their qubits,
+/// barriers on undeclared registers, gates shadowing those of included
+/// libraries, and conditionals on registers never written.
+///
+/// # Errors
+///
+/// The function fails if the program cannot be parsed or linked, returning
+/// an `Err` variant with a value of [`QasmSimError`].
+///
+/// [`QasmSimError`]: ./error/enum.QasmSimError.html
+///
+/// # Examples
+///
+/// ```
+/// use qasmsim::{lint, LanguageOptions};
+/// use qasmsim::lint::Lint;
+///
+/// let warnings = lint(r#"
+/// OPENQASM 2.0;
+/// include "qelib1.inc";
+/// qreg q[1];
+/// creg c[1];
+/// if (c==1) x q[0];
+/// "#, &LanguageOptions::new())?;
+///
+/// assert_eq!(warnings[0].lint, Lint::UnwrittenCondition { symbol_name: "c".into() });
+/// # Ok::<(), qasmsim::QasmSimError>(())
+/// ```
+pub fn lint<'src>(
+    input: &'src str,
+    options: &LanguageOptions,
+) -> api::Result<'src, Vec<Warning<'src>>> {
+    let program = parse_program_with_options(input, options)?;
+    let (linked, shadowed) = api::default_linker()
+        .link_with_shadowing(program.clone(), options)
+        .map_err(|err| QasmSimError::from((input, err)))?;
+    Ok(crate::lint::lint(&program, &linked, &shadowed)
+        .into_iter()
+        .map(|located| Warning::from((input, located)))
+        .collect())
+}
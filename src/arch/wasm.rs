@@ -14,6 +14,7 @@ use wasm_bindgen::prelude::{wasm_bindgen, JsValue};
 
 use crate::api;
 use crate::error::QasmSimError;
+use crate::grammar::LanguageOptions;
 use crate::grammar::{self, ast};
 
 macro_rules! adapt_parse_functions {
@@ -66,10 +67,17 @@ pub fn simulate(program: JsValue, shots: Option<usize>) -> Result<JsValue, JsVal
     computation.map(|v| v.into()).map_err(|err| err.into())
 }
 
+#[wasm_bindgen]
+#[allow(non_snake_case)]
+pub fn toQasm(program: JsValue) -> Result<String, JsValue> {
+    let openqasm_program: ast::OpenQasmProgram = serde_wasm_bindgen::from_value(program)?;
+    Ok(api::to_qasm(&openqasm_program))
+}
+
 #[wasm_bindgen]
 #[allow(non_snake_case)]
 pub fn getGateInfo(input: &str, gate_name: &str) -> Result<JsValue, JsValue> {
-    api::get_gate_info(input, gate_name)
+    api::get_gate_info(input, gate_name, &LanguageOptions::new())
         .map(|(docstring, (name, real_params, quantum_params))| {
             let gate_info = js_sys::Object::new();
             set!(&gate_info,
@@ -83,6 +91,12 @@ pub fn getGateInfo(input: &str, gate_name: &str) -> Result<JsValue, JsValue> {
         .map_err(|err| err.into())
 }
 
+#[wasm_bindgen]
+#[allow(non_snake_case)]
+pub fn getGateDefinition(input: &str, gate_name: &str) -> Result<String, JsValue> {
+    api::get_gate_definition(input, gate_name, &LanguageOptions::new()).map_err(|err| err.into())
+}
+
 adapt_parse_functions! {
     pub fn parseAndLink(source) => api::parse_and_link;
     pub fn parseProgram(source) => grammar::parse_program;
@@ -42,6 +42,19 @@ impl From<QasmSimError<'_>> for JsValue {
                     set!(&obj, "token" => &format!("{}", token));
                 }
             }
+            QasmSimError::InvalidModifier {
+                lineno,
+                startpos,
+                modifier,
+                ..
+            } => {
+                set!(&obj,
+                    "type" => "InvalidModifier",
+                    "lineNumber" => lineno as f64,
+                    "startPosition" => startpos as f64,
+                    "modifier" => &modifier
+                );
+            }
             QasmSimError::UnexpectedEOF {
                 lineno,
                 startpos,
@@ -104,6 +117,8 @@ impl From<QasmSimError<'_>> for JsValue {
             }
             QasmSimError::IndexOutOfBounds {
                 lineno,
+                startpos,
+                endpos,
                 symbol_name,
                 index,
                 size,
@@ -112,13 +127,19 @@ impl From<QasmSimError<'_>> for JsValue {
                 set!(&obj,
                     "type" => "IndexOutOfBounds",
                     "lineNumber" => lineno as f64,
+                    "startPosition" => startpos as f64,
                     "symbolName" => &symbol_name,
                     "index" => index as f64,
                     "size" => size as f64
                 );
+                if let Some(endpos) = endpos {
+                    set!(&obj, "endPosition" => endpos as f64);
+                }
             }
             QasmSimError::SymbolNotFound {
                 lineno,
+                startpos,
+                endpos,
                 symbol_name,
                 expected,
                 ..
@@ -126,12 +147,18 @@ impl From<QasmSimError<'_>> for JsValue {
                 set!(&obj,
                     "type" => "SymbolNotFound",
                     "lineNumber" => lineno as f64,
+                    "startPosition" => startpos as f64,
                     "symbolName" => &symbol_name,
                     "expected" => &format!("{}", expected)
                 );
+                if let Some(endpos) = endpos {
+                    set!(&obj, "endPosition" => endpos as f64);
+                }
             }
             QasmSimError::WrongNumberOfParameters {
                 lineno,
+                startpos,
+                endpos,
                 symbol_name,
                 are_registers,
                 given,
@@ -141,11 +168,15 @@ impl From<QasmSimError<'_>> for JsValue {
                 set!(&obj,
                     "type" => "WrongNumberOfParameters",
                     "lineNumber" => lineno as f64,
+                    "startPosition" => startpos as f64,
                     "symbolName" => &symbol_name,
                     "kind" => if are_registers { "register" } else { "real" },
                     "given" => given as f64,
                     "expected" => expected as f64
                 );
+                if let Some(endpos) = endpos {
+                    set!(&obj, "endPosition" => endpos as f64);
+                }
             }
             QasmSimError::UndefinedGate {
                 symbol_name,
@@ -161,27 +192,194 @@ impl From<QasmSimError<'_>> for JsValue {
             QasmSimError::TypeMismatch {
                 symbol_name,
                 lineno,
+                startpos,
+                endpos,
                 expected,
                 ..
             } => {
                 set!(&obj,
                     "type" => "TypeMismatch",
                     "lineNumber" => lineno as f64,
+                    "startPosition" => startpos as f64,
                     "symbolName" => &symbol_name,
                     "expected" => &format!("{}", expected)
                 );
+                if let Some(endpos) = endpos {
+                    set!(&obj, "endPosition" => endpos as f64);
+                }
             }
             QasmSimError::RegisterSizeMismatch {
                 symbol_name,
                 lineno,
+                startpos,
+                endpos,
                 ..
             } => {
                 set!(&obj,
                     "type" => "RegisterSizeMismatch",
                     "lineNumber" => lineno as f64,
+                    "startPosition" => startpos as f64,
+                    "symbolName" => &symbol_name
+                );
+                if let Some(endpos) = endpos {
+                    set!(&obj, "endPosition" => endpos as f64);
+                }
+            }
+            QasmSimError::RepeatedQubit {
+                symbol_name,
+                qubit,
+                lineno,
+                startpos,
+                endpos,
+                ..
+            } => {
+                set!(&obj,
+                    "type" => "RepeatedQubit",
+                    "lineNumber" => lineno as f64,
+                    "startPosition" => startpos as f64,
+                    "symbolName" => &symbol_name,
+                    "qubit" => &qubit
+                );
+                if let Some(endpos) = endpos {
+                    set!(&obj, "endPosition" => endpos as f64);
+                }
+            }
+            QasmSimError::UnboundOpaqueGate {
+                symbol_name,
+                lineno,
+                ..
+            } => {
+                set!(&obj,
+                    "type" => "UnboundOpaqueGate",
+                    "lineNumber" => lineno as f64,
+                    "symbolName" => &symbol_name
+                );
+            }
+            QasmSimError::ModifiedOpaqueGate {
+                symbol_name,
+                lineno,
+                ..
+            } => {
+                set!(&obj,
+                    "type" => "ModifiedOpaqueGate",
+                    "lineNumber" => lineno as f64,
+                    "symbolName" => &symbol_name
+                );
+            }
+            QasmSimError::NonIntegerPower {
+                symbol_name,
+                lineno,
+                exponent,
+                ..
+            } => {
+                set!(&obj,
+                    "type" => "NonIntegerPower",
+                    "lineNumber" => lineno as f64,
+                    "symbolName" => &symbol_name,
+                    "exponent" => &exponent
+                );
+            }
+            QasmSimError::PowerTooLarge {
+                symbol_name,
+                lineno,
+                exponent,
+                ..
+            } => {
+                set!(&obj,
+                    "type" => "PowerTooLarge",
+                    "lineNumber" => lineno as f64,
+                    "symbolName" => &symbol_name,
+                    "exponent" => &exponent
+                );
+            }
+            QasmSimError::UnsupportedFeature {
+                lineno,
+                startpos,
+                feature,
+                ..
+            } => {
+                set!(&obj,
+                    "type" => "UnsupportedFeature",
+                    "lineNumber" => lineno as f64,
+                    "startPosition" => startpos as f64,
+                    "feature" => &feature
+                );
+            }
+            QasmSimError::InvalidInteger {
+                lineno,
+                value,
+                non_negative,
+                ..
+            } => {
+                set!(&obj,
+                    "type" => "InvalidInteger",
+                    "lineNumber" => lineno as f64,
+                    "value" => &value,
+                    "nonNegative" => non_negative
+                );
+            }
+            QasmSimError::DisabledExtension {
+                lineno,
+                startpos,
+                extension,
+                ..
+            } => {
+                set!(&obj,
+                    "type" => "DisabledExtension",
+                    "lineNumber" => lineno as f64,
+                    "startPosition" => startpos as f64,
+                    "extension" => extension.name()
+                );
+            }
+            QasmSimError::NonUnitaryOperation {
+                symbol_name,
+                lineno,
+                ..
+            } => {
+                set!(&obj,
+                    "type" => "NonUnitaryOperation",
+                    "lineNumber" => lineno as f64,
                     "symbolName" => &symbol_name
                 );
             }
+            QasmSimError::InvalidParameter {
+                lineno,
+                startpos,
+                endpos,
+                expression,
+                value,
+                ..
+            } => {
+                set!(&obj,
+                    "type" => "InvalidParameter",
+                    "lineNumber" => lineno as f64,
+                    "startPosition" => startpos as f64,
+                    "expression" => &expression,
+                    "value" => &value
+                );
+                if let Some(endpos) = endpos {
+                    set!(&obj, "endPosition" => endpos as f64);
+                }
+            }
+            QasmSimError::NonFiniteValue {
+                lineno, operation, ..
+            } => {
+                set!(&obj,
+                    "type" => "NonFiniteValue",
+                    "lineNumber" => lineno as f64,
+                    "operation" => &operation
+                );
+            }
+            QasmSimError::MultipleErrors(errors) => {
+                let array = js_sys::Array::new();
+                for error in errors {
+                    array.push(&error.into());
+                }
+                set!(&obj,
+                    "type" => "MultipleErrors",
+                    "errors" => array
+                );
+            }
         };
         obj.into()
     }
@@ -284,6 +482,91 @@ impl From<RuntimeError> for JsValue {
                     "symbolName" => &symbol_name
                 );
             }
+            RuntimeError::RepeatedQubit {
+                symbol_name,
+                qubit,
+                location,
+                ..
+            } => {
+                set!(&obj,
+                    "type" => "RepeatedQubit",
+                    "location" => location.0 as f64,
+                    "symbolName" => &symbol_name,
+                    "qubit" => &qubit
+                );
+            }
+            RuntimeError::UnboundOpaqueGate {
+                symbol_name,
+                location,
+                ..
+            } => {
+                set!(&obj,
+                    "type" => "UnboundOpaqueGate",
+                    "location" => location.0 as f64,
+                    "symbolName" => &symbol_name
+                );
+            }
+            RuntimeError::ModifiedOpaqueGate {
+                symbol_name,
+                location,
+                ..
+            } => {
+                set!(&obj,
+                    "type" => "ModifiedOpaqueGate",
+                    "location" => location.0 as f64,
+                    "symbolName" => &symbol_name
+                );
+            }
+            RuntimeError::NonIntegerPower {
+                symbol_name,
+                location,
+                exponent,
+                ..
+            } => {
+                set!(&obj,
+                    "type" => "NonIntegerPower",
+                    "location" => location.0 as f64,
+                    "symbolName" => &symbol_name,
+                    "exponent" => &exponent
+                );
+            }
+            RuntimeError::PowerTooLarge {
+                symbol_name,
+                location,
+                exponent,
+                ..
+            } => {
+                set!(&obj,
+                    "type" => "PowerTooLarge",
+                    "location" => location.0 as f64,
+                    "symbolName" => &symbol_name,
+                    "exponent" => &exponent
+                );
+            }
+            RuntimeError::NonUnitaryOperation {
+                symbol_name,
+                location,
+                ..
+            } => {
+                set!(&obj,
+                    "type" => "NonUnitaryOperation",
+                    "location" => location.0 as f64,
+                    "symbolName" => &symbol_name
+                );
+            }
+            RuntimeError::InvalidParameter {
+                location,
+                expression,
+                value,
+                ..
+            } => {
+                set!(&obj,
+                    "type" => "InvalidParameter",
+                    "location" => location.0 as f64,
+                    "expression" => &expression,
+                    "value" => &value
+                );
+            }
         };
         obj.into()
     }
@@ -313,6 +596,17 @@ impl From<SemanticError> for JsValue {
                     "symbolName" => &symbol_name
                 );
             }
+            SemanticError::DisabledExtension {
+                location,
+                extension,
+                ..
+            } => {
+                set!(&obj,
+                    "type" => "DisabledExtension",
+                    "location" => location.0 as f64,
+                    "extension" => extension.name()
+                );
+            }
         };
         obj.into()
     }
@@ -0,0 +1,98 @@
+use qasmsim::error::{Diagnostic, Severity};
+use qasmsim::grammar::lexer::{tokenize, Location, Tok};
+use qasmsim::{LanguageOptions, OpaqueGates, QasmSimError, Symbol};
+
+/// An open document, with the symbols of the last version which could be
+/// parsed, so they are still available while the user is typing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Document {
+    pub text: String,
+    pub symbols: Vec<Symbol>,
+}
+
+impl Document {
+    pub fn new(text: String) -> Self {
+        let mut document = Document {
+            text: String::new(),
+            symbols: vec![],
+        };
+        document.update(text);
+        document
+    }
+
+    pub fn update(&mut self, text: String) {
+        self.text = text;
+        if let Ok(symbols) = qasmsim::get_symbols(&self.text, &LanguageOptions::new()) {
+            self.symbols = symbols;
+        }
+    }
+
+    /// Return the errors and the warnings of the document. Calls to opaque
+    /// gates are not errors since their implementation is bound when running
+    /// the program.
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        let options = LanguageOptions::new();
+        let errors = qasmsim::check(&self.text, &options, &OpaqueGates::new());
+        let warnings = qasmsim::lint(&self.text, &options).unwrap_or_default();
+        errors
+            .iter()
+            .flat_map(QasmSimError::diagnostics)
+            .filter(|diagnostic| diagnostic.kind != "UnboundOpaqueGate")
+            .chain(warnings.iter().map(|warning| warning.diagnostic()))
+            .collect()
+    }
+
+    /// Return the symbol named `name`.
+    pub fn symbol(&self, name: &str) -> Option<&Symbol> {
+        self.symbols.iter().find(|symbol| symbol.name == name)
+    }
+
+    /// Return the identifier at `offset`, if any.
+    pub fn identifier_at(&self, offset: usize) -> Option<String> {
+        tokenize(&self.text)
+            .into_iter()
+            .find_map(|(start, token, end)| match token {
+                Tok::Id { repr } if start.0 <= offset && offset <= end.0 => Some(repr),
+                _ => None,
+            })
+    }
+
+    /// Return the offset of the zero-based `line` and `character`.
+    pub fn offset(&self, line: usize, character: usize) -> usize {
+        let line_start: usize = self
+            .text
+            .split_inclusive('\n')
+            .take(line)
+            .map(str::len)
+            .sum();
+        let line_length = self.text[line_start..]
+            .find('\n')
+            .unwrap_or(self.text.len() - line_start);
+        line_start + character.min(line_length)
+    }
+
+    /// Return the zero-based line and character of `location`.
+    pub fn position(&self, location: Location) -> (usize, usize) {
+        let before = &self.text[..location.0.min(self.text.len())];
+        let line = before.matches('\n').count();
+        let character = before.len() - before.rfind('\n').map_or(0, |index| index + 1);
+        (line, character)
+    }
+}
+
+/// Return the zero-based start and end of a diagnostic, as line and
+/// character.
+pub fn diagnostic_range(diagnostic: &Diagnostic) -> ((usize, usize), (usize, usize)) {
+    let line = diagnostic.line.unwrap_or(1) - 1;
+    let start = diagnostic.column.unwrap_or(1) - 1;
+    let end = diagnostic.end_column.map_or(start + 1, |end| end - 1);
+    ((line, start), (line, end))
+}
+
+/// Return the severity of a diagnostic as defined by the protocol.
+pub fn diagnostic_severity(diagnostic: &Diagnostic) -> u8 {
+    match diagnostic.severity {
+        Severity::Error => 1,
+        Severity::Warning => 2,
+    }
+}
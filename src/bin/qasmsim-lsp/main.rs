@@ -0,0 +1,28 @@
+//! A language server for OPENQASM. It talks the Language Server Protocol
+//! over the standard input and output, and provides diagnostics, hover
+//! documentation, go-to-definition, completion and document symbols.
+
+mod document;
+mod rpc;
+mod server;
+
+use std::io;
+
+use crate::server::Server;
+
+fn main() -> io::Result<()> {
+    let stdin = io::stdin();
+    let mut reader = stdin.lock();
+    let stdout = io::stdout();
+    let mut writer = stdout.lock();
+    let mut server = Server::new();
+    while let Some(message) = rpc::read_message(&mut reader)? {
+        for outgoing in server.handle(&message) {
+            rpc::write_message(&mut writer, &outgoing)?;
+        }
+        if let Some(code) = server.exit_code() {
+            std::process::exit(code);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,270 @@
+use std::collections::HashMap;
+
+use serde_json::{json, Value};
+
+use qasmsim::grammar::lexer::Location;
+use qasmsim::{Symbol, SymbolKind};
+
+use crate::document::{diagnostic_range, diagnostic_severity, Document};
+
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+type RequestResult = Result<Value, (i64, String)>;
+
+/// The state of the language server: the open documents and whether the
+/// client asked to shut the server down.
+#[derive(Debug, Clone, Default)]
+pub struct Server {
+    documents: HashMap<String, Document>,
+    shutdown_requested: bool,
+    exit_code: Option<i32>,
+}
+
+impl Server {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Return the exit code once the client sends the `exit` notification.
+    pub fn exit_code(&self) -> Option<i32> {
+        self.exit_code
+    }
+
+    /// Handle a message from the client and return the messages to send
+    /// back: the response to a request and any notification.
+    pub fn handle(&mut self, message: &Value) -> Vec<Value> {
+        let method = match message["method"].as_str() {
+            Some(method) => method,
+            // A response to a request of the server; none is sent.
+            None => return vec![],
+        };
+        let params = &message["params"];
+        match message.get("id") {
+            Some(id) => {
+                let response = match self.request(method, params) {
+                    Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                    Err((code, message)) => json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": { "code": code, "message": message }
+                    }),
+                };
+                vec![response]
+            }
+            None => self.notification(method, params),
+        }
+    }
+
+    fn request(&mut self, method: &str, params: &Value) -> RequestResult {
+        match method {
+            "initialize" => Ok(json!({
+                "capabilities": {
+                    "textDocumentSync": 1,
+                    "hoverProvider": true,
+                    "definitionProvider": true,
+                    "completionProvider": {},
+                    "documentSymbolProvider": true
+                },
+                "serverInfo": {
+                    "name": "qasmsim-lsp",
+                    "version": env!("CARGO_PKG_VERSION")
+                }
+            })),
+            "shutdown" => {
+                self.shutdown_requested = true;
+                Ok(Value::Null)
+            }
+            "textDocument/hover" => self.hover(params),
+            "textDocument/definition" => self.definition(params),
+            "textDocument/completion" => self.completion(params),
+            "textDocument/documentSymbol" => self.document_symbols(params),
+            _ => Err((METHOD_NOT_FOUND, format!("unknown method `{}`", method))),
+        }
+    }
+
+    fn notification(&mut self, method: &str, params: &Value) -> Vec<Value> {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        match method {
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                self.documents
+                    .insert(uri.into(), Document::new(text.into()));
+                vec![self.publish_diagnostics(uri)]
+            }
+            "textDocument/didChange" => {
+                // The server asks for full synchronization, so the last change
+                // contains the whole text.
+                let text = params["contentChanges"]
+                    .as_array()
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change["text"].as_str());
+                match (self.documents.get_mut(uri), text) {
+                    (Some(document), Some(text)) => {
+                        document.update(text.into());
+                        vec![self.publish_diagnostics(uri)]
+                    }
+                    _ => vec![],
+                }
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(uri);
+                vec![publish(uri, vec![])]
+            }
+            "exit" => {
+                self.exit_code = Some(if self.shutdown_requested { 0 } else { 1 });
+                vec![]
+            }
+            _ => vec![],
+        }
+    }
+
+    fn publish_diagnostics(&self, uri: &str) -> Value {
+        let diagnostics = self.documents[uri]
+            .diagnostics()
+            .iter()
+            .map(|diagnostic| {
+                let (start, end) = diagnostic_range(diagnostic);
+                let message = match &diagnostic.hint {
+                    Some(hint) if *hint != diagnostic.message => {
+                        format!("{}\nhelp: {}", diagnostic.message, hint)
+                    }
+                    _ => diagnostic.message.clone(),
+                };
+                json!({
+                    "range": range(start, end),
+                    "severity": diagnostic_severity(diagnostic),
+                    "code": diagnostic.kind,
+                    "source": "qasmsim",
+                    "message": message
+                })
+            })
+            .collect();
+        publish(uri, diagnostics)
+    }
+
+    fn hover(&self, params: &Value) -> RequestResult {
+        let (_, symbol) = match self.symbol_at(params)? {
+            Some(found) => found,
+            None => return Ok(Value::Null),
+        };
+        let mut contents = format!("```qasm\n{}\n```", symbol.signature);
+        if let Some(docstring) = &symbol.docstring {
+            let docstring: Vec<&str> = docstring
+                .lines()
+                .map(|line| line.strip_prefix(' ').unwrap_or(line))
+                .collect();
+            contents.push_str("\n\n");
+            contents.push_str(docstring.join("\n").trim());
+        }
+        Ok(json!({ "contents": { "kind": "markdown", "value": contents } }))
+    }
+
+    fn definition(&self, params: &Value) -> RequestResult {
+        let (document, symbol) = match self.symbol_at(params)? {
+            Some(found) => found,
+            None => return Ok(Value::Null),
+        };
+        Ok(json!({
+            "uri": params["textDocument"]["uri"],
+            "range": symbol_range(document, symbol)
+        }))
+    }
+
+    fn completion(&self, params: &Value) -> RequestResult {
+        let document = match self.document(params) {
+            Some(document) => document,
+            None => return Ok(Value::Null),
+        };
+        let items: Vec<Value> = document
+            .symbols
+            .iter()
+            .map(|symbol| {
+                let kind = match symbol.kind {
+                    SymbolKind::Gate | SymbolKind::OpaqueGate => 3,
+                    _ => 6,
+                };
+                let mut item = json!({
+                    "label": symbol.name,
+                    "kind": kind,
+                    "detail": symbol.signature
+                });
+                if let Some(docstring) = &symbol.docstring {
+                    item["documentation"] = json!(docstring.trim());
+                }
+                item
+            })
+            .collect();
+        Ok(json!(items))
+    }
+
+    fn document_symbols(&self, params: &Value) -> RequestResult {
+        let document = match self.document(params) {
+            Some(document) => document,
+            None => return Ok(Value::Null),
+        };
+        let symbols: Vec<Value> = document
+            .symbols
+            .iter()
+            .filter(|symbol| symbol.library.is_none())
+            .map(|symbol| {
+                let kind = match symbol.kind {
+                    SymbolKind::Gate | SymbolKind::OpaqueGate => 12,
+                    _ => 13,
+                };
+                let range = symbol_range(document, symbol);
+                json!({
+                    "name": symbol.name,
+                    "detail": symbol.signature,
+                    "kind": kind,
+                    "range": range,
+                    "selectionRange": range
+                })
+            })
+            .collect();
+        Ok(json!(symbols))
+    }
+
+    fn document(&self, params: &Value) -> Option<&Document> {
+        let uri = params["textDocument"]["uri"].as_str()?;
+        self.documents.get(uri)
+    }
+
+    /// Return the document and the symbol at the position of a request.
+    fn symbol_at(&self, params: &Value) -> Result<Option<(&Document, &Symbol)>, (i64, String)> {
+        let document = match self.document(params) {
+            Some(document) => document,
+            None => return Ok(None),
+        };
+        let position = &params["position"];
+        let (line, character) = match (position["line"].as_u64(), position["character"].as_u64()) {
+            (Some(line), Some(character)) => (line as usize, character as usize),
+            _ => return Err((INVALID_PARAMS, "missing position".into())),
+        };
+        let offset = document.offset(line, character);
+        Ok(document
+            .identifier_at(offset)
+            .and_then(|name| document.symbol(&name))
+            .map(|symbol| (document, symbol)))
+    }
+}
+
+fn publish(uri: &str, diagnostics: Vec<Value>) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": { "uri": uri, "diagnostics": diagnostics }
+    })
+}
+
+fn symbol_range(document: &Document, symbol: &Symbol) -> Value {
+    let (start, end): (Location, Location) = symbol.boundaries;
+    range(document.position(start), document.position(end))
+}
+
+fn range(start: (usize, usize), end: (usize, usize)) -> Value {
+    json!({
+        "start": { "line": start.0, "character": start.1 },
+        "end": { "line": end.0, "character": end.1 }
+    })
+}
@@ -1,40 +1,251 @@
+mod opaque;
 mod options;
 mod output;
 
+use std::env;
 use std::fs;
 use std::io::{self, Read};
 use std::path::PathBuf;
 
 use structopt::StructOpt;
 
-use qasmsim::Execution;
+use qasmsim::error::{Diagnostic, ErrorStage};
+use qasmsim::lint::Warning;
+use qasmsim::{Equivalence, Execution, LanguageOptions, OpaqueGates, QasmSimError, Stats, Unitary};
+
+use crate::options::{Command, ErrorFormat};
+
+/// Exit code for errors without a stage, such as unknown errors.
+const EXIT_UNKNOWN_ERROR: i32 = 1;
+/// Exit code when the file with the opaque gates cannot be loaded.
+const EXIT_OPAQUE_GATES_NOT_LOADED: i32 = 6;
+/// Exit code when `equiv` finds the programs are not equivalent.
+const EXIT_NOT_EQUIVALENT: i32 = 7;
+/// Exit code when `fmt --check` finds the program is not formatted.
+const EXIT_NOT_FORMATTED: i32 = 8;
 
 fn main() -> io::Result<()> {
     let options = options::Options::from_args();
+    if let Some(command) = &options.command {
+        return run_command(command, &options);
+    }
     let source = source(&options.source)?;
-    if let Some(gate_name) = options.info {
-        match qasmsim::get_gate_info(&source, &gate_name) {
-            Ok((docstring, (name, real_params, quantum_params))) => {
-                print_info(&docstring, &name, &real_params, &quantum_params)
-                    .expect("print gate info")
-            }
-            Err(error) => {
-                eprintln!("{}", error);
-                std::process::exit(1);
+    if let Some(gate_name) = &options.info {
+        let language_options = language_options(&options);
+        let info = qasmsim::get_gate_info(&source, gate_name, &language_options).and_then(
+            |(docstring, _)| {
+                qasmsim::get_gate_definition(&source, gate_name, &language_options)
+                    .map(|definition| (docstring, definition))
+            },
+        );
+        match info {
+            Ok((docstring, definition)) => {
+                print_info(&docstring, &definition).expect("print gate info")
             }
+            Err(error) => fail(&[error], &options),
+        }
+    } else if options.unitary {
+        match qasmsim::extract_unitary(&source, &language_options(&options)) {
+            Ok(unitary) => print_unitary(&unitary, &options).expect("print unitary"),
+            Err(error) => fail(&[error], &options),
+        }
+    } else if options.stats {
+        match qasmsim::get_stats(&source, &language_options(&options)) {
+            Ok(stats) => print_stats(&stats, &options),
+            Err(error) => fail(&[error], &options),
+        }
+    } else if options.check {
+        let errors = qasmsim::check(
+            &source,
+            &language_options(&options),
+            &opaque_gates(&options),
+        );
+        if !errors.is_empty() {
+            fail(&errors, &options);
         }
     } else {
-        match qasmsim::run(&source, options.shots) {
+        match qasmsim::run_with_options(
+            &source,
+            options.shots,
+            &language_options(&options),
+            &opaque_gates(&options),
+        ) {
             Ok(result) => print_result(&result, &options).expect("print result"),
-            Err(error) => {
-                eprintln!("{}", error);
-                std::process::exit(1);
+            Err(error) => fail(&[error], &options),
+        }
+    }
+    Ok(())
+}
+
+fn opaque_gates(options: &options::Options) -> OpaqueGates {
+    match &options.opaque {
+        None => OpaqueGates::new(),
+        Some(path) => opaque::load(path).unwrap_or_else(|error| {
+            eprintln!("error: {}", error);
+            std::process::exit(EXIT_OPAQUE_GATES_NOT_LOADED);
+        }),
+    }
+}
+
+fn language_options(options: &options::Options) -> LanguageOptions {
+    let base_options = if options.strict {
+        LanguageOptions::strict()
+    } else {
+        LanguageOptions::new()
+    };
+    options
+        .allow
+        .iter()
+        .fold(base_options, |language_options, extension| {
+            language_options.allow(*extension)
+        })
+}
+
+fn run_command(command: &Command, options: &options::Options) -> io::Result<()> {
+    match command {
+        Command::Equiv { left, right } => {
+            let left = fs::read_to_string(left)?;
+            let right = fs::read_to_string(right)?;
+            match qasmsim::check_equivalence(&left, &right, &language_options(options)) {
+                Ok(equivalence) => {
+                    print_equivalence(&equivalence).expect("print equivalence");
+                    if !equivalence.is_equivalent() {
+                        std::process::exit(EXIT_NOT_EQUIVALENT);
+                    }
+                }
+                Err(error) => fail(&[error], options),
+            }
+        }
+        Command::Lint { source: path } => {
+            let source = source(path)?;
+            match qasmsim::lint(&source, &language_options(options)) {
+                Ok(warnings) => print_warnings(&warnings, options),
+                Err(error) => fail(&[error], options),
+            }
+        }
+        Command::Fmt {
+            source: path,
+            check,
+        } => {
+            let source = source(path)?;
+            match qasmsim::format_source(&source, &language_options(options)) {
+                Ok(formatted) if *check => {
+                    if formatted != source {
+                        let name = path
+                            .as_ref()
+                            .map_or("<stdin>".into(), |path| path.display().to_string());
+                        eprintln!("{} is not formatted", name);
+                        std::process::exit(EXIT_NOT_FORMATTED);
+                    }
+                }
+                Ok(formatted) => print!("{}", formatted),
+                Err(error) => fail(&[error], options),
+            }
+        }
+        Command::Flatten {
+            source: path,
+            basis,
+        } => {
+            let source = source(path)?;
+            match qasmsim::flatten(&source, *basis, &language_options(options)) {
+                Ok(flattened) => print!("{}", flattened),
+                Err(error) => fail(&[error], options),
+            }
+        }
+        Command::Draw {
+            source: path,
+            expand,
+            wrap,
+            width,
+        } => {
+            let source = source(path)?;
+            let width = width.or_else(|| if *wrap { Some(terminal_width()) } else { None });
+            match qasmsim::draw(&source, *expand, width, &language_options(options)) {
+                Ok(diagram) => print!("{}", diagram),
+                Err(error) => fail(&[error], options),
             }
         }
     }
     Ok(())
 }
 
+/// Return the path of the program file, either given to the subcommand or
+/// to the simulator.
+fn source_path(options: &options::Options) -> Option<&PathBuf> {
+    match &options.command {
+        Some(Command::Lint { source })
+        | Some(Command::Fmt { source, .. })
+        | Some(Command::Flatten { source, .. })
+        | Some(Command::Draw { source, .. }) => source.as_ref(),
+        Some(_) => None,
+        None => options.source.as_ref(),
+    }
+}
+
+/// Return the width of the terminal, as given by `COLUMNS`, or 80.
+fn terminal_width() -> usize {
+    env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.parse().ok())
+        .unwrap_or(80)
+}
+
+fn print_warnings(warnings: &[Warning], options: &options::Options) {
+    match options.error_format {
+        ErrorFormat::Human => {
+            for warning in warnings {
+                eprintln!("{}", warning);
+            }
+        }
+        ErrorFormat::Json => {
+            let file = source_path(options).map(|path| path.display().to_string());
+            let diagnostics: Vec<_> = warnings
+                .iter()
+                .map(|warning| Diagnostic {
+                    file: file.clone(),
+                    ..warning.diagnostic()
+                })
+                .collect();
+            let stderr = io::stderr();
+            output::json::print_diagnostics(&mut stderr.lock(), &diagnostics);
+        }
+    }
+}
+
+/// Print the errors in the format chosen in the options and exit with a code
+/// telling the earliest stage at which they were found.
+fn fail(errors: &[QasmSimError], options: &options::Options) -> ! {
+    match options.error_format {
+        ErrorFormat::Human => {
+            for error in errors {
+                eprintln!("{}", error);
+            }
+        }
+        ErrorFormat::Json => {
+            let file = source_path(options).map(|path| path.display().to_string());
+            let diagnostics: Vec<_> = errors
+                .iter()
+                .flat_map(QasmSimError::diagnostics)
+                .map(|mut diagnostic| {
+                    diagnostic.file = file.clone();
+                    diagnostic
+                })
+                .collect();
+            let stderr = io::stderr();
+            output::json::print_diagnostics(&mut stderr.lock(), &diagnostics);
+        }
+    }
+    let stage = errors.iter().filter_map(QasmSimError::stage).min();
+    let code = match stage {
+        None => EXIT_UNKNOWN_ERROR,
+        Some(ErrorStage::Parse) => 2,
+        Some(ErrorStage::Link) => 3,
+        Some(ErrorStage::Semantic) => 4,
+        Some(ErrorStage::Runtime) => 5,
+    };
+    std::process::exit(code);
+}
+
 fn source(source: &Option<PathBuf>) -> io::Result<String> {
     if let Some(path) = source {
         fs::read_to_string(path)
@@ -45,27 +256,68 @@ fn source(source: &Option<PathBuf>) -> io::Result<String> {
     }
 }
 
-fn print_info(
-    docstring: &str,
-    name: &str,
-    real_params: &[String],
-    quantum_params: &[String],
-) -> io::Result<()> {
-    println!(
-        "gate {}{} {}",
-        name,
-        match real_params.len() {
-            0 => String::from(""),
-            _ => format!("({})", real_params.join(", ")),
-        },
-        quantum_params.join(" ")
-    );
-    println!("{}", docstring);
+/// Print the definition of a gate preceded by its documentation, as in the
+/// source but with the parameters of the body simplified.
+fn print_info(docstring: &str, definition: &str) -> io::Result<()> {
+    for line in docstring.lines() {
+        println!("//{}", line);
+    }
+    println!("{}", definition);
+    Ok(())
+}
+
+fn print_equivalence(equivalence: &Equivalence) -> io::Result<()> {
+    match equivalence {
+        Equivalence::Equivalent => println!("The programs are equivalent."),
+        Equivalence::DifferentWidths(left, right) => println!(
+            "The programs are not equivalent: they act on {} and {} qubits.",
+            left, right
+        ),
+        Equivalence::NotEquivalent { witness, fidelity } => {
+            println!("The programs are not equivalent.");
+            println!("Output fidelity: {:.6}", fidelity);
+            println!("Witness input state: {}", witness.ket());
+        }
+    }
+    Ok(())
+}
+
+fn print_unitary(unitary: &Unitary, options: &options::Options) -> io::Result<()> {
+    match &options.out {
+        None => {
+            let stdout = io::stdout();
+            let mut handle = io::BufWriter::new(stdout.lock());
+            if options.json {
+                output::json::print_unitary(&mut handle, unitary);
+            } else {
+                output::tabular::print_unitary(&mut handle, unitary);
+            }
+        }
+        Some(path) => {
+            let mut path = PathBuf::from(path);
+            output::csv::print_unitary(&mut path, unitary);
+        }
+    }
     Ok(())
 }
 
+fn print_stats(stats: &Stats, options: &options::Options) {
+    let stdout = io::stdout();
+    let mut handle = io::BufWriter::new(stdout.lock());
+    if options.json {
+        output::json::print_stats(&mut handle, stats);
+    } else {
+        output::tabular::print_stats(&mut handle, stats);
+    }
+}
+
 fn print_result(result: &Execution, options: &options::Options) -> io::Result<()> {
     match &options.out {
+        None if options.json => {
+            let stdout = io::stdout();
+            let mut handle = io::BufWriter::new(stdout.lock());
+            output::json::print(&mut handle, result, options);
+        }
         None => {
             let stdout = io::stdout();
             let mut handle = io::BufWriter::new(stdout.lock());
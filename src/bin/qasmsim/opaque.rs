@@ -0,0 +1,61 @@
+use std::fs;
+use std::path::Path;
+
+use serde_json::Value;
+
+use qasmsim::statevector::Complex;
+use qasmsim::{MatrixGate, OpaqueGates};
+
+/// Load the implementations of opaque gates from a JSON file mapping gate
+/// names to matrices. A matrix is a list of rows and each entry is either a
+/// real number or a pair `[real, imaginary]`.
+pub fn load(path: &Path) -> Result<OpaqueGates, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|err| format!("cannot read `{}`: {}", path.display(), err))?;
+    let json: Value = serde_json::from_str(&contents)
+        .map_err(|err| format!("cannot parse `{}`: {}", path.display(), err))?;
+    let gates = json
+        .as_object()
+        .ok_or_else(|| format!("`{}` must contain a JSON object", path.display()))?;
+
+    let mut opaque_gates = OpaqueGates::new();
+    for (name, matrix) in gates {
+        let matrix =
+            parse_matrix(matrix).map_err(|err| format!("invalid gate `{}`: {}", name, err))?;
+        let gate =
+            MatrixGate::new(matrix).map_err(|err| format!("invalid gate `{}`: {}", name, err))?;
+        opaque_gates.bind(name, gate);
+    }
+    Ok(opaque_gates)
+}
+
+fn parse_matrix(matrix: &Value) -> Result<Vec<Vec<Complex>>, String> {
+    let rows = matrix
+        .as_array()
+        .ok_or_else(|| String::from("the matrix must be a list of rows"))?;
+    rows.iter()
+        .map(|row| {
+            row.as_array()
+                .ok_or_else(|| String::from("each row must be a list of entries"))?
+                .iter()
+                .map(parse_entry)
+                .collect()
+        })
+        .collect()
+}
+
+fn parse_entry(entry: &Value) -> Result<Complex, String> {
+    if let Some(re) = entry.as_f64() {
+        return Ok(Complex::from(re));
+    }
+    match entry.as_array().map(|pair| pair.as_slice()) {
+        Some([re, im]) => match (re.as_f64(), im.as_f64()) {
+            (Some(re), Some(im)) => Ok(Complex::new(re, im)),
+            _ => Err(format!("invalid entry `{}`", entry)),
+        },
+        _ => Err(format!(
+            "invalid entry `{}`, expected a number or a pair `[real, imaginary]`",
+            entry
+        )),
+    }
+}
@@ -1,13 +1,20 @@
 use std::path::PathBuf;
+use std::str::FromStr;
 
 use structopt::StructOpt;
 
+use qasmsim::statevector::Endianness;
+use qasmsim::{Basis, Extension};
+
 #[derive(Debug, Clone, PartialEq, Eq, StructOpt, Hash)]
 #[structopt(
     name = "qasmsim",
     about = "A QASM interpreter and quantum simulator in Rust."
 )]
 pub struct Options {
+    #[structopt(subcommand)]
+    pub command: Option<Command>,
+
     /// QASM program file, read from stdin if not present.
     #[structopt(parse(from_os_str))]
     pub source: Option<PathBuf>,
@@ -42,6 +49,17 @@ pub struct Options {
     #[structopt(long)]
     pub probabilities: bool,
 
+    /// Prints the state vector of the simulation in ket notation, grouping
+    /// the bits by quantum register. Ignored if shots is set.
+    #[structopt(long)]
+    pub ket: bool,
+
+    /// Order of the bits and registers when labelling the states by quantum
+    /// register, either big (most significant bit and last register first)
+    /// or little.
+    #[structopt(long, default_value = "big", possible_values = &["big", "little"])]
+    pub endianness: Endianness,
+
     /// Prints times measured for parsing and simulating.
     #[structopt(short, long)]
     pub times: bool,
@@ -50,7 +68,150 @@ pub struct Options {
     #[structopt(long)]
     pub shots: Option<usize>,
 
-    /// Show gate-related information.
+    /// Show the documentation and definition of a gate.
     #[structopt(long)]
     pub info: Option<String>,
+
+    /// Prints the unitary matrix implemented by the program instead of
+    /// simulating it. The program cannot contain measurements, resets or
+    /// conditionals.
+    #[structopt(long, conflicts_with = "shots")]
+    pub unitary: bool,
+
+    /// Checks the program for errors without simulating it. Reports all the
+    /// errors found, including those in branches and gates that never run.
+    #[structopt(long, conflicts_with_all = &["shots", "unitary", "info"])]
+    pub check: bool,
+
+    /// Prints metrics of the circuit without simulating it: number of qubits
+    /// and bits, gates after expanding the gate calls, depth, two-qubit gates,
+    /// T-count, measurements and whether the circuit is dynamic.
+    #[structopt(long, conflicts_with_all = &["shots", "unitary", "info", "check"])]
+    pub stats: bool,
+
+    /// JSON file with the unitary matrices implementing the opaque gates of
+    /// the program. It maps each gate name to a list of rows, and each entry
+    /// is either a real number or a pair [real, imaginary].
+    #[structopt(long, parse(from_os_str))]
+    pub opaque: Option<PathBuf>,
+
+    /// Rejects anything outside the OPENQASM 2.0 specification. Extensions
+    /// can be enabled one by one with --allow.
+    #[structopt(long)]
+    pub strict: bool,
+
+    /// Enables an extension to OPENQASM 2.0. Can be repeated.
+    #[structopt(
+        long,
+        value_name = "extension",
+        number_of_values = 1,
+        possible_values = &[
+            "extended-conditionals",
+            "gate-modifiers",
+            "embedded-libraries",
+            "forward-references",
+            "openqasm3",
+            "extended-math"
+        ]
+    )]
+    pub allow: Vec<Extension>,
+
+    /// Prints the output in JSON format.
+    #[structopt(long, conflicts_with = "out")]
+    pub json: bool,
+
+    /// Format of the errors, either human (with the context of the error in
+    /// the source) or json (one JSON object per line and error).
+    #[structopt(long, default_value = "human", possible_values = &["human", "json"])]
+    pub error_format: ErrorFormat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorFormat {
+    Human,
+    Json,
+}
+
+impl FromStr for ErrorFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(ErrorFormat::Human),
+            "json" => Ok(ErrorFormat::Json),
+            _ => Err(format!(
+                "unknown error format `{}`, expected `human` or `json`",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, StructOpt, Hash)]
+pub enum Command {
+    /// Check if two programs implement the same unitary up to a global phase.
+    /// Exits with 7 if they do not.
+    Equiv {
+        /// QASM program file to compare.
+        #[structopt(parse(from_os_str))]
+        left: PathBuf,
+
+        /// QASM program file to compare with.
+        #[structopt(parse(from_os_str))]
+        right: PathBuf,
+    },
+
+    /// Report warnings about suspicious constructions in a program. Language
+    /// options such as --strict go before the subcommand.
+    Lint {
+        /// QASM program file, read from stdin if not present.
+        #[structopt(parse(from_os_str))]
+        source: Option<PathBuf>,
+    },
+
+    /// Print an OPENQASM 2.0 program formatted in the canonical layout,
+    /// keeping its comments. Language options go before the subcommand.
+    Fmt {
+        /// QASM program file, read from stdin if not present.
+        #[structopt(parse(from_os_str))]
+        source: Option<PathBuf>,
+
+        /// Prints nothing but exits with 8 if the program is not formatted.
+        #[structopt(long)]
+        check: bool,
+    },
+
+    /// Print an OPENQASM 2.0 program with all its gates inlined and written
+    /// in a basis, one statement per qubit. Language options go before the
+    /// subcommand.
+    Flatten {
+        /// QASM program file, read from stdin if not present.
+        #[structopt(parse(from_os_str))]
+        source: Option<PathBuf>,
+
+        /// Gates of the flattened program, up to a global phase.
+        #[structopt(long, default_value = "U,CX", possible_values = &["U,CX", "u3,cx", "rz,sx,x,cx"])]
+        basis: Basis,
+    },
+
+    /// Print a text circuit diagram of an OPENQASM 2.0 program, with one
+    /// wire per qubit and classical bit. Language options go before the
+    /// subcommand.
+    Draw {
+        /// QASM program file, read from stdin if not present.
+        #[structopt(parse(from_os_str))]
+        source: Option<PathBuf>,
+
+        /// Draws the primitive gates instead of the gates called in the program.
+        #[structopt(long)]
+        expand: bool,
+
+        /// Wraps the diagram at the width of the terminal.
+        #[structopt(long)]
+        wrap: bool,
+
+        /// Wraps the diagram at this number of columns.
+        #[structopt(long, value_name = "columns")]
+        width: Option<usize>,
+    },
 }
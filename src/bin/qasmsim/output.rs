@@ -1,2 +1,27 @@
+use qasmsim::statevector::{Endianness, QuantumRegister};
+
 pub mod csv;
+pub mod json;
 pub mod tabular;
+
+/// Return the label of the basis state `index` as the bitstrings of each
+/// quantum register, e.g. `b=1 a=01`. Registers go in the same order as in
+/// the ket notation, given by `endianness`.
+pub fn register_label(
+    index: usize,
+    registers: &[QuantumRegister],
+    endianness: Endianness,
+) -> String {
+    let labels: Vec<String> = endianness
+        .sort_registers(registers)
+        .iter()
+        .map(|register| {
+            format!(
+                "{}={}",
+                register.name(),
+                register.bitstring_of(index, endianness)
+            )
+        })
+        .collect();
+    labels.join(" ")
+}
@@ -3,10 +3,11 @@ use std::io::{self, Write};
 use std::iter::FromIterator;
 use std::path::PathBuf;
 
-use qasmsim::statevector::StateVector;
-use qasmsim::{Execution, ExecutionTimes, Histogram};
+use qasmsim::statevector::{QuantumRegister, StateVector};
+use qasmsim::{Execution, ExecutionTimes, Histogram, Unitary};
 
 use crate::options::Options;
+use crate::output::register_label;
 
 pub fn print(path: &mut PathBuf, result: &Execution, options: &Options) {
     // TODO: Add error handling for path operations.
@@ -39,6 +40,7 @@ pub fn print(path: &mut PathBuf, result: &Execution, options: &Options) {
             writer_ref,
             result.statevector(),
             result.probabilities(),
+            result.quantum_registers(),
             options,
         )
         .expect("writes");
@@ -52,6 +54,37 @@ pub fn print(path: &mut PathBuf, result: &Execution, options: &Options) {
     }
 }
 
+pub fn print_unitary(path: &mut PathBuf, unitary: &Unitary) {
+    let prefix = path
+        .file_name()
+        .expect("a valid file name")
+        .to_str()
+        .expect("a valid name for the filename")
+        .to_owned();
+
+    path.set_file_name(format!("{}.unitary.csv", prefix));
+    let mut writer = csv::Writer::from_path(&path).expect("can open the file");
+    do_print_unitary(&mut writer, unitary).expect("writes");
+}
+
+fn do_print_unitary<W>(writer: &mut csv::Writer<W>, unitary: &Unitary) -> io::Result<()>
+where
+    W: Write,
+{
+    writer.write_record(["Row", "Column", "Real", "Imaginary"])?;
+    for (row_idx, row) in unitary.rows().iter().enumerate() {
+        for (column_idx, entry) in row.iter().enumerate() {
+            writer.write_record(&[
+                format!("{}", row_idx),
+                format!("{}", column_idx),
+                format!("{:.6}", entry.re),
+                format!("{:.6}", entry.im),
+            ])?;
+        }
+    }
+    Ok(())
+}
+
 fn print_memory<W>(
     writer: &mut csv::Writer<W>,
     memory: &HashMap<String, u64>,
@@ -137,6 +170,7 @@ fn print_state<W>(
     writer: &mut csv::Writer<W>,
     statevector: &StateVector,
     probabilities: &[f64],
+    registers: &[QuantumRegister],
     options: &Options,
 ) -> io::Result<()>
 where
@@ -148,6 +182,9 @@ where
     );
 
     let mut titles = vec!["Base"];
+    if !registers.is_empty() {
+        titles.push("Registers");
+    }
     if options.statevector {
         titles.push("Real");
         titles.push("Imaginary");
@@ -164,6 +201,9 @@ where
         .enumerate();
     for (idx, (amplitude, probability)) in amplitudes_and_probabilities {
         let mut record = vec![format!("{}", idx)];
+        if !registers.is_empty() {
+            record.push(register_label(idx, registers, options.endianness));
+        }
         if options.statevector {
             record.push(format!("{:.6}", amplitude.re));
             record.push(format!("{:.6}", amplitude.im));
@@ -181,7 +221,7 @@ fn print_times<W>(writer: &mut csv::Writer<W>, times: &ExecutionTimes) -> io::Re
 where
     W: Write,
 {
-    writer.write_record(&["Name", "Duration (ms)"])?;
+    writer.write_record(["Name", "Duration (ms)"])?;
     writer.serialize(("parsing", times.parsing_time()))?;
     writer.serialize(("simulation", times.parsing_time()))?;
     Ok(())
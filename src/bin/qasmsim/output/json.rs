@@ -0,0 +1,76 @@
+use std::io::Write;
+
+use serde_json::{json, Map, Value};
+
+use qasmsim::error::Diagnostic;
+use qasmsim::{Execution, Stats, Unitary};
+
+use crate::options::Options;
+
+pub fn print<W>(buffer: &mut W, result: &Execution, options: &Options)
+where
+    W: Write,
+{
+    let mut output = Map::new();
+    if options.shots.is_some() {
+        output.insert("histogram".into(), json!(result.histogram()));
+    } else {
+        output.insert("memory".into(), json!(result.memory()));
+    }
+
+    if options.shots.is_none() {
+        if options.statevector {
+            output.insert("statevector".into(), json!(result.statevector()));
+        }
+        if options.probabilities {
+            output.insert("probabilities".into(), json!(result.probabilities()));
+        }
+        if options.ket {
+            let ket = result
+                .statevector()
+                .ket()
+                .with_registers(result.quantum_registers())
+                .with_endianness(options.endianness);
+            output.insert("ket".into(), json!(ket.to_string()));
+        }
+    }
+
+    if options.times {
+        output.insert("times".into(), json!(result.times()));
+    }
+
+    write_value(buffer, &Value::Object(output));
+}
+
+pub fn print_unitary<W>(buffer: &mut W, unitary: &Unitary)
+where
+    W: Write,
+{
+    write_value(buffer, &json!(unitary));
+}
+
+pub fn print_stats<W>(buffer: &mut W, stats: &Stats)
+where
+    W: Write,
+{
+    write_value(buffer, &json!(stats));
+}
+
+/// Write the diagnostics as JSON lines, one object per line.
+pub fn print_diagnostics<W>(buffer: &mut W, diagnostics: &[Diagnostic])
+where
+    W: Write,
+{
+    for diagnostic in diagnostics {
+        serde_json::to_writer(&mut *buffer, diagnostic).expect("writes in stderr");
+        writeln!(buffer).expect("writes in stderr");
+    }
+}
+
+fn write_value<W>(buffer: &mut W, value: &Value)
+where
+    W: Write,
+{
+    serde_json::to_writer_pretty(&mut *buffer, value).expect("writes in stdout");
+    writeln!(buffer).expect("writes in stdout");
+}
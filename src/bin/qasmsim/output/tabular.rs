@@ -4,10 +4,11 @@ use std::iter::FromIterator;
 
 use prettytable::{cell, format, row, Table};
 
-use qasmsim::statevector::StateVector;
-use qasmsim::{Execution, ExecutionTimes, Histogram};
+use qasmsim::statevector::{QuantumRegister, StateVector};
+use qasmsim::{Execution, ExecutionTimes, Histogram, Stats, Unitary};
 
 use crate::options::Options;
+use crate::output::register_label;
 
 /// Writes the `msg` in the `buffer` if `options.verbose` is greater than 0.
 macro_rules! vvprint {
@@ -67,11 +68,26 @@ where
             buffer,
             result.statevector(),
             result.probabilities(),
+            result.quantum_registers(),
             options,
         )?;
         vvprintln!(options, buffer)?;
     }
 
+    if options.ket && options.shots.is_none() {
+        vvprintln!(options, buffer, "Simulation state (ket notation):")?;
+        writeln!(
+            buffer,
+            "{}",
+            result
+                .statevector()
+                .ket()
+                .with_registers(result.quantum_registers())
+                .with_endianness(options.endianness)
+        )?;
+        vvprintln!(options, buffer)?;
+    }
+
     if options.times {
         vvprintln!(options, buffer, "Times:")?;
         print_times(buffer, result.times())?;
@@ -80,6 +96,76 @@ where
     Ok(())
 }
 
+pub fn print_unitary<W>(buffer: &mut W, unitary: &Unitary)
+where
+    W: Write,
+{
+    do_print_unitary(buffer, unitary).expect("writes in stdout");
+}
+
+fn do_print_unitary<W>(buffer: &mut W, unitary: &Unitary) -> io::Result<()>
+where
+    W: Write,
+{
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+
+    let width = unitary.qubit_width();
+    let mut titles = row![c -> ""];
+    for column_idx in 0..unitary.dimension() {
+        titles.add_cell(cell!(c -> format!("{:0width$b}", column_idx, width = width)));
+    }
+    table.set_titles(titles);
+
+    for (row_idx, row) in unitary.rows().iter().enumerate() {
+        let mut table_row = row![format!("{:0width$b}", row_idx, width = width)];
+        for entry in row {
+            table_row.add_cell(cell!(r -> format!("{:.6}{:+.6}i", entry.re, entry.im)));
+        }
+        table.add_row(table_row);
+    }
+
+    write!(buffer, "{}", table)
+}
+
+pub fn print_stats<W>(buffer: &mut W, stats: &Stats)
+where
+    W: Write,
+{
+    do_print_stats(buffer, stats).expect("writes in stdout");
+}
+
+fn do_print_stats<W>(buffer: &mut W, stats: &Stats) -> io::Result<()>
+where
+    W: Write,
+{
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+
+    table.set_titles(row!["Metric", "Value"]);
+    table.add_row(row!["qubits", r -> stats.qubits]);
+    table.add_row(row!["clbits", r -> stats.clbits]);
+    table.add_row(row!["depth", r -> stats.depth]);
+    table.add_row(row!["top-level depth", r -> stats.top_level_depth]);
+    table.add_row(row!["two-qubit gates", r -> stats.two_qubit_gates]);
+    table.add_row(row!["T-count", r -> stats.t_count]);
+    table.add_row(row!["measurements", r -> stats.measurements]);
+    table.add_row(row!["dynamic", r -> if stats.dynamic { "yes" } else { "no" }]);
+    write!(buffer, "{}", table)?;
+
+    if !stats.gate_counts.is_empty() {
+        let mut table = Table::new();
+        table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+        table.set_titles(row!["Gate", "Count"]);
+        for (name, count) in &stats.gate_counts {
+            table.add_row(row![name, r -> count]);
+        }
+        writeln!(buffer)?;
+        write!(buffer, "{}", table)?;
+    }
+    Ok(())
+}
+
 fn print_memory<W>(
     buffer: &mut W,
     memory: &HashMap<String, u64>,
@@ -164,6 +250,7 @@ fn print_state<W>(
     buffer: &mut W,
     statevector: &StateVector,
     probabilities: &[f64],
+    registers: &[QuantumRegister],
     options: &Options,
 ) -> io::Result<()>
 where
@@ -178,6 +265,9 @@ where
     table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
 
     let mut titles = row![c -> "Base"];
+    if !registers.is_empty() {
+        titles.add_cell(cell!(c -> "Registers"));
+    }
     if options.statevector {
         titles.add_cell(cell!(c -> "Real"));
         titles.add_cell(cell!(c -> "Imaginary"));
@@ -194,6 +284,9 @@ where
         .enumerate();
     for (idx, (amplitude, probability)) in amplitudes_and_probabilities {
         let mut row = row![idx];
+        if !registers.is_empty() {
+            row.add_cell(cell!(register_label(idx, registers, options.endianness)));
+        }
         if options.statevector {
             row.add_cell(cell!(format!("{:.6}", amplitude.re)));
             row.add_cell(cell!(format!("{:.6}", amplitude.im)));
@@ -3,6 +3,7 @@
 
 #[macro_use]
 pub(crate) mod humanize;
+mod diagnostic;
 
 use std::convert;
 use std::error;
@@ -11,8 +12,10 @@ use std::fmt;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+pub use self::diagnostic::{Diagnostic, ErrorStage, Severity};
 use self::humanize::humanize_error;
-use crate::grammar::lexer::{self, Location, Tok};
+use crate::grammar::lexer::{self, Lexer, Location, Tok};
+pub use crate::grammar::{Extension, ExtensionError, LoweringError};
 pub use crate::interpreter::runtime::RuntimeError;
 pub use crate::linker::LinkerError;
 use crate::semantics::QasmType;
@@ -30,7 +33,8 @@ pub type SrcAndErr<'src, E> = (&'src str, E);
 /// `QasmSimError` instances can be printed. They refer to the source code and
 /// try to provide contextual information for fixing the problem.
 ///
-/// Conversion between [`ParseError`], [`RuntimeError`] and [`LinkerError`] is
+/// Conversion between [`ParseError`], [`RuntimeError`], [`LinkerError`],
+/// [`LoweringError`] and [`ExtensionError`] is
 /// possible thanks to the trait `From` is defined for the pair
 /// `(&'source str, T)` (see alias [`SrcAndErr`]) for all the errors listed
 /// above.
@@ -62,6 +66,8 @@ pub type SrcAndErr<'src, E> = (&'src str, E);
 /// [`ParseError`]: ./type.ParseError.html
 /// [`RuntimeError`]: ./enum.RuntimeError.html
 /// [`LinkerError`]: ../linker/enum.LinkerError.html
+/// [`LoweringError`]: ../grammar/enum.LoweringError.html
+/// [`ExtensionError`]: ../grammar/enum.ExtensionError.html
 /// [`SrcAndErr`]: ./type.SrcAndErr.html
 /// [`simulate()`]: ../fn.simulate.html
 /// [`map_err`]: ../../std/result/enum.Result.html#method.map_err
@@ -86,6 +92,17 @@ pub enum QasmSimError<'src> {
         /// A list of expected tokens.
         expected: Vec<String>,
     },
+    /// Found a gate modifier other than `inv`, `ctrl(n)` or `pow(k)`.
+    InvalidModifier {
+        /// Line source.
+        source: &'src str,
+        /// Line number.
+        lineno: usize,
+        /// Position inside the line (0-based) where the modifier starts.
+        startpos: usize,
+        /// The modifier, as written.
+        modifier: String,
+    },
     /// Found an unexpected end of file.
     UnexpectedEOF {
         /// Line source.
@@ -138,10 +155,17 @@ pub enum QasmSimError<'src> {
     },
     /// Use of register index that does not fit the register size.
     IndexOutOfBounds {
-        /// Line source.
+        /// Source of the lines from the start of the statement to the line
+        /// of the error.
         source: &'src str,
         /// Line number.
         lineno: usize,
+        /// Position inside the line (0-based) where the offending argument
+        /// or parameter starts.
+        startpos: usize,
+        /// Position inside the line (0-based) where the offending argument
+        /// or parameter ends.
+        endpos: Option<usize>,
         /// Name of the register being indexed.
         symbol_name: String,
         /// Index tried to access.
@@ -151,10 +175,17 @@ pub enum QasmSimError<'src> {
     },
     /// Use of an unknown/undeclared symbol.
     SymbolNotFound {
-        /// Line source.
+        /// Source of the lines from the start of the statement to the line
+        /// of the error.
         source: &'src str,
         /// Line number.
         lineno: usize,
+        /// Position inside the line (0-based) where the offending argument
+        /// or parameter starts.
+        startpos: usize,
+        /// Position inside the line (0-based) where the offending argument
+        /// or parameter ends.
+        endpos: Option<usize>,
         /// Name of the unknown symbol.
         symbol_name: String,
         /// The expected type.
@@ -163,10 +194,17 @@ pub enum QasmSimError<'src> {
     /// The attempt of applying an operation passing the wrong number of
     /// parameters.
     WrongNumberOfParameters {
-        /// Line source.
+        /// Source of the lines from the start of the statement to the line
+        /// of the error.
         source: &'src str,
         /// Line number.
         lineno: usize,
+        /// Position inside the line (0-based) where the offending argument
+        /// or parameter starts.
+        startpos: usize,
+        /// Position inside the line (0-based) where the offending argument
+        /// or parameter ends.
+        endpos: Option<usize>,
         /// Name of the operation.
         symbol_name: String,
         /// Indicate if the parameters are registers or real values.
@@ -185,12 +223,28 @@ pub enum QasmSimError<'src> {
         /// Name of the unknown gate.
         symbol_name: String,
     },
+    /// Call to an opaque gate with no native implementation.
+    UnboundOpaqueGate {
+        /// Line source.
+        source: &'src str,
+        /// Line number.
+        lineno: usize,
+        /// Name of the opaque gate.
+        symbol_name: String,
+    },
     /// Found an unexpected type of value.
     TypeMismatch {
-        /// Line source.
+        /// Source of the lines from the start of the statement to the line
+        /// of the error.
         source: &'src str,
         /// Line number.
         lineno: usize,
+        /// Position inside the line (0-based) where the offending argument
+        /// or parameter starts.
+        startpos: usize,
+        /// Position inside the line (0-based) where the offending argument
+        /// or parameter ends.
+        endpos: Option<usize>,
         /// Name of the symbol with the incorrect type.
         symbol_name: String,
         /// Expected type.
@@ -198,15 +252,141 @@ pub enum QasmSimError<'src> {
     },
     /// Attempt of applying an operation to different sizes registers.
     RegisterSizeMismatch {
-        /// Line source.
+        /// Source of the lines from the start of the statement to the line
+        /// of the error.
         source: &'src str,
         /// Line number.
         lineno: usize,
+        /// Position inside the line (0-based) where the offending argument
+        /// or parameter starts.
+        startpos: usize,
+        /// Position inside the line (0-based) where the offending argument
+        /// or parameter ends.
+        endpos: Option<usize>,
         /// Name of the operation.
         symbol_name: String,
         /// Sizes of the different registers involved.
         sizes: Vec<usize>,
     },
+    /// Use of the same qubit more than once in the same gate call.
+    RepeatedQubit {
+        /// Source of the lines from the start of the statement to the line
+        /// of the error.
+        source: &'src str,
+        /// Line number.
+        lineno: usize,
+        /// Position inside the line (0-based) where the arguments start.
+        startpos: usize,
+        /// Position inside the line (0-based) where the arguments end.
+        endpos: Option<usize>,
+        /// Name of the gate.
+        symbol_name: String,
+        /// The repeated qubit.
+        qubit: String,
+    },
+    /// Use of gate modifiers on an opaque gate.
+    ModifiedOpaqueGate {
+        /// Line source.
+        source: &'src str,
+        /// Line number.
+        lineno: usize,
+        /// Name of the opaque gate.
+        symbol_name: String,
+    },
+    /// Use of the `pow` modifier with a non-integer exponent.
+    NonIntegerPower {
+        /// Line source.
+        source: &'src str,
+        /// Line number.
+        lineno: usize,
+        /// Name of the modified gate.
+        symbol_name: String,
+        /// The value of the exponent.
+        exponent: String,
+    },
+    /// Use of the `pow` modifier with an exponent expanding into too many
+    /// gates.
+    PowerTooLarge {
+        /// Line source.
+        source: &'src str,
+        /// Line number.
+        lineno: usize,
+        /// Name of the modified gate.
+        symbol_name: String,
+        /// The value of the exponent.
+        exponent: String,
+    },
+    /// Use of an OPENQASM 3 feature not supported by the simulator.
+    UnsupportedFeature {
+        /// Line source.
+        source: &'src str,
+        /// Line number.
+        lineno: usize,
+        /// Position inside the line (0-based) where the feature starts.
+        startpos: usize,
+        /// Description of the feature.
+        feature: String,
+    },
+    /// Found a value that should be an integer but is not.
+    InvalidInteger {
+        /// Line source.
+        source: &'src str,
+        /// Line number.
+        lineno: usize,
+        /// The value found.
+        value: String,
+        /// Indicate if the value is required to be non-negative.
+        non_negative: bool,
+    },
+    /// Found a construct requiring an extension to OPENQASM 2.0 which is not
+    /// enabled.
+    DisabledExtension {
+        /// Line source.
+        source: &'src str,
+        /// Line number.
+        lineno: usize,
+        /// Position inside the line (0-based) where the construct starts.
+        startpos: usize,
+        /// The extension required by the construct.
+        extension: Extension,
+    },
+    /// Found a non-unitary operation where only unitary operations are
+    /// allowed.
+    NonUnitaryOperation {
+        /// Line source.
+        source: &'src str,
+        /// Line number.
+        lineno: usize,
+        /// Name of the operation: `measure`, `reset` or `if`.
+        symbol_name: String,
+    },
+    /// A gate parameter evaluating to an infinite or undefined value.
+    InvalidParameter {
+        /// Line source.
+        source: &'src str,
+        /// Line number.
+        lineno: usize,
+        /// Position inside the line (0-based) where the parameter starts.
+        startpos: usize,
+        /// Position inside the line (0-based) where the parameter ends, if
+        /// the parameter appears in the line.
+        endpos: Option<usize>,
+        /// The expression of the parameter.
+        expression: String,
+        /// The value found while evaluating the parameter.
+        value: String,
+    },
+    /// An expression evaluating to an infinite or undefined value.
+    NonFiniteValue {
+        /// Line source.
+        source: &'src str,
+        /// Line number.
+        lineno: usize,
+        /// The failing operation, written with the values of its operands.
+        operation: String,
+    },
+    /// Several errors found at once, in the order they appear in the source.
+    MultipleErrors(Vec<QasmSimError<'src>>),
 }
 
 impl fmt::Display for QasmSimError<'_> {
@@ -251,6 +431,18 @@ impl<'src> From<SrcAndErr<'src, ParseError>> for QasmSimError<'src> {
                     expected,
                 }
             }
+            ParseError::UnrecognizedToken {
+                token: (location, Tok::InvalidModifier { repr }, _),
+                ..
+            } => {
+                let (source, lineno, startpos, _) = extract_line(location.0, None, input);
+                QasmSimError::InvalidModifier {
+                    source,
+                    lineno,
+                    startpos,
+                    modifier: repr,
+                }
+            }
             ParseError::UnrecognizedToken { token, expected } => {
                 let location = token.0;
                 let endlocation = token.2;
@@ -305,22 +497,47 @@ impl<'src> From<SrcAndErr<'src, RuntimeError>> for QasmSimError<'src> {
                 location,
                 symbol_name,
                 sizes,
+                boundaries,
             } => {
-                let (source, lineno, _, _) = extract_line(location.0, None, input);
+                let (source, lineno, startpos, endpos) =
+                    extract_statement_lines(location.0, boundaries, input);
                 QasmSimError::RegisterSizeMismatch {
+                    startpos,
+                    endpos,
                     source,
                     lineno,
                     symbol_name,
                     sizes,
                 }
             }
+            RuntimeError::RepeatedQubit {
+                location,
+                symbol_name,
+                qubit,
+                boundaries,
+            } => {
+                let (source, lineno, startpos, endpos) =
+                    extract_statement_lines(location.0, boundaries, input);
+                QasmSimError::RepeatedQubit {
+                    startpos,
+                    endpos,
+                    source,
+                    lineno,
+                    symbol_name,
+                    qubit,
+                }
+            }
             RuntimeError::TypeMismatch {
                 location,
                 symbol_name,
                 expected,
+                boundaries,
             } => {
-                let (source, lineno, _, _) = extract_line(location.0, None, input);
+                let (source, lineno, startpos, endpos) =
+                    extract_statement_lines(location.0, boundaries, input);
                 QasmSimError::TypeMismatch {
+                    startpos,
+                    endpos,
                     source,
                     lineno,
                     symbol_name,
@@ -338,15 +555,30 @@ impl<'src> From<SrcAndErr<'src, RuntimeError>> for QasmSimError<'src> {
                     symbol_name,
                 }
             }
+            RuntimeError::UnboundOpaqueGate {
+                location,
+                symbol_name,
+            } => {
+                let (source, lineno, _, _) = extract_line(location.0, None, input);
+                QasmSimError::UnboundOpaqueGate {
+                    source,
+                    lineno,
+                    symbol_name,
+                }
+            }
             RuntimeError::WrongNumberOfParameters {
                 are_registers,
                 location,
                 symbol_name,
                 given,
                 expected,
+                boundaries,
             } => {
-                let (source, lineno, _, _) = extract_line(location.0, None, input);
+                let (source, lineno, startpos, endpos) =
+                    extract_statement_lines(location.0, boundaries, input);
                 QasmSimError::WrongNumberOfParameters {
+                    startpos,
+                    endpos,
                     are_registers,
                     source,
                     symbol_name,
@@ -359,9 +591,13 @@ impl<'src> From<SrcAndErr<'src, RuntimeError>> for QasmSimError<'src> {
                 location,
                 symbol_name,
                 expected,
+                boundaries,
             } => {
-                let (source, lineno, _, _) = extract_line(location.0, None, input);
+                let (source, lineno, startpos, endpos) =
+                    extract_statement_lines(location.0, boundaries, input);
                 QasmSimError::SymbolNotFound {
+                    startpos,
+                    endpos,
                     source,
                     symbol_name,
                     lineno,
@@ -373,9 +609,13 @@ impl<'src> From<SrcAndErr<'src, RuntimeError>> for QasmSimError<'src> {
                 symbol_name,
                 index,
                 size,
+                boundaries,
             } => {
-                let (source, lineno, _, _) = extract_line(location.0, None, input);
+                let (source, lineno, startpos, endpos) =
+                    extract_statement_lines(location.0, boundaries, input);
                 QasmSimError::IndexOutOfBounds {
+                    startpos,
+                    endpos,
                     source,
                     symbol_name,
                     lineno,
@@ -383,6 +623,73 @@ impl<'src> From<SrcAndErr<'src, RuntimeError>> for QasmSimError<'src> {
                     index,
                 }
             }
+            RuntimeError::ModifiedOpaqueGate {
+                location,
+                symbol_name,
+            } => {
+                let (source, lineno, _, _) = extract_line(location.0, None, input);
+                QasmSimError::ModifiedOpaqueGate {
+                    source,
+                    lineno,
+                    symbol_name,
+                }
+            }
+            RuntimeError::NonIntegerPower {
+                location,
+                symbol_name,
+                exponent,
+            } => {
+                let (source, lineno, _, _) = extract_line(location.0, None, input);
+                QasmSimError::NonIntegerPower {
+                    source,
+                    lineno,
+                    symbol_name,
+                    exponent,
+                }
+            }
+            RuntimeError::PowerTooLarge {
+                location,
+                symbol_name,
+                exponent,
+            } => {
+                let (source, lineno, _, _) = extract_line(location.0, None, input);
+                QasmSimError::PowerTooLarge {
+                    source,
+                    lineno,
+                    symbol_name,
+                    exponent,
+                }
+            }
+            RuntimeError::NonUnitaryOperation {
+                location,
+                symbol_name,
+            } => {
+                let (source, lineno, _, _) = extract_line(location.0, None, input);
+                QasmSimError::NonUnitaryOperation {
+                    source,
+                    lineno,
+                    symbol_name,
+                }
+            }
+            RuntimeError::InvalidParameter {
+                location,
+                expression,
+                value,
+            } => {
+                let (source, lineno, _, _) = extract_line(location.0, None, input);
+                let (startpos, endpos) = match find_expression(source, &expression) {
+                    Some((startpos, endpos)) => (startpos, Some(endpos)),
+                    None => (0, None),
+                };
+                QasmSimError::InvalidParameter {
+                    source,
+                    lineno,
+                    startpos,
+                    endpos,
+                    expression,
+                    value,
+                }
+            }
             RuntimeError::SemanticError(semantic_error) => match semantic_error {
                 SemanticError::RedefinitionError {
                     symbol_name,
@@ -398,6 +705,16 @@ impl<'src> From<SrcAndErr<'src, RuntimeError>> for QasmSimError<'src> {
                         previous_lineno,
                     }
                 }
+                SemanticError::DisabledExtension {
+                    location,
+                    extension,
+                } => QasmSimError::from((
+                    input,
+                    ExtensionError::DisabledExtension {
+                        location,
+                        extension,
+                    },
+                )),
             },
         }
     }
@@ -415,10 +732,156 @@ impl<'src> From<SrcAndErr<'src, LinkerError>> for QasmSimError<'src> {
                     lineno,
                 }
             }
+            LinkerError::DisabledExtension {
+                location,
+                extension,
+            } => QasmSimError::from((
+                input,
+                ExtensionError::DisabledExtension {
+                    location,
+                    extension,
+                },
+            )),
         }
     }
 }
 
+impl<'src> From<SrcAndErr<'src, LoweringError>> for QasmSimError<'src> {
+    fn from(source_and_error: SrcAndErr<'src, LoweringError>) -> Self {
+        let (input, error) = source_and_error;
+        match error {
+            LoweringError::UnsupportedFeature { location, feature } => {
+                let (source, lineno, startpos, _) = extract_line(location.0, None, input);
+                QasmSimError::UnsupportedFeature {
+                    source,
+                    lineno,
+                    startpos,
+                    feature,
+                }
+            }
+            LoweringError::SymbolNotFound {
+                location,
+                symbol_name,
+            } => {
+                let (source, lineno, startpos, _) = extract_line(location.0, None, input);
+                QasmSimError::SymbolNotFound {
+                    source,
+                    lineno,
+                    startpos,
+                    endpos: None,
+                    symbol_name,
+                    expected: QasmType::RealValue,
+                }
+            }
+            LoweringError::InvalidInteger {
+                location,
+                value,
+                non_negative,
+            } => {
+                let (source, lineno, _, _) = extract_line(location.0, None, input);
+                QasmSimError::InvalidInteger {
+                    source,
+                    lineno,
+                    value,
+                    non_negative,
+                }
+            }
+            LoweringError::NonFiniteValue {
+                location,
+                operation,
+            } => {
+                let (source, lineno, _, _) = extract_line(location.0, None, input);
+                QasmSimError::NonFiniteValue {
+                    source,
+                    lineno,
+                    operation,
+                }
+            }
+        }
+    }
+}
+
+impl<'src> From<SrcAndErr<'src, ExtensionError>> for QasmSimError<'src> {
+    fn from(source_and_error: SrcAndErr<'src, ExtensionError>) -> Self {
+        let (input, error) = source_and_error;
+        match error {
+            ExtensionError::DisabledExtension {
+                location,
+                extension,
+            } => {
+                let (source, lineno, startpos, _) = extract_line(location.0, None, input);
+                QasmSimError::DisabledExtension {
+                    source,
+                    lineno,
+                    startpos,
+                    extension,
+                }
+            }
+        }
+    }
+}
+
+/// Return the start and end positions of `expression` inside `line`,
+/// comparing tokens so that spacing and the notation of numbers do not
+/// matter.
+fn find_expression(line: &str, expression: &str) -> Option<(usize, usize)> {
+    fn tokens(source: &str) -> Vec<(Location, Tok, Location)> {
+        Lexer::new(source).map_while(|token| token.ok()).collect()
+    }
+
+    fn same_token(left: &Tok, right: &Tok) -> bool {
+        match (left, right) {
+            (
+                Tok::Int { repr: left } | Tok::Real { repr: left },
+                Tok::Int { repr: right } | Tok::Real { repr: right },
+            ) => left.parse::<f64>().ok() == right.parse::<f64>().ok(),
+            _ => left == right,
+        }
+    }
+
+    let line_tokens = tokens(line);
+    let expression_tokens = tokens(expression);
+    if expression_tokens.is_empty() {
+        return None;
+    }
+    line_tokens
+        .windows(expression_tokens.len())
+        .find(|window| {
+            window
+                .iter()
+                .zip(&expression_tokens)
+                .all(|((_, left, _), (_, right, _))| same_token(left, right))
+        })
+        .map(|window| (window[0].0 .0, window[window.len() - 1].2 .0))
+}
+
+/// Return the source from the line where the statement at `offset` starts to
+/// the line of `boundaries`, with the number of the latter line and the
+/// positions of the boundaries inside it. Without boundaries, return the line
+/// of the statement and the position where it starts.
+pub(crate) fn extract_statement_lines(
+    offset: usize,
+    boundaries: Option<(Location, Location)>,
+    doc: &str,
+) -> (&str, usize, usize, Option<usize>) {
+    let (start, end) = match boundaries {
+        Some((start, end)) => (start.0, Some(end.0)),
+        None => (offset, None),
+    };
+    let (line, lineno, startpos, endpos) = extract_line(start, end, doc);
+    let line_start = start - startpos;
+    let statement_start = doc[..offset.min(line_start)]
+        .rfind('\n')
+        .map_or(0, |index| index + 1);
+    let endpos = endpos.map(|endpos| endpos.min(line.trim_end_matches('\n').len()));
+    (
+        &doc[statement_start..line_start + line.len()],
+        lineno,
+        startpos,
+        endpos,
+    )
+}
+
 fn extract_line(
     offset: usize,
     endoffset: Option<usize>,
@@ -484,3 +947,25 @@ mod test_into_doc_coords {
       test_last_character: 20, None => ("line 3", 3, 6, None)
     );
 }
+
+#[cfg(test)]
+mod test_find_expression {
+    use super::find_expression;
+
+    #[test]
+    fn test_ignores_spacing_and_number_notation() {
+        assert_eq!(
+            find_expression("u3(0.0, pi/2, 1.0e1) q;", "pi / 2"),
+            Some((8, 12))
+        );
+        assert_eq!(
+            find_expression("u3(0.0, pi/2, 1.0e1) q;", "10"),
+            Some((14, 19))
+        );
+    }
+
+    #[test]
+    fn test_missing_expressions() {
+        assert_eq!(find_expression("rot(0) q[0];", "pi / theta"), None);
+    }
+}
@@ -0,0 +1,267 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::humanize::human_description;
+use crate::error::QasmSimError;
+
+/// The stage of the processing of a program at which an error is found.
+///
+/// The stages are ordered as they happen: a program is parsed, then linked
+/// with its libraries, then checked for semantic errors and, finally, run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum ErrorStage {
+    /// The source is not a well-formed program.
+    Parse,
+    /// A library included by the program cannot be linked.
+    Link,
+    /// The program declares or uses something wrong, such as an undefined
+    /// symbol, an index out of bounds or a disabled extension. These errors
+    /// are found without running the program.
+    Semantic,
+    /// A value computed when expanding or simulating the program is invalid,
+    /// or an opaque gate has no implementation.
+    Runtime,
+}
+
+/// How serious the problem described by a diagnostic is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Severity {
+    /// The program cannot run.
+    Error,
+    /// The program runs but it is probably wrong.
+    Warning,
+}
+
+/// A self-contained description of an error or a warning, intended for other
+/// tools such as editors or continuous integration systems.
+///
+/// Lines and columns are 1-based, and the end column is exclusive.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Diagnostic {
+    /// Name of the [`QasmSimError`] or [`Lint`] variant.
+    ///
+    /// [`QasmSimError`]: ./enum.QasmSimError.html
+    /// [`Lint`]: ../lint/enum.Lint.html
+    pub kind: String,
+    /// Whether the diagnostic is an error or a warning.
+    pub severity: Severity,
+    /// Stage at which the error is found, if known.
+    pub stage: Option<ErrorStage>,
+    /// Description of the error.
+    pub message: String,
+    /// File containing the error. Errors do not know about files, so it is
+    /// up to the caller to fill it in.
+    pub file: Option<String>,
+    /// Line of the error.
+    pub line: Option<usize>,
+    /// Column at which the error starts.
+    pub column: Option<usize>,
+    /// Column at which the error ends, if the error spans a known range.
+    pub end_column: Option<usize>,
+    /// Tokens that would have been valid at the position of a syntax error.
+    pub expected: Vec<String>,
+    /// A suggestion for fixing the error.
+    pub hint: Option<String>,
+}
+
+impl QasmSimError<'_> {
+    /// Return the stage at which the error is found. For several errors, it
+    /// is the earliest stage of all of them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use qasmsim::error::ErrorStage;
+    ///
+    /// let error = qasmsim::run("OPENQASM 2.0;\nqreg q[2]", None).unwrap_err();
+    /// assert_eq!(error.stage(), Some(ErrorStage::Parse));
+    /// ```
+    pub fn stage(&self) -> Option<ErrorStage> {
+        match self {
+            QasmSimError::UnknownError(_) => None,
+            QasmSimError::InvalidToken { .. }
+            | QasmSimError::InvalidModifier { .. }
+            | QasmSimError::UnexpectedEOF { .. }
+            | QasmSimError::UnexpectedToken { .. }
+            | QasmSimError::UnsupportedFeature { .. }
+            | QasmSimError::InvalidInteger { .. } => Some(ErrorStage::Parse),
+            QasmSimError::LibraryNotFound { .. } => Some(ErrorStage::Link),
+            QasmSimError::RedefinitionError { .. }
+            | QasmSimError::DisabledExtension { .. }
+            | QasmSimError::IndexOutOfBounds { .. }
+            | QasmSimError::SymbolNotFound { .. }
+            | QasmSimError::WrongNumberOfParameters { .. }
+            | QasmSimError::UndefinedGate { .. }
+            | QasmSimError::TypeMismatch { .. }
+            | QasmSimError::RegisterSizeMismatch { .. }
+            | QasmSimError::RepeatedQubit { .. }
+            | QasmSimError::ModifiedOpaqueGate { .. } => Some(ErrorStage::Semantic),
+            QasmSimError::UnboundOpaqueGate { .. }
+            | QasmSimError::NonIntegerPower { .. }
+            | QasmSimError::PowerTooLarge { .. }
+            | QasmSimError::NonUnitaryOperation { .. }
+            | QasmSimError::InvalidParameter { .. }
+            | QasmSimError::NonFiniteValue { .. } => Some(ErrorStage::Runtime),
+            QasmSimError::MultipleErrors(errors) => {
+                errors.iter().filter_map(QasmSimError::stage).min()
+            }
+        }
+    }
+
+    /// Return the diagnostics describing the error, one per error when the
+    /// error groups several of them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let error = qasmsim::run("OPENQASM 2.0;\nqreg q[2]", None).unwrap_err();
+    /// let diagnostic = &error.diagnostics()[0];
+    /// assert_eq!(diagnostic.kind, "UnexpectedEOF");
+    /// assert_eq!((diagnostic.line, diagnostic.column), (Some(2), Some(10)));
+    /// assert_eq!(diagnostic.expected, vec!["\";\""]);
+    /// ```
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        if let QasmSimError::MultipleErrors(errors) = self {
+            return errors.iter().flat_map(QasmSimError::diagnostics).collect();
+        }
+        let expected = match self {
+            QasmSimError::InvalidToken { expected, .. }
+            | QasmSimError::UnexpectedEOF { expected, .. }
+            | QasmSimError::UnexpectedToken { expected, .. } => expected.clone(),
+            _ => Vec::new(),
+        };
+        let mut diagnostic = Diagnostic {
+            kind: kind(self).into(),
+            severity: Severity::Error,
+            stage: self.stage(),
+            message: String::new(),
+            file: None,
+            line: None,
+            column: None,
+            end_column: None,
+            expected,
+            hint: None,
+        };
+        match human_description(self) {
+            Some(description) => {
+                diagnostic.message = description.msg;
+                diagnostic.line = Some(description.lineno);
+                diagnostic.column = Some(description.startpos + 1);
+                diagnostic.end_column = description.endpos.map(|endpos| endpos + 1);
+                diagnostic.hint = description.help;
+            }
+            None => diagnostic.message = self.to_string(),
+        }
+        vec![diagnostic]
+    }
+}
+
+fn kind(error: &QasmSimError) -> &'static str {
+    match error {
+        QasmSimError::UnknownError(_) => "UnknownError",
+        QasmSimError::InvalidToken { .. } => "InvalidToken",
+        QasmSimError::InvalidModifier { .. } => "InvalidModifier",
+        QasmSimError::UnexpectedEOF { .. } => "UnexpectedEOF",
+        QasmSimError::UnexpectedToken { .. } => "UnexpectedToken",
+        QasmSimError::RedefinitionError { .. } => "RedefinitionError",
+        QasmSimError::LibraryNotFound { .. } => "LibraryNotFound",
+        QasmSimError::IndexOutOfBounds { .. } => "IndexOutOfBounds",
+        QasmSimError::SymbolNotFound { .. } => "SymbolNotFound",
+        QasmSimError::WrongNumberOfParameters { .. } => "WrongNumberOfParameters",
+        QasmSimError::UndefinedGate { .. } => "UndefinedGate",
+        QasmSimError::TypeMismatch { .. } => "TypeMismatch",
+        QasmSimError::RegisterSizeMismatch { .. } => "RegisterSizeMismatch",
+        QasmSimError::RepeatedQubit { .. } => "RepeatedQubit",
+        QasmSimError::UnboundOpaqueGate { .. } => "UnboundOpaqueGate",
+        QasmSimError::ModifiedOpaqueGate { .. } => "ModifiedOpaqueGate",
+        QasmSimError::NonIntegerPower { .. } => "NonIntegerPower",
+        QasmSimError::PowerTooLarge { .. } => "PowerTooLarge",
+        QasmSimError::UnsupportedFeature { .. } => "UnsupportedFeature",
+        QasmSimError::InvalidInteger { .. } => "InvalidInteger",
+        QasmSimError::DisabledExtension { .. } => "DisabledExtension",
+        QasmSimError::NonUnitaryOperation { .. } => "NonUnitaryOperation",
+        QasmSimError::InvalidParameter { .. } => "InvalidParameter",
+        QasmSimError::NonFiniteValue { .. } => "NonFiniteValue",
+        QasmSimError::MultipleErrors(_) => "MultipleErrors",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use indoc::indoc;
+
+    #[test]
+    fn test_diagnostics_of_multiple_errors() {
+        let source = indoc!(
+            "
+        OPENQASM 2.0;
+        qreg q[10]
+        qreg r[10];
+        creg c[10]
+        creg d[10];
+        "
+        );
+        let error = crate::run(source, None).unwrap_err();
+        assert_eq!(error.stage(), Some(ErrorStage::Parse));
+        let diagnostics = error.diagnostics();
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(
+            diagnostics[1],
+            Diagnostic {
+                kind: "UnexpectedToken".into(),
+                severity: Severity::Error,
+                stage: Some(ErrorStage::Parse),
+                message: "expected \";\", found \"keyword `creg`\"".into(),
+                file: None,
+                line: Some(5),
+                column: Some(1),
+                end_column: Some(5),
+                expected: vec!["\";\"".into()],
+                hint: Some("consider adding \";\" before this".into()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_diagnostics_of_semantic_errors() {
+        let source = indoc!(
+            "
+        OPENQASM 2.0;
+        qreg q[2];
+        CX q[0], q[2];
+        "
+        );
+        let error = crate::run(source, None).unwrap_err();
+        assert_eq!(
+            error.diagnostics(),
+            vec![Diagnostic {
+                kind: "IndexOutOfBounds".into(),
+                severity: Severity::Error,
+                stage: Some(ErrorStage::Semantic),
+                message: "index out of bounds".into(),
+                file: None,
+                line: Some(3),
+                column: Some(10),
+                end_column: Some(14),
+                expected: vec![],
+                hint: Some("indices of register `q` range from 0 to 1 but the index is 2".into()),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_diagnostics_of_unknown_errors() {
+        let error = QasmSimError::UnknownError("something went wrong".into());
+        assert_eq!(error.stage(), None);
+        let diagnostic = &error.diagnostics()[0];
+        assert_eq!(diagnostic.message, "something went wrong");
+        assert_eq!(diagnostic.line, None);
+    }
+}
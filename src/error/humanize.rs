@@ -1,6 +1,9 @@
 use std::fmt::{self, Write};
 
-use crate::error::QasmSimError;
+use crate::error::{find_expression, QasmSimError};
+use crate::interpreter::MAX_POWER_OPERATIONS;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::lint::{Lint, Warning};
 
 macro_rules! lazy_humanize {
     ($err:expr, $($variant:path),*) => {{
@@ -21,15 +24,15 @@ macro_rules! lazy_humanize {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct HumanDescription {
-    msg: String,
-    lineno: usize,
-    startpos: usize,
-    endpos: Option<usize>,
-    linesrc: String,
-    help: Option<String>,
+    pub(crate) msg: String,
+    pub(crate) lineno: usize,
+    pub(crate) startpos: usize,
+    pub(crate) endpos: Option<usize>,
+    pub(crate) linesrc: String,
+    pub(crate) help: Option<String>,
 }
 
-fn human_description(error: &QasmSimError) -> Option<HumanDescription> {
+pub(crate) fn human_description(error: &QasmSimError) -> Option<HumanDescription> {
     match error {
         QasmSimError::InvalidToken {
             source,
@@ -45,6 +48,21 @@ fn human_description(error: &QasmSimError) -> Option<HumanDescription> {
             linesrc: (*source).into(),
             help: None,
         }),
+        QasmSimError::InvalidModifier {
+            source,
+            lineno,
+            startpos,
+            modifier,
+        } => Some(HumanDescription {
+            msg: format!("invalid gate modifier `{}`", modifier),
+            lineno: *lineno,
+            startpos: *startpos,
+            endpos: None,
+            linesrc: (*source).into(),
+            help: Some(
+                "gate modifiers are `inv`, `ctrl`, `ctrl(n)` with n > 0, and `pow(k)`".into(),
+            ),
+        }),
         QasmSimError::UnexpectedEOF {
             source,
             lineno,
@@ -114,11 +132,13 @@ fn human_description(error: &QasmSimError) -> Option<HumanDescription> {
             lineno,
             index,
             size,
+            startpos,
+            endpos,
         } => Some(HumanDescription {
             msg: "index out of bounds".to_string(),
             lineno: *lineno,
-            startpos: 0,
-            endpos: None,
+            startpos: *startpos,
+            endpos: *endpos,
             linesrc: (*source).into(),
             help: Some(format!(
                 "indices of register `{}` range from 0 to {} but the index is {}",
@@ -132,14 +152,16 @@ fn human_description(error: &QasmSimError) -> Option<HumanDescription> {
             symbol_name,
             lineno,
             expected,
+            startpos,
+            endpos,
         } => Some(HumanDescription {
             msg: format!(
                 "cannot find the {} `{}` in this scope",
                 expected, symbol_name
             ),
             lineno: *lineno,
-            startpos: 0,
-            endpos: None,
+            startpos: *startpos,
+            endpos: *endpos,
             linesrc: (*source).into(),
             help: None,
         }),
@@ -148,6 +170,8 @@ fn human_description(error: &QasmSimError) -> Option<HumanDescription> {
             symbol_name,
             lineno,
             expected,
+            startpos,
+            endpos,
         } => Some(HumanDescription {
             msg: format!(
                 "mismatched types for symbol `{}`: expected \"{}\"",
@@ -155,8 +179,8 @@ fn human_description(error: &QasmSimError) -> Option<HumanDescription> {
             ),
             linesrc: (*source).into(),
             lineno: *lineno,
-            startpos: 0,
-            endpos: None,
+            startpos: *startpos,
+            endpos: *endpos,
             help: None,
         }),
         QasmSimError::RegisterSizeMismatch {
@@ -164,6 +188,8 @@ fn human_description(error: &QasmSimError) -> Option<HumanDescription> {
             symbol_name,
             lineno,
             sizes,
+            startpos,
+            endpos,
         } => {
             let sizes_str: Vec<String> = sizes.iter().map(|size| format!("{}", size)).collect();
             let msg = if symbol_name == "measure" {
@@ -178,14 +204,32 @@ fn human_description(error: &QasmSimError) -> Option<HumanDescription> {
                 msg,
                 linesrc: (*source).into(),
                 lineno: *lineno,
-                startpos: 0,
-                endpos: None,
+                startpos: *startpos,
+                endpos: *endpos,
                 help: Some(format!(
                     "expected registers of same size, found registers of sizes {}",
                     sizes_str.join(", ")
                 )),
             })
         }
+        QasmSimError::RepeatedQubit {
+            source,
+            symbol_name,
+            lineno,
+            qubit,
+            startpos,
+            endpos,
+        } => Some(HumanDescription {
+            msg: format!(
+                "qubit `{}` is used more than once in gate `{}`",
+                qubit, symbol_name
+            ),
+            linesrc: (*source).into(),
+            lineno: *lineno,
+            startpos: *startpos,
+            endpos: *endpos,
+            help: Some("the qubit arguments of a gate must be different".into()),
+        }),
         QasmSimError::WrongNumberOfParameters {
             source,
             symbol_name,
@@ -193,6 +237,8 @@ fn human_description(error: &QasmSimError) -> Option<HumanDescription> {
             lineno,
             expected,
             given,
+            startpos,
+            endpos,
         } => {
             let qualifier = if *are_registers {
                 "quantum registers"
@@ -206,8 +252,8 @@ fn human_description(error: &QasmSimError) -> Option<HumanDescription> {
                 ),
                 linesrc: (*source).into(),
                 lineno: *lineno,
-                startpos: 0,
-                endpos: None,
+                startpos: *startpos,
+                endpos: *endpos,
                 help: Some(format!(
                     "expected {} {}, given {}",
                     expected, qualifier, given
@@ -218,25 +264,191 @@ fn human_description(error: &QasmSimError) -> Option<HumanDescription> {
             source,
             symbol_name,
             lineno,
+        } => {
+            let (startpos, endpos) = gate_position(source, symbol_name);
+            Some(HumanDescription {
+                msg: format!("cannot find gate `{}` in this scope", symbol_name),
+                linesrc: (*source).into(),
+                lineno: *lineno,
+                startpos,
+                endpos,
+                help: None,
+            })
+        }
+        QasmSimError::UnboundOpaqueGate {
+            source,
+            symbol_name,
+            lineno,
+        } => {
+            let (startpos, endpos) = gate_position(source, symbol_name);
+            Some(HumanDescription {
+                msg: format!("opaque gate `{}` has no implementation", symbol_name),
+                linesrc: (*source).into(),
+                lineno: *lineno,
+                startpos,
+                endpos,
+                help: Some(format!(
+                    "bind a native implementation for `{}` before running the program",
+                    symbol_name
+                )),
+            })
+        }
+        QasmSimError::LibraryNotFound {
+            source,
+            lineno,
+            libpath,
         } => Some(HumanDescription {
-            msg: format!("cannot find gate `{}` in this scope", symbol_name),
+            msg: format!("cannot find library `{}`", libpath),
             linesrc: (*source).into(),
             lineno: *lineno,
             startpos: 0,
             endpos: None,
             help: None,
         }),
-        QasmSimError::LibraryNotFound {
+        QasmSimError::ModifiedOpaqueGate {
             source,
             lineno,
-            libpath,
+            symbol_name,
+        } => {
+            let (startpos, endpos) = gate_position(source, symbol_name);
+            Some(HumanDescription {
+                msg: format!(
+                    "cannot apply gate modifiers to opaque gate `{}`",
+                    symbol_name
+                ),
+                linesrc: (*source).into(),
+                lineno: *lineno,
+                startpos,
+                endpos,
+                help: Some("modifiers need the definition of the gate".into()),
+            })
+        }
+        QasmSimError::NonIntegerPower {
+            source,
+            lineno,
+            symbol_name,
+            exponent,
         } => Some(HumanDescription {
-            msg: format!("cannot find library `{}`", libpath),
+            msg: format!(
+                "cannot raise gate `{}` to the non-integer power {}",
+                symbol_name, exponent
+            ),
             linesrc: (*source).into(),
             lineno: *lineno,
             startpos: 0,
             endpos: None,
-            help: None,
+            help: Some("only integer exponents are supported in `pow` modifiers".into()),
+        }),
+        QasmSimError::PowerTooLarge {
+            source,
+            lineno,
+            symbol_name,
+            exponent,
+        } => Some(HumanDescription {
+            msg: format!(
+                "cannot raise gate `{}` to the power {}",
+                symbol_name, exponent
+            ),
+            linesrc: (*source).into(),
+            lineno: *lineno,
+            startpos: 0,
+            endpos: None,
+            help: Some(format!(
+                "`pow` modifiers cannot expand into more than {} gates",
+                MAX_POWER_OPERATIONS
+            )),
+        }),
+        QasmSimError::UnsupportedFeature {
+            source,
+            lineno,
+            startpos,
+            feature,
+        } => Some(HumanDescription {
+            msg: format!("unsupported feature: {}", feature),
+            linesrc: (*source).into(),
+            lineno: *lineno,
+            startpos: *startpos,
+            endpos: None,
+            help: Some("qasmsim only supports a core subset of OPENQASM 3".into()),
+        }),
+        QasmSimError::InvalidInteger {
+            source,
+            lineno,
+            value,
+            non_negative,
+        } => Some(HumanDescription {
+            msg: format!(
+                "expected {}, found `{}`",
+                if *non_negative {
+                    "a non-negative integer"
+                } else {
+                    "an integer"
+                },
+                value
+            ),
+            linesrc: (*source).into(),
+            lineno: *lineno,
+            startpos: 0,
+            endpos: None,
+            help: Some("sizes, indices and ranges must be integer constants".into()),
+        }),
+        QasmSimError::DisabledExtension {
+            source,
+            lineno,
+            startpos,
+            extension,
+        } => Some(HumanDescription {
+            msg: format!("{} are not part of OPENQASM 2.0", extension.description()),
+            linesrc: (*source).into(),
+            lineno: *lineno,
+            startpos: *startpos,
+            endpos: None,
+            help: Some(format!(
+                "enable the `{}` extension to allow them",
+                extension.name()
+            )),
+        }),
+        QasmSimError::NonUnitaryOperation {
+            source,
+            lineno,
+            symbol_name,
+        } => Some(HumanDescription {
+            msg: format!("`{}` is not a unitary operation", symbol_name),
+            linesrc: (*source).into(),
+            lineno: *lineno,
+            startpos: 0,
+            endpos: None,
+            help: Some("only gates and barriers are allowed in a unitary program".into()),
+        }),
+        QasmSimError::InvalidParameter {
+            source,
+            lineno,
+            startpos,
+            endpos,
+            expression,
+            value,
+        } => Some(HumanDescription {
+            msg: format!("invalid parameter `{}`", expression),
+            linesrc: (*source).into(),
+            lineno: *lineno,
+            startpos: *startpos,
+            endpos: *endpos,
+            help: Some(format!(
+                "evaluating the parameter results in `{}`, parameters must be finite numbers",
+                value
+            )),
+        }),
+        QasmSimError::NonFiniteValue {
+            source,
+            lineno,
+            operation,
+        } => Some(HumanDescription {
+            msg: format!("`{}` does not evaluate to a finite number", operation),
+            linesrc: (*source).into(),
+            lineno: *lineno,
+            startpos: 0,
+            endpos: None,
+            help: Some("evaluate functions inside their domains and avoid dividing by zero".into()),
         }),
         _ => None,
     }
@@ -245,6 +457,15 @@ fn human_description(error: &QasmSimError) -> Option<HumanDescription> {
 pub fn humanize_error<W: Write>(buffer: &mut W, error: &QasmSimError) -> fmt::Result {
     match error {
         QasmSimError::UnknownError(msg) => write!(buffer, "{}", msg),
+        QasmSimError::MultipleErrors(errors) => {
+            for (index, error) in errors.iter().enumerate() {
+                if index > 0 {
+                    writeln!(buffer)?;
+                }
+                humanize_error(buffer, error)?;
+            }
+            fmt::Result::Ok(())
+        }
         _ => {
             let description: HumanDescription =
                 human_description(error).expect("some human description");
@@ -253,7 +474,77 @@ pub fn humanize_error<W: Write>(buffer: &mut W, error: &QasmSimError) -> fmt::Re
     }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn human_warning_description(warning: &Warning) -> HumanDescription {
+    let (msg, help) = match &warning.lint {
+        Lint::UnusedRegister { symbol_name } => (
+            format!("register `{}` is never used", symbol_name),
+            "consider removing the declaration".to_string(),
+        ),
+        Lint::UnusedGate { symbol_name } => (
+            format!("gate `{}` is never called", symbol_name),
+            "consider removing the definition".to_string(),
+        ),
+        Lint::OverwrittenMeasurement { symbol_name, index } => (
+            format!(
+                "the measurement stored in `{}[{}]` is overwritten",
+                symbol_name, index
+            ),
+            "the bit is written again before being read".to_string(),
+        ),
+        Lint::GateAfterMeasurement { symbol_name } => (
+            format!(
+                "gate `{}` is applied after the final measurement",
+                symbol_name
+            ),
+            "the qubits are not measured again, so the gate has no visible effect".to_string(),
+        ),
+        Lint::UndeclaredBarrierRegister { symbol_name } => (
+            format!("barrier on the undeclared register `{}`", symbol_name),
+            format!("consider declaring `{}` with `qreg`", symbol_name),
+        ),
+        Lint::ShadowedGate {
+            symbol_name,
+            libpath,
+        } => (
+            format!(
+                "gate `{}` shadows the definition in \"{}\"",
+                symbol_name, libpath
+            ),
+            "consider renaming the gate".to_string(),
+        ),
+        Lint::UnwrittenCondition { symbol_name } => (
+            format!(
+                "register `{}` is never written before the condition",
+                symbol_name
+            ),
+            format!("`{}` is always 0 here", symbol_name),
+        ),
+    };
+    HumanDescription {
+        msg,
+        lineno: warning.lineno,
+        startpos: warning.startpos,
+        endpos: warning.endpos,
+        linesrc: warning.source.into(),
+        help: Some(help),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn humanize_warning<W: Write>(buffer: &mut W, warning: &Warning) -> fmt::Result {
+    humanize_with_label(buffer, "warning", &human_warning_description(warning))
+}
+
 fn humanize<W: Write>(buffer: &mut W, descripition: &HumanDescription) -> fmt::Result {
+    humanize_with_label(buffer, "error", descripition)
+}
+
+fn humanize_with_label<W: Write>(
+    buffer: &mut W,
+    label: &str,
+    descripition: &HumanDescription,
+) -> fmt::Result {
     let HumanDescription {
         msg,
         lineno,
@@ -265,8 +556,13 @@ fn humanize<W: Write>(buffer: &mut W, descripition: &HumanDescription) -> fmt::R
 
     let lineno_str = format!("{} ", lineno);
     let lineno_len = lineno_str.len();
-    let linesrc_str: String = linesrc.into();
-    let linesrc_str_trimmed = linesrc_str.trim_end();
+    // The source can include the previous lines of a statement spanning
+    // several lines. The error is always in the last one.
+    let mut lines: Vec<&str> = linesrc.lines().collect();
+    if lines.is_empty() {
+        lines.push("");
+    }
+    let first_lineno = lineno + 1 - lines.len();
     let help_str = help.clone().unwrap_or_else(|| msg.clone());
     let indicator_width = if let Some(pos) = endpos {
         pos - startpos
@@ -274,9 +570,17 @@ fn humanize<W: Write>(buffer: &mut W, descripition: &HumanDescription) -> fmt::R
         1
     };
 
-    writeln!(buffer, "error: {}", msg)?;
+    writeln!(buffer, "{}: {}", label, msg)?;
     writeln!(buffer, "{:>alignment$}|", "", alignment = lineno_len)?;
-    writeln!(buffer, "{}| {}", lineno_str, linesrc_str_trimmed)?;
+    for (offset, line) in lines.iter().enumerate() {
+        writeln!(
+            buffer,
+            "{:<alignment$}| {}",
+            first_lineno + offset,
+            line.trim_end(),
+            alignment = lineno_len
+        )?;
+    }
     writeln!(
         buffer,
         "{:>alignment$}| {:>padding$}{:^>indicator_width$} help: {}",
@@ -292,6 +596,15 @@ fn humanize<W: Write>(buffer: &mut W, descripition: &HumanDescription) -> fmt::R
     fmt::Result::Ok(())
 }
 
+/// Return the start and end positions of the gate `name` inside `line`, or
+/// the start of the line if it is not found.
+fn gate_position(line: &str, name: &str) -> (usize, Option<usize>) {
+    match find_expression(line, name) {
+        Some((startpos, endpos)) => (startpos, Some(endpos)),
+        None => (0, None),
+    }
+}
+
 fn expectation(expected: &[String]) -> String {
     let choices = list_of_choices(expected).expect("len() is greater than 0");
     format!("expected {}", choices)
@@ -328,6 +641,7 @@ mod test_humanize_error {
     use indoc::indoc;
 
     use super::*;
+    use crate::grammar::lexer::Tok;
 
     #[test]
     fn test_eof_error() {
@@ -453,4 +767,70 @@ mod test_humanize_error {
             )
         );
     }
+
+    #[test]
+    fn test_multiline_source() {
+        let error = HumanDescription {
+            msg: "index out of bounds".into(),
+            lineno: 9,
+            startpos: 5,
+            endpos: Some(9),
+            linesrc: "CX q[1],\n     q[2];\n".into(),
+            help: Some("indices of register `q` range from 0 to 1 but the index is 2".into()),
+        };
+        let mut buffer = String::new();
+        humanize(&mut buffer, &error).expect("should not fail");
+        assert_eq!(
+            buffer,
+            indoc!(
+                r#"
+      error: index out of bounds
+        |
+      8 | CX q[1],
+      9 |      q[2];
+        |      ^^^^ help: indices of register `q` range from 0 to 1 but the index is 2
+    "#
+            )
+        );
+    }
+
+    #[test]
+    fn test_multiple_errors() {
+        let error = QasmSimError::MultipleErrors(vec![
+            QasmSimError::UnexpectedToken {
+                source: "qreg r[10];\n",
+                lineno: 3,
+                startpos: 0,
+                endpos: Some(4),
+                token: Some(Tok::QReg),
+                expected: vec!["\";\"".into()],
+            },
+            QasmSimError::UnexpectedToken {
+                source: "creg d[10];\n",
+                lineno: 5,
+                startpos: 0,
+                endpos: Some(4),
+                token: Some(Tok::CReg),
+                expected: vec!["\";\"".into()],
+            },
+        ]);
+        let mut buffer = String::new();
+        humanize_error(&mut buffer, &error).expect("should not fail");
+        assert_eq!(
+            buffer,
+            indoc!(
+                r#"
+      error: expected ";", found "keyword `qreg`"
+        |
+      3 | qreg r[10];
+        | ^^^^ help: consider adding ";" before this
+
+      error: expected ";", found "keyword `creg`"
+        |
+      5 | creg d[10];
+        | ^^^^ help: consider adding ";" before this
+    "#
+            )
+        );
+    }
 }
@@ -10,7 +10,7 @@
 //! for enabling the users to experiment with performing code manipulations at
 //! the abstract level.
 
-use lalrpop_util::{self, lalrpop_mod};
+use lalrpop_util::{self, lalrpop_mod, ErrorRecovery};
 
 pub mod ast;
 pub mod lexer;
@@ -19,20 +19,168 @@ lalrpop_mod!(
     open_qasm2,
     "/grammar/open_qasm2.rs"
 );
+lalrpop_mod!(
+    #[allow(clippy::all)]
+    open_qasm3,
+    "/grammar/open_qasm3.rs"
+);
+mod formatter;
+mod options;
+mod qasm3;
+mod simplifier;
+
+pub(crate) use self::formatter::format_program;
+pub(crate) use self::options::is_open_qasm2;
+pub use self::options::{Extension, ExtensionError, LanguageOptions};
+pub use self::qasm3::LoweringError;
+
+use self::ast::GateModifier;
+use self::ast::{
+    Argument, Expression, GateOperation, OpenQasmLibrary, OpenQasmProgram, Span, Statement,
+    UnitaryOperation,
+};
+use self::lexer::{Lexer, LexicalError, Location, Tok};
+use crate::error::{ParseError, QasmSimError};
+
+/// Build the modifier `name` with arguments `args`. Modifiers are not
+/// key-words in OPENQASM 2, so gates can still be named after them; the name
+/// is validated once the `@` separator is found. `ctrl` needs a positive
+/// integer number of controls. An invalid modifier, written from `location`
+/// to `end`, is reported as an unrecognized [`Tok::InvalidModifier`] token.
+fn gate_modifier(
+    (location, end): (Location, Location),
+    name: &str,
+    args: Vec<Expression>,
+) -> Result<GateModifier, ParseError> {
+    match (name, args.as_slice()) {
+        ("inv", []) => Ok(GateModifier::Inv),
+        ("ctrl", []) => Ok(GateModifier::Ctrl(1)),
+        ("ctrl", [Expression::Real(count)]) if count.fract() == 0.0 && *count >= 1.0 => {
+            Ok(GateModifier::Ctrl(*count as usize))
+        }
+        ("pow", [exponent]) => Ok(GateModifier::Pow(exponent.clone())),
+        _ => {
+            let modifier = if args.is_empty() {
+                name.to_string()
+            } else {
+                let args: Vec<String> = args.iter().map(ToString::to_string).collect();
+                format!("{}({})", name, args.join(", "))
+            };
+            Err(ParseError::UnrecognizedToken {
+                token: (location, Tok::InvalidModifier { repr: modifier }, end),
+                expected: vec![],
+            })
+        }
+    }
+}
+
+/// The boundaries of the parameters and arguments of a statement, and the
+/// spans of the operations in the body of a gate declaration, collected while
+/// parsing to complete the span of the statement.
+#[derive(Debug, Clone, Default)]
+struct Parts {
+    parameters: Vec<(Location, Location)>,
+    arguments: Vec<(Location, Location)>,
+    operations: Vec<Span<()>>,
+}
 
-use self::ast::{Expression, OpenQasmLibrary, OpenQasmProgram, Span, Statement};
-use self::lexer::Lexer;
-use crate::error::QasmSimError;
+impl Parts {
+    /// Return the parts with the boundaries of `arguments` in front of the
+    /// current arguments.
+    fn after_arguments(mut self, mut arguments: Vec<(Location, Location)>) -> Self {
+        arguments.append(&mut self.arguments);
+        self.arguments = arguments;
+        self
+    }
+}
+
+/// Split a list of nodes paired with their boundaries into the list of nodes
+/// and the list of boundaries.
+fn unzip<T>(located: Vec<(T, (Location, Location))>) -> (Vec<T>, Vec<(Location, Location)>) {
+    located.into_iter().unzip()
+}
+
+fn unitary(
+    name: &str,
+    real_args: Vec<(Expression, (Location, Location))>,
+    args: Vec<(Argument, (Location, Location))>,
+) -> (UnitaryOperation, Parts) {
+    let (real_args, parameters) = unzip(real_args);
+    let (args, arguments) = unzip(args);
+    (
+        UnitaryOperation(name.to_owned(), real_args, args),
+        Parts {
+            parameters,
+            arguments,
+            ..Parts::default()
+        },
+    )
+}
+
+/// Return the declaration of a gate with the operations of `body`, and the
+/// parts keeping the spans of the operations.
+fn gate_decl(
+    docstring: Option<String>,
+    (name, real_params, params): (String, Vec<String>, Vec<String>),
+    body: Vec<(GateOperation, Span<()>)>,
+) -> (Statement, Parts) {
+    let (operations, spans) = body.into_iter().unzip();
+    (
+        Statement::GateDecl {
+            signature: (name, real_params, params, operations),
+            docstring,
+        },
+        Parts {
+            operations: spans,
+            ..Parts::default()
+        },
+    )
+}
+
+fn span<S>(boundaries: (Location, Location), node: S, parts: Parts) -> Span<S> {
+    Span {
+        boundaries,
+        node: Box::new(node),
+        parameters: parts.parameters,
+        arguments: parts.arguments,
+        operations: parts.operations,
+    }
+}
+
+/// Return the result of the parser if it found no errors or, otherwise, all
+/// the errors it recovered from followed by the error that stopped it.
+fn collect_errors<'src, T>(
+    source: &'src str,
+    recovered: Vec<ErrorRecovery<Location, Tok, LexicalError<Location>>>,
+    result: Result<T, ParseError>,
+) -> Result<T, QasmSimError<'src>> {
+    let mut errors: Vec<QasmSimError<'src>> = recovered
+        .into_iter()
+        .map(|recovery| (source, recovery.error).into())
+        .collect();
+    match result {
+        Ok(value) if errors.is_empty() => return Ok(value),
+        Ok(_) => (),
+        Err(error) => errors.push((source, error).into()),
+    }
+    if errors.len() == 1 {
+        Err(errors.remove(0))
+    } else {
+        Err(QasmSimError::MultipleErrors(errors))
+    }
+}
 
 macro_rules! parse_functions {
     ($($(#[$attr:meta])* $vis:vis fn $funcname:ident ($param:ident) -> $rettype:ty => $parser:ty;)*) => {
         $(
             $(#[$attr])* $vis fn $funcname(
                 $param: &str
-            ) -> Result<$rettype, QasmSimError> {
+            ) -> Result<$rettype, QasmSimError<'_>> {
                 let lexer = Lexer::new($param);
                 let parser = <$parser>::new();
-                parser.parse(lexer).map_err(|err| ($param, err).into())
+                let mut recovered = Vec::new();
+                let result = parser.parse(&mut recovered, lexer);
+                collect_errors($param, recovered, result)
             }
         )*
     };
@@ -130,70 +278,7 @@ parse_functions! {
     /// [`OpenQasmLib`]: ./ast/struct.OpenQasmLibrary.html
     pub fn parse_library(source) -> OpenQasmLibrary => open_qasm2::OpenQasmLibraryParser;
 
-    /// Parse `source` into a [`OpenQasmProgram`] AST.
-    ///
-    /// # Examples
-    ///
-    /// Basic usage:
-    ///
-    /// ```
-    /// # use qasmsim::{QasmSimError, grammar::parse_program};
-    /// use qasmsim::grammar::lexer::Location;
-    /// use qasmsim::grammar::ast::{
-    ///     Argument,
-    ///     Expression,
-    ///     GateOperation,
-    ///     OpenQasmProgram,
-    ///     Span,
-    ///     Statement,
-    ///     UnitaryOperation
-    /// };
-    ///
-    /// let program_ast = parse_program(r"
-    /// OPENQASM 2.0;
-    /// gate idle q {
-    ///   U(0, 0, 0) q;
-    /// }")?;
-    ///
-    /// assert_eq!(program_ast, OpenQasmProgram{
-    ///     version: "2.0".to_string(),
-    ///     program: vec![Span{
-    ///         boundaries: (Location(15), Location(46)),
-    ///         node: Box::new(Statement::GateDecl {
-    ///             signature: (
-    ///                 "idle".to_string(),
-    ///                 vec![],
-    ///                 vec!["q".to_string()],
-    ///                 vec![
-    ///                     GateOperation::Unitary(
-    ///                         UnitaryOperation(
-    ///                             "U".to_string(),
-    ///                             vec![
-    ///                                 Expression::Real(0.0),
-    ///                                 Expression::Real(0.0),
-    ///                                 Expression::Real(0.0)
-    ///                             ],
-    ///                             vec![Argument::Id("q".to_string())]
-    ///                         )
-    ///                     )
-    ///                 ]
-    ///             ),
-    ///             docstring: None
-    ///         })
-    ///     }]
-    /// });
-    /// # Ok::<(), QasmSimError>(())
-    /// ```
-    ///
-    /// Compare this example with the result of [`parse_library()`],
-    /// [`parse_program_body()`] or [`parse_statement()`].
-    ///
-    /// [`Statement`]: ./ast/enum.Statement.html
-    /// [`parse_library()`]: ./fn.parse_library.html
-    /// [`parse_program_body()`]: ./fn.parse_program_body.html
-    /// [`parse_statement()`]: ./fn.parse_statement.html
-    /// [`OpenQasmProgram`]: ./ast/struct.OpenQasmProgram.html
-    pub fn parse_program(source) -> OpenQasmProgram => open_qasm2::OpenQasmProgramParser;
+    fn parse_qasm2_program(source) -> OpenQasmProgram => open_qasm2::OpenQasmProgramParser;
 
     /// Parse `source` into a list of [`Statement`]s.
     ///
@@ -237,7 +322,20 @@ parse_functions! {
     ///             ]
     ///         ),
     ///         docstring: None
-    ///     })
+    ///     }),
+    ///     parameters: vec![],
+    ///     arguments: vec![],
+    ///     operations: vec![Span {
+    ///         boundaries: (Location(14), Location(27)),
+    ///         node: Box::new(()),
+    ///         parameters: vec![
+    ///             (Location(16), Location(17)),
+    ///             (Location(19), Location(20)),
+    ///             (Location(22), Location(23))
+    ///         ],
+    ///         arguments: vec![(Location(25), Location(26))],
+    ///         operations: vec![]
+    ///     }]
     /// }]);
     /// # Ok::<(), QasmSimError>(())
     /// ```
@@ -303,6 +401,147 @@ parse_functions! {
     pub fn parse_statement(source) -> Statement => open_qasm2::StatementParser;
 }
 
+/// Parse `source` into a [`OpenQasmProgram`] AST.
+///
+/// The grammar is chosen by the version in the `OPENQASM` header. OPENQASM 3
+/// programs are lowered into the same AST, using the extensions for gate
+/// modifiers and `if`/`else` blocks, and programs using features the
+/// simulator does not support are rejected with a [`LoweringError`].
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// # use qasmsim::{QasmSimError, grammar::parse_program};
+/// use qasmsim::grammar::lexer::Location;
+/// use qasmsim::grammar::ast::{
+///     Argument,
+///     Expression,
+///     GateOperation,
+///     OpenQasmProgram,
+///     Span,
+///     Statement,
+///     UnitaryOperation
+/// };
+///
+/// let program_ast = parse_program(r"
+/// OPENQASM 2.0;
+/// gate idle q {
+///   U(0, 0, 0) q;
+/// }")?;
+///
+/// assert_eq!(program_ast, OpenQasmProgram{
+///     version: "2.0".to_string(),
+///     program: vec![Span{
+///         boundaries: (Location(15), Location(46)),
+///         node: Box::new(Statement::GateDecl {
+///             signature: (
+///                 "idle".to_string(),
+///                 vec![],
+///                 vec!["q".to_string()],
+///                 vec![
+///                     GateOperation::Unitary(
+///                         UnitaryOperation(
+///                             "U".to_string(),
+///                             vec![
+///                                 Expression::Real(0.0),
+///                                 Expression::Real(0.0),
+///                                 Expression::Real(0.0)
+///                             ],
+///                             vec![Argument::Id("q".to_string())]
+///                         )
+///                     )
+///                 ]
+///             ),
+///             docstring: None
+///         }),
+///         parameters: vec![],
+///         arguments: vec![],
+///         operations: vec![Span {
+///             boundaries: (Location(31), Location(44)),
+///             node: Box::new(()),
+///             parameters: vec![
+///                 (Location(33), Location(34)),
+///                 (Location(36), Location(37)),
+///                 (Location(39), Location(40))
+///             ],
+///             arguments: vec![(Location(42), Location(43))],
+///             operations: vec![]
+///         }]
+///     }]
+/// });
+/// # Ok::<(), QasmSimError>(())
+/// ```
+///
+/// Compare this example with the result of [`parse_library()`],
+/// [`parse_program_body()`] or [`parse_statement()`].
+///
+/// [`Statement`]: ./ast/enum.Statement.html
+/// [`parse_library()`]: ./fn.parse_library.html
+/// [`parse_program_body()`]: ./fn.parse_program_body.html
+/// [`parse_statement()`]: ./fn.parse_statement.html
+/// [`OpenQasmProgram`]: ./ast/struct.OpenQasmProgram.html
+/// [`LoweringError`]: ./enum.LoweringError.html
+pub fn parse_program(source: &str) -> Result<OpenQasmProgram, QasmSimError<'_>> {
+    parse_program_with_options(source, &LanguageOptions::new())
+}
+
+/// Parse `source` into a [`OpenQasmProgram`] AST, accepting the extensions
+/// to OPENQASM 2.0 enabled in `options`.
+///
+/// # Errors
+///
+/// Besides the errors of [`parse_program()`], the function fails if the
+/// program uses an extension not enabled in `options`, returning an `Err`
+/// variant wrapping [`QasmSimError::DisabledExtension`].
+///
+/// # Examples
+///
+/// ```
+/// use qasmsim::grammar::{parse_program_with_options, Extension, LanguageOptions};
+///
+/// let source = r"
+/// OPENQASM 2.0;
+/// qreg q[1];
+/// creg c[2];
+/// if (c[0]==1 && c[1]==0) U(pi, 0, pi) q[0];
+/// ";
+///
+/// assert!(parse_program_with_options(source, &LanguageOptions::new()).is_err());
+///
+/// let options = LanguageOptions::new().allow(Extension::ExtendedConditionals);
+/// assert!(parse_program_with_options(source, &options).is_ok());
+/// ```
+///
+/// [`OpenQasmProgram`]: ./ast/struct.OpenQasmProgram.html
+/// [`parse_program()`]: ./fn.parse_program.html
+/// [`QasmSimError::DisabledExtension`]: ../error/enum.QasmSimError.html#variant.DisabledExtension
+pub fn parse_program_with_options<'src>(
+    source: &'src str,
+    options: &LanguageOptions,
+) -> Result<OpenQasmProgram, QasmSimError<'src>> {
+    let mut header = Lexer::new(source).take(2);
+    let open_qasm3_header = match (header.next(), header.next()) {
+        (Some(Ok((location, Tok::QASMHeader, _))), Some(Ok((_, Tok::Version { repr }, _))))
+            if repr == "3" || repr.starts_with("3.") =>
+        {
+            Some(location)
+        }
+        _ => None,
+    };
+    if let Some(location) = open_qasm3_header {
+        options
+            .assert_allows(Extension::OpenQasm3, location)
+            .map_err(|err| (source, err))?;
+        qasm3::parse_program(source)
+    } else {
+        let program = parse_qasm2_program(source)?;
+        options::check_extensions(&program, options).map_err(|err| (source, err))?;
+        Ok(program)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use indoc::indoc;
@@ -313,9 +552,15 @@ mod tests {
 
     macro_rules! span {
         ($left:expr, $node:expr, $right:expr) => {
+            Span::new((Location($left), Location($right)), $node)
+        };
+        ($left:expr, $node:expr, $right:expr, parameters: [$(($pl:expr, $pr:expr)),*], arguments: [$(($al:expr, $ar:expr)),*]) => {
             Span {
                 boundaries: (Location($left), Location($right)),
                 node: Box::new($node),
+                parameters: vec![$((Location($pl), Location($pr))),*],
+                arguments: vec![$((Location($al), Location($ar))),*],
+                operations: vec![],
             }
         };
     }
@@ -331,7 +576,7 @@ mod tests {
         );
         let lexer = Lexer::new(source);
         let parser = open_qasm2::OpenQasmProgramParser::new();
-        let tree = parser.parse(lexer).unwrap();
+        let tree = parser.parse(&mut vec![], lexer).unwrap();
         assert_eq!(
             tree,
             OpenQasmProgram {
@@ -351,7 +596,7 @@ mod tests {
     ";
         let lexer = Lexer::new(source);
         let parser = open_qasm2::StatementParser::new();
-        let tree = parser.parse(lexer).unwrap();
+        let tree = parser.parse(&mut vec![], lexer).unwrap();
         assert_eq!(
             tree,
             Statement::GateDecl {
@@ -368,7 +613,7 @@ mod tests {
     ";
         let lexer = Lexer::new(source);
         let parser = open_qasm2::StatementParser::new();
-        let tree = parser.parse(lexer).unwrap();
+        let tree = parser.parse(&mut vec![], lexer).unwrap();
         assert_eq!(
             tree,
             Statement::GateDecl {
@@ -387,7 +632,7 @@ mod tests {
     ";
         let lexer = Lexer::new(source);
         let parser = open_qasm2::StatementParser::new();
-        let tree = parser.parse(lexer).unwrap();
+        let tree = parser.parse(&mut vec![], lexer).unwrap();
         assert_eq!(
             tree,
             Statement::GateDecl {
@@ -415,7 +660,7 @@ mod tests {
     ";
         let lexer = Lexer::new(source);
         let parser = open_qasm2::StatementParser::new();
-        let tree = parser.parse(lexer).unwrap();
+        let tree = parser.parse(&mut vec![], lexer).unwrap();
         assert_eq!(
             tree,
             Statement::GateDecl {
@@ -447,7 +692,7 @@ mod tests {
     ";
         let lexer = Lexer::new(source);
         let parser = open_qasm2::StatementParser::new();
-        let tree = parser.parse(lexer).unwrap();
+        let tree = parser.parse(&mut vec![], lexer).unwrap();
         assert_eq!(
             tree,
             Statement::GateDecl {
@@ -473,7 +718,7 @@ mod tests {
     ";
         let lexer = Lexer::new(source);
         let parser = open_qasm2::StatementParser::new();
-        let tree = parser.parse(lexer).unwrap();
+        let tree = parser.parse(&mut vec![], lexer).unwrap();
         assert_eq!(
             tree,
             Statement::QuantumOperation(QuantumOperation::Unitary(UnitaryOperation(
@@ -499,7 +744,7 @@ mod tests {
     ";
         let lexer = Lexer::new(source);
         let parser = open_qasm2::ExprParser::new();
-        let tree = parser.parse(lexer).unwrap();
+        let tree = parser.parse(&mut vec![], lexer).unwrap();
         assert_eq!(
             tree,
             Expression::Op(
@@ -522,6 +767,34 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_extended_functions_and_constants() {
+        let source = "
+    atan2(floor(tau), mod(euler, asin(1)))
+    ";
+        let lexer = Lexer::new(source);
+        let parser = open_qasm2::ExprParser::new();
+        let tree = parser.parse(&mut vec![], lexer).unwrap();
+        assert_eq!(
+            tree,
+            Expression::BinaryFunction(
+                BinaryFuncCode::Atan2,
+                Box::new(Expression::Function(
+                    FuncCode::Floor,
+                    Box::new(Expression::Tau)
+                )),
+                Box::new(Expression::BinaryFunction(
+                    BinaryFuncCode::Mod,
+                    Box::new(Expression::Euler),
+                    Box::new(Expression::Function(
+                        FuncCode::Asin,
+                        Box::new(Expression::Real(1.0))
+                    ))
+                ))
+            )
+        );
+    }
+
     #[test]
     fn test_parse_program_without_version_string() {
         let source = indoc!(
@@ -533,7 +806,7 @@ mod tests {
         );
         let lexer = Lexer::new(source);
         let parser = open_qasm2::ProgramBodyParser::new();
-        let tree = parser.parse(lexer).unwrap();
+        let tree = parser.parse(&mut vec![], lexer).unwrap();
         assert_eq!(
             tree,
             vec![
@@ -546,7 +819,43 @@ mod tests {
                         vec![],
                         vec![Argument::Id("q".to_string())]
                     ))),
-                    26
+                    26,
+                    parameters: [],
+                    arguments: [(24, 25)]
+                )
+            ]
+        );
+    }
+
+    #[test]
+    fn test_statements_keep_the_boundaries_of_parameters_and_arguments() {
+        let source = indoc!(
+            "
+    U(pi / 2, 0, x) q[0];
+    barrier q, r;
+    "
+        );
+        let lexer = Lexer::new(source);
+        let parser = open_qasm2::ProgramBodyParser::new();
+        let tree = parser.parse(&mut vec![], lexer).unwrap();
+        let parts: Vec<_> = tree
+            .iter()
+            .map(|span| (span.parameters.clone(), span.arguments.clone()))
+            .collect();
+        assert_eq!(
+            parts,
+            vec![
+                (
+                    vec![
+                        (Location(2), Location(8)),
+                        (Location(10), Location(11)),
+                        (Location(13), Location(14))
+                    ],
+                    vec![(Location(16), Location(20))]
+                ),
+                (
+                    vec![],
+                    vec![(Location(30), Location(31)), (Location(33), Location(34))]
                 )
             ]
         );
@@ -565,7 +874,7 @@ mod tests {
         );
         let lexer = Lexer::new(source);
         let parser = open_qasm2::ProgramBodyParser::new();
-        let tree = parser.parse(lexer).unwrap();
+        let tree = parser.parse(&mut vec![], lexer).unwrap();
         assert_eq!(
             tree,
             vec![
@@ -578,7 +887,9 @@ mod tests {
                         vec![],
                         vec![Argument::Id("q".to_string())]
                     ))),
-                    26
+                    26,
+                    parameters: [],
+                    arguments: [(24, 25)]
                 ),
                 span!(
                     27,
@@ -586,14 +897,18 @@ mod tests {
                         Argument::Id("q".to_string()),
                         Argument::Id("c".to_string())
                     )),
-                    42
+                    42,
+                    parameters: [],
+                    arguments: [(35, 36), (40, 41)]
                 ),
                 span!(
                     43,
                     Statement::QuantumOperation(QuantumOperation::Reset(Argument::Id(
                         "q".to_string()
                     ))),
-                    51
+                    51,
+                    parameters: [],
+                    arguments: [(49, 50)]
                 )
             ]
         );
@@ -614,7 +929,7 @@ mod tests {
         );
         let lexer = Lexer::new(source);
         let parser = open_qasm2::OpenQasmProgramParser::new();
-        let tree = parser.parse(lexer).unwrap();
+        let tree = parser.parse(&mut vec![], lexer).unwrap();
         assert_eq!(
             tree,
             OpenQasmProgram {
@@ -649,7 +964,7 @@ mod tests {
         );
         let lexer = Lexer::new(source);
         let parser = open_qasm2::OpenQasmProgramParser::new();
-        let tree = parser.parse(lexer).unwrap();
+        let tree = parser.parse(&mut vec![], lexer).unwrap();
         assert_eq!(
             tree,
             OpenQasmProgram {
@@ -666,6 +981,106 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_gate_modifiers() {
+        let source = "
+    ctrl(2) @ inv @ pow(-1) @ u1(pi) a, b, c;
+    ";
+        let lexer = Lexer::new(source);
+        let parser = open_qasm2::StatementParser::new();
+        let tree = parser.parse(&mut vec![], lexer).unwrap();
+        assert_eq!(
+            tree,
+            Statement::QuantumOperation(QuantumOperation::Modified(
+                vec![
+                    GateModifier::Ctrl(2),
+                    GateModifier::Inv,
+                    GateModifier::Pow(Expression::Minus(Box::new(Expression::Real(1.0))))
+                ],
+                UnitaryOperation(
+                    String::from("u1"),
+                    vec![Expression::Pi],
+                    vec![
+                        Argument::Id(String::from("a")),
+                        Argument::Id(String::from("b")),
+                        Argument::Id(String::from("c"))
+                    ]
+                )
+            ))
+        );
+    }
+
+    #[test]
+    fn test_gate_modifiers_inside_gate_definitions() {
+        let source = "
+    gate cs a, b { ctrl @ U(0, 0, pi/2) a, b; }
+    ";
+        let lexer = Lexer::new(source);
+        let parser = open_qasm2::StatementParser::new();
+        let tree = parser.parse(&mut vec![], lexer).unwrap();
+        assert_eq!(
+            tree,
+            Statement::GateDecl {
+                signature: (
+                    String::from("cs"),
+                    vec![],
+                    vec![String::from("a"), String::from("b")],
+                    vec![GateOperation::Modified(
+                        vec![GateModifier::Ctrl(1)],
+                        UnitaryOperation(
+                            String::from("U"),
+                            vec![
+                                Expression::Real(0.0),
+                                Expression::Real(0.0),
+                                Expression::Op(
+                                    OpCode::Div,
+                                    Box::new(Expression::Pi),
+                                    Box::new(Expression::Real(2.0))
+                                )
+                            ],
+                            vec![
+                                Argument::Id(String::from("a")),
+                                Argument::Id(String::from("b"))
+                            ]
+                        )
+                    )]
+                ),
+                docstring: None
+            }
+        );
+    }
+
+    #[test]
+    fn test_gates_can_be_named_after_modifiers() {
+        let source = "
+    inv q;
+    ";
+        let lexer = Lexer::new(source);
+        let parser = open_qasm2::StatementParser::new();
+        let tree = parser.parse(&mut vec![], lexer).unwrap();
+        assert_eq!(
+            tree,
+            Statement::QuantumOperation(QuantumOperation::Unitary(UnitaryOperation(
+                String::from("inv"),
+                vec![],
+                vec![Argument::Id(String::from("q"))]
+            )))
+        );
+    }
+
+    #[test]
+    fn test_unknown_modifiers_are_rejected() {
+        for source in &["foo @ x q;", "ctrl(1.5) @ x a, b;", "inv(2) @ x q;"] {
+            let lexer = Lexer::new(source);
+            let parser = open_qasm2::StatementParser::new();
+            assert!(
+                parser.parse(&mut vec![], lexer).is_err(),
+                "`{}` should not parse",
+                source
+            );
+        }
+    }
+
     #[test]
     fn test_conditional_application() {
         let source = "
@@ -673,7 +1088,7 @@ mod tests {
     ";
         let lexer = Lexer::new(source);
         let parser = open_qasm2::StatementParser::new();
-        let tree = parser.parse(lexer).unwrap();
+        let tree = parser.parse(&mut vec![], lexer).unwrap();
         assert_eq!(
             tree,
             Statement::Conditional(
@@ -690,4 +1105,66 @@ mod tests {
             )
         );
     }
+
+    #[test]
+    fn test_extended_conditional_application() {
+        let source = "
+    if (c[0]==1 && d!=2) x t;
+    ";
+        let lexer = Lexer::new(source);
+        let parser = open_qasm2::StatementParser::new();
+        let tree = parser.parse(&mut vec![], lexer).unwrap();
+        assert_eq!(
+            tree,
+            Statement::IfElse {
+                condition: Condition(vec![
+                    Comparison(Argument::Item(String::from("c"), 0), ComparisonOp::Equal, 1),
+                    Comparison(Argument::Id(String::from("d")), ComparisonOp::NotEqual, 2)
+                ]),
+                then_branch: vec![span!(
+                    26,
+                    Statement::QuantumOperation(QuantumOperation::Unitary(UnitaryOperation(
+                        String::from("x"),
+                        vec![],
+                        vec![Argument::Id(String::from("t"))]
+                    ))),
+                    30,
+                    parameters: [],
+                    arguments: [(28, 29)]
+                )],
+                else_branch: vec![]
+            }
+        );
+    }
+
+    #[test]
+    fn test_comparison_operators() {
+        let operators = vec![
+            ("==", ComparisonOp::Equal),
+            ("!=", ComparisonOp::NotEqual),
+            ("<", ComparisonOp::Less),
+            ("<=", ComparisonOp::LessOrEqual),
+            (">", ComparisonOp::Greater),
+            (">=", ComparisonOp::GreaterOrEqual),
+        ];
+        for (symbol, operator) in operators {
+            let source = format!("if (c[1]{}1) x t;", symbol);
+            let lexer = Lexer::new(&source);
+            let parser = open_qasm2::StatementParser::new();
+            match parser.parse(&mut vec![], lexer).unwrap() {
+                Statement::IfElse { condition, .. } => assert_eq!(
+                    condition,
+                    Condition(vec![Comparison(
+                        Argument::Item(String::from("c"), 1),
+                        operator,
+                        1
+                    )])
+                ),
+                other => panic!(
+                    "`{}` should be an extended conditional: {:?}",
+                    source, other
+                ),
+            }
+        }
+    }
 }
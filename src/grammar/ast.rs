@@ -8,6 +8,8 @@
 //! statements, and adding new features to the language would require the
 //! modification os certain layouts.
 
+use std::fmt;
+
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
@@ -51,7 +53,10 @@ use crate::grammar::lexer::Location;
 ///                     "q".to_string(),
 ///                     1
 ///                 )
-///             )
+///             ),
+///             parameters: vec![],
+///             arguments: vec![],
+///             operations: vec![]
 ///         },
 ///         Span {
 ///             boundaries: (Location(25), Location(45)),
@@ -75,7 +80,14 @@ use crate::grammar::lexer::Location;
 ///                         )
 ///                     )
 ///                 )
-///             )
+///             ),
+///             parameters: vec![
+///                 (Location(27), Location(31)),
+///                 (Location(33), Location(34)),
+///                 (Location(36), Location(38))
+///             ],
+///             arguments: vec![(Location(40), Location(44))],
+///             operations: vec![]
 ///         }
 ///     ]
 /// };
@@ -90,6 +102,32 @@ pub struct OpenQasmProgram {
     pub program: Vec<Span<Statement>>,
 }
 
+/// Write the program in OPENQASM syntax, one statement per line. The
+/// statements produced by the OPENQASM 3 front end are written in the syntax
+/// both languages share, or in OPENQASM 3 syntax for `if`/`else` blocks.
+///
+/// # Examples
+///
+/// ```
+/// use qasmsim::grammar::parse_program;
+///
+/// let program = parse_program("OPENQASM 2.0; qreg q[1]; U(pi/2,0,pi) q[0];")?;
+/// assert_eq!(
+///     program.to_string(),
+///     "OPENQASM 2.0;\nqreg q[1];\nU(pi / 2, 0, pi) q[0];\n"
+/// );
+/// # Ok::<(), qasmsim::QasmSimError>(())
+/// ```
+impl fmt::Display for OpenQasmProgram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "OPENQASM {};", self.version)?;
+        for span in &self.program {
+            writeln!(f, "{}", span)?;
+        }
+        Ok(())
+    }
+}
+
 /// Represent a OPENQASM library. OPENQASM libraries can contain gate
 /// declarations only.
 ///
@@ -157,6 +195,16 @@ pub struct OpenQasmLibrary {
     pub definitions: Vec<Statement>,
 }
 
+/// Write the gate declarations of the library, one per line.
+impl fmt::Display for OpenQasmLibrary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for definition in &self.definitions {
+            writeln!(f, "{}", definition)?;
+        }
+        Ok(())
+    }
+}
+
 // TODO: This should not be part of the grammar. It is a directive for
 // the optimizer or compiler.
 
@@ -189,6 +237,14 @@ pub struct OpenQasmLibrary {
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct BarrierPragma(pub Vec<Argument>);
 
+impl fmt::Display for BarrierPragma {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "barrier ")?;
+        fmt_list(f, &self.0)?;
+        write!(f, ";")
+    }
+}
+
 /// Each of the statements you can find in a OPENQASM program.
 ///
 /// OPENQASM programs are made exclusively of list of statements. The statements
@@ -253,6 +309,180 @@ pub enum Statement {
     /// equality condition holds. The wrapper takes the left-side of the
     /// comparison, the right side, and the operation to perform.
     Conditional(Argument, u64, QuantumOperation),
+    /// A conditional block with an optional alternative. The first list of
+    /// statements runs if the condition holds, the second one otherwise.
+    /// Produced by the OPENQASM 3 front end and by extended conditionals in
+    /// OPENQASM 2.
+    IfElse {
+        /// The condition to test.
+        condition: Condition,
+        /// Statements to run if the condition holds.
+        then_branch: Vec<Span<Statement>>,
+        /// Statements to run if the condition does not hold.
+        else_branch: Vec<Span<Statement>>,
+    },
+}
+
+impl Statement {
+    /// Return the real parameters passed to the gate of the statement, if
+    /// any. The exponents of `pow` modifiers are not included.
+    pub fn parameters(&self) -> Vec<&Expression> {
+        match self {
+            Statement::QuantumOperation(operation) | Statement::Conditional(_, _, operation) => {
+                match operation {
+                    QuantumOperation::Unitary(UnitaryOperation(_, real_args, _))
+                    | QuantumOperation::Modified(_, UnitaryOperation(_, real_args, _)) => {
+                        real_args.iter().collect()
+                    }
+                    _ => vec![],
+                }
+            }
+            _ => vec![],
+        }
+    }
+
+    /// Return the arguments of the statement in the order they appear in the
+    /// source, including the registers tested by conditionals.
+    pub fn arguments(&self) -> Vec<&Argument> {
+        fn operation_arguments(operation: &QuantumOperation) -> Vec<&Argument> {
+            match operation {
+                QuantumOperation::Unitary(UnitaryOperation(_, _, args))
+                | QuantumOperation::Modified(_, UnitaryOperation(_, _, args)) => {
+                    args.iter().collect()
+                }
+                QuantumOperation::Measure(source, target) => vec![source, target],
+                QuantumOperation::Reset(target) => vec![target],
+            }
+        }
+
+        match self {
+            Statement::Barrier(BarrierPragma(args)) => args.iter().collect(),
+            Statement::QuantumOperation(operation) => operation_arguments(operation),
+            Statement::Conditional(register, _, operation) => {
+                let mut arguments = vec![register];
+                arguments.extend(operation_arguments(operation));
+                arguments
+            }
+            Statement::IfElse { condition, .. } => condition
+                .0
+                .iter()
+                .map(|Comparison(register, _, _)| register)
+                .collect(),
+            _ => vec![],
+        }
+    }
+}
+
+/// Write the statement in OPENQASM syntax. Gate declarations are preceded by
+/// their docstrings, as comments, and span several lines, with the body
+/// indented with two spaces, as do `if`/`else` blocks.
+///
+/// # Examples
+///
+/// ```
+/// use qasmsim::grammar::parse_statement;
+///
+/// let statement = parse_statement("// Flip.\ngate flip a { x a; }")?;
+/// assert_eq!(statement.to_string(), "// Flip.\ngate flip a {\n  x a;\n}");
+/// # Ok::<(), qasmsim::QasmSimError>(())
+/// ```
+impl fmt::Display for Statement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Statement::QRegDecl(name, size) => write!(f, "qreg {}[{}];", name, size),
+            Statement::CRegDecl(name, size) => write!(f, "creg {}[{}];", name, size),
+            Statement::GateDecl {
+                signature: (name, real_args, args, operations),
+                docstring,
+            } => {
+                fmt_docstring(f, docstring)?;
+                write!(f, "gate {} {{", gate_signature(name, real_args, args))?;
+                if operations.is_empty() {
+                    return write!(f, "}}");
+                }
+                for operation in operations {
+                    write!(f, "\n{}{}", INDENT, operation)?;
+                }
+                write!(f, "\n}}")
+            }
+            Statement::Include(libpath) => write!(f, "include \"{}\";", libpath),
+            Statement::Barrier(barrier) => write!(f, "{}", barrier),
+            Statement::OpaqueGateDecl {
+                signature: (name, real_args, args),
+                docstring,
+            } => {
+                fmt_docstring(f, docstring)?;
+                write!(f, "opaque {};", gate_signature(name, real_args, args))
+            }
+            Statement::QuantumOperation(operation) => write!(f, "{}", operation),
+            Statement::Conditional(register, value, operation) => {
+                write!(f, "if ({} == {}) {}", register, value, operation)
+            }
+            Statement::IfElse {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                // The conditional operations of OPENQASM 2 fit in a line.
+                if let ([span], []) = (then_branch.as_slice(), else_branch.as_slice()) {
+                    if let Statement::QuantumOperation(operation) = &*span.node {
+                        return write!(f, "if ({}) {}", condition, operation);
+                    }
+                }
+                write!(f, "if ({}) ", condition)?;
+                fmt_block(f, then_branch)?;
+                if !else_branch.is_empty() {
+                    write!(f, " else ")?;
+                    fmt_block(f, else_branch)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+const INDENT: &str = "  ";
+
+/// Return the name of a gate followed by its formal parameters, if any, and
+/// arguments, as in `rot(theta) a, b`.
+pub(crate) fn gate_signature(name: &str, real_args: &[String], args: &[String]) -> String {
+    if real_args.is_empty() {
+        format!("{} {}", name, args.join(", "))
+    } else {
+        format!("{}({}) {}", name, real_args.join(", "), args.join(", "))
+    }
+}
+
+fn fmt_docstring(f: &mut fmt::Formatter<'_>, docstring: &Option<String>) -> fmt::Result {
+    if let Some(docstring) = docstring {
+        for line in docstring.lines() {
+            writeln!(f, "//{}", line)?;
+        }
+    }
+    Ok(())
+}
+
+fn fmt_block(f: &mut fmt::Formatter<'_>, statements: &[Span<Statement>]) -> fmt::Result {
+    write!(f, "{{")?;
+    for span in statements {
+        for line in span.to_string().lines() {
+            write!(f, "\n{}{}", INDENT, line)?;
+        }
+    }
+    if !statements.is_empty() {
+        writeln!(f)?;
+    }
+    write!(f, "}}")
+}
+
+fn fmt_list<T: fmt::Display>(f: &mut fmt::Formatter<'_>, items: &[T]) -> fmt::Result {
+    for (index, item) in items.iter().enumerate() {
+        if index > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{}", item)?;
+    }
+    Ok(())
 }
 
 /// Relates a node with the fragment of source code where the node appears.
@@ -279,15 +509,25 @@ pub enum Statement {
 ///             "q".to_string(),
 ///             1
 ///         )
-///     )
+///     ),
+///     parameters: vec![],
+///     arguments: vec![],
+///     operations: vec![]
 /// };
 /// ```
 ///
 /// Boundaries run from characters 14 to 25 corresponding to the starting-0
 /// character index of the source code.
 ///
-/// Right, now, only statements are tied to spans making impossible to
-/// accurately localize inner AST nodes.
+/// Only statements are tied to spans. The spans of statements also keep the
+/// boundaries of the parameters and arguments of the statement, in the order
+/// returned by [`Statement::parameters()`] and [`Statement::arguments()`], so
+/// errors can point at them. The spans of gate declarations keep, in addition,
+/// the spans of the operations in the body, in the same order. They are empty
+/// when not known.
+///
+/// [`Statement::parameters()`]: ./enum.Statement.html#method.parameters
+/// [`Statement::arguments()`]: ./enum.Statement.html#method.arguments
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Span<S> {
@@ -295,6 +535,39 @@ pub struct Span<S> {
     pub boundaries: (Location, Location),
     /// Boxed AST node.
     pub node: Box<S>,
+    /// Pairs of source locations where the real parameters of the node can be
+    /// found.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub parameters: Vec<(Location, Location)>,
+    /// Pairs of source locations where the arguments of the node can be
+    /// found.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub arguments: Vec<(Location, Location)>,
+    /// Spans of the operations in the body of a gate declaration. Their nodes
+    /// are empty since the operations are found in the body.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub operations: Vec<Span<()>>,
+}
+
+impl<S> Span<S> {
+    /// Create a span for `node` without the boundaries of its parameters and
+    /// arguments.
+    pub fn new(boundaries: (Location, Location), node: S) -> Self {
+        Span {
+            boundaries,
+            node: Box::new(node),
+            parameters: vec![],
+            arguments: vec![],
+            operations: vec![],
+        }
+    }
+}
+
+/// Write the node of the span.
+impl<S: fmt::Display> fmt::Display for Span<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.node)
+    }
 }
 
 /// Any of the statements that can appear inside a gate definition.
@@ -313,10 +586,25 @@ pub struct Span<S> {
 pub enum GateOperation {
     /// A gate invocation.
     Unitary(UnitaryOperation),
+    /// A gate invocation affected by gate modifiers.
+    Modified(Vec<GateModifier>, UnitaryOperation),
     /// A barrier pragma.
     Barrier(BarrierPragma),
 }
 
+impl fmt::Display for GateOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GateOperation::Unitary(unitary) => write!(f, "{};", unitary),
+            GateOperation::Modified(modifiers, unitary) => {
+                fmt_modifiers(f, modifiers)?;
+                write!(f, "{};", unitary)
+            }
+            GateOperation::Barrier(barrier) => write!(f, "{}", barrier),
+        }
+    }
+}
+
 /// Any of the operations that actuates over quantum registers.
 ///
 /// # Examples
@@ -330,12 +618,30 @@ pub enum GateOperation {
 pub enum QuantumOperation {
     /// A gate invocation.
     Unitary(UnitaryOperation),
+    /// A gate invocation affected by gate modifiers.
+    Modified(Vec<GateModifier>, UnitaryOperation),
     /// A measurement on a quantum register to a classical register.
     Measure(Argument, Argument),
     /// A reset operation on a quantum register.
     Reset(Argument),
 }
 
+impl fmt::Display for QuantumOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QuantumOperation::Unitary(unitary) => write!(f, "{};", unitary),
+            QuantumOperation::Modified(modifiers, unitary) => {
+                fmt_modifiers(f, modifiers)?;
+                write!(f, "{};", unitary)
+            }
+            QuantumOperation::Measure(source, target) => {
+                write!(f, "measure {} -> {};", source, target)
+            }
+            QuantumOperation::Reset(target) => write!(f, "reset {};", target),
+        }
+    }
+}
+
 /// A gate "invocation".
 ///
 /// The name comes after the fact that all quantum gates are [unitary]
@@ -352,6 +658,171 @@ pub enum QuantumOperation {
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct UnitaryOperation(pub String, pub Vec<Expression>, pub Vec<Argument>);
 
+/// Write the gate invocation without the final semicolon, as in
+/// `U(pi / 2, 0, pi) q[0]`. The parentheses are omitted if there are no real
+/// parameters.
+impl fmt::Display for UnitaryOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let UnitaryOperation(name, real_args, args) = self;
+        write!(f, "{}", name)?;
+        if !real_args.is_empty() {
+            write!(f, "(")?;
+            fmt_list(f, real_args)?;
+            write!(f, ")")?;
+        }
+        write!(f, " ")?;
+        fmt_list(f, args)
+    }
+}
+
+/// A modifier altering the gate it precedes. A list of modifiers is applied
+/// from right to left, this is, the modifier closest to the gate goes first.
+///
+/// The control qubits introduced by `Ctrl` modifiers precede the arguments of
+/// the gate in the [`UnitaryOperation`], in the same order as the modifiers.
+///
+/// # Examples
+///
+/// The operation `ctrl @ inv @ s c, t;`, valid in OPENQASM 3 and accepted as
+/// an extension of OPENQASM 2, corresponds to:
+///
+/// ```
+/// use qasmsim::grammar::ast::{Argument, GateModifier, QuantumOperation, UnitaryOperation};
+///
+/// let operation = QuantumOperation::Modified(
+///     vec![GateModifier::Ctrl(1), GateModifier::Inv],
+///     UnitaryOperation(
+///         "s".to_string(),
+///         vec![],
+///         vec![Argument::Id("c".to_string()), Argument::Id("t".to_string())]
+///     )
+/// );
+/// ```
+///
+/// [`UnitaryOperation`]: ./struct.UnitaryOperation.html
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum GateModifier {
+    /// Replace the gate with its inverse.
+    Inv,
+    /// Control the gate on the given number of qubits.
+    Ctrl(usize),
+    /// Repeat the gate the given number of times. Negative integers repeat
+    /// the inverse of the gate.
+    Pow(Expression),
+}
+
+/// Write the modifier without the `@` separator.
+impl fmt::Display for GateModifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GateModifier::Inv => write!(f, "inv"),
+            GateModifier::Ctrl(1) => write!(f, "ctrl"),
+            GateModifier::Ctrl(count) => write!(f, "ctrl({})", count),
+            GateModifier::Pow(exponent) => write!(f, "pow({})", exponent),
+        }
+    }
+}
+
+fn fmt_modifiers(f: &mut fmt::Formatter<'_>, modifiers: &[GateModifier]) -> fmt::Result {
+    for modifier in modifiers {
+        write!(f, "{} @ ", modifier)?;
+    }
+    Ok(())
+}
+
+/// A condition on the classical memory. The condition holds if all the
+/// comparisons hold.
+///
+/// # Examples
+///
+/// The condition of `if (c[0] == 1 && d < 2) x q;` corresponds to:
+///
+/// ```
+/// use qasmsim::grammar::ast::{Argument, Comparison, ComparisonOp, Condition};
+///
+/// let condition = Condition(vec![
+///     Comparison(Argument::Item("c".to_string(), 0), ComparisonOp::Equal, 1),
+///     Comparison(Argument::Id("d".to_string()), ComparisonOp::Less, 2),
+/// ]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Condition(pub Vec<Comparison>);
+
+/// Write the comparisons of the condition joined by `&&`.
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, comparison) in self.0.iter().enumerate() {
+            if index > 0 {
+                write!(f, " && ")?;
+            }
+            write!(f, "{}", comparison)?;
+        }
+        Ok(())
+    }
+}
+
+/// A comparison between a classical register, or one of its bits, and an
+/// integer value.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Comparison(pub Argument, pub ComparisonOp, pub u64);
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.0, self.1, self.2)
+    }
+}
+
+/// Any of the operators that can appear in a comparison.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ComparisonOp {
+    /// Code for the equality operator `==`.
+    Equal,
+    /// Code for the inequality operator `!=`.
+    NotEqual,
+    /// Code for the operator `<`.
+    Less,
+    /// Code for the operator `<=`.
+    LessOrEqual,
+    /// Code for the operator `>`.
+    Greater,
+    /// Code for the operator `>=`.
+    GreaterOrEqual,
+}
+
+impl ComparisonOp {
+    /// Compare `left` and `right` with the operator.
+    pub fn compare(self, left: u64, right: u64) -> bool {
+        match self {
+            ComparisonOp::Equal => left == right,
+            ComparisonOp::NotEqual => left != right,
+            ComparisonOp::Less => left < right,
+            ComparisonOp::LessOrEqual => left <= right,
+            ComparisonOp::Greater => left > right,
+            ComparisonOp::GreaterOrEqual => left >= right,
+        }
+    }
+}
+
+impl fmt::Display for ComparisonOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            ComparisonOp::Equal => "==",
+            ComparisonOp::NotEqual => "!=",
+            ComparisonOp::Less => "<",
+            ComparisonOp::LessOrEqual => "<=",
+            ComparisonOp::Greater => ">",
+            ComparisonOp::GreaterOrEqual => ">=",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
 /// Any of the operators that can appear in an expression.
 ///
 /// # Examples
@@ -393,6 +864,28 @@ pub enum FuncCode {
     Ln,
     /// Function square root `sqrt`.
     Sqrt,
+    /// Function arcsine `asin`.
+    Asin,
+    /// Function arccosine `acos`.
+    Acos,
+    /// Function arctangent `atan`.
+    Atan,
+    /// Function floor `floor`.
+    Floor,
+    /// Function ceiling `ceil`.
+    Ceil,
+}
+
+/// Any of the functions of two arguments that can appear in an expression.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum BinaryFuncCode {
+    /// Function arctangent of the quotient of its arguments `atan2`, taking
+    /// the signs of both into account.
+    Atan2,
+    /// Function modulo `mod`. The result has the same sign as the divisor.
+    Mod,
 }
 
 /// Any of the subexpressions that can appear inside a expression.
@@ -408,6 +901,10 @@ pub enum FuncCode {
 pub enum Expression {
     /// The pi constant `pi`.
     Pi,
+    /// The tau constant `tau`, equal to `2*pi`.
+    Tau,
+    /// The Euler's number constant `euler`.
+    Euler,
     /// A valid OPENQASM identifier.
     Id(String),
     /// A real number.
@@ -418,10 +915,119 @@ pub enum Expression {
     Op(OpCode, Box<Expression>, Box<Expression>),
     /// A call to a function.
     Function(FuncCode, Box<Expression>),
+    /// A call to a function of two arguments.
+    BinaryFunction(BinaryFuncCode, Box<Expression>, Box<Expression>),
     /// A negation of an expression.
     Minus(Box<Expression>),
 }
 
+impl OpCode {
+    fn symbol(self) -> &'static str {
+        match self {
+            OpCode::Add => "+",
+            OpCode::Sub => "-",
+            OpCode::Mul => "*",
+            OpCode::Div => "/",
+            OpCode::Pow => "^",
+        }
+    }
+
+    fn precedence(self) -> u8 {
+        match self {
+            OpCode::Add | OpCode::Sub => 1,
+            OpCode::Mul | OpCode::Div => 2,
+            OpCode::Pow => 3,
+        }
+    }
+}
+
+impl FuncCode {
+    fn name(self) -> &'static str {
+        match self {
+            FuncCode::Sin => "sin",
+            FuncCode::Cos => "cos",
+            FuncCode::Tan => "tan",
+            FuncCode::Exp => "exp",
+            FuncCode::Ln => "ln",
+            FuncCode::Sqrt => "sqrt",
+            FuncCode::Asin => "asin",
+            FuncCode::Acos => "acos",
+            FuncCode::Atan => "atan",
+            FuncCode::Floor => "floor",
+            FuncCode::Ceil => "ceil",
+        }
+    }
+}
+
+impl BinaryFuncCode {
+    fn name(self) -> &'static str {
+        match self {
+            BinaryFuncCode::Atan2 => "atan2",
+            BinaryFuncCode::Mod => "mod",
+        }
+    }
+}
+
+impl Expression {
+    /// Return the precedence of the outermost operation of the expression.
+    /// Atoms, function calls and negations bind the tightest.
+    fn precedence(&self) -> u8 {
+        match self {
+            Expression::Op(op_code, _, _) => op_code.precedence(),
+            _ => u8::MAX,
+        }
+    }
+
+    fn fmt_operand(&self, f: &mut fmt::Formatter<'_>, parenthesize: bool) -> fmt::Result {
+        if parenthesize {
+            write!(f, "({})", self)
+        } else {
+            write!(f, "{}", self)
+        }
+    }
+}
+
+/// Write the expression in OPENQASM 2 syntax with the minimum number of
+/// parentheses.
+///
+/// # Examples
+///
+/// ```
+/// use qasmsim::grammar::parse_expression;
+///
+/// let expression = parse_expression("-(pi/2) + 2*(theta - 1)")?;
+/// assert_eq!(expression.to_string(), "-(pi / 2) + 2 * (theta - 1)");
+/// # Ok::<(), qasmsim::QasmSimError>(())
+/// ```
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expression::Pi => write!(f, "pi"),
+            Expression::Tau => write!(f, "tau"),
+            Expression::Euler => write!(f, "euler"),
+            Expression::Id(name) => write!(f, "{}", name),
+            Expression::Real(value) => write!(f, "{}", value),
+            Expression::Int(value) => write!(f, "{}", value),
+            Expression::Op(op_code, left, right) => {
+                // Operators are left-associative.
+                left.fmt_operand(f, left.precedence() < op_code.precedence())?;
+                write!(f, " {} ", op_code.symbol())?;
+                right.fmt_operand(f, right.precedence() <= op_code.precedence())
+            }
+            Expression::Function(func_code, argument) => {
+                write!(f, "{}({})", func_code.name(), argument)
+            }
+            Expression::BinaryFunction(func_code, left, right) => {
+                write!(f, "{}({}, {})", func_code.name(), left, right)
+            }
+            Expression::Minus(operand) => {
+                write!(f, "-")?;
+                operand.fmt_operand(f, matches!(**operand, Expression::Op(..)))
+            }
+        }
+    }
+}
+
 /// A reference to a register or register component.
 ///
 /// # Examples
@@ -454,3 +1060,144 @@ pub enum Argument {
     /// One of the bits/qubits of a register `q[0]`.
     Item(String, usize),
 }
+
+impl fmt::Display for Argument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Argument::Id(name) => write!(f, "{}", name),
+            Argument::Item(name, index) => write!(f, "{}[{}]", name, index),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::*;
+
+    fn id(name: &str) -> Box<Expression> {
+        Box::new(Expression::Id(name.into()))
+    }
+
+    fn op(op_code: OpCode, left: Box<Expression>, right: Box<Expression>) -> Box<Expression> {
+        Box::new(Expression::Op(op_code, left, right))
+    }
+
+    #[test]
+    fn test_expressions_have_the_minimum_parentheses() {
+        let cases = vec![
+            (
+                op(OpCode::Sub, id("a"), op(OpCode::Sub, id("b"), id("c"))),
+                "a - (b - c)",
+            ),
+            (
+                op(OpCode::Sub, op(OpCode::Sub, id("a"), id("b")), id("c")),
+                "a - b - c",
+            ),
+            (
+                op(OpCode::Mul, op(OpCode::Add, id("a"), id("b")), id("c")),
+                "(a + b) * c",
+            ),
+            (
+                op(OpCode::Add, id("a"), op(OpCode::Mul, id("b"), id("c"))),
+                "a + b * c",
+            ),
+            (
+                op(OpCode::Pow, id("a"), op(OpCode::Pow, id("b"), id("c"))),
+                "a ^ (b ^ c)",
+            ),
+            (
+                Box::new(Expression::Minus(op(OpCode::Add, id("a"), id("b")))),
+                "-(a + b)",
+            ),
+            (
+                Box::new(Expression::Function(
+                    FuncCode::Sqrt,
+                    Box::new(Expression::Minus(Box::new(Expression::Real(0.5)))),
+                )),
+                "sqrt(-0.5)",
+            ),
+        ];
+        for (expression, expected) in cases {
+            assert_eq!(expression.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn test_gate_declarations_span_several_lines() {
+        let statement = Statement::GateDecl {
+            signature: (
+                "g".into(),
+                vec!["theta".into()],
+                vec!["a".into(), "b".into()],
+                vec![
+                    GateOperation::Modified(
+                        vec![GateModifier::Ctrl(1), GateModifier::Pow(Expression::Int(2))],
+                        UnitaryOperation(
+                            "rx".into(),
+                            vec![Expression::Id("theta".into())],
+                            vec![Argument::Id("a".into()), Argument::Id("b".into())],
+                        ),
+                    ),
+                    GateOperation::Barrier(BarrierPragma(vec![Argument::Id("a".into())])),
+                ],
+            ),
+            docstring: Some(" A gate.\n Documented.\n".into()),
+        };
+        assert_eq!(
+            statement.to_string(),
+            indoc!(
+                "
+                // A gate.
+                // Documented.
+                gate g(theta) a, b {
+                  ctrl @ pow(2) @ rx(theta) a, b;
+                  barrier a;
+                }"
+            )
+        );
+    }
+
+    #[test]
+    fn test_conditional_blocks_are_indented() {
+        let operation = |name: &str| {
+            Span::new(
+                (Location(0), Location(0)),
+                Statement::QuantumOperation(QuantumOperation::Unitary(UnitaryOperation(
+                    name.into(),
+                    vec![],
+                    vec![Argument::Item("q".into(), 0)],
+                ))),
+            )
+        };
+        let condition = Condition(vec![
+            Comparison(Argument::Item("c".into(), 0), ComparisonOp::Equal, 1),
+            Comparison(Argument::Id("d".into()), ComparisonOp::LessOrEqual, 2),
+        ]);
+        let statement = Statement::IfElse {
+            condition: condition.clone(),
+            then_branch: vec![operation("x"), operation("y")],
+            else_branch: vec![operation("z")],
+        };
+        assert_eq!(
+            statement.to_string(),
+            indoc!(
+                "
+                if (c[0] == 1 && d <= 2) {
+                  x q[0];
+                  y q[0];
+                } else {
+                  z q[0];
+                }"
+            )
+        );
+
+        let statement = Statement::IfElse {
+            condition,
+            then_branch: vec![operation("x")],
+            else_branch: vec![],
+        };
+        assert_eq!(statement.to_string(), "if (c[0] == 1 && d <= 2) x q[0];");
+    }
+}
@@ -0,0 +1,391 @@
+//! Lay out OPENQASM 2.0 programs in a canonical format, keeping the comments
+//! of the source.
+//!
+//! The formatter writes one statement per line, indents gate bodies and
+//! normalizes the spacing inside statements. Comments are trivia the parser
+//! does not see, so they are placed back by their location: a comment
+//! following a statement in the same line stays at the end of the line and
+//! any other comment goes in its own line before the next statement. Runs of
+//! blank lines are collapsed into one, and blank lines are never added, so
+//! comments documenting gates keep documenting them.
+
+use crate::grammar::ast::{gate_signature, GateOperation, OpenQasmProgram, Span, Statement};
+use crate::grammar::lexer::{self, Location, Tok};
+
+const INDENT: &str = "  ";
+
+/// Return `source` formatted in the canonical layout. `program` must be the
+/// AST of `source`.
+pub(crate) fn format_program(source: &str, program: &OpenQasmProgram) -> String {
+    let tokens = lexer::tokenize(source);
+    let mut formatter = Formatter {
+        source,
+        comments: lexer::comments(source),
+        next_comment: 0,
+        lines: vec![],
+        last_end: 0,
+        at_block_start: true,
+    };
+    let header_end = tokens
+        .iter()
+        .find(|(_, token, _)| *token == Tok::Semi)
+        .map_or(0, |(_, _, end)| end.0);
+    let header_start = tokens.first().map_or(0, |(start, _, _)| start.0);
+    formatter.comments_before(header_start, 0);
+    formatter.line(
+        0,
+        (header_start, header_end),
+        format!("OPENQASM {};", program.version),
+    );
+    let next_start = program
+        .program
+        .first()
+        .map_or(source.len(), |span| span.boundaries.0 .0);
+    formatter.trailing_comment(header_end, next_start);
+    formatter.statements(&tokens, &program.program, 0, source.len());
+
+    let mut formatted = formatter.lines.join("\n");
+    formatted.push('\n');
+    formatted
+}
+
+struct Formatter<'src> {
+    source: &'src str,
+    comments: Vec<(Location, String, Location)>,
+    next_comment: usize,
+    lines: Vec<String>,
+    /// The end in the source of the last line written.
+    last_end: usize,
+    /// Whether the last line written opens a block, or nothing was written
+    /// yet, so no blank line must follow.
+    at_block_start: bool,
+}
+
+impl<'src> Formatter<'src> {
+    /// Write the statements of a block ending at `limit`, including the
+    /// comments before the end of the block.
+    fn statements(
+        &mut self,
+        tokens: &[(Location, Tok, Location)],
+        statements: &[Span<Statement>],
+        indent: usize,
+        limit: usize,
+    ) {
+        for (index, span) in statements.iter().enumerate() {
+            let (start, end) = (span.boundaries.0 .0, span.boundaries.1 .0);
+            // Comments documenting a gate are part of its span.
+            let code_start = tokens
+                .iter()
+                .find(|(location, token, _)| {
+                    location.0 >= start && !matches!(token, Tok::DocStr { .. })
+                })
+                .map_or(start, |(location, _, _)| location.0);
+            let next_start = statements
+                .get(index + 1)
+                .map_or(limit, |next| next.boundaries.0 .0);
+
+            match &*span.node {
+                Statement::GateDecl {
+                    signature: (name, real_args, args, operations),
+                    ..
+                } => {
+                    self.comments_before(code_start, indent);
+                    let head = format!("gate {} {{", gate_signature(name, real_args, args));
+                    self.gate_body(tokens, (code_start, end), head, operations, indent);
+                }
+                Statement::OpaqueGateDecl {
+                    signature: (name, real_args, args),
+                    ..
+                } => {
+                    // The docstring is among the comments of the source.
+                    self.comments_before(end, indent);
+                    let text = format!("opaque {};", gate_signature(name, real_args, args));
+                    self.line(indent, (code_start, end), text);
+                }
+                statement => {
+                    // Comments inside multiline statements go before them.
+                    self.comments_before(end, indent);
+                    self.line(indent, (code_start, end), statement.to_string());
+                }
+            }
+            self.trailing_comment(end, next_start);
+        }
+        self.comments_before(limit, indent);
+    }
+
+    /// Write the line `head` opening the declaration of a gate between
+    /// `start` and `end`, followed by the operations of its body. Comments
+    /// between the start of the declaration and the opening brace go at the
+    /// beginning of the body.
+    fn gate_body(
+        &mut self,
+        tokens: &[(Location, Tok, Location)],
+        (start, end): (usize, usize),
+        head: String,
+        operations: &[GateOperation],
+        indent: usize,
+    ) {
+        let boundaries = operation_boundaries(tokens, start, end);
+        let open_brace = tokens
+            .iter()
+            .find(|(location, token, _)| location.0 >= start && *token == Tok::LBrace)
+            .map_or(start, |(_, _, end)| end.0);
+        let close_brace = end.saturating_sub(1);
+        let first_start = boundaries.first().map_or(close_brace, |(start, _)| *start);
+
+        let header_comments = self.take_comments_before(open_brace);
+        self.line(indent, (start, open_brace), head);
+        let head_index = self.lines.len() - 1;
+        self.at_block_start = true;
+        let has_trailing_comment = self.trailing_comment(open_brace, first_start);
+        for (_, comment, _) in header_comments {
+            self.lines
+                .push(format!("{}{}", INDENT.repeat(indent + 1), comment));
+        }
+        for (index, operation) in operations.iter().enumerate() {
+            let (start, end) = boundaries
+                .get(index)
+                .copied()
+                .unwrap_or((self.last_end, self.last_end));
+            let next_start = boundaries
+                .get(index + 1)
+                .map_or(close_brace, |(start, _)| *start);
+            self.comments_before(end, indent + 1);
+            self.line(indent + 1, (start, end), operation.to_string());
+            self.trailing_comment(end, next_start);
+        }
+        self.comments_before(close_brace, indent + 1);
+
+        if self.lines.len() == head_index + 1 && !has_trailing_comment {
+            self.lines[head_index].push('}');
+        } else {
+            self.lines.push(format!("{}}}", INDENT.repeat(indent)));
+        }
+        self.last_end = end;
+        self.at_block_start = false;
+    }
+
+    /// Write `text` in a new line, preceded by a blank line if the source has
+    /// blank lines before `start`.
+    fn line(&mut self, indent: usize, (start, end): (usize, usize), text: String) {
+        let gap = self.source.get(self.last_end..start).unwrap_or_default();
+        let mut gap_lines = gap.split('\n');
+        gap_lines.next_back();
+        let has_blank_line = gap_lines.skip(1).any(|line| line.trim().is_empty());
+        if !self.at_block_start && has_blank_line {
+            self.lines.push(String::new());
+        }
+        self.lines
+            .push(format!("{}{}", INDENT.repeat(indent), text));
+        self.last_end = self.last_end.max(end);
+        self.at_block_start = false;
+    }
+
+    /// Write each comment starting before `location` in its own line.
+    fn comments_before(&mut self, location: usize, indent: usize) {
+        while let Some((start, comment, end)) = self.comments.get(self.next_comment).cloned() {
+            if start.0 >= location {
+                break;
+            }
+            self.next_comment += 1;
+            self.line(indent, (start.0, end.0), comment);
+        }
+    }
+
+    /// Remove the comments starting before `location` without writing them.
+    fn take_comments_before(&mut self, location: usize) -> Vec<(Location, String, Location)> {
+        let first = self.next_comment;
+        while let Some((start, _, _)) = self.comments.get(self.next_comment) {
+            if start.0 >= location {
+                break;
+            }
+            self.next_comment += 1;
+        }
+        self.comments[first..self.next_comment].to_vec()
+    }
+
+    /// Append to the last line the comment following `end` in the same line
+    /// of the source, if it starts before `next_start`. Return if there was
+    /// such a comment.
+    fn trailing_comment(&mut self, end: usize, next_start: usize) -> bool {
+        if let Some((start, comment, comment_end)) = self.comments.get(self.next_comment) {
+            let gap = self.source.get(end..start.0).unwrap_or("\n");
+            if start.0 < next_start && !gap.contains('\n') {
+                let last_line = self.lines.last_mut().expect("a line was written");
+                last_line.push(' ');
+                last_line.push_str(comment);
+                self.last_end = comment_end.0;
+                self.next_comment += 1;
+                return true;
+            }
+        }
+        false
+    }
+}
+
+/// Return the boundaries of the operations in the body of the gate declared
+/// between `start` and `end`. Each operation ends with a semicolon.
+fn operation_boundaries(
+    tokens: &[(Location, Tok, Location)],
+    start: usize,
+    end: usize,
+) -> Vec<(usize, usize)> {
+    let mut body = tokens
+        .iter()
+        .filter(|(location, _, _)| start <= location.0 && location.0 < end)
+        .skip_while(|(_, token, _)| *token != Tok::LBrace)
+        .skip(1);
+    let mut boundaries = vec![];
+    while let Some((first, token, last)) = body.next() {
+        if *token == Tok::RBrace {
+            break;
+        }
+        let operation_end = if *token == Tok::Semi {
+            last.0
+        } else {
+            body.find(|(_, token, _)| *token == Tok::Semi)
+                .map_or(end, |(_, _, last)| last.0)
+        };
+        boundaries.push((first.0, operation_end));
+    }
+    boundaries
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::*;
+    use crate::grammar::parse_program;
+
+    fn format(source: &str) -> String {
+        format_program(source, &parse_program(source).unwrap())
+    }
+
+    #[test]
+    fn test_normalize_the_spacing_and_indent_gate_bodies() {
+        let source = indoc!(
+            "
+            OPENQASM   2.0 ;
+            include\"qelib1.inc\";
+            gate rot ( theta,phi ) a,b { U(theta,0,-(phi/2)) a; CX a,b; }
+            gate id a {}
+            qreg q [2]; creg c[2];
+            rot(pi/2 , 0) q[0],q[1];
+            measure q->c; if(c==1) reset q;
+            "
+        );
+        assert_eq!(
+            format(source),
+            indoc!(
+                "
+                OPENQASM 2.0;
+                include \"qelib1.inc\";
+                gate rot(theta, phi) a, b {
+                  U(theta, 0, -(phi / 2)) a;
+                  CX a, b;
+                }
+                gate id a {}
+                qreg q[2];
+                creg c[2];
+                rot(pi / 2, 0) q[0], q[1];
+                measure q -> c;
+                if (c == 1) reset q;
+                "
+            )
+        );
+    }
+
+    #[test]
+    fn test_keep_comments_and_collapse_blank_lines() {
+        let source = indoc!(
+            "
+            // A program.
+            OPENQASM 2.0; // The version.
+
+
+            // Flip a qubit.
+            //   Twice.
+            gate flip a {
+            // The first flip.
+            x a; x a; // The second flip.
+            // Done.
+            }
+
+            qreg q[1]; // One qubit.
+            flip
+              // In the middle.
+              q[0];
+            // The end.
+            "
+        );
+        assert_eq!(
+            format(source),
+            indoc!(
+                "
+                // A program.
+                OPENQASM 2.0; // The version.
+
+                // Flip a qubit.
+                //   Twice.
+                gate flip a {
+                  // The first flip.
+                  x a;
+                  x a; // The second flip.
+                  // Done.
+                }
+
+                qreg q[1]; // One qubit.
+                // In the middle.
+                flip q[0];
+                // The end.
+                "
+            )
+        );
+    }
+
+    #[test]
+    fn test_comments_in_gate_headers_go_inside_the_body() {
+        let source = indoc!(
+            "
+            OPENQASM 2.0;
+            qreg q[1]; gate // Not a docstring.
+            g a { // The body.
+            }
+            "
+        );
+        let formatted = format(source);
+        assert_eq!(
+            formatted,
+            indoc!(
+                "
+                OPENQASM 2.0;
+                qreg q[1];
+                gate g a { // The body.
+                  // Not a docstring.
+                }
+                "
+            )
+        );
+        assert_eq!(
+            parse_program(&formatted).unwrap().program[1].node,
+            parse_program(source).unwrap().program[1].node
+        );
+    }
+
+    #[test]
+    fn test_formatting_is_idempotent() {
+        let source = indoc!(
+            "
+            OPENQASM 2.0;
+            include \"qelib1.inc\";
+            // Documented.
+            opaque magic(theta) a, b;
+            qreg q[3];
+            ctrl(2) @ inv @ pow(1 / 2) @ x q[0], q[1], q[2];
+            barrier q;
+            "
+        );
+        assert_eq!(format(source), source);
+    }
+}
@@ -129,8 +129,26 @@ pub enum Tok {
     Comma,
     /// The arrow symbol `->`.
     Arrow,
-    /// The equal symbol `=`.
+    /// The equal symbol `==`.
     Equal,
+    /// The inequality symbol `!=`.
+    NotEqual,
+    /// The less-than symbol `<`.
+    Less,
+    /// The less-than-or-equal symbol `<=`.
+    LessEqual,
+    /// The greater-than symbol `>`.
+    Greater,
+    /// The greater-than-or-equal symbol `>=`.
+    GreaterEqual,
+    /// The logical and symbol `&&`.
+    And,
+    /// The assignment symbol `=`. Only in OPENQASM 3.
+    Assign,
+    /// The modifier separator `@`.
+    At,
+    /// A colon `:`. Only in OPENQASM 3.
+    Colon,
     /// The sinus function id `sin`.
     Sin,
     /// The cosinus function id `cos`.
@@ -143,8 +161,26 @@ pub enum Tok {
     Ln,
     /// The square root function id `sqrt`.
     Sqrt,
+    /// The arcsine function id `asin`.
+    Asin,
+    /// The arccosine function id `acos`.
+    Acos,
+    /// The arctangent function id `atan`.
+    Atan,
+    /// The two-argument arctangent function id `atan2`.
+    Atan2,
+    /// The floor function id `floor`.
+    Floor,
+    /// The ceiling function id `ceil`.
+    Ceil,
+    /// The modulo function id `mod`.
+    Mod,
     /// The const `pi`.
     ConstPi,
+    /// The const `tau`.
+    ConstTau,
+    /// The const `euler`.
+    ConstEuler,
     /// The key-word `U`.
     U,
     /// The key-word `CX`.
@@ -167,6 +203,44 @@ pub enum Tok {
     Barrier,
     /// The key-word `if`.
     If,
+    /// The key-word `else`. Only in OPENQASM 3.
+    Else,
+    /// The key-word `for`. Only in OPENQASM 3.
+    For,
+    /// The key-word `in`. Only in OPENQASM 3.
+    In,
+    /// The key-word `qubit`. Only in OPENQASM 3.
+    Qubit,
+    /// The key-word `bit`. Only in OPENQASM 3.
+    Bit,
+    /// The key-word `const`. Only in OPENQASM 3.
+    Const,
+    /// The type `float`. Only in OPENQASM 3.
+    FloatType,
+    /// The type `int`. Only in OPENQASM 3.
+    IntType,
+    /// The type `uint`. Only in OPENQASM 3.
+    UIntType,
+    /// The type `angle`. Only in OPENQASM 3.
+    AngleType,
+    /// The gate modifier `inv`. Only in OPENQASM 3.
+    Inv,
+    /// The gate modifier `ctrl`. Only in OPENQASM 3.
+    Ctrl,
+    /// The gate modifier `pow`. Only in OPENQASM 3.
+    PowModifier,
+    /// A gate modifier other than `inv`, `ctrl(n)` or `pow(k)`, as written
+    /// before the `@` separator. The lexer never produces it: the parser
+    /// reports invalid modifiers with it.
+    InvalidModifier {
+        /// The modifier as it appears in the source code.
+        repr: String,
+    },
+    /// An OPENQASM 3 key-word for a feature not supported by the simulator.
+    Reserved {
+        /// The key-word as it appears in the source code.
+        repr: String,
+    },
     /// The QASM header `OPENQASM`.
     QASMHeader,
     /// The version of OPENQASM as `X.Y`.
@@ -254,13 +328,31 @@ impl fmt::Display for Tok {
             Tok::Comma => ",".into(),
             Tok::Arrow => "=>".into(),
             Tok::Equal => "==".into(),
+            Tok::NotEqual => "!=".into(),
+            Tok::Less => "<".into(),
+            Tok::LessEqual => "<=".into(),
+            Tok::Greater => ">".into(),
+            Tok::GreaterEqual => ">=".into(),
+            Tok::And => "&&".into(),
+            Tok::Assign => "=".into(),
+            Tok::At => "@".into(),
+            Tok::Colon => ":".into(),
             Tok::Sin => "function `sin`".into(),
             Tok::Cos => "function `cos`".into(),
             Tok::Tan => "function `tan`".into(),
             Tok::Exp => "function `exp`".into(),
             Tok::Ln => "function `ln`".into(),
             Tok::Sqrt => "function `sqrt`".into(),
+            Tok::Asin => "function `asin`".into(),
+            Tok::Acos => "function `acos`".into(),
+            Tok::Atan => "function `atan`".into(),
+            Tok::Atan2 => "function `atan2`".into(),
+            Tok::Floor => "function `floor`".into(),
+            Tok::Ceil => "function `ceil`".into(),
+            Tok::Mod => "function `mod`".into(),
             Tok::ConstPi => "constant `pi`".into(),
+            Tok::ConstTau => "constant `tau`".into(),
+            Tok::ConstEuler => "constant `euler`".into(),
             Tok::U => "primitive gate `U`".into(),
             Tok::CX => "primitive gate `CX`".into(),
             Tok::Opaque => "keyword `opaque`".into(),
@@ -272,6 +364,21 @@ impl fmt::Display for Tok {
             Tok::Reset => "keyword `reset`".into(),
             Tok::Barrier => "keyword `barrier`".into(),
             Tok::If => "keyword `if`".into(),
+            Tok::Else => "keyword `else`".into(),
+            Tok::For => "keyword `for`".into(),
+            Tok::In => "keyword `in`".into(),
+            Tok::Qubit => "keyword `qubit`".into(),
+            Tok::Bit => "keyword `bit`".into(),
+            Tok::Const => "keyword `const`".into(),
+            Tok::FloatType => "type `float`".into(),
+            Tok::IntType => "type `int`".into(),
+            Tok::UIntType => "type `uint`".into(),
+            Tok::AngleType => "type `angle`".into(),
+            Tok::Inv => "modifier `inv`".into(),
+            Tok::Ctrl => "modifier `ctrl`".into(),
+            Tok::PowModifier => "modifier `pow`".into(),
+            Tok::InvalidModifier { repr } => format!("modifier `{}`", &repr),
+            Tok::Reserved { repr } => format!("unsupported keyword `{}`", &repr),
             Tok::QASMHeader => "qasm header `OPENQASM`".into(),
             Tok::Version { repr } => format!("open qasm version `{}`", &repr),
             Tok::Id { repr } => format!("identifier `{}`", &repr),
@@ -292,7 +399,16 @@ fn keywords() -> HashMap<String, Tok> {
     kw.insert(String::from("exp"), Tok::Exp);
     kw.insert(String::from("ln"), Tok::Ln);
     kw.insert(String::from("sqrt"), Tok::Sqrt);
+    kw.insert(String::from("asin"), Tok::Asin);
+    kw.insert(String::from("acos"), Tok::Acos);
+    kw.insert(String::from("atan"), Tok::Atan);
+    kw.insert(String::from("atan2"), Tok::Atan2);
+    kw.insert(String::from("floor"), Tok::Floor);
+    kw.insert(String::from("ceil"), Tok::Ceil);
+    kw.insert(String::from("mod"), Tok::Mod);
     kw.insert(String::from("pi"), Tok::ConstPi);
+    kw.insert(String::from("tau"), Tok::ConstTau);
+    kw.insert(String::from("euler"), Tok::ConstEuler);
     kw.insert(String::from("opaque"), Tok::Opaque);
     kw.insert(String::from("gate"), Tok::Gate);
     kw.insert(String::from("include"), Tok::Include);
@@ -305,6 +421,72 @@ fn keywords() -> HashMap<String, Tok> {
     kw
 }
 
+/// Key-words of the OPENQASM 3 language with no support in the simulator.
+const RESERVED_QASM3_KEYWORDS: &[&str] = &[
+    "array",
+    "bool",
+    "box",
+    "break",
+    "cal",
+    "case",
+    "complex",
+    "continue",
+    "def",
+    "defcal",
+    "defcalgrammar",
+    "delay",
+    "duration",
+    "durationof",
+    "end",
+    "extern",
+    "gphase",
+    "input",
+    "let",
+    "mutable",
+    "negctrl",
+    "opaque",
+    "output",
+    "readonly",
+    "return",
+    "stretch",
+    "switch",
+    "while",
+];
+
+fn qasm3_keywords() -> HashMap<String, Tok> {
+    let mut kw = keywords();
+    kw.remove("opaque");
+    kw.insert(String::from("else"), Tok::Else);
+    kw.insert(String::from("for"), Tok::For);
+    kw.insert(String::from("in"), Tok::In);
+    kw.insert(String::from("qubit"), Tok::Qubit);
+    kw.insert(String::from("bit"), Tok::Bit);
+    kw.insert(String::from("const"), Tok::Const);
+    kw.insert(String::from("float"), Tok::FloatType);
+    kw.insert(String::from("int"), Tok::IntType);
+    kw.insert(String::from("uint"), Tok::UIntType);
+    kw.insert(String::from("angle"), Tok::AngleType);
+    kw.insert(String::from("inv"), Tok::Inv);
+    kw.insert(String::from("ctrl"), Tok::Ctrl);
+    kw.insert(String::from("pow"), Tok::PowModifier);
+    for keyword in RESERVED_QASM3_KEYWORDS {
+        kw.insert(
+            String::from(*keyword),
+            Tok::Reserved {
+                repr: String::from(*keyword),
+            },
+        );
+    }
+    kw
+}
+
+/// The version of the language being tokenized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Dialect {
+    OpenQasm2,
+    OpenQasm3,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 enum Mode {
     Base,
@@ -316,6 +498,7 @@ enum Mode {
 
 #[derive(Debug, Clone)]
 pub(crate) struct Lexer<'input> {
+    dialect: Dialect,
     mode: VecDeque<Mode>,
     lineno: usize,
     lineoffset: usize,
@@ -326,11 +509,13 @@ pub(crate) struct Lexer<'input> {
     errored: bool,
     docstring: Option<(Location, String, Location)>,
     delayed_token: Option<(Location, Tok, Location)>,
+    comments: Vec<(Location, String, Location)>,
 }
 
 impl<'input> Lexer<'input> {
     pub fn new(input: &'input str) -> Self {
         Lexer {
+            dialect: Dialect::OpenQasm2,
             mode: VecDeque::from(vec![Mode::Base]),
             lineno: 1,
             lineoffset: 0,
@@ -341,6 +526,16 @@ impl<'input> Lexer<'input> {
             errored: false,
             docstring: None,
             delayed_token: None,
+            comments: vec![],
+        }
+    }
+
+    /// Create a lexer for the OPENQASM 3 language.
+    pub fn for_open_qasm3(input: &'input str) -> Self {
+        Lexer {
+            dialect: Dialect::OpenQasm3,
+            keywords: qasm3_keywords(),
+            ..Lexer::new(input)
         }
     }
 
@@ -419,12 +614,16 @@ impl<'input> Iterator for Lexer<'input> {
             static ref BLANK: Regex = Regex::new(r"^[ \t]+").unwrap();
             static ref GATE: Regex = Regex::new(r"^(CX|U)\b").unwrap();
             static ref OPENQASM: Regex = Regex::new(r"^OPENQASM\b").unwrap();
-            static ref VERSION: Regex = Regex::new(r"^([0-9]+\.[0-9]+)").unwrap();
+            static ref VERSION: Regex = Regex::new(r"^([0-9]+(\.[0-9]+)?)").unwrap();
             static ref ID: Regex = Regex::new(r"^([a-z][A-Za-z0-9_]*)").unwrap();
+            static ref QASM3_ID: Regex = Regex::new(r"^([A-Za-z_][A-Za-z0-9_]*)").unwrap();
             static ref INTEGER: Regex = Regex::new(r"^([1-9]+[0-9]*|0)").unwrap();
             static ref REAL: Regex =
                 Regex::new(r"^([0-9]+\.[0-9]*|[0-9]*\.[0-9]+)([eE][+-]?([0-9]+))?").unwrap();
-            static ref SYMBOL: Regex = Regex::new(r"^(->|==|//|[+\-\*/\^\[\]\{\}\(\);,])").unwrap();
+            static ref SYMBOL: Regex = Regex::new(r"^(->|==|!=|<=|>=|&&|//|[+\-\*/\^\[\]\{\}\(\);,@<>])").unwrap();
+            static ref QASM3_SYMBOL: Regex =
+                Regex::new(r"^(->|==|!=|<=|>=|&&|//|\*\*|[+\-\*/\[\]\{\}\(\);,=@:<>])").unwrap();
+            static ref BLOCK_COMMENT: Regex = Regex::new(r"^(?s)/\*.*?\*/").unwrap();
         }
 
         loop {
@@ -435,7 +634,7 @@ impl<'input> Iterator for Lexer<'input> {
             // it happens when emitting `DocStr` and `Gate`.
 
             // #[mode(EmitDelayedToken)]
-            match self.mode.get(0) {
+            match self.mode.front() {
                 Some(Mode::EmitDelayedToken) => {
                     if self.delayed_token.is_none() {
                         unreachable!("Trying to return a non existend delayed gate.");
@@ -458,13 +657,13 @@ impl<'input> Iterator for Lexer<'input> {
             // TODO: Finally they come the regular lexer actions per active mode.
 
             // TODO: Should transform this into
-            // `match self.mode.get(0) { ... }` to generalize the stacked lexer
+            // `match self.mode.front() { ... }` to generalize the stacked lexer
             // structure and start recognizing syntax patterns to extract into
             // macros.
             if let Some(new_line) = self.try_pattern(&NEW_LINE) {
                 self.lineno += 1;
                 self.lineoffset = self.offset;
-                match self.mode.get(0) {
+                match self.mode.front() {
                     Some(Mode::Comment) => {
                         self.extend_docstring(&new_line);
                         self.update_docstring_end(self.location(start + new_line.len()));
@@ -478,7 +677,7 @@ impl<'input> Iterator for Lexer<'input> {
             }
 
             // #[modes(Base, Version)]
-            match self.mode.get(0) {
+            match self.mode.front() {
                 Some(Mode::Base) | Some(Mode::Version) => {
                     if let Some(_blank) = self.try_pattern(&BLANK) {
                         continue;
@@ -488,7 +687,7 @@ impl<'input> Iterator for Lexer<'input> {
             }
 
             // #[modes(Base)]
-            match self.mode.get(0) {
+            match self.mode.front() {
                 Some(Mode::Base) => {
                     if let Some((_, c)) = self.chars.peek() {
                         if *c == '"' {
@@ -502,7 +701,7 @@ impl<'input> Iterator for Lexer<'input> {
             }
 
             // #[modes(Str)]
-            match self.mode.get(0) {
+            match self.mode.front() {
                 Some(Mode::Str) => {
                     loop {
                         match self.chars.next() {
@@ -532,7 +731,7 @@ impl<'input> Iterator for Lexer<'input> {
             }
 
             // #[modes(Comment)]
-            match self.mode.get(0) {
+            match self.mode.front() {
                 Some(Mode::Comment) => {
                     if let Some(content) = self.try_pattern(&ALL_THE_LINE) {
                         self.extend_docstring(&content);
@@ -564,8 +763,26 @@ impl<'input> Iterator for Lexer<'input> {
                 });
             }
 
+            // #[modes(Base)]
+            match (self.mode.front(), self.dialect) {
+                (Some(Mode::Base), Dialect::OpenQasm3) => {
+                    if let Some(comment) = self.try_pattern(&BLOCK_COMMENT) {
+                        self.flush_docstring();
+                        let end = start + comment.len();
+                        self.comments
+                            .push((self.location(start), comment, self.location(end)));
+                        continue;
+                    }
+                }
+                _ => (),
+            }
+
             // #[modes(all)]
-            if let Some(repr) = self.try_pattern(&ID) {
+            let id_pattern: &Regex = match self.dialect {
+                Dialect::OpenQasm2 => &ID,
+                Dialect::OpenQasm3 => &QASM3_ID,
+            };
+            if let Some(repr) = self.try_pattern(id_pattern) {
                 let end = start + repr.len();
                 return Some(match self.keywords.get(&repr) {
                     None => Ok((self.location(start), Tok::Id { repr }, self.location(end))),
@@ -593,7 +810,7 @@ impl<'input> Iterator for Lexer<'input> {
             }
 
             // #[modes(Base)]
-            match self.mode.get(0) {
+            match self.mode.front() {
                 Some(Mode::Base) => {
                     if let Some(repr) = self.try_pattern(&REAL) {
                         let end = start + repr.len();
@@ -608,7 +825,7 @@ impl<'input> Iterator for Lexer<'input> {
             }
 
             // #[modes(Version)]
-            match self.mode.get(0) {
+            match self.mode.front() {
                 Some(Mode::Version) => {
                     if let Some(repr) = self.try_pattern(&VERSION) {
                         let end = start + repr.len();
@@ -634,14 +851,18 @@ impl<'input> Iterator for Lexer<'input> {
             }
 
             // #[modes(all)]
-            if let Some(symbol) = self.try_pattern(&SYMBOL) {
+            let symbol_pattern: &Regex = match self.dialect {
+                Dialect::OpenQasm2 => &SYMBOL,
+                Dialect::OpenQasm3 => &QASM3_SYMBOL,
+            };
+            if let Some(symbol) = self.try_pattern(symbol_pattern) {
                 let end = start + symbol.len();
                 let token = match symbol.as_str() {
                     "+" => Tok::Add,
                     "-" => Tok::Minus,
                     "*" => Tok::Mult,
                     "/" => Tok::Div,
-                    "^" => Tok::Pow,
+                    "^" | "**" => Tok::Pow,
                     "[" => Tok::LBracket,
                     "]" => Tok::RBracket,
                     "{" => Tok::LBrace,
@@ -652,7 +873,24 @@ impl<'input> Iterator for Lexer<'input> {
                     "," => Tok::Comma,
                     "->" => Tok::Arrow,
                     "==" => Tok::Equal,
+                    "!=" => Tok::NotEqual,
+                    "<" => Tok::Less,
+                    "<=" => Tok::LessEqual,
+                    ">" => Tok::Greater,
+                    ">=" => Tok::GreaterEqual,
+                    "&&" => Tok::And,
+                    "=" => Tok::Assign,
+                    "@" => Tok::At,
+                    ":" => Tok::Colon,
                     "//" => {
+                        let line_end = self.input[start..]
+                            .find('\n')
+                            .map_or(self.input.len(), |length| start + length);
+                        self.comments.push((
+                            self.location(start),
+                            String::from(&self.input[start..line_end]),
+                            self.location(line_end),
+                        ));
                         if !self.is_building_docstring() {
                             self.start_docstring(self.location(start));
                         }
@@ -665,17 +903,99 @@ impl<'input> Iterator for Lexer<'input> {
             }
 
             self.errored = true;
-            return Some(Err(LexicalError {
-                location: self.location(start),
-            }));
+            return Some(Err(LexicalError::new_at(self.location(start))));
         }
     }
 }
 
+/// Return the tokens of `input` along with their boundaries, up to the first
+/// lexical error. Like the parser, the lexer follows the dialect of the
+/// `OPENQASM` header.
+///
+/// # Examples
+///
+/// ```
+/// use qasmsim::grammar::lexer::{tokenize, Location, Tok};
+///
+/// let tokens = tokenize("qreg q[2];");
+/// assert_eq!(tokens[1], (Location(5), Tok::Id { repr: "q".into() }, Location(6)));
+/// ```
+pub fn tokenize(input: &str) -> Vec<(Location, Tok, Location)> {
+    lexer_for(input).map_while(Result::ok).collect()
+}
+
+/// Return the comments of `input`, including the comment marks, along with
+/// their boundaries, up to the first lexical error. Comments are trivia: the
+/// parser never sees them, except for those documenting gates.
+///
+/// # Examples
+///
+/// ```
+/// use qasmsim::grammar::lexer::{comments, Location};
+///
+/// let comments = comments("qreg q[2]; // Two qubits.\n");
+/// assert_eq!(comments, vec![(Location(11), "// Two qubits.".into(), Location(25))]);
+/// ```
+pub fn comments(input: &str) -> Vec<(Location, String, Location)> {
+    let mut lexer = lexer_for(input);
+    lexer.by_ref().take_while(Result::is_ok).for_each(drop);
+    lexer.comments
+}
+
+/// Return a lexer for `input` following the dialect of the `OPENQASM` header.
+fn lexer_for(input: &str) -> Lexer<'_> {
+    let mut header = Lexer::new(input).take(2);
+    let is_open_qasm3 = matches!(
+        (header.next(), header.next()),
+        (Some(Ok((_, Tok::QASMHeader, _))), Some(Ok((_, Tok::Version { repr }, _))))
+            if repr == "3" || repr.starts_with("3.")
+    );
+    if is_open_qasm3 {
+        Lexer::for_open_qasm3(input)
+    } else {
+        Lexer::new(input)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_tokenize_follows_the_dialect_of_the_header() {
+        let tokens = tokenize("OPENQASM 3;\nqubit q;");
+        assert_eq!(tokens[3], (Location(12), Tok::Qubit, Location(17)));
+        let tokens = tokenize("OPENQASM 2.0;\nqubit q;");
+        assert_eq!(
+            tokens[3],
+            (
+                Location(14),
+                Tok::Id {
+                    repr: "qubit".into()
+                },
+                Location(19)
+            )
+        );
+    }
+
+    #[test]
+    fn test_tokenize_stops_at_the_first_error() {
+        let tokens = tokenize("qreg q[2]; $ creg c[2];");
+        assert_eq!(tokens.len(), 6);
+    }
+
+    #[test]
+    fn test_comments_are_kept_as_trivia() {
+        let source = "// Header.\nOPENQASM 3;\nqubit q; /* a\nblock */ x q;";
+        assert_eq!(
+            comments(source),
+            vec![
+                (Location(0), "// Header.".into(), Location(10)),
+                (Location(32), "/* a\nblock */".into(), Location(45)),
+            ]
+        );
+    }
+
     #[test]
     fn test_all_blankspace() {
         let source = "  \t\t\n\n\n\t\t  ";
@@ -1001,5 +1321,101 @@ mod tests {
                 ]
             );
         }
+
+        #[test]
+        fn test_open_qasm3_tokens() {
+            let source = "ctrl @ X_gate q; /* block\ncomment */ c[0:1] = 2 ** 3; while";
+            let lexer = Lexer::for_open_qasm3(source);
+            assert_eq!(
+                lexer.collect::<Vec<_>>(),
+                vec![
+                    Ok((Location(0), Tok::Ctrl, Location(4))),
+                    Ok((Location(5), Tok::At, Location(6))),
+                    Ok((
+                        Location(7),
+                        Tok::Id {
+                            repr: String::from("X_gate")
+                        },
+                        Location(13)
+                    )),
+                    Ok((
+                        Location(14),
+                        Tok::Id {
+                            repr: String::from("q")
+                        },
+                        Location(15)
+                    )),
+                    Ok((Location(15), Tok::Semi, Location(16))),
+                    Ok((
+                        Location(37),
+                        Tok::Id {
+                            repr: String::from("c")
+                        },
+                        Location(38)
+                    )),
+                    Ok((Location(38), Tok::LBracket, Location(39))),
+                    Ok((
+                        Location(39),
+                        Tok::Int {
+                            repr: String::from("0")
+                        },
+                        Location(40)
+                    )),
+                    Ok((Location(40), Tok::Colon, Location(41))),
+                    Ok((
+                        Location(41),
+                        Tok::Int {
+                            repr: String::from("1")
+                        },
+                        Location(42)
+                    )),
+                    Ok((Location(42), Tok::RBracket, Location(43))),
+                    Ok((Location(44), Tok::Assign, Location(45))),
+                    Ok((
+                        Location(46),
+                        Tok::Int {
+                            repr: String::from("2")
+                        },
+                        Location(47)
+                    )),
+                    Ok((Location(48), Tok::Pow, Location(50))),
+                    Ok((
+                        Location(51),
+                        Tok::Int {
+                            repr: String::from("3")
+                        },
+                        Location(52)
+                    )),
+                    Ok((Location(52), Tok::Semi, Location(53))),
+                    Ok((
+                        Location(54),
+                        Tok::Reserved {
+                            repr: String::from("while")
+                        },
+                        Location(59)
+                    )),
+                ]
+            );
+        }
+
+        #[test]
+        fn test_open_qasm3_version_without_minor() {
+            let source = "OPENQASM 3;";
+            let lexer = Lexer::for_open_qasm3(source);
+            assert_eq!(
+                lexer.collect::<Vec<_>>(),
+                vec![
+                    Ok((Location(0), Tok::QASMHeader, Location(8))),
+                    Ok((
+                        Location(9),
+                        Tok::Version {
+                            repr: String::from("3")
+                        },
+                        Location(10)
+                    )),
+                    Ok((Location(10), Tok::Semi, Location(11))),
+                ]
+            );
+        }
     }
 }
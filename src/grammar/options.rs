@@ -0,0 +1,362 @@
+//! Contain the options controlling the extensions to OPENQASM 2.0 accepted by
+//! the parser.
+
+use std::collections::HashSet;
+use std::error;
+use std::fmt;
+use std::str::FromStr;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::grammar::ast;
+use crate::grammar::lexer::Location;
+
+/// An extension to the OPENQASM 2.0 language.
+///
+/// Extensions are known by a name in kebab-case, which is the value used
+/// for parsing and displaying them.
+///
+/// # Examples
+///
+/// ```
+/// use qasmsim::grammar::Extension;
+///
+/// let extension: Extension = "extended-conditionals".parse().unwrap();
+/// assert_eq!(extension, Extension::ExtendedConditionals);
+/// assert_eq!(extension.to_string(), "extended-conditionals");
+/// ```
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Extension {
+    /// Conditionals on single bits, with the comparison operators `!=`, `<`,
+    /// `<=`, `>` and `>=`, and on several registers joined with `&&`.
+    ExtendedConditionals,
+    /// The gate modifiers `ctrl @`, `inv @` and `pow(k) @`.
+    GateModifiers,
+    /// Including the embedded libraries other than `qelib1.inc`, such as
+    /// `stdgates.inc`.
+    EmbeddedLibraries,
+    /// Calling a gate before its declaration, either in the program or in
+    /// the body of another gate.
+    ForwardReferences,
+    /// Programs with the `OPENQASM 3` header.
+    OpenQasm3,
+    /// The functions `asin`, `acos`, `atan`, `atan2`, `floor`, `ceil` and
+    /// `mod`, and the constants `tau` and `euler`.
+    ExtendedMath,
+}
+
+impl Extension {
+    /// Return all the extensions.
+    pub fn all() -> &'static [Extension] {
+        &[
+            Extension::ExtendedConditionals,
+            Extension::GateModifiers,
+            Extension::EmbeddedLibraries,
+            Extension::ForwardReferences,
+            Extension::OpenQasm3,
+            Extension::ExtendedMath,
+        ]
+    }
+
+    /// Return the name of the extension.
+    pub fn name(self) -> &'static str {
+        match self {
+            Extension::ExtendedConditionals => "extended-conditionals",
+            Extension::GateModifiers => "gate-modifiers",
+            Extension::EmbeddedLibraries => "embedded-libraries",
+            Extension::ForwardReferences => "forward-references",
+            Extension::OpenQasm3 => "openqasm3",
+            Extension::ExtendedMath => "extended-math",
+        }
+    }
+
+    /// Return a short description of the constructs the extension allows.
+    pub fn description(self) -> &'static str {
+        match self {
+            Extension::ExtendedConditionals => "extended conditionals",
+            Extension::GateModifiers => "gate modifiers",
+            Extension::EmbeddedLibraries => "libraries other than `qelib1.inc`",
+            Extension::ForwardReferences => "calls to gates before their declaration",
+            Extension::OpenQasm3 => "OPENQASM 3 programs",
+            Extension::ExtendedMath => "extended math functions and constants",
+        }
+    }
+}
+
+impl fmt::Display for Extension {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Extension {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Extension::all()
+            .iter()
+            .copied()
+            .find(|extension| extension.name() == name)
+            .ok_or_else(|| format!("unknown extension `{}`", name))
+    }
+}
+
+/// The set of extensions to OPENQASM 2.0 accepted when processing a program.
+///
+/// The default options enable all the extensions except for the
+/// [extended conditionals], which must be enabled explicitly. The strict
+/// options enable none, so only the programs following the OPENQASM 2.0
+/// specification are accepted.
+///
+/// # Examples
+///
+/// ```
+/// use qasmsim::grammar::{Extension, LanguageOptions};
+///
+/// let options = LanguageOptions::new().allow(Extension::ExtendedConditionals);
+/// assert!(options.allows(Extension::ExtendedConditionals));
+///
+/// let options = LanguageOptions::strict().allow(Extension::GateModifiers);
+/// assert!(options.allows(Extension::GateModifiers));
+/// assert!(!options.allows(Extension::OpenQasm3));
+/// ```
+///
+/// [extended conditionals]: ./enum.Extension.html#variant.ExtendedConditionals
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LanguageOptions {
+    extensions: HashSet<Extension>,
+}
+
+impl Default for LanguageOptions {
+    fn default() -> Self {
+        LanguageOptions::strict()
+            .allow(Extension::GateModifiers)
+            .allow(Extension::EmbeddedLibraries)
+            .allow(Extension::ForwardReferences)
+            .allow(Extension::OpenQasm3)
+            .allow(Extension::ExtendedMath)
+    }
+}
+
+impl LanguageOptions {
+    /// Create the default options.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Create the options with no extension enabled.
+    pub fn strict() -> Self {
+        LanguageOptions {
+            extensions: HashSet::new(),
+        }
+    }
+
+    /// Enable `extension`.
+    pub fn allow(mut self, extension: Extension) -> Self {
+        self.extensions.insert(extension);
+        self
+    }
+
+    /// Check if `extension` is enabled.
+    pub fn allows(&self, extension: Extension) -> bool {
+        self.extensions.contains(&extension)
+    }
+
+    /// Fail with a [`ExtensionError::DisabledExtension`] if `extension` is
+    /// not enabled.
+    ///
+    /// [`ExtensionError::DisabledExtension`]: ./enum.ExtensionError.html#variant.DisabledExtension
+    pub(crate) fn assert_allows(
+        &self,
+        extension: Extension,
+        location: Location,
+    ) -> Result<(), ExtensionError> {
+        if self.allows(extension) {
+            Ok(())
+        } else {
+            Err(ExtensionError::DisabledExtension {
+                location,
+                extension,
+            })
+        }
+    }
+}
+
+/// Represent the use of an extension which is not enabled.
+///
+/// # Examples
+///
+/// The following program conditions an operation on a single bit:
+///
+/// ```qasm
+/// OPENQASM 2.0;
+/// include "qelib1.inc";
+/// qreg q[1];
+/// creg c[1];
+/// if (c[0]==1) x q[0];
+/// ```
+///
+/// Parsing it without enabling the extended conditionals would cause the
+/// following error:
+///
+/// ```
+/// use qasmsim::grammar::{Extension, ExtensionError};
+/// use qasmsim::grammar::lexer::Location;
+///
+/// ExtensionError::DisabledExtension {
+///     location: Location(58),
+///     extension: Extension::ExtendedConditionals
+/// };
+/// ```
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ExtensionError {
+    /// The construct at `location` requires `extension`.
+    DisabledExtension {
+        /// Location of the construct.
+        location: Location,
+        /// The extension required by the construct.
+        extension: Extension,
+    },
+}
+
+impl fmt::Display for ExtensionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match lazy_humanize! {
+            self,
+            ExtensionError::DisabledExtension
+        } {
+            Some(message) => message,
+            None => unreachable!(),
+        };
+        write!(f, "{}", message)
+    }
+}
+
+impl error::Error for ExtensionError {}
+
+/// Check if `program` is an OPENQASM 2 program, the only ones the extensions
+/// apply to.
+pub(crate) fn is_open_qasm2(program: &ast::OpenQasmProgram) -> bool {
+    program.version.starts_with('2')
+}
+
+/// Check `program` uses only the syntactic extensions enabled in `options`.
+pub(crate) fn check_extensions(
+    program: &ast::OpenQasmProgram,
+    options: &LanguageOptions,
+) -> Result<(), ExtensionError> {
+    check_statements(&program.program, options)
+}
+
+fn check_statements(
+    statements: &[ast::Span<ast::Statement>],
+    options: &LanguageOptions,
+) -> Result<(), ExtensionError> {
+    for span in statements {
+        let location = span.boundaries.0;
+        match &*span.node {
+            ast::Statement::IfElse {
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                options.assert_allows(Extension::ExtendedConditionals, location)?;
+                check_statements(then_branch, options)?;
+                check_statements(else_branch, options)?
+            }
+            ast::Statement::QuantumOperation(operation)
+            | ast::Statement::Conditional(_, _, operation) => match operation {
+                ast::QuantumOperation::Unitary(unitary) => {
+                    check_unitary(unitary, location, options)?
+                }
+                ast::QuantumOperation::Modified(modifiers, unitary) => {
+                    check_modified(modifiers, unitary, location, options)?
+                }
+                _ => (),
+            },
+            ast::Statement::GateDecl {
+                signature: (_, _, _, operations),
+                ..
+            } => {
+                for operation in operations {
+                    match operation {
+                        ast::GateOperation::Unitary(unitary) => {
+                            check_unitary(unitary, location, options)?
+                        }
+                        ast::GateOperation::Modified(modifiers, unitary) => {
+                            check_modified(modifiers, unitary, location, options)?
+                        }
+                        _ => (),
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+    Ok(())
+}
+
+fn check_modified(
+    modifiers: &[ast::GateModifier],
+    unitary: &ast::UnitaryOperation,
+    location: Location,
+    options: &LanguageOptions,
+) -> Result<(), ExtensionError> {
+    options.assert_allows(Extension::GateModifiers, location)?;
+    for modifier in modifiers {
+        if let ast::GateModifier::Pow(exponent) = modifier {
+            check_expression(exponent, location, options)?;
+        }
+    }
+    check_unitary(unitary, location, options)
+}
+
+fn check_unitary(
+    unitary: &ast::UnitaryOperation,
+    location: Location,
+    options: &LanguageOptions,
+) -> Result<(), ExtensionError> {
+    let ast::UnitaryOperation(_, real_args, _) = unitary;
+    for expression in real_args {
+        check_expression(expression, location, options)?;
+    }
+    Ok(())
+}
+
+fn check_expression(
+    expression: &ast::Expression,
+    location: Location,
+    options: &LanguageOptions,
+) -> Result<(), ExtensionError> {
+    if uses_extended_math(expression) {
+        options.assert_allows(Extension::ExtendedMath, location)?;
+    }
+    Ok(())
+}
+
+fn uses_extended_math(expression: &ast::Expression) -> bool {
+    match expression {
+        ast::Expression::Tau | ast::Expression::Euler | ast::Expression::BinaryFunction(..) => true,
+        ast::Expression::Function(func_code, argument) => {
+            !matches!(
+                func_code,
+                ast::FuncCode::Sin
+                    | ast::FuncCode::Cos
+                    | ast::FuncCode::Tan
+                    | ast::FuncCode::Exp
+                    | ast::FuncCode::Ln
+                    | ast::FuncCode::Sqrt
+            ) || uses_extended_math(argument)
+        }
+        ast::Expression::Op(_, left, right) => {
+            uses_extended_math(left) || uses_extended_math(right)
+        }
+        ast::Expression::Minus(operand) => uses_extended_math(operand),
+        _ => false,
+    }
+}
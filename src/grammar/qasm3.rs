@@ -0,0 +1,901 @@
+//! Contain the front end for OPENQASM 3 programs.
+//!
+//! OPENQASM 3 sources are parsed into a syntax tree of their own which is
+//! lowered into the [`ast`] shared with OPENQASM 2. Lowering evaluates
+//! classical constants, unrolls `for` loops and keeps gate modifiers and
+//! `if`/`else` blocks as extensions of the OPENQASM 2 AST. Features with no
+//! counterpart in the simulator are rejected with a [`LoweringError`].
+//!
+//! [`ast`]: ../ast/index.html
+//! [`LoweringError`]: ./enum.LoweringError.html
+
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::error::QasmSimError;
+use crate::grammar::ast;
+use crate::grammar::lexer::{Lexer, Location, Tok};
+use crate::grammar::open_qasm3;
+use crate::interpreter::expression_solver::{ExpressionError, ExpressionSolver};
+
+/// Maximum number of iterations of all the `for` loops of a program, once
+/// unrolled.
+const MAX_LOOP_ITERATIONS: usize = 100_000;
+
+/// An OPENQASM 3 program.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Program {
+    pub version: String,
+    pub statements: Vec<ast::Span<Statement>>,
+}
+
+/// Any of the OPENQASM 3 statements supported by the parser. Not all of them
+/// can be lowered.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Statement {
+    Include(String),
+    QubitDecl(String, Option<ast::Expression>),
+    BitDecl(String, Option<ast::Expression>),
+    /// A classical declaration with a flag indicating if it is constant, the
+    /// name of the variable and the initializer, if any.
+    ClassicalDecl(bool, String, Option<ast::Expression>),
+    Assignment(Operand, ast::Expression),
+    /// A measurement with the measured qubits and the target bits, if any.
+    Measure(Operand, Option<Operand>),
+    Reset(Operand),
+    Barrier(Vec<Operand>),
+    GateCall(GateCall),
+    GateDecl {
+        name: String,
+        params: Vec<String>,
+        qubits: Vec<String>,
+        body: Vec<ast::Span<Statement>>,
+        docstring: Option<String>,
+    },
+    If(
+        Condition,
+        Vec<ast::Span<Statement>>,
+        Vec<ast::Span<Statement>>,
+    ),
+    For(String, Range, Vec<ast::Span<Statement>>),
+}
+
+/// A register, or one of its components when indexed.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Operand(pub String, pub Option<ast::Expression>);
+
+/// A gate call with its modifiers, name, real parameters and operands.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct GateCall(
+    pub Vec<Modifier>,
+    pub String,
+    pub Vec<ast::Expression>,
+    pub Vec<Operand>,
+);
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Modifier {
+    Inv,
+    Ctrl(Option<ast::Expression>),
+    Pow(ast::Expression),
+}
+
+/// A list of comparisons that must all hold.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Condition(pub Vec<Comparison>);
+
+/// A comparison between a classical operand and a value.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Comparison(pub Operand, pub ast::ComparisonOp, pub ast::Expression);
+
+/// A range with start, optional step and inclusive end.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Range(
+    pub ast::Expression,
+    pub Option<ast::Expression>,
+    pub ast::Expression,
+);
+
+/// Represent a failure while lowering an OPENQASM 3 program.
+///
+/// # Examples
+///
+/// The following program uses a `while` loop:
+///
+/// ```qasm
+/// OPENQASM 3;
+/// while (true) { }
+/// ```
+///
+/// Loops other than `for` are not supported so it would cause the
+/// following error:
+///
+/// ```
+/// use qasmsim::grammar::lexer::Location;
+/// use qasmsim::grammar::LoweringError;
+///
+/// LoweringError::UnsupportedFeature {
+///     location: Location(12),
+///     feature: "`while`".to_string()
+/// };
+/// ```
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum LoweringError {
+    /// Use of a language feature the simulator does not support.
+    UnsupportedFeature {
+        /// Location of the unsupported feature.
+        location: Location,
+        /// Description of the feature.
+        feature: String,
+    },
+    /// Use of an unknown constant.
+    SymbolNotFound {
+        /// Location of the statement using the constant.
+        location: Location,
+        /// Name of the unknown constant.
+        symbol_name: String,
+    },
+    /// Found a value that should be an integer but is not.
+    InvalidInteger {
+        /// Location of the statement using the value.
+        location: Location,
+        /// The value found.
+        value: String,
+        /// Indicate if the value is required to be non-negative.
+        non_negative: bool,
+    },
+    /// A constant expression evaluating to an infinite or undefined value.
+    NonFiniteValue {
+        /// Location of the statement using the expression.
+        location: Location,
+        /// The failing operation, written with the values of its operands.
+        operation: String,
+    },
+}
+
+type Result<T> = std::result::Result<T, LoweringError>;
+
+/// Parse `source` as an OPENQASM 3 program and lower it into the OPENQASM 2
+/// AST.
+pub(crate) fn parse_program(
+    source: &str,
+) -> std::result::Result<ast::OpenQasmProgram, QasmSimError<'_>> {
+    if let Some(error) = find_reserved_keyword(source) {
+        return Err((source, error).into());
+    }
+    let lexer = Lexer::for_open_qasm3(source);
+    let parser = open_qasm3::OpenQasmProgramParser::new();
+    let program = parser
+        .parse(lexer)
+        .map_err(|err| QasmSimError::from((source, err)))?;
+    lower(program).map_err(|err| (source, err).into())
+}
+
+/// Look for the first key-word of an unsupported feature. Lexical errors are
+/// left for the parser to report.
+fn find_reserved_keyword(source: &str) -> Option<LoweringError> {
+    Lexer::for_open_qasm3(source)
+        .map_while(|token| token.ok())
+        .find_map(|(location, token, _)| match token {
+            Tok::Reserved { repr } => Some(LoweringError::UnsupportedFeature {
+                location,
+                feature: format!("`{}`", repr),
+            }),
+            _ => None,
+        })
+}
+
+fn lower(program: Program) -> Result<ast::OpenQasmProgram> {
+    let mut scope = Scope::default();
+    let mut statements = Vec::new();
+    for span in &program.statements {
+        scope.lower_scoped_statement(span, true, &mut statements)?;
+    }
+    Ok(ast::OpenQasmProgram {
+        version: program.version,
+        program: statements,
+    })
+}
+
+/// The classical constants visible at some point of the program.
+#[derive(Debug, Clone, Default)]
+struct Scope {
+    constants: HashMap<String, f64>,
+    /// Number of loop iterations unrolled so far, shared by all the scopes.
+    iterations: Rc<Cell<usize>>,
+}
+
+impl Scope {
+    fn lower_statement(
+        &self,
+        span: &ast::Span<Statement>,
+        top_level: bool,
+        output: &mut Vec<ast::Span<ast::Statement>>,
+    ) -> Result<()> {
+        let location = span.boundaries.0;
+        let lowered = match &*span.node {
+            Statement::Include(path) => {
+                self.assert_top_level(top_level, location, "`include` inside a block")?;
+                ast::Statement::Include(path.clone())
+            }
+            Statement::QubitDecl(name, size) => {
+                self.assert_top_level(top_level, location, "declarations inside a block")?;
+                ast::Statement::QRegDecl(name.clone(), self.size(size, location)?)
+            }
+            Statement::BitDecl(name, size) => {
+                self.assert_top_level(top_level, location, "declarations inside a block")?;
+                ast::Statement::CRegDecl(name.clone(), self.size(size, location)?)
+            }
+            Statement::GateDecl {
+                name,
+                params,
+                qubits,
+                body,
+                docstring,
+            } => {
+                self.assert_top_level(top_level, location, "gate definitions inside a block")?;
+                let mut scope = self.clone();
+                for formal in params.iter().chain(qubits) {
+                    scope.constants.remove(formal);
+                }
+                let mut operations = Vec::new();
+                for span in body {
+                    scope.lower_gate_operation(span, &mut operations)?;
+                }
+                ast::Statement::GateDecl {
+                    signature: (name.clone(), params.clone(), qubits.clone(), operations),
+                    docstring: docstring.clone(),
+                }
+            }
+            Statement::ClassicalDecl(..) | Statement::Assignment(..) => {
+                unreachable!("after `lower_scoped_statement()`, classical statements are handled")
+            }
+            Statement::Measure(source, Some(target)) => {
+                ast::Statement::QuantumOperation(ast::QuantumOperation::Measure(
+                    self.argument(source, location)?,
+                    self.argument(target, location)?,
+                ))
+            }
+            Statement::Measure(_, None) => {
+                return Err(LoweringError::UnsupportedFeature {
+                    location,
+                    feature: "measurements without a target".into(),
+                })
+            }
+            Statement::Reset(target) => ast::Statement::QuantumOperation(
+                ast::QuantumOperation::Reset(self.argument(target, location)?),
+            ),
+            Statement::Barrier(operands) => {
+                ast::Statement::Barrier(self.barrier(operands, location)?)
+            }
+            Statement::GateCall(call) => {
+                ast::Statement::QuantumOperation(self.quantum_operation(call, location)?)
+            }
+            Statement::If(Condition(comparisons), then_branch, else_branch) => {
+                ast::Statement::IfElse {
+                    condition: ast::Condition(
+                        comparisons
+                            .iter()
+                            .map(|Comparison(operand, operator, value)| {
+                                Ok(ast::Comparison(
+                                    self.argument(operand, location)?,
+                                    *operator,
+                                    self.natural(value, location)? as u64,
+                                ))
+                            })
+                            .collect::<Result<_>>()?,
+                    ),
+                    then_branch: self.lower_block(then_branch)?,
+                    else_branch: self.lower_block(else_branch)?,
+                }
+            }
+            Statement::For(variable, range, body) => {
+                for value in self.range(range, location)? {
+                    self.count_iteration(location)?;
+                    let mut scope = self.clone();
+                    scope.constants.insert(variable.clone(), value as f64);
+                    output.extend(scope.lower_block(body)?);
+                }
+                return Ok(());
+            }
+        };
+        output.push(ast::Span::new(span.boundaries, lowered));
+        Ok(())
+    }
+
+    /// Lower a list of statements in a nested scope.
+    fn lower_block(
+        &self,
+        block: &[ast::Span<Statement>],
+    ) -> Result<Vec<ast::Span<ast::Statement>>> {
+        let mut scope = self.clone();
+        let mut output = Vec::new();
+        for span in block {
+            scope.lower_scoped_statement(span, false, &mut output)?;
+        }
+        Ok(output)
+    }
+
+    /// Lower `span`, updating the scope with the classical declarations.
+    fn lower_scoped_statement(
+        &mut self,
+        span: &ast::Span<Statement>,
+        top_level: bool,
+        output: &mut Vec<ast::Span<ast::Statement>>,
+    ) -> Result<()> {
+        let location = span.boundaries.0;
+        match &*span.node {
+            Statement::ClassicalDecl(_, name, Some(initializer)) => {
+                let value = self.evaluate(initializer, location)?;
+                self.constants.insert(name.clone(), value);
+                Ok(())
+            }
+            Statement::ClassicalDecl(_, _, None) => Err(LoweringError::UnsupportedFeature {
+                location,
+                feature: "classical declarations without an initial value".into(),
+            }),
+            Statement::Assignment(..) => Err(LoweringError::UnsupportedFeature {
+                location,
+                feature: "classical assignments".into(),
+            }),
+            _ => self.lower_statement(span, top_level, output),
+        }
+    }
+
+    fn lower_gate_operation(
+        &self,
+        span: &ast::Span<Statement>,
+        output: &mut Vec<ast::GateOperation>,
+    ) -> Result<()> {
+        let location = span.boundaries.0;
+        match &*span.node {
+            Statement::GateCall(GateCall(modifiers, name, params, operands)) => {
+                let unitary = ast::UnitaryOperation(
+                    name.clone(),
+                    params.iter().map(|param| self.substitute(param)).collect(),
+                    operands
+                        .iter()
+                        .map(|operand| self.argument(operand, location))
+                        .collect::<Result<_>>()?,
+                );
+                output.push(if modifiers.is_empty() {
+                    ast::GateOperation::Unitary(unitary)
+                } else {
+                    ast::GateOperation::Modified(self.modifiers(modifiers, location)?, unitary)
+                });
+            }
+            Statement::Barrier(operands) => {
+                output.push(ast::GateOperation::Barrier(
+                    self.barrier(operands, location)?,
+                ));
+            }
+            Statement::For(variable, range, body) => {
+                for value in self.range(range, location)? {
+                    self.count_iteration(location)?;
+                    let mut scope = self.clone();
+                    scope.constants.insert(variable.clone(), value as f64);
+                    for span in body {
+                        scope.lower_gate_operation(span, output)?;
+                    }
+                }
+            }
+            _ => {
+                return Err(LoweringError::UnsupportedFeature {
+                    location,
+                    feature: "statements other than gate calls, barriers and `for` loops \
+                        inside gate definitions"
+                        .into(),
+                })
+            }
+        }
+        Ok(())
+    }
+
+    fn assert_top_level(&self, top_level: bool, location: Location, feature: &str) -> Result<()> {
+        if top_level {
+            Ok(())
+        } else {
+            Err(LoweringError::UnsupportedFeature {
+                location,
+                feature: feature.into(),
+            })
+        }
+    }
+
+    fn quantum_operation(
+        &self,
+        call: &GateCall,
+        location: Location,
+    ) -> Result<ast::QuantumOperation> {
+        let GateCall(modifiers, name, params, operands) = call;
+        let unitary = ast::UnitaryOperation(
+            name.clone(),
+            params.iter().map(|param| self.substitute(param)).collect(),
+            operands
+                .iter()
+                .map(|operand| self.argument(operand, location))
+                .collect::<Result<_>>()?,
+        );
+        Ok(if modifiers.is_empty() {
+            ast::QuantumOperation::Unitary(unitary)
+        } else {
+            ast::QuantumOperation::Modified(self.modifiers(modifiers, location)?, unitary)
+        })
+    }
+
+    fn modifiers(
+        &self,
+        modifiers: &[Modifier],
+        location: Location,
+    ) -> Result<Vec<ast::GateModifier>> {
+        modifiers
+            .iter()
+            .map(|modifier| {
+                Ok(match modifier {
+                    Modifier::Inv => ast::GateModifier::Inv,
+                    Modifier::Ctrl(None) => ast::GateModifier::Ctrl(1),
+                    Modifier::Ctrl(Some(count)) => match self.natural(count, location)? {
+                        0 => {
+                            return Err(LoweringError::UnsupportedFeature {
+                                location,
+                                feature: "`ctrl` modifiers with no controls".into(),
+                            })
+                        }
+                        count => ast::GateModifier::Ctrl(count),
+                    },
+                    Modifier::Pow(exponent) => ast::GateModifier::Pow(self.substitute(exponent)),
+                })
+            })
+            .collect()
+    }
+
+    fn barrier(&self, operands: &[Operand], location: Location) -> Result<ast::BarrierPragma> {
+        Ok(ast::BarrierPragma(
+            operands
+                .iter()
+                .map(|operand| self.argument(operand, location))
+                .collect::<Result<_>>()?,
+        ))
+    }
+
+    fn argument(&self, operand: &Operand, location: Location) -> Result<ast::Argument> {
+        let Operand(name, index) = operand;
+        Ok(match index {
+            None => ast::Argument::Id(name.clone()),
+            Some(index) => ast::Argument::Item(name.clone(), self.natural(index, location)?),
+        })
+    }
+
+    fn size(&self, size: &Option<ast::Expression>, location: Location) -> Result<usize> {
+        match size {
+            None => Ok(1),
+            Some(size) => self.natural(size, location),
+        }
+    }
+
+    /// Return the values of `range`, from the start to the end, both
+    /// inclusive.
+    fn range(&self, range: &Range, location: Location) -> Result<Vec<i64>> {
+        let Range(start, step, end) = range;
+        let start = self.integer(start, location)?;
+        let end = self.integer(end, location)?;
+        let step = match step {
+            None => 1,
+            Some(step) => self.integer(step, location)?,
+        };
+        if step == 0 {
+            return Err(LoweringError::UnsupportedFeature {
+                location,
+                feature: "ranges with a step of 0".into(),
+            });
+        }
+        let length = if (step > 0 && start <= end) || (step < 0 && start >= end) {
+            (end as i128 - start as i128) / step as i128 + 1
+        } else {
+            0
+        };
+        if length > MAX_LOOP_ITERATIONS as i128 {
+            return Err(too_many_iterations(location));
+        }
+        Ok((0..length as i64)
+            .map(|index| start + index * step)
+            .collect())
+    }
+
+    /// Count one more unrolled iteration, failing if there are too many.
+    fn count_iteration(&self, location: Location) -> Result<()> {
+        let iterations = self.iterations.get() + 1;
+        if iterations > MAX_LOOP_ITERATIONS {
+            return Err(too_many_iterations(location));
+        }
+        self.iterations.set(iterations);
+        Ok(())
+    }
+
+    fn natural(&self, expression: &ast::Expression, location: Location) -> Result<usize> {
+        let value = self.evaluate(expression, location)?;
+        if value.fract() != 0.0 || value < 0.0 || !value.is_finite() {
+            return Err(LoweringError::InvalidInteger {
+                location,
+                value: format!("{}", value),
+                non_negative: true,
+            });
+        }
+        Ok(value as usize)
+    }
+
+    fn integer(&self, expression: &ast::Expression, location: Location) -> Result<i64> {
+        let value = self.evaluate(expression, location)?;
+        if value.fract() != 0.0 || !value.is_finite() {
+            return Err(LoweringError::InvalidInteger {
+                location,
+                value: format!("{}", value),
+                non_negative: false,
+            });
+        }
+        Ok(value as i64)
+    }
+
+    fn evaluate(&self, expression: &ast::Expression, location: Location) -> Result<f64> {
+        ExpressionSolver::new(&self.constants)
+            .solve(expression)
+            .map_err(|error| match error {
+                ExpressionError::SymbolNotFound(symbol_name) => LoweringError::SymbolNotFound {
+                    location,
+                    symbol_name,
+                },
+                ExpressionError::NonFiniteValue(operation, _) => LoweringError::NonFiniteValue {
+                    location,
+                    operation,
+                },
+            })
+    }
+
+    /// Replace the constants in `expression` with their values. Other
+    /// identifiers are left for the interpreter to resolve.
+    fn substitute(&self, expression: &ast::Expression) -> ast::Expression {
+        match expression {
+            ast::Expression::Id(name) => match self.constants.get(name) {
+                Some(value) => ast::Expression::Real(*value),
+                None => expression.clone(),
+            },
+            ast::Expression::Op(opcode, left, right) => ast::Expression::Op(
+                *opcode,
+                Box::new(self.substitute(left)),
+                Box::new(self.substitute(right)),
+            ),
+            ast::Expression::Function(funccode, argument) => {
+                ast::Expression::Function(*funccode, Box::new(self.substitute(argument)))
+            }
+            ast::Expression::BinaryFunction(funccode, left, right) => {
+                ast::Expression::BinaryFunction(
+                    *funccode,
+                    Box::new(self.substitute(left)),
+                    Box::new(self.substitute(right)),
+                )
+            }
+            ast::Expression::Minus(operand) => {
+                ast::Expression::Minus(Box::new(self.substitute(operand)))
+            }
+            _ => expression.clone(),
+        }
+    }
+}
+
+fn too_many_iterations(location: Location) -> LoweringError {
+    LoweringError::UnsupportedFeature {
+        location,
+        feature: format!(
+            "`for` loops unrolling into more than {} iterations",
+            MAX_LOOP_ITERATIONS
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::*;
+
+    macro_rules! span {
+        ($left:expr, $node:expr, $right:expr) => {
+            ast::Span::new((Location($left), Location($right)), $node)
+        };
+    }
+
+    fn parse(source: &str) -> ast::OpenQasmProgram {
+        parse_program(source).expect("parses and lowers")
+    }
+
+    fn unitary(
+        name: &str,
+        params: Vec<ast::Expression>,
+        args: Vec<ast::Argument>,
+    ) -> ast::UnitaryOperation {
+        ast::UnitaryOperation(name.into(), params, args)
+    }
+
+    #[test]
+    fn test_declarations() {
+        let source = indoc!(
+            "
+            OPENQASM 3;
+            qubit a;
+            qubit[2] q;
+            bit[2] c;
+            "
+        );
+        assert_eq!(
+            parse(source),
+            ast::OpenQasmProgram {
+                version: "3".into(),
+                program: vec![
+                    span!(12, ast::Statement::QRegDecl("a".into(), 1), 20),
+                    span!(21, ast::Statement::QRegDecl("q".into(), 2), 32),
+                    span!(33, ast::Statement::CRegDecl("c".into(), 2), 42),
+                ]
+            }
+        );
+    }
+
+    #[test]
+    fn test_constants_are_evaluated() {
+        let source = indoc!(
+            "
+            OPENQASM 3.0;
+            const int n = 2;
+            float theta = pi / n;
+            qubit[n] q;
+            U(theta, 0, 0) q[n - 1];
+            "
+        );
+        let program = parse(source);
+        assert_eq!(
+            *program.program[0].node,
+            ast::Statement::QRegDecl("q".into(), 2)
+        );
+        assert_eq!(
+            *program.program[1].node,
+            ast::Statement::QuantumOperation(ast::QuantumOperation::Unitary(unitary(
+                "U",
+                vec![
+                    ast::Expression::Real(std::f64::consts::PI / 2.0),
+                    ast::Expression::Real(0.0),
+                    ast::Expression::Real(0.0)
+                ],
+                vec![ast::Argument::Item("q".into(), 1)]
+            )))
+        );
+    }
+
+    #[test]
+    fn test_for_loops_are_unrolled() {
+        let source = indoc!(
+            "
+            OPENQASM 3;
+            qubit[4] q;
+            for int i in [0:2:3] {
+              h q[i];
+            }
+            "
+        );
+        let program = parse(source);
+        let operations: Vec<ast::Statement> = program.program[1..]
+            .iter()
+            .map(|span| (*span.node).clone())
+            .collect();
+        assert_eq!(
+            operations,
+            vec![
+                ast::Statement::QuantumOperation(ast::QuantumOperation::Unitary(unitary(
+                    "h",
+                    vec![],
+                    vec![ast::Argument::Item("q".into(), 0)]
+                ))),
+                ast::Statement::QuantumOperation(ast::QuantumOperation::Unitary(unitary(
+                    "h",
+                    vec![],
+                    vec![ast::Argument::Item("q".into(), 2)]
+                ))),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_gate_modifiers() {
+        let source = indoc!(
+            "
+            OPENQASM 3;
+            qubit[3] q;
+            ctrl(2) @ inv @ pow(2) @ s q[0], q[1], q[2];
+            "
+        );
+        assert_eq!(
+            *parse(source).program[1].node,
+            ast::Statement::QuantumOperation(ast::QuantumOperation::Modified(
+                vec![
+                    ast::GateModifier::Ctrl(2),
+                    ast::GateModifier::Inv,
+                    ast::GateModifier::Pow(ast::Expression::Real(2.0))
+                ],
+                unitary(
+                    "s",
+                    vec![],
+                    vec![
+                        ast::Argument::Item("q".into(), 0),
+                        ast::Argument::Item("q".into(), 1),
+                        ast::Argument::Item("q".into(), 2)
+                    ]
+                )
+            ))
+        );
+    }
+
+    #[test]
+    fn test_if_else_and_measure_assignment() {
+        let source = indoc!(
+            "
+            OPENQASM 3;
+            qubit q;
+            bit c;
+            c = measure q;
+            if (c == 1) x q; else { reset q; }
+            "
+        );
+        let program = parse(source);
+        assert_eq!(
+            *program.program[2].node,
+            ast::Statement::QuantumOperation(ast::QuantumOperation::Measure(
+                ast::Argument::Id("q".into()),
+                ast::Argument::Id("c".into())
+            ))
+        );
+        match &*program.program[3].node {
+            ast::Statement::IfElse {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                assert_eq!(
+                    *condition,
+                    ast::Condition(vec![ast::Comparison(
+                        ast::Argument::Id("c".into()),
+                        ast::ComparisonOp::Equal,
+                        1
+                    )])
+                );
+                assert_eq!(then_branch.len(), 1);
+                assert_eq!(
+                    *else_branch[0].node,
+                    ast::Statement::QuantumOperation(ast::QuantumOperation::Reset(
+                        ast::Argument::Id("q".into())
+                    ))
+                );
+            }
+            other => panic!("expected an if-else statement, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_gate_parameters_shadow_constants() {
+        let source = indoc!(
+            "
+            OPENQASM 3;
+            const float theta = 1.0;
+            gate r(theta) a { U(theta, 0, 0) a; }
+            "
+        );
+        match &*parse(source).program[0].node {
+            ast::Statement::GateDecl { signature, .. } => assert_eq!(
+                signature.3,
+                vec![ast::GateOperation::Unitary(unitary(
+                    "U",
+                    vec![
+                        ast::Expression::Id("theta".into()),
+                        ast::Expression::Real(0.0),
+                        ast::Expression::Real(0.0)
+                    ],
+                    vec![ast::Argument::Id("a".into())]
+                ))]
+            ),
+            other => panic!("expected a gate declaration, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_reserved_keywords_are_unsupported() {
+        let source = indoc!(
+            "
+            OPENQASM 3;
+            while (true) { }
+            "
+        );
+        let error = find_reserved_keyword(source).expect("finds `while`");
+        assert_eq!(
+            error,
+            LoweringError::UnsupportedFeature {
+                location: Location(12),
+                feature: "`while`".into()
+            }
+        );
+    }
+
+    #[test]
+    fn test_classical_assignments_are_unsupported() {
+        let source = indoc!(
+            "
+            OPENQASM 3;
+            int i = 0;
+            i = 1;
+            "
+        );
+        let program = open_qasm3::OpenQasmProgramParser::new()
+            .parse(Lexer::for_open_qasm3(source))
+            .unwrap();
+        assert_eq!(
+            lower(program),
+            Err(LoweringError::UnsupportedFeature {
+                location: Location(23),
+                feature: "classical assignments".into()
+            })
+        );
+    }
+
+    #[test]
+    fn test_ctrl_modifiers_need_controls() {
+        let source = indoc!(
+            "
+            OPENQASM 3;
+            qubit q;
+            ctrl(0) @ x q;
+            "
+        );
+        let program = open_qasm3::OpenQasmProgramParser::new()
+            .parse(Lexer::for_open_qasm3(source))
+            .unwrap();
+        assert_eq!(
+            lower(program),
+            Err(LoweringError::UnsupportedFeature {
+                location: Location(21),
+                feature: "`ctrl` modifiers with no controls".into()
+            })
+        );
+    }
+
+    #[test]
+    fn test_long_loops_are_unsupported() {
+        let long_loop = indoc!(
+            "
+            OPENQASM 3;
+            qubit q;
+            for int i in [0:1000000000] { x q; }
+            "
+        );
+        let nested_loops = indoc!(
+            "
+            OPENQASM 3;
+            qubit q;
+            for int i in [0:1000] { for int j in [0:1000] { } }
+            "
+        );
+        for (source, offset) in &[(long_loop, 21), (nested_loops, 45)] {
+            let program = open_qasm3::OpenQasmProgramParser::new()
+                .parse(Lexer::for_open_qasm3(source))
+                .unwrap();
+            assert_eq!(
+                lower(program),
+                Err(LoweringError::UnsupportedFeature {
+                    location: Location(*offset),
+                    feature: "`for` loops unrolling into more than 100000 iterations".into()
+                })
+            );
+        }
+    }
+}
@@ -0,0 +1,430 @@
+use std::collections::HashMap;
+use std::f64::consts::PI;
+
+use crate::grammar::ast::{
+    Expression, GateOperation, OpCode, OpenQasmProgram, QuantumOperation, Span, Statement,
+    UnitaryOperation,
+};
+use crate::interpreter::expression_solver::ExpressionSolver;
+
+/// Largest denominator tried when rewriting a value as a fraction of pi.
+const MAX_PI_DENOMINATOR: u32 = 16;
+
+/// Largest numerator tried when rewriting a value as a fraction of pi. Beyond
+/// it, the value is kept as a real number so its digits are not lost.
+const MAX_PI_NUMERATOR: u32 = 16 * MAX_PI_DENOMINATOR;
+
+/// Absolute tolerance for considering a value a fraction of pi.
+const PI_FRACTION_TOLERANCE: f64 = 1e-12;
+
+impl Expression {
+    /// Return an equivalent expression with its constant subexpressions
+    /// folded.
+    ///
+    /// Folded values that are fractions of pi are written exactly as
+    /// `k * pi / n`, so `tau / 4` becomes `pi / 2`. Identities such as
+    /// `x + 0`, `x * 1` or `--x` are removed. Subexpressions that do not
+    /// evaluate to a finite number are kept as they are so the error can be
+    /// reported when the expression is evaluated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use qasmsim::grammar::parse_expression;
+    ///
+    /// let expression = parse_expression("theta * (tau / 4 - 0) + 2 * 3")?;
+    /// assert_eq!(expression.simplify().to_string(), "theta * (pi / 2) + 6");
+    /// # Ok::<(), qasmsim::QasmSimError>(())
+    /// ```
+    pub fn simplify(&self) -> Expression {
+        if self.is_atom() {
+            return self.clone();
+        }
+        if let Some(value) = constant_value(self) {
+            return from_value(value);
+        }
+        match self {
+            Expression::Op(op_code, left, right) => {
+                simplify_op(*op_code, left.simplify(), right.simplify())
+            }
+            Expression::Function(func_code, argument) => {
+                Expression::Function(*func_code, Box::new(argument.simplify()))
+            }
+            Expression::BinaryFunction(func_code, left, right) => Expression::BinaryFunction(
+                *func_code,
+                Box::new(left.simplify()),
+                Box::new(right.simplify()),
+            ),
+            Expression::Minus(operand) => negate(operand.simplify()),
+            _ => self.clone(),
+        }
+    }
+
+    /// Return an equivalent angle in the interval `(-pi, pi]` if the
+    /// expression is constant, or the [`simplify()`]'d expression otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use qasmsim::grammar::parse_expression;
+    ///
+    /// let expression = parse_expression("5 * pi / 2")?;
+    /// assert_eq!(expression.normalize_angle().to_string(), "pi / 2");
+    /// # Ok::<(), qasmsim::QasmSimError>(())
+    /// ```
+    ///
+    /// [`simplify()`]: #method.simplify
+    pub fn normalize_angle(&self) -> Expression {
+        match constant_value(self) {
+            Some(value) => {
+                let mut angle = value - 2.0 * PI * (value / (2.0 * PI)).round();
+                if angle <= -PI {
+                    angle += 2.0 * PI;
+                }
+                if self.is_atom() && angle == value {
+                    self.clone()
+                } else {
+                    from_value(angle)
+                }
+            }
+            None => self.simplify(),
+        }
+    }
+
+    fn is_atom(&self) -> bool {
+        matches!(
+            self,
+            Expression::Pi
+                | Expression::Tau
+                | Expression::Euler
+                | Expression::Id(_)
+                | Expression::Real(_)
+                | Expression::Int(_)
+        )
+    }
+}
+
+impl OpenQasmProgram {
+    /// Return the program with the real parameters of its gate calls
+    /// simplified, including those in gate bodies and conditionals. The
+    /// angles `phi` and `lambda` of `U` are also normalized with
+    /// [`Expression::normalize_angle()`].
+    ///
+    /// [`Expression::normalize_angle()`]: ./enum.Expression.html#method.normalize_angle
+    pub fn simplify(&self) -> OpenQasmProgram {
+        OpenQasmProgram {
+            version: self.version.clone(),
+            program: self.program.iter().map(simplify_statement).collect(),
+        }
+    }
+}
+
+fn simplify_statement(span: &Span<Statement>) -> Span<Statement> {
+    let statement = match &*span.node {
+        Statement::GateDecl {
+            signature: (name, real_args, args, operations),
+            docstring,
+        } => Statement::GateDecl {
+            signature: (
+                name.clone(),
+                real_args.clone(),
+                args.clone(),
+                operations.iter().map(GateOperation::simplify).collect(),
+            ),
+            docstring: docstring.clone(),
+        },
+        Statement::QuantumOperation(operation) => Statement::QuantumOperation(operation.simplify()),
+        Statement::Conditional(register, value, operation) => {
+            Statement::Conditional(register.clone(), *value, operation.simplify())
+        }
+        Statement::IfElse {
+            condition,
+            then_branch,
+            else_branch,
+        } => Statement::IfElse {
+            condition: condition.clone(),
+            then_branch: then_branch.iter().map(simplify_statement).collect(),
+            else_branch: else_branch.iter().map(simplify_statement).collect(),
+        },
+        other => other.clone(),
+    };
+    Span {
+        boundaries: span.boundaries,
+        node: Box::new(statement),
+        parameters: span.parameters.clone(),
+        arguments: span.arguments.clone(),
+        operations: span.operations.clone(),
+    }
+}
+
+impl GateOperation {
+    pub(crate) fn simplify(&self) -> GateOperation {
+        match self {
+            GateOperation::Unitary(unitary) => GateOperation::Unitary(unitary.simplify()),
+            GateOperation::Modified(modifiers, unitary) => {
+                GateOperation::Modified(modifiers.clone(), unitary.simplify())
+            }
+            other => other.clone(),
+        }
+    }
+}
+
+impl QuantumOperation {
+    fn simplify(&self) -> QuantumOperation {
+        match self {
+            QuantumOperation::Unitary(unitary) => QuantumOperation::Unitary(unitary.simplify()),
+            QuantumOperation::Modified(modifiers, unitary) => {
+                QuantumOperation::Modified(modifiers.clone(), unitary.simplify())
+            }
+            other => other.clone(),
+        }
+    }
+}
+
+impl UnitaryOperation {
+    /// Return the gate call with its real parameters simplified. The angles
+    /// `phi` and `lambda` of `U` are normalized too, since whole turns of
+    /// them leave the gate unchanged. Its `theta` is not: a whole turn
+    /// changes the sign of the gate, which is observable under `ctrl`.
+    fn simplify(&self) -> UnitaryOperation {
+        let UnitaryOperation(name, real_args, args) = self;
+        let real_args = real_args
+            .iter()
+            .enumerate()
+            .map(|(index, parameter)| match (name.as_str(), index) {
+                ("U", 1) | ("U", 2) => parameter.normalize_angle(),
+                _ => parameter.simplify(),
+            })
+            .collect();
+        UnitaryOperation(name.clone(), real_args, args.clone())
+    }
+}
+
+/// Return the value of the expression if it does not depend on any symbol and
+/// evaluates to a finite number.
+fn constant_value(expression: &Expression) -> Option<f64> {
+    let no_bindings = HashMap::new();
+    ExpressionSolver::new(&no_bindings).solve(expression).ok()
+}
+
+fn is_value(expression: &Expression, expected: f64) -> bool {
+    match expression {
+        Expression::Real(value) => *value == expected,
+        Expression::Int(value) => *value as f64 == expected,
+        _ => false,
+    }
+}
+
+fn simplify_op(op_code: OpCode, left: Expression, right: Expression) -> Expression {
+    match op_code {
+        OpCode::Add if is_value(&left, 0.0) => right,
+        OpCode::Add | OpCode::Sub if is_value(&right, 0.0) => left,
+        OpCode::Sub if is_value(&left, 0.0) => negate(right),
+        OpCode::Mul if is_value(&left, 1.0) => right,
+        OpCode::Mul | OpCode::Div | OpCode::Pow if is_value(&right, 1.0) => left,
+        _ => Expression::Op(op_code, Box::new(left), Box::new(right)),
+    }
+}
+
+fn negate(expression: Expression) -> Expression {
+    match expression {
+        Expression::Minus(operand) => *operand,
+        _ => Expression::Minus(Box::new(expression)),
+    }
+}
+
+/// Write the value as an expression, exactly as `k * pi / n` if it is a
+/// fraction of pi. Negative values are written as negations so the
+/// expression prints back to valid OPENQASM.
+fn from_value(value: f64) -> Expression {
+    let magnitude = value.abs();
+    let expression = match pi_fraction(magnitude) {
+        Some((1, 1)) => Expression::Pi,
+        Some((1, denominator)) => real_op(OpCode::Div, Expression::Pi, denominator),
+        Some((numerator, 1)) => Expression::Op(
+            OpCode::Mul,
+            Box::new(Expression::Real(numerator as f64)),
+            Box::new(Expression::Pi),
+        ),
+        Some((numerator, denominator)) => real_op(
+            OpCode::Div,
+            Expression::Op(
+                OpCode::Mul,
+                Box::new(Expression::Real(numerator as f64)),
+                Box::new(Expression::Pi),
+            ),
+            denominator,
+        ),
+        None => Expression::Real(magnitude),
+    };
+    if value < 0.0 {
+        negate_leading_factor(expression)
+    } else {
+        expression
+    }
+}
+
+fn real_op(op_code: OpCode, left: Expression, right: u32) -> Expression {
+    Expression::Op(
+        op_code,
+        Box::new(left),
+        Box::new(Expression::Real(right as f64)),
+    )
+}
+
+/// Negate the leftmost factor so `-(pi / 2)` reads `-pi / 2`.
+fn negate_leading_factor(expression: Expression) -> Expression {
+    match expression {
+        Expression::Op(op_code, left, right) => {
+            Expression::Op(op_code, Box::new(negate_leading_factor(*left)), right)
+        }
+        _ => negate(expression),
+    }
+}
+
+/// Return the numerator and denominator, in lowest terms, of the fraction of
+/// pi equal to the positive value.
+fn pi_fraction(value: f64) -> Option<(u32, u32)> {
+    if value == 0.0 {
+        return None;
+    }
+    (1..=MAX_PI_DENOMINATOR).find_map(|denominator| {
+        let numerator = (value * denominator as f64 / PI).round();
+        if numerator < 1.0 || numerator > MAX_PI_NUMERATOR as f64 {
+            return None;
+        }
+        let error = (value - numerator * PI / denominator as f64).abs();
+        if error <= PI_FRACTION_TOLERANCE {
+            Some((numerator as u32, denominator))
+        } else {
+            None
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::iter::FromIterator;
+
+    use indoc::indoc;
+
+    use crate::grammar::{parse_expression, parse_program};
+
+    fn simplified(source: &str) -> String {
+        parse_expression(source).unwrap().simplify().to_string()
+    }
+
+    fn normalized(source: &str) -> String {
+        parse_expression(source)
+            .unwrap()
+            .normalize_angle()
+            .to_string()
+    }
+
+    #[test]
+    fn test_fold_constant_subexpressions() {
+        assert_eq!(simplified("1 + 2 * 3"), "7");
+        assert_eq!(simplified("x + 2 * 3"), "x + 6");
+        assert_eq!(simplified("sin(x) * (1 - 3)"), "sin(x) * -2");
+        assert_eq!(simplified("cos(x + (0.5 + 0.25))"), "cos(x + 0.75)");
+        assert_eq!(simplified("atan2(y, 1 - 1)"), "atan2(y, 0)");
+    }
+
+    #[test]
+    fn test_rewrite_fractions_of_pi_exactly() {
+        assert_eq!(simplified("pi"), "pi");
+        assert_eq!(simplified("pi / 2"), "pi / 2");
+        assert_eq!(simplified("tau / 4"), "pi / 2");
+        assert_eq!(simplified("pi / 4 + pi / 2"), "3 * pi / 4");
+        assert_eq!(simplified("-(pi / 2)"), "-pi / 2");
+        assert_eq!(simplified("-3 * pi / 6"), "-pi / 2");
+        assert_eq!(simplified("2 * pi"), "2 * pi");
+        assert_eq!(simplified("acos(-1)"), "pi");
+        assert_eq!(simplified("pi * pi"), (PI * PI).to_string());
+        assert_eq!(simplified("256 * pi"), "256 * pi");
+        assert_eq!(simplified("257 * pi"), (257.0 * PI).to_string());
+        assert_eq!(simplified("2 * 500000000000"), "1000000000000");
+    }
+
+    #[test]
+    fn test_remove_identities() {
+        assert_eq!(simplified("0 + x"), "x");
+        assert_eq!(simplified("x - 0"), "x");
+        assert_eq!(simplified("0 - x"), "-x");
+        assert_eq!(simplified("1 * x / 1"), "x");
+        assert_eq!(simplified("x ^ (2 - 1)"), "x");
+        assert_eq!(simplified("--x"), "x");
+        assert_eq!(simplified("x * 0"), "x * 0");
+    }
+
+    #[test]
+    fn test_normalize_angles() {
+        assert_eq!(normalized("5 * pi / 2"), "pi / 2");
+        assert_eq!(normalized("tau"), "0");
+        assert_eq!(normalized("-pi"), "pi");
+        assert_eq!(normalized("3 * pi / 2"), "-pi / 2");
+        assert_eq!(normalized("pi"), "pi");
+        assert_eq!(normalized("1"), "1");
+        assert_eq!(normalized("7"), (7.0 - 2.0 * PI).to_string());
+        assert_eq!(normalized("x + (1 + 1)"), "x + 2");
+    }
+
+    #[test]
+    fn test_normalize_the_periodic_angles_of_u() {
+        let program = parse_program(indoc!(
+            "
+            OPENQASM 2.0;
+            qreg q[2];
+            gate g(a) r { U(2 * pi + a, 4 * pi + a, 3 * pi) r; }
+            U(5 * pi / 2, -3 * pi / 2, tau) q[0];
+            if (c == 1) U(1 + 1, 0, 1 + 1) q[1];
+            ctrl @ U(tau, tau, tau) q[0], q[1];
+            "
+        ))
+        .unwrap();
+        let statements: Vec<String> = program
+            .simplify()
+            .program
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            statements,
+            vec![
+                "qreg q[2];",
+                "gate g(a) r {\n  U(2 * pi + a, 4 * pi + a, pi) r;\n}",
+                "U(5 * pi / 2, pi / 2, 0) q[0];",
+                "if (c == 1) U(2, 0, 2) q[1];",
+                "ctrl @ U(tau, 0, 0) q[0], q[1];",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_keep_non_finite_subexpressions() {
+        assert_eq!(simplified("ln(-1) + x"), "ln(-1) + x");
+        assert_eq!(simplified("1 / 0"), "1 / 0");
+    }
+
+    #[test]
+    fn test_simplified_expressions_keep_their_value() {
+        let bindings = HashMap::from_iter(vec![("x".to_owned(), 0.3), ("y".to_owned(), -1.7)]);
+        let solver = ExpressionSolver::new(&bindings);
+        for source in &[
+            "x * (tau / 3 - 1) + euler ^ 2",
+            "-(y - 0) / (2 * pi / 8)",
+            "mod(x + 7 * pi, 2 * pi) - floor(y * 3)",
+            "2 * 500000000000 + 0.5",
+            "-(2 ^ 70) + x",
+            "1000 * pi + 1 / 3",
+            "256 * pi + 16 * pi",
+        ] {
+            let expression = parse_expression(source).unwrap();
+            let expected = solver.solve(&expression).unwrap();
+            let actual = solver.solve(&expression.simplify()).unwrap();
+            assert!((expected - actual).abs() < 1e-12, "{}", source);
+        }
+    }
+}
@@ -1,6 +1,23 @@
 mod argument_solver;
+mod compiler;
 mod computation;
-mod expression_solver;
+mod diagram;
+pub mod equivalence;
+pub(crate) mod expression_solver;
+mod flattener;
+mod opaque;
 pub mod runtime;
+mod stats;
+mod unitary;
+pub(crate) mod validation;
 
+pub(crate) use self::compiler::MAX_POWER_OPERATIONS;
 pub use self::computation::{Computation, Histogram};
+pub(crate) use self::diagram::draw;
+pub use self::equivalence::Equivalence;
+pub(crate) use self::flattener::flatten;
+pub use self::flattener::Basis;
+pub use self::opaque::{MatrixGate, OpaqueGate, OpaqueGates};
+pub(crate) use self::stats::collect_stats;
+pub use self::stats::Stats;
+pub use self::unitary::Unitary;
@@ -0,0 +1,1345 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::f64::consts::PI;
+use std::fmt;
+use std::iter::FromIterator;
+
+use crate::grammar::{ast, lexer::Location};
+use crate::interpreter::argument_solver::ArgumentSolver;
+use crate::interpreter::expression_solver::{ExpressionError, ExpressionSolver};
+use crate::interpreter::opaque::OpaqueGates;
+use crate::interpreter::runtime::{Result, RuntimeError};
+use crate::semantics::{QasmType, RegisterType, Semantics};
+
+/// Maximum number of gates a `pow` modifier can expand into.
+pub(crate) const MAX_POWER_OPERATIONS: usize = 100_000;
+
+type BindingMappings = (HashMap<String, f64>, HashMap<String, ast::Argument>);
+
+/// A `U` gate on `target`, controlled by the `controls` qubits. Gates under
+/// modifiers are expanded into a list of these before being applied.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ControlledU {
+    pub theta: f64,
+    pub phi: f64,
+    pub lambda: f64,
+    pub controls: Vec<usize>,
+    pub target: usize,
+}
+
+impl ControlledU {
+    fn inverse(&self) -> Self {
+        ControlledU {
+            theta: -self.theta,
+            phi: -self.lambda,
+            lambda: -self.phi,
+            controls: self.controls.clone(),
+            target: self.target,
+        }
+    }
+}
+
+/// A primitive operation with its qubits resolved to absolute indices and its
+/// parameters evaluated.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Instruction {
+    /// The `U` gate.
+    U {
+        theta: f64,
+        phi: f64,
+        lambda: f64,
+        target: usize,
+    },
+    /// The `CX` gate.
+    CX { control: usize, target: usize },
+    /// A `U` gate resulting from expanding a gate under modifiers.
+    ControlledU(ControlledU),
+    /// A call to an opaque gate, bound to a native implementation.
+    Opaque {
+        name: String,
+        real_args: Vec<f64>,
+        qubits: Vec<usize>,
+    },
+    /// A measurement of `qubit` into the bit `bit` of the classical register.
+    Measure {
+        qubit: usize,
+        register: String,
+        bit: usize,
+    },
+    /// A reset of `qubit`.
+    Reset(usize),
+    /// A choice between two lists of instructions depending on the classical
+    /// memory. The condition holds if all the tests hold.
+    Branch {
+        condition: Vec<Test>,
+        then_branch: Vec<Instruction>,
+        else_branch: Vec<Instruction>,
+    },
+    /// An error found while compiling a conditional branch. It is raised only
+    /// if the branch runs.
+    Fail(RuntimeError),
+}
+
+/// A comparison of a classical register, or one of its bits, against a value.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Test {
+    register: String,
+    bit: Option<usize>,
+    operator: ast::ComparisonOp,
+    value: u64,
+}
+
+impl Test {
+    /// Return the classical register the test reads and, if the test is on a
+    /// single bit, the index of the bit.
+    pub fn operand(&self) -> (&str, Option<usize>) {
+        (&self.register, self.bit)
+    }
+
+    pub fn holds(&self, memory: &HashMap<String, u64>) -> bool {
+        let value = *memory
+            .get(&self.register)
+            .expect("after compiling, the register exists");
+        match self.bit {
+            None => self.operator.compare(value, self.value),
+            Some(bit) => self.operator.compare((value >> bit) & 1, self.value),
+        }
+    }
+}
+
+/// Write the test as in `c == 1` or `c[0] != 0`.
+impl fmt::Display for Test {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.register)?;
+        if let Some(bit) = self.bit {
+            write!(f, "[{}]", bit)?;
+        }
+        write!(f, " {} {}", self.operator, self.value)
+    }
+}
+
+/// Turn `program` into a flat list of instructions by expanding all the gate
+/// calls into primitive gates acting on absolute qubit indices.
+///
+/// Errors outside conditional statements are returned right away. Errors
+/// inside the branches of a conditional become [`Instruction::Fail`] so they
+/// are raised only if the branch runs.
+pub(crate) fn compile(
+    program: &ast::OpenQasmProgram,
+    semantics: &Semantics,
+    opaque_gates: Option<&OpaqueGates>,
+) -> Result<Vec<Instruction>> {
+    let mut compiler = Compiler::new(semantics, opaque_gates);
+    compiler.compile_statements(&program.program)?;
+    Ok(compiler.instructions)
+}
+
+/// Compile `statement` on its own and return all the errors found, including
+/// those in every branch of conditional statements.
+///
+/// The calls to the gates in `skipped_gates` are checked but not expanded, so
+/// the errors in their bodies, reported elsewhere, are not repeated for each
+/// call.
+pub(crate) fn statement_errors(
+    statement: &ast::Span<ast::Statement>,
+    semantics: &Semantics,
+    opaque_gates: Option<&OpaqueGates>,
+    skipped_gates: &HashSet<String>,
+) -> Vec<RuntimeError> {
+    let mut compiler = Compiler::new(semantics, opaque_gates);
+    compiler.skipped_gates = Some(skipped_gates);
+    let mut errors = Vec::new();
+    compiler.check_statements(std::slice::from_ref(statement), &mut errors);
+    errors
+}
+
+#[derive(Debug)]
+struct Compiler<'a> {
+    macro_stack: VecDeque<BindingMappings>,
+    semantics: &'a Semantics,
+    location: Option<Location>,
+    opaque_gates: Option<&'a OpaqueGates>,
+    recording: Option<Vec<ControlledU>>,
+    instructions: Vec<Instruction>,
+    skipped_gates: Option<&'a HashSet<String>>,
+}
+
+impl<'a> Compiler<'a> {
+    fn new(semantics: &'a Semantics, opaque_gates: Option<&'a OpaqueGates>) -> Self {
+        Compiler {
+            macro_stack: VecDeque::new(),
+            semantics,
+            location: None,
+            opaque_gates,
+            recording: None,
+            instructions: Vec::new(),
+            skipped_gates: None,
+        }
+    }
+
+    fn check_statements(
+        &mut self,
+        statements: &[ast::Span<ast::Statement>],
+        errors: &mut Vec<RuntimeError>,
+    ) {
+        for span in statements {
+            self.location = Some(span.boundaries.0);
+            if let ast::Statement::IfElse {
+                condition,
+                then_branch,
+                else_branch,
+            } = &*span.node
+            {
+                for ast::Comparison(register, operator, test) in &condition.0 {
+                    if let Err(error) = self.test(register, *operator, *test) {
+                        errors.push(locate(error, span));
+                    }
+                }
+                self.check_statements(then_branch, errors);
+                self.check_statements(else_branch, errors);
+            } else {
+                let instructions = self.compile_branch(|compiler| {
+                    compiler.compile_statements(std::slice::from_ref(span))
+                });
+                collect_failures(&instructions, errors);
+            }
+        }
+    }
+    fn compile_statements(&mut self, statements: &[ast::Span<ast::Statement>]) -> Result<()> {
+        for span in statements {
+            self.location = Some(span.boundaries.0);
+            match &*span.node {
+                ast::Statement::QuantumOperation(operation) => {
+                    self.compile_quantum_operation(operation)
+                        .map_err(|error| locate(error, span))?;
+                }
+                ast::Statement::Conditional(register, test, operation) => {
+                    let condition = vec![self
+                        .test(register, ast::ComparisonOp::Equal, *test)
+                        .map_err(|error| locate(error, span))?];
+                    let then_branch = self.compile_branch(|compiler| {
+                        compiler
+                            .compile_quantum_operation(operation)
+                            .map_err(|error| locate(error, span))
+                    });
+                    self.instructions.push(Instruction::Branch {
+                        condition,
+                        then_branch,
+                        else_branch: vec![],
+                    });
+                }
+                ast::Statement::IfElse {
+                    condition,
+                    then_branch,
+                    else_branch,
+                } => {
+                    let condition = condition
+                        .0
+                        .iter()
+                        .map(|ast::Comparison(register, operator, test)| {
+                            self.test(register, *operator, *test)
+                                .map_err(|error| locate(error, span))
+                        })
+                        .collect::<Result<Vec<Test>>>()?;
+                    let then_branch =
+                        self.compile_branch(|compiler| compiler.compile_statements(then_branch));
+                    let else_branch =
+                        self.compile_branch(|compiler| compiler.compile_statements(else_branch));
+                    self.instructions.push(Instruction::Branch {
+                        condition,
+                        then_branch,
+                        else_branch,
+                    });
+                }
+                _ => (),
+            };
+        }
+        Ok(())
+    }
+
+    /// Run `action` collecting the instructions it compiles apart from the
+    /// rest. A failure ends the collected instructions.
+    fn compile_branch<F>(&mut self, action: F) -> Vec<Instruction>
+    where
+        F: FnOnce(&mut Self) -> Result<()>,
+    {
+        let outer_instructions = std::mem::take(&mut self.instructions);
+        let result = action(self);
+        let mut branch = std::mem::replace(&mut self.instructions, outer_instructions);
+        if let Err(error) = result {
+            branch.push(Instruction::Fail(error));
+        }
+        branch
+    }
+
+    fn compile_quantum_operation(&mut self, operation: &ast::QuantumOperation) -> Result<()> {
+        match operation {
+            ast::QuantumOperation::Unitary(unitary) => self.apply_unitary(unitary),
+            ast::QuantumOperation::Modified(modifiers, unitary) => {
+                self.apply_modified(modifiers, unitary)
+            }
+            ast::QuantumOperation::Measure(source, target) => {
+                self.apply_measurement(vec![(*source).clone(), (*target).clone()])
+            }
+            ast::QuantumOperation::Reset(target) => self.apply_reset(target),
+        }
+    }
+
+    fn test(
+        &self,
+        register: &ast::Argument,
+        operator: ast::ComparisonOp,
+        value: u64,
+    ) -> Result<Test> {
+        let register_name = register_name(register);
+        self.assert_is_classical_register(register_name)?;
+        let bit = match register {
+            ast::Argument::Id(_) => None,
+            ast::Argument::Item(_, index) => {
+                let size = self
+                    .semantics
+                    .register_table
+                    .get(register_name)
+                    .expect("after `assert_is_classical_register()`, must exist")
+                    .2;
+                if *index >= size {
+                    return Err(RuntimeError::IndexOutOfBounds {
+                        location: self.location(),
+                        boundaries: None,
+                        symbol_name: register_name.into(),
+                        index: *index,
+                        size,
+                    });
+                }
+                Some(*index)
+            }
+        };
+        Ok(Test {
+            register: register_name.into(),
+            bit,
+            operator,
+            value,
+        })
+    }
+
+    fn location(&self) -> Location {
+        self.location
+            .expect("after `compile_statements()`, the location of the statement")
+    }
+
+    fn apply_reset(&mut self, target: &ast::Argument) -> Result<()> {
+        self.assert_is_quantum_register(register_name(target))?;
+        let expanded_arguments = self
+            .expand_arguments(std::slice::from_ref(target))
+            .expect("a single argument always expands");
+        for argument_expansion in expanded_arguments {
+            let qubit = self.bit_mapping(&argument_expansion[0])?;
+            self.instructions.push(Instruction::Reset(qubit));
+        }
+        Ok(())
+    }
+
+    fn apply_modified(
+        &mut self,
+        modifiers: &[ast::GateModifier],
+        unitary: &ast::UnitaryOperation,
+    ) -> Result<()> {
+        let name = &unitary.0;
+        let real_args = &unitary.1;
+        let args = &unitary.2;
+        let location = self.location();
+
+        if self.semantics.opaque_definitions.contains_key(name) {
+            return Err(RuntimeError::ModifiedOpaqueGate {
+                location,
+                symbol_name: name.clone(),
+            });
+        }
+
+        let control_count: usize = modifiers
+            .iter()
+            .map(|modifier| match modifier {
+                ast::GateModifier::Ctrl(count) => *count,
+                _ => 0,
+            })
+            .sum();
+        let expected = control_count + self.gate_arity(name)?;
+        if args.len() != expected {
+            return Err(RuntimeError::WrongNumberOfParameters {
+                are_registers: true,
+                location,
+                boundaries: None,
+                symbol_name: name.clone(),
+                given: args.len(),
+                expected,
+            });
+        }
+
+        let actual_args = self.resolve_actual_args(args)?;
+        self.check_all_are_quantum_registers(&actual_args)?;
+        let solved_real_args = self.resolve_real_expressions(real_args)?;
+        let exponents = self.resolve_exponents(name, modifiers)?;
+
+        let expanded_arguments = self.expand_arguments(&actual_args).map_err(|sizes| {
+            RuntimeError::RegisterSizeMismatch {
+                location,
+                boundaries: None,
+                symbol_name: name.clone(),
+                sizes,
+            }
+        })?;
+
+        for argument_expansion in expanded_arguments {
+            self.assert_distinct_qubits(name, &argument_expansion)?;
+            let controls = argument_expansion[..control_count]
+                .iter()
+                .map(|argument| self.bit_mapping(argument))
+                .collect::<Result<Vec<usize>>>()?;
+            let gate_args = &argument_expansion[control_count..];
+            let mut operations = self
+                .record(|compiler| compiler.apply_one_gate(name, &solved_real_args, gate_args))?;
+
+            let mut remaining_controls = &controls[..];
+            let mut modifiers_with_controls = Vec::with_capacity(modifiers.len());
+            for (modifier, exponent) in modifiers.iter().zip(&exponents) {
+                let count = match modifier {
+                    ast::GateModifier::Ctrl(count) => *count,
+                    _ => 0,
+                };
+                let (modifier_controls, rest) = remaining_controls.split_at(count);
+                modifiers_with_controls.push((modifier, modifier_controls, exponent));
+                remaining_controls = rest;
+            }
+
+            for (modifier, modifier_controls, exponent) in modifiers_with_controls.into_iter().rev()
+            {
+                operations = match modifier {
+                    ast::GateModifier::Inv => invert(&operations),
+                    ast::GateModifier::Ctrl(_) => operations
+                        .into_iter()
+                        .map(|mut operation| {
+                            operation.controls.extend_from_slice(modifier_controls);
+                            operation
+                        })
+                        .collect(),
+                    ast::GateModifier::Pow(_) => {
+                        let exponent = exponent.expect("`pow` modifiers have an exponent");
+                        let base = if exponent < 0 {
+                            invert(&operations)
+                        } else {
+                            operations
+                        };
+                        let repetitions = exponent.unsigned_abs() as usize;
+                        if base.len().saturating_mul(repetitions) > MAX_POWER_OPERATIONS {
+                            return Err(RuntimeError::PowerTooLarge {
+                                location,
+                                symbol_name: name.clone(),
+                                exponent: format!("{}", exponent),
+                            });
+                        }
+                        base.iter()
+                            .cloned()
+                            .cycle()
+                            .take(base.len() * repetitions)
+                            .collect()
+                    }
+                };
+            }
+
+            self.emit(operations);
+        }
+
+        Ok(())
+    }
+
+    fn resolve_exponents(
+        &self,
+        name: &str,
+        modifiers: &[ast::GateModifier],
+    ) -> Result<Vec<Option<i64>>> {
+        modifiers
+            .iter()
+            .map(|modifier| match modifier {
+                ast::GateModifier::Pow(expression) => {
+                    let exponent =
+                        self.resolve_real_expressions(std::slice::from_ref(expression))?[0];
+                    if exponent.fract() != 0.0 || !exponent.is_finite() {
+                        return Err(RuntimeError::NonIntegerPower {
+                            location: self.location(),
+                            symbol_name: name.into(),
+                            exponent: format!("{}", exponent),
+                        });
+                    }
+                    Ok(Some(exponent as i64))
+                }
+                _ => Ok(None),
+            })
+            .collect()
+    }
+
+    fn gate_arity(&self, name: &str) -> Result<usize> {
+        match name {
+            "U" => Ok(1),
+            "CX" => Ok(2),
+            _ => match self.semantics.macro_definitions.get(name) {
+                Some(definition) => Ok(definition.2.len()),
+                None => Err(RuntimeError::UndefinedGate {
+                    location: self.location(),
+                    symbol_name: name.into(),
+                }),
+            },
+        }
+    }
+
+    /// Run `action` collecting the primitive gates it applies instead of
+    /// emitting them as instructions.
+    fn record<F>(&mut self, action: F) -> Result<Vec<ControlledU>>
+    where
+        F: FnOnce(&mut Self) -> Result<()>,
+    {
+        let outer_recording = self.recording.replace(Vec::new());
+        let result = action(self);
+        let recorded = std::mem::replace(&mut self.recording, outer_recording)
+            .expect("after `record()`, the recorded operations");
+        result.map(|_| recorded)
+    }
+
+    fn emit(&mut self, operations: Vec<ControlledU>) {
+        match &mut self.recording {
+            Some(recording) => recording.extend(operations),
+            None => self
+                .instructions
+                .extend(operations.into_iter().map(Instruction::ControlledU)),
+        }
+    }
+
+    fn apply_unitary(&mut self, unitary: &ast::UnitaryOperation) -> Result<()> {
+        let name = &unitary.0;
+        let real_args = &unitary.1;
+        let args = &unitary.2;
+
+        let actual_args = self.resolve_actual_args(args)?;
+        self.check_all_are_quantum_registers(&actual_args)?;
+
+        let solved_real_args = self.resolve_real_expressions(real_args)?;
+
+        let expanded_arguments = self.expand_arguments(&actual_args).map_err(|sizes| {
+            RuntimeError::RegisterSizeMismatch {
+                location: self.location(),
+                boundaries: None,
+                symbol_name: name.clone(),
+                sizes,
+            }
+        })?;
+
+        for argument_expansion in expanded_arguments {
+            self.apply_one_gate(name, &solved_real_args, &argument_expansion)?;
+        }
+
+        Ok(())
+    }
+
+    fn resolve_actual_args(&self, args: &[ast::Argument]) -> Result<Vec<ast::Argument>> {
+        let actual = if !self.is_running_macro() {
+            args.iter()
+                .map(|argument| Ok(argument.clone()))
+                .collect::<Result<Vec<ast::Argument>>>()
+        } else {
+            let stack_entry = self
+                .macro_stack
+                .front()
+                .expect("if `is_running_macro()`, get first entry of the stack");
+            let arg_bindings = &stack_entry.1;
+            let argument_solver = ArgumentSolver::new(arg_bindings);
+            args.iter()
+                .map(|argument| {
+                    argument_solver.solve(argument).map_err(|symbol_name| {
+                        RuntimeError::SymbolNotFound {
+                            location: self.location(),
+                            boundaries: None,
+                            symbol_name,
+                            expected: QasmType::QuantumRegister,
+                        }
+                    })
+                })
+                .collect::<Result<Vec<ast::Argument>>>()
+        }?;
+        Ok(actual)
+    }
+
+    fn resolve_real_expressions(&self, exprs: &[ast::Expression]) -> Result<Vec<f64>> {
+        let mut real_bindings = &HashMap::new();
+        if self.is_running_macro() {
+            let stack_entry = self
+                .macro_stack
+                .front()
+                .expect("if `is_running_macro()`, get first stack entry");
+            real_bindings = &stack_entry.0;
+        };
+        let expression_solver = ExpressionSolver::new(real_bindings);
+        let mut solved = Vec::new();
+        // The solver fails as soon as some operation results in a non-finite
+        // value, so the parameters reaching the state-vector are always valid.
+        for expression in exprs {
+            let location = self.location();
+            let value = expression_solver
+                .solve(expression)
+                .map_err(|error| match error {
+                    ExpressionError::SymbolNotFound(symbol_name) => RuntimeError::SymbolNotFound {
+                        location,
+                        boundaries: None,
+                        symbol_name,
+                        expected: QasmType::RealValue,
+                    },
+                    ExpressionError::NonFiniteValue(_, value) => RuntimeError::InvalidParameter {
+                        location,
+                        expression: expression.to_string(),
+                        value: value.to_string(),
+                    },
+                })?;
+            solved.push(value);
+        }
+        Ok(solved)
+    }
+
+    fn is_running_macro(&self) -> bool {
+        !self.macro_stack.is_empty()
+    }
+
+    fn apply_measurement(&mut self, args: Vec<ast::Argument>) -> Result<()> {
+        self.assert_is_quantum_register(register_name(&args[0]))?;
+        self.assert_is_classical_register(register_name(&args[1]))?;
+
+        let expanded_arguments =
+            self.expand_arguments(&args)
+                .map_err(|sizes| RuntimeError::RegisterSizeMismatch {
+                    location: self.location(),
+                    boundaries: None,
+                    symbol_name: "measure".into(),
+                    sizes,
+                })?;
+
+        for argument_expansion in expanded_arguments {
+            self.apply_one_measurement(argument_expansion)?;
+        }
+
+        Ok(())
+    }
+
+    fn apply_one_measurement(&mut self, args: Vec<ast::Argument>) -> Result<()> {
+        let qubit = self.bit_mapping(&args[0])?;
+        let bit = self.bit_mapping(&args[1])?;
+        self.instructions.push(Instruction::Measure {
+            qubit,
+            register: register_name(&args[1]).into(),
+            bit,
+        });
+        Ok(())
+    }
+
+    fn apply_one_gate(
+        &mut self,
+        name: &str,
+        real_args: &[f64],
+        args: &[ast::Argument],
+    ) -> Result<()> {
+        match name {
+            "U" => {
+                let theta = real_args[0];
+                let phi = real_args[1];
+                let lambda = real_args[2];
+                let target = self.bit_mapping(&args[0])?;
+                match &mut self.recording {
+                    Some(recording) => recording.push(ControlledU {
+                        theta,
+                        phi,
+                        lambda,
+                        controls: vec![],
+                        target,
+                    }),
+                    None => self.instructions.push(Instruction::U {
+                        theta,
+                        phi,
+                        lambda,
+                        target,
+                    }),
+                }
+            }
+            "CX" => {
+                self.assert_distinct_qubits(name, args)?;
+                let control = self.bit_mapping(&args[0])?;
+                let target = self.bit_mapping(&args[1])?;
+                match &mut self.recording {
+                    Some(recording) => recording.push(ControlledU {
+                        theta: PI,
+                        phi: 0.0,
+                        lambda: PI,
+                        controls: vec![control],
+                        target,
+                    }),
+                    None => self.instructions.push(Instruction::CX { control, target }),
+                }
+            }
+            opaque_name if self.semantics.opaque_definitions.contains_key(opaque_name) => {
+                if self.recording.is_some() {
+                    return Err(RuntimeError::ModifiedOpaqueGate {
+                        location: self.location(),
+                        symbol_name: opaque_name.into(),
+                    });
+                }
+                self.apply_opaque_gate(opaque_name, real_args, args)?;
+            }
+            macro_name => {
+                let binding_mappings = self.bind(macro_name, real_args, args)?;
+                self.assert_distinct_qubits(macro_name, args)?;
+                let skipped = matches!(
+                    self.skipped_gates,
+                    Some(skipped_gates) if skipped_gates.contains(macro_name)
+                );
+                if !skipped {
+                    self.call(macro_name, binding_mappings)?;
+                }
+            }
+        };
+        Ok(())
+    }
+
+    fn apply_opaque_gate(
+        &mut self,
+        name: &str,
+        real_args: &[f64],
+        args: &[ast::Argument],
+    ) -> Result<()> {
+        let location = self.location();
+        let definition = self
+            .semantics
+            .opaque_definitions
+            .get(name)
+            .expect("after `apply_one_gate()`, get the opaque definition");
+
+        if real_args.len() != definition.1.len() {
+            return Err(RuntimeError::WrongNumberOfParameters {
+                are_registers: false,
+                location,
+                boundaries: None,
+                symbol_name: name.into(),
+                given: real_args.len(),
+                expected: definition.1.len(),
+            });
+        }
+
+        if args.len() != definition.2.len() {
+            return Err(RuntimeError::WrongNumberOfParameters {
+                are_registers: true,
+                location,
+                boundaries: None,
+                symbol_name: name.into(),
+                given: args.len(),
+                expected: definition.2.len(),
+            });
+        }
+
+        let implementation = match self.opaque_gates.and_then(|gates| gates.get(name)) {
+            None => {
+                return Err(RuntimeError::UnboundOpaqueGate {
+                    location,
+                    symbol_name: name.into(),
+                })
+            }
+            Some(implementation) => implementation,
+        };
+
+        if let Some(qubit_width) = implementation.qubit_width() {
+            if args.len() != qubit_width {
+                return Err(RuntimeError::WrongNumberOfParameters {
+                    are_registers: true,
+                    location,
+                    boundaries: None,
+                    symbol_name: name.into(),
+                    given: args.len(),
+                    expected: qubit_width,
+                });
+            }
+        }
+
+        self.assert_distinct_qubits(name, args)?;
+        let qubits = args
+            .iter()
+            .map(|argument| self.bit_mapping(argument))
+            .collect::<Result<Vec<usize>>>()?;
+        self.instructions.push(Instruction::Opaque {
+            name: name.into(),
+            real_args: real_args.to_vec(),
+            qubits,
+        });
+        Ok(())
+    }
+
+    /// Fail if some qubit appears more than once in the arguments of a call to
+    /// the gate `name`, controls included.
+    fn assert_distinct_qubits(&self, name: &str, args: &[ast::Argument]) -> Result<()> {
+        let mut qubits = HashSet::new();
+        for argument in args {
+            if !qubits.insert(self.bit_mapping(argument)?) {
+                return Err(RuntimeError::RepeatedQubit {
+                    location: self.location(),
+                    boundaries: None,
+                    symbol_name: name.into(),
+                    qubit: argument.to_string(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn check_all_are_quantum_registers(&self, args: &[ast::Argument]) -> Result<()> {
+        for argument in args {
+            let register_name = register_name(argument);
+            self.assert_is_quantum_register(register_name)?;
+        }
+        Ok(())
+    }
+
+    fn assert_is_quantum_register(&self, name: &str) -> Result<()> {
+        if !self.is_register_of_type(RegisterType::Q, name)? {
+            Err(RuntimeError::TypeMismatch {
+                location: self.location(),
+                boundaries: None,
+                symbol_name: name.into(),
+                expected: QasmType::QuantumRegister,
+            })
+        } else {
+            Ok(())
+        }
+    }
+
+    fn assert_is_classical_register(&self, name: &str) -> Result<()> {
+        if !self.is_register_of_type(RegisterType::C, name)? {
+            Err(RuntimeError::TypeMismatch {
+                location: self.location(),
+                boundaries: None,
+                symbol_name: name.into(),
+                expected: QasmType::ClassicalRegister,
+            })
+        } else {
+            Ok(())
+        }
+    }
+
+    fn is_register_of_type(&self, rtype: RegisterType, name: &str) -> Result<bool> {
+        match self.semantics.register_table.get(name) {
+            Some(entry) => Ok(entry.1 == rtype),
+            None => Err(RuntimeError::SymbolNotFound {
+                location: self.location(),
+                boundaries: None,
+                symbol_name: name.into(),
+                expected: match rtype {
+                    RegisterType::Q => QasmType::QuantumRegister,
+                    RegisterType::C => QasmType::ClassicalRegister,
+                },
+            }),
+        }
+    }
+
+    /// Apply the `operations` of a gate body. Errors in the operations with a
+    /// known span are located there instead of at the gate call.
+    fn apply_gate_operations(
+        &mut self,
+        operations: &[ast::GateOperation],
+        spans: &[ast::Span<()>],
+    ) -> Result<()> {
+        for (index, one_operation) in operations.iter().enumerate() {
+            match spans.get(index) {
+                None => self.apply_gate_operation(one_operation)?,
+                Some(span) => {
+                    let call_location = self.location.replace(span.boundaries.0);
+                    let result = self
+                        .apply_gate_operation(one_operation)
+                        .map_err(|error| locate_operation(error, one_operation, span));
+                    self.location = call_location;
+                    result?
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn apply_gate_operation(&mut self, operation: &ast::GateOperation) -> Result<()> {
+        match operation {
+            ast::GateOperation::Unitary(unitary) => self.apply_unitary(unitary),
+            ast::GateOperation::Modified(modifiers, unitary) => {
+                self.apply_modified(modifiers, unitary)
+            }
+            ast::GateOperation::Barrier(_) => Ok(()),
+        }
+    }
+
+    fn expand_arguments(
+        &self,
+        args: &[ast::Argument],
+    ) -> std::result::Result<Vec<Vec<ast::Argument>>, Vec<usize>> {
+        let range = self.range(args)?;
+        Ok(range.map(|index| Compiler::specify(args, index)).collect())
+    }
+
+    fn bit_mapping(&self, argument: &ast::Argument) -> Result<usize> {
+        match argument {
+            ast::Argument::Item(name, index) => match self.semantics.memory_map.get(name) {
+                None => Err(RuntimeError::SymbolNotFound {
+                    location: self.location(),
+                    boundaries: None,
+                    symbol_name: name.into(),
+                    expected: QasmType::Register,
+                }),
+                Some(mapping) => {
+                    let size = mapping.2 - mapping.1 + 1;
+                    if *index >= size {
+                        return Err(RuntimeError::IndexOutOfBounds {
+                            location: self.location(),
+                            boundaries: None,
+                            symbol_name: name.into(),
+                            index: *index,
+                            size,
+                        });
+                    }
+                    Ok(mapping.1 + *index)
+                }
+            },
+            _ => unreachable!("after `expand_arguments()`, argument should be Argument::Item"),
+        }
+    }
+
+    fn range(
+        &self,
+        args: &[ast::Argument],
+    ) -> std::result::Result<std::ops::Range<usize>, Vec<usize>> {
+        // XXX: This is performed after validating the type of args.
+
+        let whole_registers: Vec<&ast::Argument> = args
+            .iter()
+            .filter(|arg| matches!(arg, ast::Argument::Id(_)))
+            .collect();
+
+        // Return a one-iteration range, `specify()` takes care of ignoring Item arugments.
+        if whole_registers.is_empty() {
+            return Ok(0..1);
+        }
+
+        let all_sizes: Vec<usize> = whole_registers
+            .iter()
+            .map(|arg| {
+                let register_name = register_name(arg);
+                let register_entry = self
+                    .semantics
+                    .register_table
+                    .get(register_name)
+                    .expect("after validation, get register entry");
+                register_entry.2
+            })
+            .collect();
+
+        let reference_size = all_sizes[0];
+        let all_the_same_size = all_sizes.iter().all(|size| *size == reference_size);
+
+        if all_the_same_size {
+            Ok(0..reference_size)
+        } else {
+            Err(all_sizes)
+        }
+    }
+
+    fn specify(args: &[ast::Argument], index: usize) -> Vec<ast::Argument> {
+        let mut result = vec![];
+        for arg in args {
+            match arg {
+                ast::Argument::Id(name) => result.push(ast::Argument::Item(name.clone(), index)),
+                other => result.push(other.clone()),
+            }
+        }
+        result
+    }
+
+    fn bind(
+        &mut self,
+        macro_name: &str,
+        real_args: &[f64],
+        args: &[ast::Argument],
+    ) -> Result<BindingMappings> {
+        let definition = match self.semantics.macro_definitions.get(macro_name) {
+            None => {
+                return Err(RuntimeError::UndefinedGate {
+                    location: self.location(),
+                    symbol_name: macro_name.into(),
+                });
+            }
+            Some(definition) => definition,
+        };
+
+        if real_args.len() != definition.1.len() {
+            return Err(RuntimeError::WrongNumberOfParameters {
+                are_registers: false,
+                location: self.location(),
+                boundaries: None,
+                symbol_name: macro_name.into(),
+                given: real_args.len(),
+                expected: definition.1.len(),
+            });
+        }
+        let real_args_mapping = HashMap::from_iter(
+            definition
+                .1
+                .iter()
+                .zip(real_args.iter()) // pair formal arguments with their float values
+                .map(|(s, f)| (s.to_owned(), *f)), // convert them into proper copies
+        );
+
+        if args.len() != definition.2.len() {
+            return Err(RuntimeError::WrongNumberOfParameters {
+                are_registers: true,
+                location: self.location(),
+                boundaries: None,
+                symbol_name: macro_name.into(),
+                given: args.len(),
+                expected: definition.2.len(),
+            });
+        }
+        let args_mapping = HashMap::from_iter(
+            definition
+                .2
+                .iter()
+                .zip(args.iter().cloned()) // pair formal arguments with their registers
+                .map(|(s, r)| (s.to_owned(), r)), // convert them into proper copies
+        );
+
+        Ok((real_args_mapping, args_mapping))
+    }
+
+    fn call(&mut self, macro_name: &str, bindings: BindingMappings) -> Result<()> {
+        // The semantics outlive the compiler so the body can be borrowed
+        // while compiling it.
+        let semantics = self.semantics;
+        let definition = semantics
+            .macro_definitions
+            .get(macro_name)
+            .expect("after `bind()`, get the macro definition");
+        self.macro_stack.push_front(bindings);
+        let result = self.apply_gate_operations(&definition.3, &definition.5);
+        self.macro_stack.pop_front();
+        result
+    }
+}
+
+fn invert(operations: &[ControlledU]) -> Vec<ControlledU> {
+    operations.iter().rev().map(ControlledU::inverse).collect()
+}
+
+/// Complete `error` with the boundaries of the argument or parameter of the
+/// statement in `span` causing it, if the span knows them. Errors raised
+/// elsewhere, such as inside the body of a gate, are left untouched.
+fn locate(mut error: RuntimeError, span: &ast::Span<ast::Statement>) -> RuntimeError {
+    if error.location() != Some(span.boundaries.0) {
+        return error;
+    }
+    let find_argument = |found: &dyn Fn(&ast::Argument, &QasmType) -> bool| {
+        span.node
+            .arguments()
+            .into_iter()
+            .zip(argument_types(&span.node))
+            .zip(&span.arguments)
+            .find(|((argument, argument_type), _)| found(argument, argument_type))
+            .map(|(_, boundaries)| *boundaries)
+    };
+    let find_register = |symbol_name: &str, expected: &QasmType| {
+        find_argument(&|argument, argument_type| {
+            register_name(argument) == symbol_name
+                && (*expected == QasmType::Register || expected == argument_type)
+        })
+    };
+    let find_parameter = |name: &str| {
+        span.node
+            .parameters()
+            .into_iter()
+            .zip(&span.parameters)
+            .find(|(parameter, _)| mentions(parameter, name))
+            .map(|(_, boundaries)| *boundaries)
+    };
+    // The condition register of a conditional is not an argument of its
+    // operation.
+    let operation_arguments = match &*span.node {
+        ast::Statement::Conditional(..) => span.arguments.get(1..).unwrap_or(&[]),
+        _ => &span.arguments[..],
+    };
+    let gate_name = match &*span.node {
+        ast::Statement::QuantumOperation(operation)
+        | ast::Statement::Conditional(_, _, operation) => match operation {
+            ast::QuantumOperation::Unitary(ast::UnitaryOperation(name, _, _))
+            | ast::QuantumOperation::Modified(_, ast::UnitaryOperation(name, _, _)) => {
+                Some(name.as_str())
+            }
+            _ => None,
+        },
+        _ => None,
+    };
+
+    match &mut error {
+        RuntimeError::IndexOutOfBounds {
+            boundaries: boundaries @ None,
+            symbol_name,
+            index,
+            ..
+        } => {
+            *boundaries = find_argument(
+                &|argument, _| matches!(argument, ast::Argument::Item(name, item) if name == symbol_name && item == index),
+            )
+        }
+        RuntimeError::SymbolNotFound {
+            boundaries: boundaries @ None,
+            symbol_name,
+            expected,
+            ..
+        } => {
+            *boundaries = if *expected == QasmType::RealValue {
+                find_parameter(symbol_name)
+            } else {
+                find_register(symbol_name, expected)
+            }
+        }
+        RuntimeError::TypeMismatch {
+            boundaries: boundaries @ None,
+            symbol_name,
+            expected,
+            ..
+        } => *boundaries = find_register(symbol_name, expected),
+        RuntimeError::RegisterSizeMismatch {
+            boundaries: boundaries @ None,
+            ..
+        }
+        | RuntimeError::RepeatedQubit {
+            boundaries: boundaries @ None,
+            ..
+        } => *boundaries = enclosing(operation_arguments),
+        RuntimeError::WrongNumberOfParameters {
+            boundaries: boundaries @ None,
+            symbol_name,
+            are_registers,
+            ..
+        } if gate_name == Some(symbol_name.as_str()) => {
+            *boundaries = if *are_registers {
+                enclosing(operation_arguments)
+            } else {
+                enclosing(&span.parameters)
+            }
+        }
+        _ => (),
+    }
+    error
+}
+
+/// Complete `error` with the boundaries found in the `span` of `operation`,
+/// an operation in the body of a gate.
+pub(crate) fn locate_operation(
+    error: RuntimeError,
+    operation: &ast::GateOperation,
+    span: &ast::Span<()>,
+) -> RuntimeError {
+    let statement = match operation {
+        ast::GateOperation::Unitary(unitary) => {
+            ast::Statement::QuantumOperation(ast::QuantumOperation::Unitary(unitary.clone()))
+        }
+        ast::GateOperation::Modified(modifiers, unitary) => ast::Statement::QuantumOperation(
+            ast::QuantumOperation::Modified(modifiers.clone(), unitary.clone()),
+        ),
+        ast::GateOperation::Barrier(barrier) => ast::Statement::Barrier(barrier.clone()),
+    };
+    let span = ast::Span {
+        boundaries: span.boundaries,
+        node: Box::new(statement),
+        parameters: span.parameters.clone(),
+        arguments: span.arguments.clone(),
+        operations: vec![],
+    };
+    locate(error, &span)
+}
+
+/// Return the type of register expected for each of the arguments of the
+/// statement, in the same order as [`ast::Statement::arguments`].
+fn argument_types(statement: &ast::Statement) -> Vec<QasmType> {
+    use QasmType::{ClassicalRegister as C, QuantumRegister as Q};
+    let count = statement.arguments().len();
+    match statement {
+        ast::Statement::QuantumOperation(ast::QuantumOperation::Measure(_, _)) => vec![Q, C],
+        ast::Statement::Conditional(_, _, ast::QuantumOperation::Measure(_, _)) => vec![C, Q, C],
+        ast::Statement::Conditional(..) => {
+            let mut types = vec![C];
+            types.extend(vec![Q; count - 1]);
+            types
+        }
+        ast::Statement::IfElse { .. } => vec![C; count],
+        _ => vec![Q; count],
+    }
+}
+
+fn register_name(argument: &ast::Argument) -> &str {
+    match argument {
+        ast::Argument::Id(name) | ast::Argument::Item(name, _) => name,
+    }
+}
+
+/// Return the boundaries from the start of the first pair to the end of the
+/// last one.
+fn enclosing(boundaries: &[(Location, Location)]) -> Option<(Location, Location)> {
+    match (boundaries.first(), boundaries.last()) {
+        (Some((start, _)), Some((_, end))) => Some((*start, *end)),
+        _ => None,
+    }
+}
+
+/// Return if `expression` refers to the symbol `name`.
+fn mentions(expression: &ast::Expression, name: &str) -> bool {
+    match expression {
+        ast::Expression::Id(id) => id == name,
+        ast::Expression::Op(_, left, right) | ast::Expression::BinaryFunction(_, left, right) => {
+            mentions(left, name) || mentions(right, name)
+        }
+        ast::Expression::Function(_, operand) | ast::Expression::Minus(operand) => {
+            mentions(operand, name)
+        }
+        _ => false,
+    }
+}
+
+pub(crate) fn collect_failures(instructions: &[Instruction], errors: &mut Vec<RuntimeError>) {
+    for instruction in instructions {
+        match instruction {
+            Instruction::Fail(error) => errors.push(error.clone()),
+            Instruction::Branch {
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                collect_failures(then_branch, errors);
+                collect_failures(else_branch, errors);
+            }
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use indoc::indoc;
+
+    use crate::grammar::LanguageOptions;
+    use crate::parse_and_link;
+    use crate::semantics::extract_semantics;
+
+    fn compile_source(source: &str) -> Result<Vec<Instruction>> {
+        let program = parse_and_link(source).unwrap();
+        let semantics = extract_semantics(&program, &LanguageOptions::new()).unwrap();
+        compile(&program, &semantics, None)
+    }
+
+    #[test]
+    fn test_expand_macros_into_primitive_gates() {
+        let source = indoc!(
+            "
+        OPENQASM 2.0;
+        qreg q[1];
+        qreg r[2];
+        gate h a { U(pi/2, 0, pi) a; }
+        gate bell a, b { h a; CX a, b; }
+        bell q[0], r;
+        "
+        );
+        assert_eq!(
+            compile_source(source).unwrap(),
+            vec![
+                Instruction::U {
+                    theta: PI / 2.0,
+                    phi: 0.0,
+                    lambda: PI,
+                    target: 0
+                },
+                Instruction::CX {
+                    control: 0,
+                    target: 1
+                },
+                Instruction::U {
+                    theta: PI / 2.0,
+                    phi: 0.0,
+                    lambda: PI,
+                    target: 0
+                },
+                Instruction::CX {
+                    control: 0,
+                    target: 2
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compile_measurements_and_conditionals() {
+        let source = indoc!(
+            "
+        OPENQASM 2.0;
+        qreg q[2];
+        creg c[2];
+        measure q[1] -> c[1];
+        if (c == 2) reset q;
+        "
+        );
+        assert_eq!(
+            compile_source(source).unwrap(),
+            vec![
+                Instruction::Measure {
+                    qubit: 1,
+                    register: "c".into(),
+                    bit: 1
+                },
+                Instruction::Branch {
+                    condition: vec![Test {
+                        register: "c".into(),
+                        bit: None,
+                        operator: ast::ComparisonOp::Equal,
+                        value: 2
+                    }],
+                    then_branch: vec![Instruction::Reset(0), Instruction::Reset(1)],
+                    else_branch: vec![]
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_errors_in_conditional_branches_are_deferred() {
+        let source = indoc!(
+            "
+        OPENQASM 2.0;
+        qreg q[2];
+        creg c[2];
+        if (c == 1) U(0, 0, 0) q[2];
+        "
+        );
+        assert_eq!(
+            compile_source(source).unwrap(),
+            vec![Instruction::Branch {
+                condition: vec![Test {
+                    register: "c".into(),
+                    bit: None,
+                    operator: ast::ComparisonOp::Equal,
+                    value: 1
+                }],
+                then_branch: vec![Instruction::Fail(RuntimeError::IndexOutOfBounds {
+                    location: Location(36),
+                    boundaries: Some((Location(59), Location(63))),
+                    symbol_name: "q".into(),
+                    index: 2,
+                    size: 2
+                })],
+                else_branch: vec![]
+            }]
+        );
+    }
+}
@@ -3,7 +3,7 @@ use std::collections::HashMap;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use crate::statevector::StateVector;
+use crate::statevector::{QuantumRegister, StateVector};
 
 /// Map classical registers with values and number of outcomes.
 pub type Histogram = HashMap<String, Vec<(u64, usize)>>;
@@ -27,6 +27,8 @@ pub struct Computation {
     memory: HashMap<String, u64>,
     probabilities: Vec<f64>,
     histogram: Option<Histogram>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    quantum_registers: Vec<QuantumRegister>,
 }
 
 impl Computation {
@@ -43,9 +45,16 @@ impl Computation {
             statevector,
             memory,
             histogram,
+            quantum_registers: Vec::new(),
         }
     }
 
+    /// Set the layout of the quantum registers in the state-vector.
+    pub fn with_quantum_registers(mut self, quantum_registers: Vec<QuantumRegister>) -> Self {
+        self.quantum_registers = quantum_registers;
+        self
+    }
+
     /// Return the statevector of the quantum system.
     pub fn statevector(&self) -> &StateVector {
         &self.statevector
@@ -65,6 +74,12 @@ impl Computation {
     pub fn histogram(&self) -> &Option<Histogram> {
         &self.histogram
     }
+
+    /// Return the quantum registers sorted by their position in the
+    /// state-vector.
+    pub fn quantum_registers(&self) -> &[QuantumRegister] {
+        &self.quantum_registers
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
@@ -0,0 +1,732 @@
+//! Draw programs as text circuit diagrams.
+//!
+//! Diagrams have one wire per qubit, drawn with `-`, followed by one wire per
+//! classical bit, drawn with `=`. Each operation is drawn as a symbol on each
+//! of the wires it acts on, joined by a vertical line:
+//!
+//! - gates are boxes with their name and parameters, as in `[rz(pi/4)]`, and
+//!   gates on several qubits have one box per argument, as in `[swap:0]`;
+//! - controls are `*` and the targets of `CX`, `cx` and `ccx` are `(+)`;
+//! - measurements are `[M]` with an arrow `v` on the classical bit;
+//! - resets are `[|0>]`;
+//! - conditions are boxes on the classical registers tested, as in `[c==1]`;
+//! - barriers are columns of `:`.
+//!
+//! Operations share a column when their vertical lines do not overlap.
+
+use std::collections::HashMap;
+
+use crate::grammar::ast;
+use crate::interpreter::compiler::{collect_failures, compile, ControlledU, Instruction};
+use crate::interpreter::flattener::angle;
+use crate::interpreter::runtime::Result;
+use crate::interpreter::OpaqueGates;
+use crate::semantics::{RegisterEntry, RegisterType, Semantics};
+
+/// Return the diagram of `program`, wrapped at `width` columns if given.
+/// With `expand`, gate calls are expanded into primitive gates.
+pub(crate) fn draw(
+    program: &ast::OpenQasmProgram,
+    semantics: &Semantics,
+    expand: bool,
+    width: Option<usize>,
+) -> Result<String> {
+    // Opaque gates are drawn but never applied.
+    let opaque_gates = OpaqueGates::placeholders(semantics);
+    let instructions = compile(program, semantics, Some(&opaque_gates))?;
+    let mut errors = vec![];
+    collect_failures(&instructions, &mut errors);
+    if let Some(error) = errors.into_iter().next() {
+        return Err(error);
+    }
+
+    let wires = Wires::new(semantics);
+    let mut operations = vec![];
+    if expand {
+        for instruction in &instructions {
+            wires.instruction(instruction, &[], &mut operations);
+        }
+    } else {
+        for span in &program.program {
+            wires.statement(span, &[], &mut operations);
+        }
+    }
+    Ok(wires.render(&operations, width))
+}
+
+/// An operation of the diagram, given by its symbol on each of the rows of
+/// the wires it acts on.
+#[derive(Debug, Clone, PartialEq)]
+struct Operation {
+    symbols: Vec<(usize, String)>,
+    /// The character of the line joining the symbols.
+    connector: char,
+}
+
+impl Operation {
+    fn new(symbols: Vec<(usize, String)>) -> Self {
+        Operation {
+            symbols,
+            connector: '|',
+        }
+    }
+
+    /// Return the first and last rows of the operation.
+    fn span(&self) -> (usize, usize) {
+        let rows = self.symbols.iter().map(|(row, _)| *row);
+        (
+            rows.clone().min().unwrap_or_default(),
+            rows.max().unwrap_or_default(),
+        )
+    }
+
+    fn symbol(&self, row: usize) -> Option<&str> {
+        self.symbols
+            .iter()
+            .find(|(symbol_row, _)| *symbol_row == row)
+            .map(|(_, symbol)| symbol.as_str())
+    }
+}
+
+/// The wires of a diagram.
+struct Wires<'a> {
+    semantics: &'a Semantics,
+    /// The label of each wire, by row.
+    labels: Vec<String>,
+    /// The number of quantum wires, which go first.
+    quantum_wires: usize,
+    /// The row of each qubit, by absolute index.
+    qubit_rows: Vec<usize>,
+    /// The row of the first bit of each classical register.
+    clbit_rows: HashMap<String, usize>,
+}
+
+impl<'a> Wires<'a> {
+    /// Create the wires of the registers of `semantics`, quantum registers
+    /// first, in the order of declaration.
+    fn new(semantics: &'a Semantics) -> Self {
+        let mut registers: Vec<&RegisterEntry> = semantics.register_table.values().collect();
+        registers.sort_by_key(|entry| (entry.1 == RegisterType::C, entry.3, entry.0.clone()));
+
+        let mut wires = Wires {
+            semantics,
+            labels: vec![],
+            quantum_wires: 0,
+            qubit_rows: vec![0; semantics.quantum_memory_size],
+            clbit_rows: HashMap::new(),
+        };
+        for RegisterEntry(name, register_type, size, _) in registers {
+            if *register_type == RegisterType::Q {
+                let start = semantics
+                    .memory_map
+                    .get(name)
+                    .expect("all registers are mapped")
+                    .1;
+                for index in 0..*size {
+                    wires.qubit_rows[start + index] = wires.labels.len() + index;
+                }
+                wires.quantum_wires += size;
+            } else {
+                wires.clbit_rows.insert(name.clone(), wires.labels.len());
+            }
+            wires
+                .labels
+                .extend((0..*size).map(|index| format!("{}[{}]", name, index)));
+        }
+        wires
+    }
+
+    /// Return the row of the qubit `argument`, or of its qubit `index` if it
+    /// is a whole register.
+    fn qubit_row(&self, argument: &ast::Argument, index: usize) -> usize {
+        let (name, index) = match argument {
+            ast::Argument::Id(name) => (name, index),
+            ast::Argument::Item(name, index) => (name, *index),
+        };
+        let start = self
+            .semantics
+            .memory_map
+            .get(name)
+            .expect("after compiling, the register exists")
+            .1;
+        self.qubit_rows[start + index]
+    }
+
+    /// Return the row of the bit `argument`, or of its bit `index` if it is
+    /// a whole register.
+    fn clbit_row(&self, argument: &ast::Argument, index: usize) -> usize {
+        let (name, index) = match argument {
+            ast::Argument::Id(name) => (name, index),
+            ast::Argument::Item(name, index) => (name, *index),
+        };
+        self.clbit_rows[name] + index
+    }
+
+    /// Add the operations of the statement in `span`, with the symbols of
+    /// `condition`, to `operations`.
+    fn statement(
+        &self,
+        span: &ast::Span<ast::Statement>,
+        condition: &[(usize, String)],
+        operations: &mut Vec<Operation>,
+    ) {
+        match &*span.node {
+            ast::Statement::QuantumOperation(operation) => {
+                self.operation(operation, condition, operations)
+            }
+            ast::Statement::Conditional(register, value, operation) => {
+                let mut symbols = condition.to_vec();
+                symbols.push((
+                    self.clbit_row(register, 0),
+                    format!("[{}=={}]", register, value),
+                ));
+                self.operation(operation, &symbols, operations);
+            }
+            ast::Statement::IfElse {
+                condition: ast::Condition(comparisons),
+                then_branch,
+                else_branch,
+            } => {
+                let symbols = |prefix: &str| {
+                    let mut symbols = condition.to_vec();
+                    symbols.extend(comparisons.iter().map(|comparison| {
+                        (
+                            self.clbit_row(&comparison.0, 0),
+                            format!("[{}{}]", prefix, compact(&comparison.to_string())),
+                        )
+                    }));
+                    symbols
+                };
+                for span in then_branch {
+                    self.statement(span, &symbols(""), operations);
+                }
+                for span in else_branch {
+                    self.statement(span, &symbols("else "), operations);
+                }
+            }
+            ast::Statement::Barrier(ast::BarrierPragma(args)) => {
+                let mut rows: Vec<usize> = args
+                    .iter()
+                    .flat_map(|argument| match argument {
+                        ast::Argument::Id(name) => (0..self.register_size(name))
+                            .map(|index| self.qubit_row(argument, index))
+                            .collect(),
+                        ast::Argument::Item(_, _) => vec![self.qubit_row(argument, 0)],
+                    })
+                    .collect();
+                rows.sort_unstable();
+                rows.dedup();
+                operations.push(Operation {
+                    symbols: rows.into_iter().map(|row| (row, ":".into())).collect(),
+                    connector: ':',
+                });
+            }
+            _ => (),
+        }
+    }
+
+    /// Add `operation`, once per qubit if it acts on whole registers, to
+    /// `operations`.
+    fn operation(
+        &self,
+        operation: &ast::QuantumOperation,
+        condition: &[(usize, String)],
+        operations: &mut Vec<Operation>,
+    ) {
+        let arguments: Vec<&ast::Argument> = match operation {
+            ast::QuantumOperation::Unitary(ast::UnitaryOperation(_, _, args))
+            | ast::QuantumOperation::Modified(_, ast::UnitaryOperation(_, _, args)) => {
+                args.iter().collect()
+            }
+            ast::QuantumOperation::Measure(qubits, clbits) => vec![qubits, clbits],
+            ast::QuantumOperation::Reset(qubits) => vec![qubits],
+        };
+        let size = arguments
+            .iter()
+            .filter_map(|argument| match argument {
+                ast::Argument::Id(name) => Some(self.register_size(name)),
+                ast::Argument::Item(_, _) => None,
+            })
+            .max()
+            .unwrap_or(1);
+
+        for index in 0..size {
+            let mut symbols = match operation {
+                ast::QuantumOperation::Unitary(unitary) => {
+                    let rows: Vec<usize> = arguments
+                        .iter()
+                        .map(|argument| self.qubit_row(argument, index))
+                        .collect();
+                    gate_symbols(&[], unitary, &rows)
+                }
+                ast::QuantumOperation::Modified(modifiers, unitary) => {
+                    let rows: Vec<usize> = arguments
+                        .iter()
+                        .map(|argument| self.qubit_row(argument, index))
+                        .collect();
+                    gate_symbols(modifiers, unitary, &rows)
+                }
+                ast::QuantumOperation::Measure(qubits, clbits) => vec![
+                    (self.qubit_row(qubits, index), "[M]".into()),
+                    (self.clbit_row(clbits, index), "v".into()),
+                ],
+                ast::QuantumOperation::Reset(qubits) => {
+                    vec![(self.qubit_row(qubits, index), "[|0>]".into())]
+                }
+            };
+            symbols.extend_from_slice(condition);
+            operations.push(Operation::new(symbols));
+        }
+    }
+
+    /// Add the operations of `instruction`, with the symbols of `condition`,
+    /// to `operations`.
+    fn instruction(
+        &self,
+        instruction: &Instruction,
+        condition: &[(usize, String)],
+        operations: &mut Vec<Operation>,
+    ) {
+        let mut symbols = match instruction {
+            Instruction::U {
+                theta,
+                phi,
+                lambda,
+                target,
+            } => vec![(self.qubit_rows[*target], u_label(*theta, *phi, *lambda))],
+            Instruction::CX { control, target } => vec![
+                (self.qubit_rows[*control], "*".into()),
+                (self.qubit_rows[*target], "(+)".into()),
+            ],
+            Instruction::ControlledU(ControlledU {
+                theta,
+                phi,
+                lambda,
+                controls,
+                target,
+            }) => controls
+                .iter()
+                .map(|control| (self.qubit_rows[*control], "*".into()))
+                .chain(vec![(
+                    self.qubit_rows[*target],
+                    u_label(*theta, *phi, *lambda),
+                )])
+                .collect(),
+            Instruction::Opaque {
+                name,
+                real_args,
+                qubits,
+            } => {
+                let real_args: Vec<String> = real_args
+                    .iter()
+                    .map(|value| compact(&angle(*value).to_string()))
+                    .collect();
+                let label = if real_args.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{}({})", name, real_args.join(","))
+                };
+                let rows: Vec<usize> = qubits.iter().map(|qubit| self.qubit_rows[*qubit]).collect();
+                boxes(&label, &rows)
+            }
+            Instruction::Measure {
+                qubit,
+                register,
+                bit,
+            } => vec![
+                (self.qubit_rows[*qubit], "[M]".into()),
+                (self.clbit_rows[register] + bit, "v".into()),
+            ],
+            Instruction::Reset(qubit) => vec![(self.qubit_rows[*qubit], "[|0>]".into())],
+            Instruction::Branch {
+                condition: tests,
+                then_branch,
+                else_branch,
+            } => {
+                let symbols = |prefix: &str| {
+                    let mut symbols = condition.to_vec();
+                    symbols.extend(tests.iter().map(|test| {
+                        let (register, bit) = test.operand();
+                        (
+                            self.clbit_rows[register] + bit.unwrap_or_default(),
+                            format!("[{}{}]", prefix, compact(&test.to_string())),
+                        )
+                    }));
+                    symbols
+                };
+                for instruction in then_branch {
+                    self.instruction(instruction, &symbols(""), operations);
+                }
+                for instruction in else_branch {
+                    self.instruction(instruction, &symbols("else "), operations);
+                }
+                return;
+            }
+            Instruction::Fail(_) => unreachable!("failures are reported before drawing"),
+        };
+        symbols.extend_from_slice(condition);
+        operations.push(Operation::new(symbols));
+    }
+
+    fn register_size(&self, name: &str) -> usize {
+        self.semantics
+            .register_table
+            .get(name)
+            .expect("after compiling, the register exists")
+            .2
+    }
+
+    /// Return the diagram with the `operations` on the wires, wrapped at
+    /// `width` columns if given.
+    fn render(&self, operations: &[Operation], width: Option<usize>) -> String {
+        if self.labels.is_empty() {
+            return String::new();
+        }
+
+        // Each operation goes in the column after the last one used by any
+        // of the rows it spans.
+        let mut levels = vec![0; self.labels.len()];
+        let mut columns: Vec<Vec<&Operation>> = vec![];
+        for operation in operations {
+            let (first, last) = operation.span();
+            let column = levels[first..=last]
+                .iter()
+                .copied()
+                .max()
+                .unwrap_or_default();
+            for level in &mut levels[first..=last] {
+                *level = column + 1;
+            }
+            if columns.len() <= column {
+                columns.push(vec![]);
+            }
+            columns[column].push(operation);
+        }
+        let blocks: Vec<Vec<String>> = columns
+            .iter()
+            .map(|operations| self.render_column(operations))
+            .collect();
+
+        let label_width = self
+            .labels
+            .iter()
+            .map(|label| label.len() + 2)
+            .max()
+            .unwrap_or_default();
+        // Split the blocks in chunks fitting in the width, with at least one
+        // block per chunk.
+        let mut chunks: Vec<&[Vec<String>]> = vec![];
+        let mut first = 0;
+        let mut chunk_width = label_width + 2;
+        for (index, block) in blocks.iter().enumerate() {
+            let block_width = block[0].len();
+            if index > first && width.is_some_and(|width| chunk_width + block_width > width) {
+                chunks.push(&blocks[first..index]);
+                first = index;
+                chunk_width = label_width + 2;
+            }
+            chunk_width += block_width;
+        }
+        chunks.push(&blocks[first..]);
+
+        let mut lines: Vec<String> = vec![];
+        for (index, chunk) in chunks.iter().enumerate() {
+            if index > 0 {
+                lines.push(String::new());
+            }
+            for line in 0..2 * self.labels.len() - 1 {
+                let mut text = if line % 2 == 0 {
+                    let wire = self.wire(line / 2);
+                    format!(
+                        "{:width$}{}",
+                        format!("{}:", self.labels[line / 2]),
+                        wire,
+                        width = label_width
+                    )
+                } else {
+                    " ".repeat(label_width + 1)
+                };
+                for block in chunk.iter() {
+                    text.push_str(&block[line]);
+                }
+                if line % 2 == 0 {
+                    text.push(self.wire(line / 2));
+                }
+                lines.push(text.trim_end().into());
+            }
+        }
+        let mut diagram = lines.join("\n");
+        diagram.push('\n');
+        diagram
+    }
+
+    /// Return the lines of the column with `operations`, alternating the
+    /// lines of the wires and the lines between them.
+    fn render_column(&self, operations: &[&Operation]) -> Vec<String> {
+        let width = operations
+            .iter()
+            .flat_map(|operation| operation.symbols.iter())
+            .map(|(_, symbol)| symbol.len())
+            .max()
+            .unwrap_or(1)
+            + 2;
+        let spanning = |first: usize, last: usize| {
+            operations.iter().find(|operation| {
+                let span = operation.span();
+                span.0 <= first && last <= span.1
+            })
+        };
+
+        (0..2 * self.labels.len() - 1)
+            .map(|line| {
+                let row = line / 2;
+                if line % 2 == 1 {
+                    return match spanning(row, row + 1) {
+                        Some(operation) => center(&operation.connector.to_string(), width, ' '),
+                        None => " ".repeat(width),
+                    };
+                }
+                let wire = self.wire(row);
+                let symbol = operations
+                    .iter()
+                    .find_map(|operation| operation.symbol(row));
+                match (symbol, spanning(row, row)) {
+                    (Some(symbol), _) => center(symbol, width, wire),
+                    (None, Some(operation)) => {
+                        center(&operation.connector.to_string(), width, wire)
+                    }
+                    (None, None) => wire.to_string().repeat(width),
+                }
+            })
+            .collect()
+    }
+
+    /// Return the character of the wire in `row`.
+    fn wire(&self, row: usize) -> char {
+        if row < self.quantum_wires {
+            '-'
+        } else {
+            '='
+        }
+    }
+}
+
+/// Return the symbols of the gate `operation` under `modifiers`, on the
+/// qubits in `rows`.
+fn gate_symbols(
+    modifiers: &[ast::GateModifier],
+    operation: &ast::UnitaryOperation,
+    rows: &[usize],
+) -> Vec<(usize, String)> {
+    let ast::UnitaryOperation(name, real_args, _) = operation;
+    let mut controls: usize = modifiers
+        .iter()
+        .map(|modifier| match modifier {
+            ast::GateModifier::Ctrl(count) => *count,
+            _ => 0,
+        })
+        .sum();
+    let others: Vec<String> = modifiers
+        .iter()
+        .filter(|modifier| !matches!(modifier, ast::GateModifier::Ctrl(_)))
+        .map(|modifier| format!("{}@", compact(&modifier.to_string())))
+        .collect();
+    let is_plain = others.is_empty() && real_args.is_empty();
+    let is_x = match name.as_str() {
+        "CX" | "cx" if is_plain => {
+            controls += 1;
+            true
+        }
+        "ccx" if is_plain => {
+            controls += 2;
+            true
+        }
+        "x" => is_plain && controls > 0,
+        _ => false,
+    };
+    let controls = controls.min(rows.len());
+
+    let mut symbols: Vec<(usize, String)> = rows[..controls]
+        .iter()
+        .map(|row| (*row, "*".into()))
+        .collect();
+    if is_x {
+        symbols.extend(rows[controls..].iter().map(|row| (*row, "(+)".into())));
+    } else {
+        let mut label = format!("{}{}", others.join(""), name);
+        if !real_args.is_empty() {
+            let real_args: Vec<String> = real_args
+                .iter()
+                .map(|argument| compact(&argument.to_string()))
+                .collect();
+            label = format!("{}({})", label, real_args.join(","));
+        }
+        symbols.extend(boxes(&label, &rows[controls..]));
+    }
+    symbols
+}
+
+/// Return the boxes labelled `label` on the qubits in `rows`, numbered by
+/// argument if there are several.
+fn boxes(label: &str, rows: &[usize]) -> Vec<(usize, String)> {
+    if rows.len() == 1 {
+        return vec![(rows[0], format!("[{}]", label))];
+    }
+    rows.iter()
+        .enumerate()
+        .map(|(index, row)| (*row, format!("[{}:{}]", label, index)))
+        .collect()
+}
+
+fn u_label(theta: f64, phi: f64, lambda: f64) -> String {
+    let angles: Vec<String> = vec![theta, phi, lambda]
+        .into_iter()
+        .map(|value| compact(&angle(value).to_string()))
+        .collect();
+    format!("[U({})]", angles.join(","))
+}
+
+/// Return `text` without spaces.
+fn compact(text: &str) -> String {
+    text.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+/// Return `text` centered in `width` characters, padded with `fill`.
+fn center(text: &str, width: usize, fill: char) -> String {
+    let padding = width - text.len();
+    format!(
+        "{}{}{}",
+        fill.to_string().repeat(padding / 2),
+        text,
+        fill.to_string().repeat(padding - padding / 2)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use indoc::indoc;
+
+    use crate::grammar::LanguageOptions;
+    use crate::parse_and_link;
+    use crate::semantics::extract_semantics;
+
+    fn draw_source(source: &str, expand: bool, width: Option<usize>) -> String {
+        let program = parse_and_link(source).unwrap();
+        let semantics = extract_semantics(&program, &LanguageOptions::new()).unwrap();
+        draw(&program, &semantics, expand, width).unwrap()
+    }
+
+    #[test]
+    fn test_draw_top_level_gates() {
+        let source = indoc!(
+            "
+            OPENQASM 2.0;
+            include \"qelib1.inc\";
+            qreg q[2];
+            creg c[2];
+            h q[0];
+            cx q[0], q[1];
+            rz(pi / 4) q[1];
+            barrier q;
+            measure q -> c;
+            if (c == 1) x q[1];
+            "
+        );
+        assert_eq!(
+            draw_source(source, false, None),
+            indoc!(
+                "
+                q[0]: --[h]---*---------------:--[M]---------------
+                              |               :   |
+                q[1]: -------(+)--[rz(pi/4)]--:---|---[M]---[x]----
+                                                  |    |     |
+                c[0]: ============================v====|===[c==1]==
+                                                       |
+                c[1]: =================================v===========
+                "
+            )
+        );
+    }
+
+    #[test]
+    fn test_draw_multi_qubit_gates_and_modifiers() {
+        let source = indoc!(
+            "
+            OPENQASM 2.0;
+            include \"qelib1.inc\";
+            qreg a[1];
+            qreg b[2];
+            swap a[0], b[1];
+            ctrl @ inv @ t b[0], a[0];
+            ctrl(2) @ x a[0], b[0], b[1];
+            ccx b[1], b[0], a[0];
+            "
+        );
+        assert_eq!(
+            draw_source(source, false, None),
+            indoc!(
+                "
+                a[0]: --[swap:0]--[inv@t]---*---(+)--
+                           |         |      |    |
+                b[0]: -----|---------*------*----*---
+                           |                |    |
+                b[1]: --[swap:1]-----------(+)---*---
+                "
+            )
+        );
+    }
+
+    #[test]
+    fn test_draw_primitive_gates() {
+        let source = indoc!(
+            "
+            OPENQASM 2.0;
+            include \"qelib1.inc\";
+            qreg q[2];
+            creg c[1];
+            cz q[0], q[1];
+            measure q[1] -> c[0];
+            if (c == 1) reset q[0];
+            "
+        );
+        assert_eq!(
+            draw_source(source, true, None),
+            indoc!(
+                "
+                q[0]: -------------------*------------------------[|0>]---
+                                         |                          |
+                q[1]: --[U(pi/2,0,pi)]--(+)--[U(pi/2,0,pi)]--[M]----|-----
+                                                              |     |
+                c[0]: ========================================v===[c==1]==
+                "
+            )
+        );
+    }
+
+    #[test]
+    fn test_wrap_long_diagrams() {
+        let source = indoc!(
+            "
+            OPENQASM 2.0;
+            include \"qelib1.inc\";
+            qreg q[1];
+            h q[0];
+            s q[0];
+            t q[0];
+            x q[0];
+            "
+        );
+        assert_eq!(
+            draw_source(source, false, Some(20)),
+            indoc!(
+                "
+                q[0]: --[h]--[s]--
+
+                q[0]: --[t]--[x]--
+                "
+            )
+        );
+    }
+}
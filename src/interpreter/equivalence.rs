@@ -0,0 +1,178 @@
+use std::f64::consts::{FRAC_1_SQRT_2, PI};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::random;
+use crate::statevector::{fidelity, inner_product, Complex, StateVector};
+
+/// Number of random product states to try before comparing the whole unitary.
+const RANDOM_TRIALS: usize = 16;
+
+/// Maximum number of qubits for comparing the whole unitary, column by column.
+const MAX_FULL_CHECK_WIDTH: usize = 8;
+
+/// Two quantities closer than this tolerance are considered the same.
+const TOLERANCE: f64 = 1e-9;
+
+/// Represent the result of comparing the unitaries implemented by two
+/// programs.
+///
+/// # Examples
+///
+/// See the [`check_equivalence()`] function for a complete example.
+///
+/// [`check_equivalence()`]: ./fn.check_equivalence.html
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Equivalence {
+    /// Both programs implement the same unitary up to a global phase.
+    Equivalent,
+    /// The programs act on a different number of qubits.
+    DifferentWidths(usize, usize),
+    /// The programs implement different unitaries.
+    NotEquivalent {
+        /// An input state for which the programs produce different outputs.
+        witness: StateVector,
+        /// The fidelity between both outputs when starting from `witness`.
+        fidelity: f64,
+    },
+}
+
+impl Equivalence {
+    /// Check if the programs were found equivalent.
+    pub fn is_equivalent(&self) -> bool {
+        matches!(self, Equivalence::Equivalent)
+    }
+}
+
+/// Compare the unitaries `left` and `right`, both acting on `qubit_width`
+/// qubits, by checking their outputs on random product states and, for small
+/// widths, on every computational basis state.
+pub(crate) fn compare<E, L, R>(
+    qubit_width: usize,
+    mut left: L,
+    mut right: R,
+) -> std::result::Result<Equivalence, E>
+where
+    L: FnMut(StateVector) -> std::result::Result<StateVector, E>,
+    R: FnMut(StateVector) -> std::result::Result<StateVector, E>,
+{
+    for _ in 0..RANDOM_TRIALS {
+        let input = random_product_state(qubit_width);
+        let left_output = left(input.clone())?;
+        let right_output = right(input.clone())?;
+        let fidelity = fidelity(&left_output, &right_output);
+        if fidelity < 1.0 - TOLERANCE {
+            return Ok(Equivalence::NotEquivalent {
+                witness: input,
+                fidelity,
+            });
+        }
+    }
+
+    if qubit_width > MAX_FULL_CHECK_WIDTH {
+        return Ok(Equivalence::Equivalent);
+    }
+
+    // Columns must be the same up to a phase, and that phase must be the
+    // same for all the columns.
+    let mut reference_phase = None;
+    for index in 0..(1 << qubit_width) {
+        let input = StateVector::from_basis_state(qubit_width, index);
+        let left_output = left(input.clone())?;
+        let right_output = right(input.clone())?;
+        let overlap = inner_product(&right_output, &left_output);
+        let column_fidelity = overlap.norm_sqr();
+        if column_fidelity < 1.0 - TOLERANCE {
+            return Ok(Equivalence::NotEquivalent {
+                witness: input,
+                fidelity: column_fidelity,
+            });
+        }
+
+        match reference_phase {
+            None => reference_phase = Some(overlap),
+            Some(phase) if (overlap - phase).norm() > TOLERANCE.sqrt() => {
+                let witness = superposition_of(qubit_width, 0, index);
+                let left_output = left(witness.clone())?;
+                let right_output = right(witness.clone())?;
+                return Ok(Equivalence::NotEquivalent {
+                    fidelity: fidelity(&left_output, &right_output),
+                    witness,
+                });
+            }
+            _ => (),
+        }
+    }
+
+    Ok(Equivalence::Equivalent)
+}
+
+fn random_product_state(qubit_width: usize) -> StateVector {
+    let mut state = StateVector::new(qubit_width);
+    for target in 0..qubit_width {
+        // Uniformly distributed on the Bloch sphere.
+        let theta = (1.0 - 2.0 * random::random()).acos();
+        let phi = 2.0 * PI * random::random();
+        state.u(theta, phi, 0.0, target);
+    }
+    state
+}
+
+fn superposition_of(qubit_width: usize, index_a: usize, index_b: usize) -> StateVector {
+    let mut bases = vec![Complex::from(0.0); 1 << qubit_width];
+    bases[index_a] = Complex::from(FRAC_1_SQRT_2);
+    bases[index_b] = Complex::from(FRAC_1_SQRT_2);
+    StateVector::from_complex_bases(bases)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::convert::Infallible;
+
+    fn identity(state: StateVector) -> Result<StateVector, Infallible> {
+        Ok(state)
+    }
+
+    #[test]
+    fn test_equivalent_up_to_global_phase() {
+        let global_phase = |state: StateVector| -> Result<StateVector, Infallible> {
+            Ok(state
+                .as_complex_bases()
+                .iter()
+                .map(|amplitude| amplitude * Complex::new(0.0, 1.0))
+                .collect())
+        };
+        let result = compare(2, identity, global_phase).unwrap();
+        assert_eq!(result, Equivalence::Equivalent);
+    }
+
+    #[test]
+    fn test_relative_phase_is_detected() {
+        let z_gate = |mut state: StateVector| -> Result<StateVector, Infallible> {
+            state.u(0.0, 0.0, PI, 0);
+            Ok(state)
+        };
+        let result = compare(1, identity, z_gate).unwrap();
+        assert!(!result.is_equivalent());
+    }
+
+    #[test]
+    fn test_witness_produces_different_outputs() {
+        let x_gate = |mut state: StateVector| -> Result<StateVector, Infallible> {
+            state.u(PI, 0.0, PI, 1);
+            Ok(state)
+        };
+        match compare(2, identity, x_gate).unwrap() {
+            Equivalence::NotEquivalent { witness, fidelity } => {
+                let output = x_gate(witness.clone()).unwrap();
+                assert!(fidelity < 1.0);
+                assert!((super::fidelity(&witness, &output) - fidelity).abs() < TOLERANCE);
+            }
+            other => panic!("expected non-equivalent programs, found {:?}", other),
+        }
+    }
+}
@@ -2,6 +2,16 @@ use std::collections::HashMap;
 
 use crate::grammar::ast;
 
+/// Represent the failures when solving an expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExpressionError {
+    /// The symbol has no value bound.
+    SymbolNotFound(String),
+    /// The operation, written with the values of its operands, results in
+    /// the non-finite value.
+    NonFiniteValue(String, f64),
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct ExpressionSolver<'bindings>(&'bindings HashMap<String, f64>);
 
@@ -10,35 +20,80 @@ impl<'bindings> ExpressionSolver<'bindings> {
         ExpressionSolver::<'bindings>(symbol_table)
     }
 
-    pub fn solve(&self, expression: &ast::Expression) -> Result<f64, String> {
+    pub fn solve(&self, expression: &ast::Expression) -> Result<f64, ExpressionError> {
         Ok(match expression {
             ast::Expression::Pi => std::f64::consts::PI,
+            ast::Expression::Tau => 2.0 * std::f64::consts::PI,
+            ast::Expression::Euler => std::f64::consts::E,
             ast::Expression::Int(value) => *value as f64,
             ast::Expression::Real(value) => *value,
             ast::Expression::Minus(expr) => -self.solve(expr)?,
-            ast::Expression::Op(op_code, left, right) => match op_code {
-                ast::OpCode::Add => self.solve(left)? + self.solve(right)?,
-                ast::OpCode::Sub => self.solve(left)? - self.solve(right)?,
-                ast::OpCode::Mul => self.solve(left)? * self.solve(right)?,
-                ast::OpCode::Div => self.solve(left)? / self.solve(right)?,
-                ast::OpCode::Pow => self.solve(left)?.powf(self.solve(right)?),
-            },
-            ast::Expression::Function(func_code, expr) => match func_code {
-                ast::FuncCode::Sin => self.solve(expr)?.sin(),
-                ast::FuncCode::Cos => self.solve(expr)?.cos(),
-                ast::FuncCode::Tan => self.solve(expr)?.tan(),
-                ast::FuncCode::Exp => self.solve(expr)?.exp(),
-                ast::FuncCode::Ln => self.solve(expr)?.ln(),
-                ast::FuncCode::Sqrt => self.solve(expr)?.sqrt(),
-            },
+            ast::Expression::Op(op_code, left, right) => {
+                let (left, right) = (self.solve(left)?, self.solve(right)?);
+                let (value, symbol) = match op_code {
+                    ast::OpCode::Add => (left + right, "+"),
+                    ast::OpCode::Sub => (left - right, "-"),
+                    ast::OpCode::Mul => (left * right, "*"),
+                    ast::OpCode::Div => (left / right, "/"),
+                    ast::OpCode::Pow => (left.powf(right), "^"),
+                };
+                finite(value, || format!("{} {} {}", left, symbol, right))?
+            }
+            ast::Expression::Function(func_code, expr) => {
+                let argument = self.solve(expr)?;
+                let (value, name) = match func_code {
+                    ast::FuncCode::Sin => (argument.sin(), "sin"),
+                    ast::FuncCode::Cos => (argument.cos(), "cos"),
+                    ast::FuncCode::Tan => (argument.tan(), "tan"),
+                    ast::FuncCode::Exp => (argument.exp(), "exp"),
+                    ast::FuncCode::Ln => (argument.ln(), "ln"),
+                    ast::FuncCode::Sqrt => (argument.sqrt(), "sqrt"),
+                    ast::FuncCode::Asin => (argument.asin(), "asin"),
+                    ast::FuncCode::Acos => (argument.acos(), "acos"),
+                    ast::FuncCode::Atan => (argument.atan(), "atan"),
+                    ast::FuncCode::Floor => (argument.floor(), "floor"),
+                    ast::FuncCode::Ceil => (argument.ceil(), "ceil"),
+                };
+                finite(value, || format!("{}({})", name, argument))?
+            }
+            ast::Expression::BinaryFunction(func_code, left, right) => {
+                let (left, right) = (self.solve(left)?, self.solve(right)?);
+                let (value, name) = match func_code {
+                    ast::BinaryFuncCode::Atan2 => (left.atan2(right), "atan2"),
+                    ast::BinaryFuncCode::Mod => (modulo(left, right), "mod"),
+                };
+                finite(value, || format!("{}({}, {})", name, left, right))?
+            }
             ast::Expression::Id(name) => match self.0.get(name) {
-                None => return Err(name.into()),
+                None => return Err(ExpressionError::SymbolNotFound(name.into())),
                 Some(value) => *value,
             },
         })
     }
 }
 
+/// Return the remainder of dividing `dividend` by `divisor`, with the sign
+/// of `divisor`.
+fn modulo(dividend: f64, divisor: f64) -> f64 {
+    let remainder = dividend % divisor;
+    if remainder != 0.0 && remainder.signum() != divisor.signum() {
+        remainder + divisor
+    } else {
+        remainder
+    }
+}
+
+fn finite<F>(value: f64, describe: F) -> Result<f64, ExpressionError>
+where
+    F: FnOnce() -> String,
+{
+    if value.is_finite() {
+        Ok(value)
+    } else {
+        Err(ExpressionError::NonFiniteValue(describe(), value))
+    }
+}
+
 #[cfg(test)]
 mod test {
     use std::f64::consts::PI;
@@ -143,6 +198,80 @@ mod test {
         let error = solver
             .solve(&expression)
             .expect_err("fails at replacing `some_name`");
-        assert_eq!(error, String::from("some_name"));
+        assert_eq!(
+            error,
+            ExpressionError::SymbolNotFound(String::from("some_name"))
+        );
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_expression_solver_with_extended_functions() {
+        let expression = Expression::Op(
+            OpCode::Add,
+            Box::new(Expression::BinaryFunction(
+                BinaryFuncCode::Mod,
+                Box::new(Expression::Minus(Box::new(Expression::Tau))),
+                Box::new(Expression::Real(4.0)),
+            )),
+            Box::new(Expression::BinaryFunction(
+                BinaryFuncCode::Atan2,
+                Box::new(Expression::Function(
+                    FuncCode::Floor,
+                    Box::new(Expression::Euler),
+                )),
+                Box::new(Expression::Function(
+                    FuncCode::Ceil,
+                    Box::new(Expression::Function(
+                        FuncCode::Asin,
+                        Box::new(Expression::Real(1.0)),
+                    )),
+                )),
+            )),
+        );
+        let empty = HashMap::new();
+        let solver = ExpressionSolver::new(&empty);
+        let result = solver.solve(&expression).expect("get value of expression");
+        assert_eq!(result, (-2.0 * PI).rem_euclid(4.0) + 2.0_f64.atan2(2.0));
+    }
+
+    #[test]
+    fn test_expression_solver_fails_at_non_finite_values() {
+        let cases = vec![
+            (
+                Expression::Function(
+                    FuncCode::Ln,
+                    Box::new(Expression::Minus(Box::new(Expression::Real(1.0)))),
+                ),
+                "ln(-1)",
+            ),
+            (
+                Expression::Op(
+                    OpCode::Div,
+                    Box::new(Expression::Real(1.0)),
+                    Box::new(Expression::Real(0.0)),
+                ),
+                "1 / 0",
+            ),
+            (
+                Expression::BinaryFunction(
+                    BinaryFuncCode::Mod,
+                    Box::new(Expression::Real(1.0)),
+                    Box::new(Expression::Real(0.0)),
+                ),
+                "mod(1, 0)",
+            ),
+        ];
+        let empty = HashMap::new();
+        let solver = ExpressionSolver::new(&empty);
+        for (expression, operation) in cases {
+            match solver.solve(&expression) {
+                Err(ExpressionError::NonFiniteValue(description, value)) => {
+                    assert_eq!(description, operation);
+                    assert!(!value.is_finite());
+                }
+                other => panic!("`{}` should not be finite: {:?}", operation, other),
+            }
+        }
     }
 }
@@ -0,0 +1,591 @@
+//! Turn OPENQASM 2.0 programs into flat lists of primitive statements.
+//!
+//! Flattening inlines every gate call, substitutes the real parameters by
+//! their values and expands the operations on whole registers into one
+//! statement per qubit. The controlled gates resulting from gate modifiers
+//! are decomposed into one-qubit gates and `CX` gates, and the gates are
+//! finally rewritten in the chosen [`Basis`].
+
+use std::f64::consts::PI;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::complex::Complex;
+use crate::grammar::ast;
+use crate::grammar::lexer::Location;
+use crate::interpreter::compiler::{compile, ControlledU, Instruction};
+use crate::interpreter::runtime::Result;
+use crate::semantics::Semantics;
+
+/// Two angles closer than this tolerance are considered the same.
+const TOLERANCE: f64 = 1e-10;
+
+/// The set of gates a flattened program is written with.
+///
+/// Bases are known by the comma-separated list of their gates, which is the
+/// value used for parsing and displaying them.
+///
+/// # Examples
+///
+/// ```
+/// use qasmsim::Basis;
+///
+/// let basis: Basis = "rz,sx,x,cx".parse().unwrap();
+/// assert_eq!(basis, Basis::RzSxXCx);
+/// assert_eq!(Basis::default().to_string(), "U,CX");
+/// ```
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Basis {
+    /// The built-in gates `U` and `CX`.
+    #[default]
+    UCx,
+    /// The gates `u3` and `cx` of `qelib1.inc`.
+    U3Cx,
+    /// The gates `rz`, `x` and `cx` of `qelib1.inc`, and `sx`, the square
+    /// root of `x`, which the flattened program declares.
+    RzSxXCx,
+}
+
+impl Basis {
+    /// Return all the bases.
+    pub fn all() -> &'static [Basis] {
+        &[Basis::UCx, Basis::U3Cx, Basis::RzSxXCx]
+    }
+
+    /// Return the name of the basis.
+    pub fn name(self) -> &'static str {
+        match self {
+            Basis::UCx => "U,CX",
+            Basis::U3Cx => "u3,cx",
+            Basis::RzSxXCx => "rz,sx,x,cx",
+        }
+    }
+
+    /// Return the statements a program written in the basis starts with.
+    fn prelude(self) -> Vec<ast::Statement> {
+        let include = ast::Statement::Include("qelib1.inc".into());
+        match self {
+            Basis::UCx => vec![],
+            Basis::U3Cx => vec![include],
+            Basis::RzSxXCx => {
+                let call = |name: &str| {
+                    ast::GateOperation::Unitary(ast::UnitaryOperation(
+                        name.into(),
+                        vec![],
+                        vec![ast::Argument::Id("a".into())],
+                    ))
+                };
+                let sx = ast::Statement::GateDecl {
+                    signature: (
+                        "sx".into(),
+                        vec![],
+                        vec!["a".into()],
+                        vec![call("h"), call("s"), call("h")],
+                    ),
+                    docstring: Some(" Square root of X.".into()),
+                };
+                vec![include, sx]
+            }
+        }
+    }
+
+    /// Return the operations implementing `gate` in the basis. Gates equal
+    /// to the identity result in no operations.
+    fn rewrite(self, gate: Gate, qubits: &[ast::Argument]) -> Vec<ast::UnitaryOperation> {
+        let operation = |name: &str, real_args: Vec<f64>, args: &[usize]| {
+            ast::UnitaryOperation(
+                name.into(),
+                real_args.into_iter().map(angle).collect(),
+                args.iter().map(|qubit| qubits[*qubit].clone()).collect(),
+            )
+        };
+        let (theta, phi, lambda, target) = match gate {
+            Gate::CX(control, target) => {
+                let name = if self == Basis::UCx { "CX" } else { "cx" };
+                return vec![operation(name, vec![], &[control, target])];
+            }
+            Gate::U(theta, phi, lambda, target) => {
+                (normalize(theta), normalize(phi), normalize(lambda), target)
+            }
+        };
+        if is_zero(theta) && is_zero(normalize(phi + lambda)) {
+            return vec![];
+        }
+
+        let rz = |angle: f64| {
+            let angle = normalize(angle);
+            if is_zero(angle) {
+                None
+            } else {
+                Some(operation("rz", vec![angle], &[target]))
+            }
+        };
+        match self {
+            Basis::UCx => vec![operation("U", vec![theta, phi, lambda], &[target])],
+            Basis::U3Cx => vec![operation("u3", vec![theta, phi, lambda], &[target])],
+            Basis::RzSxXCx if is_zero(theta) => rz(phi + lambda).into_iter().collect(),
+            Basis::RzSxXCx if is_zero(theta.abs() - PI) => rz(lambda - phi + PI)
+                .into_iter()
+                .chain(vec![operation("x", vec![], &[target])])
+                .collect(),
+            // U(θ, φ, λ) = rz(φ + π) · sx · rz(θ + π) · sx · rz(λ), up to a
+            // global phase.
+            Basis::RzSxXCx => rz(lambda)
+                .into_iter()
+                .chain(Some(operation("sx", vec![], &[target])))
+                .chain(rz(theta + PI))
+                .chain(Some(operation("sx", vec![], &[target])))
+                .chain(rz(phi + PI))
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Display for Basis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Basis {
+    type Err = String;
+
+    fn from_str(name: &str) -> std::result::Result<Self, Self::Err> {
+        let gates: String = name.chars().filter(|c| !c.is_whitespace()).collect();
+        Basis::all()
+            .iter()
+            .copied()
+            .find(|basis| basis.name() == gates)
+            .ok_or_else(|| {
+                format!(
+                    "unknown basis `{}`, expected `U,CX`, `u3,cx` or `rz,sx,x,cx`",
+                    name
+                )
+            })
+    }
+}
+
+/// A gate of the flattened program before rewriting it in the basis.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Gate {
+    /// The `U` gate with its angles and target.
+    U(f64, f64, f64, usize),
+    /// The `CX` gate with its control and target.
+    CX(usize, usize),
+}
+
+/// A 2×2 matrix, given by rows.
+type Matrix = [Complex; 4];
+
+/// Return the flat version of `program`, written in `basis`. The program
+/// must be an OPENQASM 2.0 program.
+///
+/// The declarations of registers and the barriers are kept, while the
+/// declarations of gates and the includes are replaced by those the basis
+/// needs. Conditional measurements writing on the tested registers are kept
+/// as they are, since splitting them in one measurement per qubit would
+/// change the result of the test for the following ones.
+pub(crate) fn flatten(
+    program: &ast::OpenQasmProgram,
+    semantics: &Semantics,
+    basis: Basis,
+) -> Result<ast::OpenQasmProgram> {
+    let mut qubits = vec![ast::Argument::Id(String::new()); semantics.quantum_memory_size];
+    for register in semantics.quantum_registers() {
+        for index in 0..register.size() {
+            qubits[register.start() + index] = ast::Argument::Item(register.name().into(), index);
+        }
+    }
+    let flattener = Flattener {
+        program,
+        semantics,
+        basis,
+        qubits,
+    };
+
+    let mut statements: Vec<ast::Span<ast::Statement>> = basis
+        .prelude()
+        .into_iter()
+        .map(|statement| ast::Span::new((Location(0), Location(0)), statement))
+        .collect();
+    for span in &program.program {
+        let keep = |statement: ast::Statement| ast::Span::new(span.boundaries, statement);
+        match &*span.node {
+            ast::Statement::QRegDecl(_, _)
+            | ast::Statement::CRegDecl(_, _)
+            | ast::Statement::Barrier(_) => statements.push(span.clone()),
+            ast::Statement::QuantumOperation(_) => statements.extend(
+                flattener
+                    .operations(span)?
+                    .into_iter()
+                    .map(|operation| keep(ast::Statement::QuantumOperation(operation))),
+            ),
+            ast::Statement::Conditional(register, _, operation)
+                if measures_into(operation, &[register]) =>
+            {
+                statements.push(span.clone())
+            }
+            ast::Statement::Conditional(register, value, _) => {
+                statements.extend(flattener.operations(span)?.into_iter().map(|operation| {
+                    keep(ast::Statement::Conditional(
+                        register.clone(),
+                        *value,
+                        operation,
+                    ))
+                }))
+            }
+            ast::Statement::IfElse {
+                condition,
+                then_branch,
+                ..
+            } => {
+                let registers: Vec<&ast::Argument> =
+                    condition.0.iter().map(|comparison| &comparison.0).collect();
+                let writes_on_tests = then_branch.iter().any(|statement| {
+                    matches!(&*statement.node, ast::Statement::QuantumOperation(operation) if measures_into(operation, &registers))
+                });
+                if writes_on_tests {
+                    statements.push(span.clone());
+                    continue;
+                }
+                statements.extend(flattener.operations(span)?.into_iter().map(|operation| {
+                    keep(ast::Statement::IfElse {
+                        condition: condition.clone(),
+                        then_branch: vec![keep(ast::Statement::QuantumOperation(operation))],
+                        else_branch: vec![],
+                    })
+                }))
+            }
+            ast::Statement::Include(_)
+            | ast::Statement::GateDecl { .. }
+            | ast::Statement::OpaqueGateDecl { .. } => (),
+        }
+    }
+
+    Ok(ast::OpenQasmProgram {
+        version: program.version.clone(),
+        program: statements,
+    })
+}
+
+struct Flattener<'a> {
+    program: &'a ast::OpenQasmProgram,
+    semantics: &'a Semantics,
+    basis: Basis,
+    /// The register and index of each qubit, by absolute index.
+    qubits: Vec<ast::Argument>,
+}
+
+impl<'a> Flattener<'a> {
+    /// Return the primitive operations the statement in `span` expands to.
+    /// For conditionals, they are the operations of the branch.
+    fn operations(&self, span: &ast::Span<ast::Statement>) -> Result<Vec<ast::QuantumOperation>> {
+        let statement = ast::OpenQasmProgram {
+            version: self.program.version.clone(),
+            program: vec![span.clone()],
+        };
+        let mut operations = vec![];
+        for instruction in compile(&statement, self.semantics, None)? {
+            match instruction {
+                // OPENQASM 2.0 conditionals have no alternative.
+                Instruction::Branch { then_branch, .. } => {
+                    for instruction in then_branch {
+                        self.push_operations(instruction, &mut operations)?;
+                    }
+                }
+                instruction => self.push_operations(instruction, &mut operations)?,
+            }
+        }
+        Ok(operations)
+    }
+
+    /// Append the operations implementing `instruction` to `operations`.
+    fn push_operations(
+        &self,
+        instruction: Instruction,
+        operations: &mut Vec<ast::QuantumOperation>,
+    ) -> Result<()> {
+        let mut gates = vec![];
+        match instruction {
+            Instruction::U {
+                theta,
+                phi,
+                lambda,
+                target,
+            } => gates.push(Gate::U(theta, phi, lambda, target)),
+            Instruction::CX { control, target } => gates.push(Gate::CX(control, target)),
+            Instruction::ControlledU(ControlledU {
+                theta,
+                phi,
+                lambda,
+                controls,
+                target,
+            }) => decompose(&u_matrix(theta, phi, lambda), &controls, target, &mut gates),
+            Instruction::Measure {
+                qubit,
+                register,
+                bit,
+            } => operations.push(ast::QuantumOperation::Measure(
+                self.qubits[qubit].clone(),
+                ast::Argument::Item(register, bit),
+            )),
+            Instruction::Reset(qubit) => {
+                operations.push(ast::QuantumOperation::Reset(self.qubits[qubit].clone()))
+            }
+            Instruction::Fail(error) => return Err(error),
+            Instruction::Opaque { .. } | Instruction::Branch { .. } => {
+                unreachable!("opaque gates are unbound and conditionals are not nested")
+            }
+        }
+        for gate in gates {
+            operations.extend(
+                self.basis
+                    .rewrite(gate, &self.qubits)
+                    .into_iter()
+                    .map(ast::QuantumOperation::Unitary),
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Return if `operation` measures into any of the `registers`.
+fn measures_into(operation: &ast::QuantumOperation, registers: &[&ast::Argument]) -> bool {
+    let name = |argument: &ast::Argument| match argument {
+        ast::Argument::Id(name) | ast::Argument::Item(name, _) => name.clone(),
+    };
+    match operation {
+        ast::QuantumOperation::Measure(_, target) => registers
+            .iter()
+            .any(|register| name(register) == name(target)),
+        _ => false,
+    }
+}
+
+/// Append to `gates` the `U` and `CX` gates implementing `matrix` on `target`
+/// controlled by the `controls` qubits.
+///
+/// The global phase of `matrix` is lost if there are no controls, but it
+/// becomes a relative phase otherwise, so it is applied to the last
+/// control. Several controls are reduced to one by splitting the matrix in
+/// two square roots, following Barenco et al., "Elementary gates for quantum
+/// computation" (1995), lemma 7.5.
+fn decompose(matrix: &Matrix, controls: &[usize], target: usize, gates: &mut Vec<Gate>) {
+    match controls.split_last() {
+        None => {
+            let (_, theta, phi, lambda) = zyz(matrix);
+            gates.push(Gate::U(theta, phi, lambda, target));
+        }
+        Some((control, [])) => {
+            // The decomposition of `cu3` in `qelib1.inc`.
+            let (gamma, theta, phi, lambda) = zyz(matrix);
+            gates.push(Gate::U(0.0, 0.0, gamma + (lambda + phi) / 2.0, *control));
+            gates.push(Gate::U(0.0, 0.0, (lambda - phi) / 2.0, target));
+            gates.push(Gate::CX(*control, target));
+            gates.push(Gate::U(-theta / 2.0, 0.0, -(phi + lambda) / 2.0, target));
+            gates.push(Gate::CX(*control, target));
+            gates.push(Gate::U(theta / 2.0, phi, 0.0, target));
+        }
+        Some((control, others)) => {
+            let root = square_root(matrix);
+            decompose(&root, &[*control], target, gates);
+            decompose_x(others, *control, gates);
+            decompose(&adjoint(&root), &[*control], target, gates);
+            decompose_x(others, *control, gates);
+            decompose(&root, others, target, gates);
+        }
+    }
+}
+
+/// Append to `gates` the gates implementing the `X` gate on `target`
+/// controlled by the `controls` qubits.
+fn decompose_x(controls: &[usize], target: usize, gates: &mut Vec<Gate>) {
+    match controls {
+        [control] => gates.push(Gate::CX(*control, target)),
+        _ => {
+            let zero = Complex::new(0.0, 0.0);
+            let one = Complex::new(1.0, 0.0);
+            decompose(&[zero, one, one, zero], controls, target, gates)
+        }
+    }
+}
+
+/// Return the matrix of the `U` gate, as applied by the state-vector.
+fn u_matrix(theta: f64, phi: f64, lambda: f64) -> Matrix {
+    let (cos, sin) = ((theta / 2.0).cos(), (theta / 2.0).sin());
+    [
+        Complex::new(cos, 0.0),
+        -Complex::from_polar(sin, lambda),
+        Complex::from_polar(sin, phi),
+        Complex::from_polar(cos, phi + lambda),
+    ]
+}
+
+/// Return the angles `(γ, θ, φ, λ)` such that `matrix` is the `U(θ, φ, λ)`
+/// gate multiplied by the global phase `e^(iγ)`.
+fn zyz(matrix: &Matrix) -> (f64, f64, f64, f64) {
+    let [m00, m01, m10, m11] = *matrix;
+    let theta = 2.0 * m10.norm().atan2(m00.norm());
+    if m10.norm() < TOLERANCE {
+        let gamma = m00.arg();
+        (gamma, theta, 0.0, m11.arg() - gamma)
+    } else if m00.norm() < TOLERANCE {
+        let gamma = (-m01).arg();
+        (gamma, theta, m10.arg() - gamma, 0.0)
+    } else {
+        let gamma = m00.arg();
+        (gamma, theta, m10.arg() - gamma, (-m01).arg() - gamma)
+    }
+}
+
+/// Return a square root of the unitary `matrix`, which is unitary too.
+fn square_root(matrix: &Matrix) -> Matrix {
+    let [m00, m01, m10, m11] = *matrix;
+    let determinant_root = (m00 * m11 - m01 * m10).sqrt();
+    let trace = m00 + m11;
+    // Of both roots of the determinant, pick the one avoiding a division by
+    // zero.
+    let s = if (trace + 2.0 * determinant_root).norm() >= (trace - 2.0 * determinant_root).norm() {
+        determinant_root
+    } else {
+        -determinant_root
+    };
+    let t = (trace + 2.0 * s).sqrt();
+    [(m00 + s) / t, m01 / t, m10 / t, (m11 + s) / t]
+}
+
+fn adjoint(matrix: &Matrix) -> Matrix {
+    let [m00, m01, m10, m11] = *matrix;
+    [m00.conj(), m10.conj(), m01.conj(), m11.conj()]
+}
+
+/// Return `angle` in the interval `(-π, π]`.
+fn normalize(angle: f64) -> f64 {
+    let angle = angle.rem_euclid(2.0 * PI);
+    if angle > PI {
+        angle - 2.0 * PI
+    } else {
+        angle
+    }
+}
+
+fn is_zero(angle: f64) -> bool {
+    angle.abs() < TOLERANCE
+}
+
+/// Return the expression for `value`, written as a fraction of `pi` if it is
+/// a simple one.
+pub(crate) fn angle(value: f64) -> ast::Expression {
+    if is_zero(value) {
+        return ast::Expression::Real(0.0);
+    }
+    for denominator in &[1.0, 2.0, 3.0, 4.0, 6.0, 8.0] {
+        let numerator = value / PI * denominator;
+        if !is_zero(numerator - numerator.round()) {
+            continue;
+        }
+        let numerator = numerator.round();
+        let multiple = if numerator == 1.0 {
+            ast::Expression::Pi
+        } else if numerator == -1.0 {
+            ast::Expression::Minus(Box::new(ast::Expression::Pi))
+        } else {
+            ast::Expression::Op(
+                ast::OpCode::Mul,
+                Box::new(ast::Expression::Real(numerator)),
+                Box::new(ast::Expression::Pi),
+            )
+        };
+        return if *denominator == 1.0 {
+            multiple
+        } else {
+            ast::Expression::Op(
+                ast::OpCode::Div,
+                Box::new(multiple),
+                Box::new(ast::Expression::Real(*denominator)),
+            )
+        };
+    }
+    ast::Expression::Real(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use float_cmp::approx_eq;
+
+    fn product(left: &Matrix, right: &Matrix) -> Matrix {
+        [
+            left[0] * right[0] + left[1] * right[2],
+            left[0] * right[1] + left[1] * right[3],
+            left[2] * right[0] + left[3] * right[2],
+            left[2] * right[1] + left[3] * right[3],
+        ]
+    }
+
+    fn assert_matrix_eq(left: &Matrix, right: &Matrix) {
+        for (left, right) in left.iter().zip(right) {
+            assert!((left - right).norm() < 1e-12, "{:?} != {:?}", left, right);
+        }
+    }
+
+    #[test]
+    fn test_zyz_recovers_the_global_phase_and_the_angles() {
+        for (gamma, theta, phi, lambda) in &[
+            (0.3, 1.2, -0.4, 2.5),
+            (-1.0, 0.0, 0.0, 0.7),
+            (2.0, PI, 0.5, 0.0),
+        ] {
+            let phase = Complex::from_polar(1.0, *gamma);
+            let matrix = u_matrix(*theta, *phi, *lambda).map(|entry| entry * phase);
+            let (gamma, theta, phi, lambda) = zyz(&matrix);
+            let phase = Complex::from_polar(1.0, gamma);
+            assert_matrix_eq(
+                &u_matrix(theta, phi, lambda).map(|entry| entry * phase),
+                &matrix,
+            );
+        }
+    }
+
+    #[test]
+    fn test_square_root() {
+        let zero = Complex::new(0.0, 0.0);
+        let one = Complex::new(1.0, 0.0);
+        for matrix in &[
+            u_matrix(1.2, -0.4, 2.5),
+            [zero, one, one, zero],
+            [-one, zero, zero, -one],
+        ] {
+            let root = square_root(matrix);
+            assert_matrix_eq(&product(&root, &root), matrix);
+            assert_matrix_eq(&product(&root, &adjoint(&root)), &[one, zero, zero, one]);
+        }
+    }
+
+    #[test]
+    fn test_angles_are_written_as_fractions_of_pi() {
+        let written = |value: f64| angle(value).to_string();
+        assert_eq!(written(0.0), "0");
+        assert_eq!(written(PI), "pi");
+        assert_eq!(written(-PI / 2.0), "-pi / 2");
+        assert_eq!(written(3.0 * PI / 4.0), "3 * pi / 4");
+        assert_eq!(written(-2.0 * PI / 3.0), "-2 * pi / 3");
+        assert_eq!(written(0.5), "0.5");
+        assert!(approx_eq!(
+            f64,
+            normalize(3.0 * PI / 2.0),
+            -PI / 2.0,
+            ulps = 2
+        ));
+    }
+
+    #[test]
+    fn test_parse_the_basis() {
+        assert_eq!("U,CX".parse(), Ok(Basis::UCx));
+        assert_eq!("u3, cx".parse(), Ok(Basis::U3Cx));
+        assert_eq!("rz,sx,x,cx".parse(), Ok(Basis::RzSxXCx));
+        assert!("u3".parse::<Basis>().is_err());
+    }
+}
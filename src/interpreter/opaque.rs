@@ -0,0 +1,260 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::semantics::Semantics;
+use crate::statevector::{Complex, StateVector};
+
+/// Entries of the product of a matrix and its adjoint closer than this
+/// tolerance to those of the identity are accepted. It is loose enough for
+/// matrices written with a few decimal digits.
+const UNITARY_TOLERANCE: f64 = 1e-6;
+
+/// Represent a native implementation for an opaque gate.
+///
+/// Closures taking the state-vector, the real parameters and the indices of
+/// the target qubits implement this trait, so they can be bound directly. For
+/// gates described by a constant unitary matrix, use [`MatrixGate`].
+///
+/// [`MatrixGate`]: ./struct.MatrixGate.html
+pub trait OpaqueGate {
+    /// Apply the gate to the `qubits` of `statevector`, with the real
+    /// parameters `real_args`.
+    fn apply(&self, statevector: &mut StateVector, real_args: &[f64], qubits: &[usize]);
+
+    /// Return the number of qubits the gate acts on, if fixed by the
+    /// implementation. It is used for checking the implementation agrees with
+    /// the opaque declaration.
+    fn qubit_width(&self) -> Option<usize> {
+        None
+    }
+}
+
+impl<F> OpaqueGate for F
+where
+    F: Fn(&mut StateVector, &[f64], &[usize]),
+{
+    fn apply(&self, statevector: &mut StateVector, real_args: &[f64], qubits: &[usize]) {
+        self(statevector, real_args, qubits)
+    }
+}
+
+/// An opaque gate implementation given by a 2^k×2^k unitary matrix, with k
+/// the number of qubits the gate acts on.
+///
+/// The matrix is given as a list of rows, and it follows the convention of the
+/// [`StateVector`]: the bit `j` of the matrix indices corresponds to the
+/// `j`-th qubit argument of the gate.
+///
+/// [`StateVector`]: ./statevector/struct.StateVector.html
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatrixGate {
+    matrix: Vec<Vec<Complex>>,
+    qubit_width: usize,
+}
+
+impl MatrixGate {
+    /// Create a new gate from the unitary `matrix`.
+    ///
+    /// # Errors
+    ///
+    /// The function fails if the matrix is not square, if its dimension is
+    /// not a power of 2 or if it is not unitary, returning an `Err` variant
+    /// with a description of the problem.
+    pub fn new(matrix: Vec<Vec<Complex>>) -> Result<Self, String> {
+        let dimension = matrix.len();
+        if !dimension.is_power_of_two() {
+            return Err(format!(
+                "the dimension of the matrix must be a power of 2, found {}",
+                dimension
+            ));
+        }
+        if let Some(row) = matrix.iter().position(|row| row.len() != dimension) {
+            return Err(format!(
+                "the matrix must be square, found {} entries in row {} of a {}-row matrix",
+                matrix[row].len(),
+                row,
+                dimension
+            ));
+        }
+        if let Some((row, column)) = non_unitary_entry(&matrix) {
+            return Err(format!(
+                "the matrix must be unitary, the product of its adjoint and itself differs from the identity at row {} and column {}",
+                row, column
+            ));
+        }
+        Ok(MatrixGate {
+            qubit_width: dimension.trailing_zeros() as usize,
+            matrix,
+        })
+    }
+
+    /// Return the rows of the matrix.
+    pub fn matrix(&self) -> &[Vec<Complex>] {
+        &self.matrix
+    }
+}
+
+/// Return the first entry of the product of the adjoint of the square
+/// `matrix` and itself that differs from the identity, if any.
+fn non_unitary_entry(matrix: &[Vec<Complex>]) -> Option<(usize, usize)> {
+    let dimension = matrix.len();
+    (0..dimension)
+        .flat_map(|row| (0..dimension).map(move |column| (row, column)))
+        .find(|&(row, column)| {
+            let entry: Complex = matrix
+                .iter()
+                .map(|matrix_row| matrix_row[row].conj() * matrix_row[column])
+                .sum();
+            let expected = if row == column { 1.0 } else { 0.0 };
+            (entry - expected).norm() > UNITARY_TOLERANCE
+        })
+}
+
+impl OpaqueGate for MatrixGate {
+    fn apply(&self, statevector: &mut StateVector, _real_args: &[f64], qubits: &[usize]) {
+        statevector.apply_matrix(&self.matrix, qubits);
+    }
+
+    fn qubit_width(&self) -> Option<usize> {
+        Some(self.qubit_width)
+    }
+}
+
+/// A collection of native implementations for the opaque gates of a program.
+///
+/// # Examples
+///
+/// See [`run_with_opaque_gates()`] for a complete example.
+///
+/// [`run_with_opaque_gates()`]: ./fn.run_with_opaque_gates.html
+#[derive(Clone, Default)]
+pub struct OpaqueGates {
+    implementations: HashMap<String, Rc<dyn OpaqueGate>>,
+}
+
+impl OpaqueGates {
+    /// Create an empty collection.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Bind the implementation `gate` to the opaque gate `name`, replacing
+    /// any previous implementation.
+    pub fn bind<G>(&mut self, name: &str, gate: G) -> &mut Self
+    where
+        G: OpaqueGate + 'static,
+    {
+        self.implementations.insert(name.into(), Rc::new(gate));
+        self
+    }
+
+    /// Return the implementation bound to `name`, if any.
+    pub fn get(&self, name: &str) -> Option<&dyn OpaqueGate> {
+        self.implementations.get(name).map(|gate| gate.as_ref())
+    }
+
+    /// Check if there is an implementation bound to `name`.
+    pub fn contains(&self, name: &str) -> bool {
+        self.implementations.contains_key(name)
+    }
+
+    /// Return implementations doing nothing for all the opaque gates of
+    /// `semantics`, for compiling programs that are never run.
+    pub(crate) fn placeholders(semantics: &Semantics) -> Self {
+        let mut opaque_gates = OpaqueGates::new();
+        for name in semantics.opaque_definitions.keys() {
+            opaque_gates.bind(name, |_: &mut StateVector, _: &[f64], _: &[usize]| ());
+        }
+        opaque_gates
+    }
+}
+
+impl fmt::Debug for OpaqueGates {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut names: Vec<&String> = self.implementations.keys().collect();
+        names.sort();
+        f.debug_struct("OpaqueGates")
+            .field("implementations", &names)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matrix_gate_requires_a_power_of_two() {
+        let one = Complex::from(1.0);
+        let error = MatrixGate::new(vec![vec![one; 3]; 3]).expect_err("should fail");
+        assert_eq!(
+            error,
+            "the dimension of the matrix must be a power of 2, found 3"
+        );
+    }
+
+    #[test]
+    fn test_matrix_gate_requires_a_square_matrix() {
+        let one = Complex::from(1.0);
+        let error = MatrixGate::new(vec![vec![one; 2], vec![one; 1]]).expect_err("should fail");
+        assert_eq!(
+            error,
+            "the matrix must be square, found 1 entries in row 1 of a 2-row matrix"
+        );
+    }
+
+    #[test]
+    fn test_matrix_gate_requires_a_unitary_matrix() {
+        let one = Complex::from(1.0);
+        let zero = Complex::from(0.0);
+        let error =
+            MatrixGate::new(vec![vec![one, one], vec![zero, one]]).expect_err("should fail");
+        assert_eq!(
+            error,
+            "the matrix must be unitary, the product of its adjoint and itself differs from the identity at row 0 and column 1"
+        );
+    }
+
+    #[test]
+    fn test_matrix_gate_accepts_rounded_entries() {
+        // A rotation of 30 degrees with the cosine rounded to 6 decimals.
+        let cos = Complex::from(0.866025);
+        let sin = Complex::from(0.5);
+        let gate = MatrixGate::new(vec![vec![cos, -sin], vec![sin, cos]]);
+        assert!(gate.is_ok());
+    }
+
+    #[test]
+    fn test_matrix_gate_width() {
+        let one = Complex::from(1.0);
+        let zero = Complex::from(0.0);
+        let identity = (0..4)
+            .map(|row| {
+                (0..4)
+                    .map(|column| if row == column { one } else { zero })
+                    .collect()
+            })
+            .collect();
+        let gate = MatrixGate::new(identity).unwrap();
+        assert_eq!(gate.qubit_width(), Some(2));
+    }
+
+    #[test]
+    fn test_closures_are_gates() {
+        let mut gates = OpaqueGates::new();
+        gates.bind(
+            "x",
+            |statevector: &mut StateVector, _: &[f64], qubits: &[usize]| {
+                statevector.u(std::f64::consts::PI, 0.0, std::f64::consts::PI, qubits[0]);
+            },
+        );
+        let mut statevector = StateVector::new(1);
+        gates
+            .get("x")
+            .expect("is bound")
+            .apply(&mut statevector, &[], &[0]);
+        assert!(statevector.as_complex_bases()[1].norm() > 0.99);
+        assert_eq!(gates.get("x").unwrap().qubit_width(), None);
+    }
+}
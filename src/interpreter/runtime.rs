@@ -1,20 +1,18 @@
-use std::collections::{HashMap, VecDeque};
+use std::collections::HashMap;
 use std::error;
+use std::f64::consts::PI;
 use std::fmt;
-use std::iter::FromIterator;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use crate::grammar::{ast, lexer::Location};
-use crate::interpreter::argument_solver::ArgumentSolver;
+use crate::grammar::{ast, lexer::Location, LanguageOptions};
+use crate::interpreter::compiler::{compile, Instruction};
 use crate::interpreter::computation::{Computation, HistogramBuilder};
-use crate::interpreter::expression_solver::ExpressionSolver;
+use crate::interpreter::opaque::OpaqueGates;
 use crate::semantics::{extract_semantics, QasmType, RegisterType, SemanticError, Semantics};
 use crate::statevector::StateVector;
 
-type BindingMappings = (HashMap<String, f64>, HashMap<String, ast::Argument>);
-
 /// Represent one of the possible errors that can happen during runtime.
 #[non_exhaustive]
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -28,6 +26,8 @@ pub enum RuntimeError {
     IndexOutOfBounds {
         /// Abstract location in the code.
         location: Location,
+        /// Boundaries of the offending argument or parameter, if known.
+        boundaries: Option<(Location, Location)>,
         /// Name of the unknown gate.
         symbol_name: String,
         /// Index tried to access.
@@ -39,6 +39,8 @@ pub enum RuntimeError {
     SymbolNotFound {
         /// Abstract location in the code.
         location: Location,
+        /// Boundaries of the offending argument or parameter, if known.
+        boundaries: Option<(Location, Location)>,
         /// Name of the unknown gate.
         symbol_name: String,
         /// The expected type.
@@ -51,6 +53,8 @@ pub enum RuntimeError {
         are_registers: bool,
         /// Abstract location in the code.
         location: Location,
+        /// Boundaries of the offending argument or parameter, if known.
+        boundaries: Option<(Location, Location)>,
         /// Name of the unknown gate.
         symbol_name: String,
         /// The number of expected parameters.
@@ -69,6 +73,8 @@ pub enum RuntimeError {
     TypeMismatch {
         /// Abstract location in the code.
         location: Location,
+        /// Boundaries of the offending argument or parameter, if known.
+        boundaries: Option<(Location, Location)>,
         /// Name of the unknown gate.
         symbol_name: String,
         /// Expected type.
@@ -78,11 +84,74 @@ pub enum RuntimeError {
     RegisterSizeMismatch {
         /// Abstract location in the code.
         location: Location,
+        /// Boundaries of the offending argument or parameter, if known.
+        boundaries: Option<(Location, Location)>,
         /// Name of the unknown gate.
         symbol_name: String,
         /// Sizes of the different registers involved.
         sizes: Vec<usize>,
     },
+    /// Use of the same qubit more than once in the same gate call.
+    RepeatedQubit {
+        /// Abstract location in the code.
+        location: Location,
+        /// Boundaries of the arguments of the call, if known.
+        boundaries: Option<(Location, Location)>,
+        /// Name of the gate.
+        symbol_name: String,
+        /// The repeated qubit.
+        qubit: String,
+    },
+    /// Call to an opaque gate with no native implementation.
+    UnboundOpaqueGate {
+        /// Abstract location in the code.
+        location: Location,
+        /// Name of the opaque gate.
+        symbol_name: String,
+    },
+    /// Use of gate modifiers on an opaque gate.
+    ModifiedOpaqueGate {
+        /// Abstract location in the code.
+        location: Location,
+        /// Name of the opaque gate.
+        symbol_name: String,
+    },
+    /// Use of the `pow` modifier with a non-integer exponent.
+    NonIntegerPower {
+        /// Abstract location in the code.
+        location: Location,
+        /// Name of the modified gate.
+        symbol_name: String,
+        /// The value of the exponent.
+        exponent: String,
+    },
+    /// Use of the `pow` modifier with an exponent expanding into too many
+    /// gates.
+    PowerTooLarge {
+        /// Abstract location in the code.
+        location: Location,
+        /// Name of the modified gate.
+        symbol_name: String,
+        /// The value of the exponent.
+        exponent: String,
+    },
+    /// Found a non-unitary operation where only unitary operations are
+    /// allowed.
+    NonUnitaryOperation {
+        /// Abstract location in the code.
+        location: Location,
+        /// Name of the operation: `measure`, `reset` or `if`.
+        symbol_name: String,
+    },
+    /// A gate parameter evaluating to an infinite or undefined value.
+    InvalidParameter {
+        /// Abstract location in the code.
+        location: Location,
+        /// The expression of the parameter.
+        expression: String,
+        /// The value found while evaluating the parameter.
+        value: String,
+    },
 }
 
 impl fmt::Display for RuntimeError {
@@ -93,9 +162,16 @@ impl fmt::Display for RuntimeError {
             _ => match lazy_humanize! {
                 self,
                 RuntimeError::IndexOutOfBounds,
+                RuntimeError::ModifiedOpaqueGate,
+                RuntimeError::InvalidParameter,
+                RuntimeError::NonIntegerPower,
+                RuntimeError::NonUnitaryOperation,
+                RuntimeError::PowerTooLarge,
                 RuntimeError::RegisterSizeMismatch,
+                RuntimeError::RepeatedQubit,
                 RuntimeError::SymbolNotFound,
                 RuntimeError::TypeMismatch,
+                RuntimeError::UnboundOpaqueGate,
                 RuntimeError::UndefinedGate,
                 RuntimeError::WrongNumberOfParameters
             } {
@@ -107,6 +183,28 @@ impl fmt::Display for RuntimeError {
     }
 }
 
+impl RuntimeError {
+    /// Return the location of the statement causing the error, if any.
+    pub(crate) fn location(&self) -> Option<Location> {
+        match self {
+            RuntimeError::Other | RuntimeError::SemanticError(_) => None,
+            RuntimeError::IndexOutOfBounds { location, .. }
+            | RuntimeError::SymbolNotFound { location, .. }
+            | RuntimeError::WrongNumberOfParameters { location, .. }
+            | RuntimeError::UndefinedGate { location, .. }
+            | RuntimeError::TypeMismatch { location, .. }
+            | RuntimeError::RegisterSizeMismatch { location, .. }
+            | RuntimeError::RepeatedQubit { location, .. }
+            | RuntimeError::UnboundOpaqueGate { location, .. }
+            | RuntimeError::ModifiedOpaqueGate { location, .. }
+            | RuntimeError::NonIntegerPower { location, .. }
+            | RuntimeError::PowerTooLarge { location, .. }
+            | RuntimeError::NonUnitaryOperation { location, .. }
+            | RuntimeError::InvalidParameter { location, .. } => Some(*location),
+        }
+    }
+}
+
 impl error::Error for RuntimeError {}
 
 pub(crate) type Result<T> = std::result::Result<T, RuntimeError>;
@@ -117,25 +215,23 @@ impl From<SemanticError> for RuntimeError {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 struct Runtime<'program> {
-    macro_stack: VecDeque<BindingMappings>,
     semantics: Semantics,
     statevector: StateVector,
     memory: HashMap<String, u64>,
-    location: Option<&'program Location>,
+    opaque_gates: Option<&'program OpaqueGates>,
 }
 
-impl<'src, 'program> Runtime<'program> {
+impl<'program> Runtime<'program> {
     pub fn new(semantics: Semantics) -> Self {
         let memory_size = semantics.quantum_memory_size;
 
         let mut runtime = Runtime {
-            macro_stack: VecDeque::new(),
             semantics,
             statevector: StateVector::new(memory_size),
             memory: HashMap::new(),
-            location: None,
+            opaque_gates: None,
         };
 
         runtime.reset();
@@ -143,7 +239,6 @@ impl<'src, 'program> Runtime<'program> {
     }
 
     pub fn reset(&mut self) {
-        self.macro_stack.clear();
         self.statevector.reset();
         self.clear_memory();
     }
@@ -157,416 +252,65 @@ impl<'src, 'program> Runtime<'program> {
         }
     }
 
-    fn apply_gates(&mut self, statements: &'program [ast::Span<ast::Statement>]) -> Result<()> {
-        for span in statements {
-            self.location = Some(&span.boundaries.0);
-            match &*span.node {
-                ast::Statement::QuantumOperation(operation) => {
-                    self.apply_quantum_operation(operation)?;
+    fn run(&mut self, instructions: &[Instruction]) -> Result<()> {
+        for instruction in instructions {
+            match instruction {
+                Instruction::U {
+                    theta,
+                    phi,
+                    lambda,
+                    target,
+                } => self.statevector.u(*theta, *phi, *lambda, *target),
+                Instruction::CX { control, target } => self.statevector.cnot(*control, *target),
+                Instruction::ControlledU(operation) => self.statevector.controlled_u(
+                    operation.theta,
+                    operation.phi,
+                    operation.lambda,
+                    &operation.controls,
+                    operation.target,
+                ),
+                Instruction::Opaque {
+                    name,
+                    real_args,
+                    qubits,
+                } => {
+                    let implementation = self
+                        .opaque_gates
+                        .and_then(|gates| gates.get(name))
+                        .expect("after `compile()`, the opaque gate has an implementation");
+                    implementation.apply(&mut self.statevector, real_args, qubits);
                 }
-                ast::Statement::Conditional(register, test, operation) => {
-                    let actual_register = (register).clone();
-                    let register_name = self.register_name(&actual_register);
-                    self.assert_is_classical_register(register_name)?;
-
-                    let value = match actual_register {
-                        ast::Argument::Id(register_name) => self
-                            .memory
-                            .get(&register_name)
-                            .expect("after `assert_is_classical_register()`, must exist"),
-                        _ => unreachable!("cannot index a register inside the condition"),
-                    };
-                    if value == test {
-                        self.apply_quantum_operation(operation)?;
-                    }
+                Instruction::Measure {
+                    qubit,
+                    register,
+                    bit,
+                } => {
+                    let measurement = self.statevector.measure(*qubit) as u64;
+                    let value = self
+                        .memory
+                        .get_mut(register)
+                        .expect("after `compile()`, the register exists");
+                    *value = (*value & !(1 << bit)) | (measurement << bit);
                 }
-                _ => (),
-            };
-        }
-        Ok(())
-    }
-
-    fn apply_quantum_operation(&mut self, operation: &ast::QuantumOperation) -> Result<()> {
-        match operation {
-            ast::QuantumOperation::Unitary(unitary) => self.apply_unitary(unitary),
-            ast::QuantumOperation::Measure(source, target) => {
-                self.apply_measurement(vec![(*source).clone(), (*target).clone()])
-            }
-            _ => Ok(()),
-        }
-    }
-
-    fn apply_unitary(&mut self, unitary: &ast::UnitaryOperation) -> Result<()> {
-        let name = &unitary.0;
-        let real_args = &unitary.1;
-        let args = &unitary.2;
-
-        let actual_args = self.resolve_actual_args(args)?;
-        self.check_all_are_quantum_registers(&actual_args)?;
-
-        let solved_real_args = self.resolve_real_expressions(real_args)?;
-
-        let expanded_arguments = self.expand_arguments(&actual_args).map_err(|sizes| {
-            RuntimeError::RegisterSizeMismatch {
-                location: *self
-                    .location
-                    .expect("after `apply_gates()`, the location of the statement"),
-                symbol_name: name.clone(),
-                sizes,
-            }
-        })?;
-
-        for argument_expansion in expanded_arguments {
-            self.apply_one_gate(name, &solved_real_args, &argument_expansion)?;
-        }
-
-        Ok(())
-    }
-
-    fn resolve_actual_args(&self, args: &[ast::Argument]) -> Result<Vec<ast::Argument>> {
-        let actual = if !self.is_running_macro() {
-            args.iter()
-                .map(|argument| Ok(argument.clone()))
-                .collect::<Result<Vec<ast::Argument>>>()
-        } else {
-            let stack_entry = self
-                .macro_stack
-                .get(0)
-                .expect("if `is_running_macro()`, get first entry of the stack");
-            let arg_bindings = &stack_entry.1;
-            let argument_solver = ArgumentSolver::new(arg_bindings);
-            args.iter()
-                .map(|argument| {
-                    argument_solver.solve(argument).map_err(|symbol_name| {
-                        RuntimeError::SymbolNotFound {
-                            location: *self
-                                .location
-                                .expect("after `apply_gates()`, the location of the statement"),
-                            symbol_name,
-                            expected: QasmType::QuantumRegister,
-                        }
-                    })
-                })
-                .collect::<Result<Vec<ast::Argument>>>()
-        }?;
-        Ok(actual)
-    }
-
-    fn resolve_real_expressions(&self, exprs: &[ast::Expression]) -> Result<Vec<f64>> {
-        let mut real_bindings = &HashMap::new();
-        if self.is_running_macro() {
-            let stack_entry = self
-                .macro_stack
-                .get(0)
-                .expect("if `is_running_macro()`, get first stack entry");
-            real_bindings = &stack_entry.0;
-        };
-        let expression_solver = ExpressionSolver::new(real_bindings);
-        let mut solved = Vec::new();
-        for expression in exprs {
-            let value = expression_solver.solve(expression).map_err(|symbol_name| {
-                RuntimeError::SymbolNotFound {
-                    location: *self
-                        .location
-                        .expect("after `apply_gates()`, the location of the statement"),
-                    symbol_name,
-                    expected: QasmType::RealValue,
+                Instruction::Reset(qubit) => {
+                    if self.statevector.measure(*qubit) {
+                        self.statevector.u(PI, 0.0, PI, *qubit);
+                    }
                 }
-            })?;
-            solved.push(value);
-        }
-        Ok(solved)
-    }
-
-    fn is_running_macro(&self) -> bool {
-        !self.macro_stack.is_empty()
-    }
-
-    fn apply_measurement(&mut self, args: Vec<ast::Argument>) -> Result<()> {
-        self.assert_is_quantum_register(self.register_name(&args[0]))?;
-        self.assert_is_classical_register(self.register_name(&args[1]))?;
-
-        let expanded_arguments =
-            self.expand_arguments(&args)
-                .map_err(|sizes| RuntimeError::RegisterSizeMismatch {
-                    location: *self
-                        .location
-                        .expect("after `apply_gates()`, the location of the statement"),
-                    symbol_name: "measure".into(),
-                    sizes,
-                })?;
-
-        for argument_expansion in expanded_arguments {
-            self.apply_one_measurement(argument_expansion)?;
-        }
-
-        Ok(())
-    }
-
-    fn apply_one_measurement(&mut self, args: Vec<ast::Argument>) -> Result<()> {
-        let classical_register_name = self.register_name(&args[1]);
-        let source = self.bit_mapping(&args[0])?;
-        let measurement = self.statevector.measure(source) as u64;
-
-        let target = self.bit_mapping(&args[1])?;
-        let value = measurement * (1 << target);
-        let prev_value = *(self
-            .memory
-            .get(classical_register_name)
-            .expect("after `apply_measurement()`, get the entry"));
-        self.memory
-            .insert(classical_register_name.into(), prev_value + value);
-
-        Ok(())
-    }
-
-    fn apply_one_gate(
-        &mut self,
-        name: &str,
-        real_args: &[f64],
-        args: &[ast::Argument],
-    ) -> Result<()> {
-        match name {
-            "U" => {
-                let theta = real_args[0];
-                let phi = real_args[1];
-                let lambda = real_args[2];
-                let target = self.bit_mapping(&args[0])?;
-                self.statevector.u(theta, phi, lambda, target);
-            }
-            "CX" => {
-                let control = self.bit_mapping(&args[0])?;
-                let target = self.bit_mapping(&args[1])?;
-                self.statevector.cnot(control, target);
-            }
-            macro_name => {
-                let binding_mappings = self.bind(macro_name.to_owned(), real_args, args)?;
-                self.call(macro_name.to_owned(), binding_mappings)?;
-            }
-        };
-        Ok(())
-    }
-
-    fn check_all_are_quantum_registers(&self, args: &[ast::Argument]) -> Result<()> {
-        for argument in args {
-            let register_name = self.register_name(argument);
-            self.assert_is_quantum_register(register_name)?;
-        }
-        Ok(())
-    }
-
-    fn register_name<'a>(&self, arg: &'a ast::Argument) -> &'a str {
-        match arg {
-            ast::Argument::Id(name) => name,
-            ast::Argument::Item(name, _) => name,
-        }
-    }
-
-    fn assert_is_quantum_register(&self, name: &str) -> Result<()> {
-        if !self.is_register_of_type(RegisterType::Q, name)? {
-            Err(RuntimeError::TypeMismatch {
-                location: *self
-                    .location
-                    .expect("after `apply_gates()`, the location of the statement"),
-                symbol_name: name.into(),
-                expected: QasmType::QuantumRegister,
-            })
-        } else {
-            Ok(())
-        }
-    }
-
-    fn assert_is_classical_register(&self, name: &str) -> Result<()> {
-        if !self.is_register_of_type(RegisterType::C, name)? {
-            Err(RuntimeError::TypeMismatch {
-                location: *self
-                    .location
-                    .expect("after `apply_gates()`, the location of the statement"),
-                symbol_name: name.into(),
-                expected: QasmType::ClassicalRegister,
-            })
-        } else {
-            Ok(())
-        }
-    }
-
-    fn is_register_of_type(&self, rtype: RegisterType, name: &str) -> Result<bool> {
-        match self.semantics.register_table.get(name) {
-            Some(entry) => Ok(entry.1 == rtype),
-            None => Err(RuntimeError::SymbolNotFound {
-                location: *self
-                    .location
-                    .expect("after `apply_gates()`, the location of the statement"),
-                symbol_name: name.into(),
-                expected: match rtype {
-                    RegisterType::Q => QasmType::QuantumRegister,
-                    RegisterType::C => QasmType::ClassicalRegister,
-                },
-            }),
-        }
-    }
-
-    fn apply_gate_operations(&mut self, operations: &[ast::GateOperation]) -> Result<()> {
-        for one_operation in operations {
-            if let ast::GateOperation::Unitary(unitary) = one_operation {
-                self.apply_unitary(unitary)?;
-            }
-        }
-        Ok(())
-    }
-
-    fn expand_arguments(
-        &self,
-        args: &[ast::Argument],
-    ) -> std::result::Result<Vec<Vec<ast::Argument>>, Vec<usize>> {
-        let range = self.range(args)?;
-        Ok(range.map(|index| Runtime::specify(args, index)).collect())
-    }
-
-    fn bit_mapping(&self, argument: &ast::Argument) -> Result<usize> {
-        match argument {
-            ast::Argument::Item(name, index) => match self.semantics.memory_map.get(name) {
-                None => Err(RuntimeError::SymbolNotFound {
-                    location: *self
-                        .location
-                        .expect("after `apply_gates()`, location of the statement"),
-                    symbol_name: name.into(),
-                    expected: QasmType::Register,
-                }),
-                Some(mapping) => {
-                    let size = mapping.2 - mapping.1 + 1;
-                    if *index >= size {
-                        return Err(RuntimeError::IndexOutOfBounds {
-                            location: *self
-                                .location
-                                .expect("after `apply_gates()`, location of the statement"),
-                            symbol_name: name.into(),
-                            index: *index,
-                            size,
-                        });
+                Instruction::Branch {
+                    condition,
+                    then_branch,
+                    else_branch,
+                } => {
+                    if condition.iter().all(|test| test.holds(&self.memory)) {
+                        self.run(then_branch)?;
+                    } else {
+                        self.run(else_branch)?;
                     }
-                    Ok(mapping.1 + *index)
                 }
-            },
-            _ => unreachable!("after `expand_arguments()`, argument should be Argument::Item"),
-        }
-    }
-
-    fn range(
-        &self,
-        args: &[ast::Argument],
-    ) -> std::result::Result<std::ops::Range<usize>, Vec<usize>> {
-        // XXX: This is performed after validating the type of args.
-
-        let whole_registers: Vec<&ast::Argument> = args
-            .iter()
-            .filter(|arg| matches!(arg, ast::Argument::Id(_)))
-            .collect();
-
-        // Return a one-iteration range, `specify()` takes care of ignoring Item arugments.
-        if whole_registers.is_empty() {
-            return Ok(0..1);
-        }
-
-        let all_sizes: Vec<usize> = whole_registers
-            .iter()
-            .map(|arg| {
-                let register_name = self.register_name(arg);
-                let register_entry = self
-                    .semantics
-                    .register_table
-                    .get(register_name)
-                    .expect("after validation, get register entry");
-                register_entry.2
-            })
-            .collect();
-
-        let reference_size = all_sizes[0];
-        let all_the_same_size = all_sizes.iter().all(|size| *size == reference_size);
-
-        if all_the_same_size {
-            Ok(0..reference_size)
-        } else {
-            Err(all_sizes)
-        }
-    }
-
-    fn specify(args: &[ast::Argument], index: usize) -> Vec<ast::Argument> {
-        let mut result = vec![];
-        for arg in args {
-            match arg {
-                ast::Argument::Id(name) => result.push(ast::Argument::Item(name.clone(), index)),
-                other => result.push(other.clone()),
-            }
-        }
-        result
-    }
-
-    fn bind(
-        &mut self,
-        macro_name: String,
-        real_args: &[f64],
-        args: &[ast::Argument],
-    ) -> Result<BindingMappings> {
-        let definition = match self.semantics.macro_definitions.get(&macro_name) {
-            None => {
-                return Err(RuntimeError::UndefinedGate {
-                    location: *self
-                        .location
-                        .expect("after `apply_gates()`, the location of the statement"),
-                    symbol_name: macro_name,
-                });
+                Instruction::Fail(error) => return Err(error.clone()),
             }
-            Some(definition) => definition,
-        };
-
-        if real_args.len() != definition.1.len() {
-            return Err(RuntimeError::WrongNumberOfParameters {
-                are_registers: false,
-                location: *self
-                    .location
-                    .expect("after `apply_gates()`, the location of the statement"),
-                symbol_name: macro_name,
-                given: real_args.len(),
-                expected: definition.1.len(),
-            });
         }
-        let real_args_mapping = HashMap::from_iter(
-            definition
-                .1
-                .iter()
-                .zip(real_args.iter()) // pair formal arguments with their float values
-                .map(|(s, f)| (s.to_owned(), *f)), // convert them into proper copies
-        );
-
-        if args.len() != definition.2.len() {
-            return Err(RuntimeError::WrongNumberOfParameters {
-                are_registers: true,
-                location: *self
-                    .location
-                    .expect("after `apply_gates()`, the location of the statement"),
-                symbol_name: macro_name,
-                given: args.len(),
-                expected: definition.2.len(),
-            });
-        }
-        let args_mapping = HashMap::from_iter(
-            definition
-                .2
-                .iter()
-                .zip(args.iter().cloned()) // pair formal arguments with their registers
-                .map(|(s, r)| (s.to_owned(), r)), // convert them into proper copies
-        );
-
-        Ok((real_args_mapping, args_mapping))
-    }
-
-    fn call(&mut self, macro_name: String, bindings: BindingMappings) -> Result<()> {
-        // XXX: Why clonning is necessary??
-        let definition = (*self.semantics.macro_definitions.get(&macro_name).unwrap()).clone();
-        self.macro_stack.push_front(bindings);
-        self.apply_gate_operations(&definition.3)?;
-        self.macro_stack.pop_front();
         Ok(())
     }
 }
@@ -613,10 +357,22 @@ impl<'src, 'program> Runtime<'program> {
 ///
 /// [`parse_and_link()`]: ./fn.parse_and_link.html
 pub fn simulate(program: &ast::OpenQasmProgram) -> Result<Computation> {
-    let semantics = extract_semantics(program)?;
+    do_simulate(program, &LanguageOptions::new(), None)
+}
+
+fn do_simulate(
+    program: &ast::OpenQasmProgram,
+    options: &LanguageOptions,
+    opaque_gates: Option<&OpaqueGates>,
+) -> Result<Computation> {
+    let semantics = extract_semantics(program, options)?;
+    let quantum_registers = semantics.quantum_registers();
+    let instructions = compile(program, &semantics, opaque_gates)?;
     let mut runtime = Runtime::new(semantics);
-    runtime.apply_gates(&program.program)?;
-    Ok(Computation::new(runtime.memory, runtime.statevector, None))
+    runtime.opaque_gates = opaque_gates;
+    runtime.run(&instructions)?;
+    Ok(Computation::new(runtime.memory, runtime.statevector, None)
+        .with_quantum_registers(quantum_registers))
 }
 
 /// Perform `shots` number of simulations of the parsed proram `program`.
@@ -661,17 +417,102 @@ pub fn simulate(program: &ast::OpenQasmProgram) -> Result<Computation> {
 ///
 /// [`parse_and_link()`]: ./fn.parse_and_link.html
 pub fn simulate_with_shots(program: &ast::OpenQasmProgram, shots: usize) -> Result<Computation> {
-    let semantics = extract_semantics(program)?;
+    do_simulate_with_shots(program, shots, &LanguageOptions::new(), None)
+}
+
+fn do_simulate_with_shots(
+    program: &ast::OpenQasmProgram,
+    shots: usize,
+    options: &LanguageOptions,
+    opaque_gates: Option<&OpaqueGates>,
+) -> Result<Computation> {
+    let semantics = extract_semantics(program, options)?;
+    let quantum_registers = semantics.quantum_registers();
+    let instructions = compile(program, &semantics, opaque_gates)?;
     let mut runtime = Runtime::new(semantics);
+    runtime.opaque_gates = opaque_gates;
     let mut histogram_builder = HistogramBuilder::new();
     for _ in 0..shots {
         runtime.reset();
-        runtime.apply_gates(&program.program)?;
+        runtime.run(&instructions)?;
         histogram_builder.update(&runtime.memory);
     }
     Ok(Computation::new(
         runtime.memory,
         runtime.statevector,
         Some(histogram_builder.histogram()),
-    ))
+    )
+    .with_quantum_registers(quantum_registers))
+}
+
+/// Perform a simulation of the parsed `program` with optional `shots`,
+/// accepting the extensions enabled in `options` and using `opaque_gates` as
+/// the implementations of the opaque gates of the program.
+pub(crate) fn simulate_with_options(
+    program: &ast::OpenQasmProgram,
+    shots: Option<usize>,
+    options: &LanguageOptions,
+    opaque_gates: &OpaqueGates,
+) -> Result<Computation> {
+    match shots {
+        None => do_simulate(program, options, Some(opaque_gates)),
+        Some(shots) => do_simulate_with_shots(program, shots, options, Some(opaque_gates)),
+    }
+}
+
+/// Apply the unitary implemented by a measurement-free program to arbitrary
+/// input states.
+#[derive(Debug, Clone)]
+pub(crate) struct UnitarySimulator<'program> {
+    runtime: Runtime<'program>,
+    instructions: Vec<Instruction>,
+}
+
+impl<'program> UnitarySimulator<'program> {
+    /// Prepare the simulation of `program`, accepting the extensions enabled
+    /// in `options`. It fails if the program contains measurements, resets or
+    /// conditionals.
+    pub fn new(program: &'program ast::OpenQasmProgram, options: &LanguageOptions) -> Result<Self> {
+        assert_is_unitary(program)?;
+        let semantics = extract_semantics(program, options)?;
+        let instructions = compile(program, &semantics, None)?;
+        Ok(UnitarySimulator {
+            runtime: Runtime::new(semantics),
+            instructions,
+        })
+    }
+
+    /// Return the number of qubits the program acts on.
+    pub fn qubit_width(&self) -> usize {
+        self.runtime.semantics.quantum_memory_size
+    }
+
+    /// Return the state resulting from applying the program to `input`.
+    pub fn apply(&mut self, input: StateVector) -> Result<StateVector> {
+        assert_eq!(
+            input.qubit_width(),
+            self.qubit_width(),
+            "the input state must have the same width as the program"
+        );
+        self.runtime.reset();
+        self.runtime.statevector = input;
+        self.runtime.run(&self.instructions)?;
+        Ok(self.runtime.statevector.clone())
+    }
+}
+
+fn assert_is_unitary(program: &ast::OpenQasmProgram) -> Result<()> {
+    for span in &program.program {
+        let symbol_name = match &*span.node {
+            ast::Statement::QuantumOperation(ast::QuantumOperation::Measure(_, _)) => "measure",
+            ast::Statement::QuantumOperation(ast::QuantumOperation::Reset(_)) => "reset",
+            ast::Statement::Conditional(_, _, _) | ast::Statement::IfElse { .. } => "if",
+            _ => continue,
+        };
+        return Err(RuntimeError::NonUnitaryOperation {
+            location: span.boundaries.0,
+            symbol_name: symbol_name.into(),
+        });
+    }
+    Ok(())
 }
@@ -0,0 +1,437 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::f64::consts::PI;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::grammar::ast;
+use crate::interpreter::compiler::{compile, ControlledU, Instruction};
+use crate::interpreter::runtime::Result;
+use crate::interpreter::OpaqueGates;
+use crate::semantics::{RegisterType, Semantics};
+
+/// Two angles closer than this tolerance are considered the same.
+const TOLERANCE: f64 = 1e-10;
+
+/// Metrics of the circuit described by a program, obtained without
+/// simulating it.
+///
+/// The gates are counted after expanding all the gate calls into the
+/// primitive gates `U` and `CX`, calls to opaque gates, and the `U` gates
+/// controlled by other qubits resulting from the `ctrl @` modifier, named
+/// `ctrl @ U` or `ctrl(n) @ U`. The gates in the branches of conditionals are
+/// counted as if they ran.
+///
+/// # Examples
+///
+/// See [`get_stats()`] for an example of generating a `Stats` instance.
+///
+/// [`get_stats()`]: ./fn.get_stats.html
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Stats {
+    /// Number of qubits.
+    pub qubits: usize,
+    /// Number of classical bits.
+    pub clbits: usize,
+    /// Number of primitive gates, by name.
+    pub gate_counts: BTreeMap<String, usize>,
+    /// Number of layers of primitive operations, where the operations in a
+    /// layer act on different qubits and bits.
+    pub depth: usize,
+    /// Number of layers of the operations in the program, without expanding
+    /// the gate calls. Operations on whole registers count as one operation
+    /// per qubit.
+    pub top_level_depth: usize,
+    /// Number of primitive gates acting on two qubits.
+    pub two_qubit_gates: usize,
+    /// Number of `U` gates equal to the `T` gate or its inverse, up to a
+    /// global phase.
+    pub t_count: usize,
+    /// Number of measurements of a single qubit.
+    pub measurements: usize,
+    /// Whether the circuit has conditionals or operations on qubits already
+    /// measured.
+    pub dynamic: bool,
+}
+
+/// Return the metrics of `program`.
+pub(crate) fn collect_stats(
+    program: &ast::OpenQasmProgram,
+    semantics: &Semantics,
+) -> Result<Stats> {
+    // Opaque gates are counted but never applied.
+    let opaque_gates = OpaqueGates::placeholders(semantics);
+    let instructions = compile(program, semantics, Some(&opaque_gates))?;
+
+    let mut collector = Collector {
+        semantics,
+        stats: Stats {
+            qubits: semantics.quantum_memory_size,
+            clbits: semantics.classical_memory_size,
+            ..Stats::default()
+        },
+        layers: Layers::default(),
+        measured: HashSet::new(),
+    };
+    for instruction in &instructions {
+        collector.instruction(instruction, &[])?;
+    }
+    collector.stats.depth = collector.layers.depth;
+
+    let mut top_level_layers = Layers::default();
+    for span in &program.program {
+        collector.top_level_statement(span, &[], &mut top_level_layers);
+    }
+    collector.stats.top_level_depth = top_level_layers.depth;
+    Ok(collector.stats)
+}
+
+/// A qubit, by absolute index, or a bit of a classical register.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Wire {
+    Qubit(usize),
+    Clbit(String, usize),
+}
+
+/// The layers of a circuit, as operations are added at its end.
+#[derive(Debug, Default)]
+struct Layers {
+    /// The layer of the last operation on each wire.
+    levels: HashMap<Wire, usize>,
+    depth: usize,
+}
+
+impl Layers {
+    /// Add an operation on `wires` in the layer following the last operation
+    /// on any of them.
+    fn add(&mut self, wires: &[Wire]) {
+        let level = 1 + wires
+            .iter()
+            .map(|wire| self.levels.get(wire).copied().unwrap_or_default())
+            .max()
+            .unwrap_or_default();
+        for wire in wires {
+            self.levels.insert(wire.clone(), level);
+        }
+        self.depth = self.depth.max(level);
+    }
+}
+
+struct Collector<'a> {
+    semantics: &'a Semantics,
+    stats: Stats,
+    layers: Layers,
+    /// The qubits measured so far.
+    measured: HashSet<usize>,
+}
+
+impl<'a> Collector<'a> {
+    /// Account for `instruction`, conditioned on the bits in `condition`.
+    fn instruction(&mut self, instruction: &Instruction, condition: &[Wire]) -> Result<()> {
+        match instruction {
+            Instruction::U {
+                theta,
+                phi,
+                lambda,
+                target,
+            } => {
+                let phase = (phi + lambda).rem_euclid(2.0 * PI);
+                if theta.abs() < TOLERANCE
+                    && ((phase - PI / 4.0).abs() < TOLERANCE
+                        || (phase - 7.0 * PI / 4.0).abs() < TOLERANCE)
+                {
+                    self.stats.t_count += 1;
+                }
+                self.gate("U", &[*target], condition);
+            }
+            Instruction::CX { control, target } => self.gate("CX", &[*control, *target], condition),
+            Instruction::ControlledU(ControlledU {
+                controls, target, ..
+            }) => {
+                let name = match controls.len() {
+                    1 => "ctrl @ U".into(),
+                    count => format!("ctrl({}) @ U", count),
+                };
+                let mut qubits = controls.clone();
+                qubits.push(*target);
+                self.gate(&name, &qubits, condition);
+            }
+            Instruction::Opaque { name, qubits, .. } => self.gate(name, qubits, condition),
+            Instruction::Measure {
+                qubit,
+                register,
+                bit,
+            } => {
+                self.stats.measurements += 1;
+                self.measured.insert(*qubit);
+                let mut wires = vec![Wire::Qubit(*qubit), Wire::Clbit(register.clone(), *bit)];
+                wires.extend_from_slice(condition);
+                self.layers.add(&wires);
+            }
+            Instruction::Reset(qubit) => self.operation(&[*qubit], condition),
+            Instruction::Branch {
+                condition: tests,
+                then_branch,
+                else_branch,
+            } => {
+                self.stats.dynamic = true;
+                let mut wires = condition.to_vec();
+                for test in tests {
+                    let (register, bit) = test.operand();
+                    wires.extend(self.clbits(register, bit));
+                }
+                for instruction in then_branch.iter().chain(else_branch) {
+                    self.instruction(instruction, &wires)?;
+                }
+            }
+            Instruction::Fail(error) => return Err(error.clone()),
+        }
+        Ok(())
+    }
+
+    fn gate(&mut self, name: &str, qubits: &[usize], condition: &[Wire]) {
+        *self.stats.gate_counts.entry(name.into()).or_default() += 1;
+        if qubits.len() == 2 {
+            self.stats.two_qubit_gates += 1;
+        }
+        self.operation(qubits, condition);
+    }
+
+    /// Account for an operation on `qubits` other than a measurement.
+    fn operation(&mut self, qubits: &[usize], condition: &[Wire]) {
+        if qubits.iter().any(|qubit| self.measured.contains(qubit)) {
+            self.stats.dynamic = true;
+        }
+        let mut wires: Vec<Wire> = qubits.iter().map(|qubit| Wire::Qubit(*qubit)).collect();
+        wires.extend_from_slice(condition);
+        self.layers.add(&wires);
+    }
+
+    /// Add the operations of the statement in `span`, conditioned on the
+    /// bits in `condition`, to the top-level `layers`.
+    fn top_level_statement(
+        &self,
+        span: &ast::Span<ast::Statement>,
+        condition: &[Wire],
+        layers: &mut Layers,
+    ) {
+        match &*span.node {
+            ast::Statement::QuantumOperation(operation) => {
+                self.top_level_operation(operation, condition, layers)
+            }
+            ast::Statement::Conditional(register, _, operation) => {
+                let mut wires = condition.to_vec();
+                wires.extend(self.clbits(register_name(register), None));
+                self.top_level_operation(operation, &wires, layers);
+            }
+            ast::Statement::IfElse {
+                condition: ast::Condition(comparisons),
+                then_branch,
+                else_branch,
+            } => {
+                let mut wires = condition.to_vec();
+                for ast::Comparison(register, _, _) in comparisons {
+                    let bit = match register {
+                        ast::Argument::Id(_) => None,
+                        ast::Argument::Item(_, index) => Some(*index),
+                    };
+                    wires.extend(self.clbits(register_name(register), bit));
+                }
+                for span in then_branch.iter().chain(else_branch) {
+                    self.top_level_statement(span, &wires, layers);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// Add `operation`, once per qubit if it acts on whole registers, to the
+    /// top-level `layers`.
+    fn top_level_operation(
+        &self,
+        operation: &ast::QuantumOperation,
+        condition: &[Wire],
+        layers: &mut Layers,
+    ) {
+        let (qubits, clbits) = match operation {
+            ast::QuantumOperation::Unitary(ast::UnitaryOperation(_, _, args))
+            | ast::QuantumOperation::Modified(_, ast::UnitaryOperation(_, _, args)) => {
+                (args.as_slice(), None)
+            }
+            ast::QuantumOperation::Measure(qubits, clbits) => {
+                (std::slice::from_ref(qubits), Some(clbits))
+            }
+            ast::QuantumOperation::Reset(qubits) => (std::slice::from_ref(qubits), None),
+        };
+        let size = qubits
+            .iter()
+            .chain(clbits)
+            .filter_map(|argument| match argument {
+                ast::Argument::Id(name) => self.semantics.register_table.get(name),
+                ast::Argument::Item(_, _) => None,
+            })
+            .map(|entry| entry.2)
+            .max()
+            .unwrap_or(1);
+
+        for index in 0..size {
+            let index_in = |argument: &ast::Argument| match argument {
+                ast::Argument::Id(_) => index,
+                ast::Argument::Item(_, index) => *index,
+            };
+            let mut wires: Vec<Wire> = qubits
+                .iter()
+                .map(|argument| {
+                    let start = self
+                        .semantics
+                        .memory_map
+                        .get(register_name(argument))
+                        .expect("after compiling, the register exists")
+                        .1;
+                    Wire::Qubit(start + index_in(argument))
+                })
+                .collect();
+            wires.extend(
+                clbits.map(|argument| {
+                    Wire::Clbit(register_name(argument).into(), index_in(argument))
+                }),
+            );
+            wires.extend_from_slice(condition);
+            layers.add(&wires);
+        }
+    }
+
+    /// Return the wires of the classical register `register`, or only the
+    /// one of `bit` if given.
+    fn clbits(&self, register: &str, bit: Option<usize>) -> Vec<Wire> {
+        match bit {
+            Some(bit) => vec![Wire::Clbit(register.into(), bit)],
+            None => {
+                let entry = self
+                    .semantics
+                    .register_table
+                    .get(register)
+                    .filter(|entry| entry.1 == RegisterType::C)
+                    .expect("after compiling, the register exists");
+                (0..entry.2)
+                    .map(|index| Wire::Clbit(register.into(), index))
+                    .collect()
+            }
+        }
+    }
+}
+
+fn register_name(argument: &ast::Argument) -> &str {
+    match argument {
+        ast::Argument::Id(name) | ast::Argument::Item(name, _) => name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use indoc::indoc;
+
+    use crate::grammar::LanguageOptions;
+    use crate::parse_and_link;
+    use crate::semantics::extract_semantics;
+
+    fn stats_of(source: &str) -> Stats {
+        let program = parse_and_link(source).unwrap();
+        let semantics = extract_semantics(&program, &LanguageOptions::new()).unwrap();
+        collect_stats(&program, &semantics).unwrap()
+    }
+
+    #[test]
+    fn test_count_primitive_gates() {
+        let stats = stats_of(indoc!(
+            "
+            OPENQASM 2.0;
+            include \"qelib1.inc\";
+            qreg q[3];
+            creg c[3];
+            ccx q[0], q[1], q[2];
+            ctrl(2) @ h q[0], q[1], q[2];
+            measure q -> c;
+            "
+        ));
+        assert_eq!(stats.qubits, 3);
+        assert_eq!(stats.clbits, 3);
+        let counts: Vec<(&str, usize)> = stats
+            .gate_counts
+            .iter()
+            .map(|(name, count)| (name.as_str(), *count))
+            .collect();
+        // `ccx` expands into 6 `CX`, 7 `t` or `tdg` and 2 `h`, while the
+        // modified `h` expands into the `U` of its definition.
+        assert_eq!(counts, vec![("CX", 6), ("U", 9), ("ctrl(2) @ U", 1)]);
+        assert_eq!(stats.t_count, 7);
+        assert_eq!(stats.two_qubit_gates, 6);
+        assert_eq!(stats.measurements, 3);
+        assert!(!stats.dynamic);
+    }
+
+    #[test]
+    fn test_depth_of_primitives_and_top_level_gates() {
+        let stats = stats_of(indoc!(
+            "
+            OPENQASM 2.0;
+            include \"qelib1.inc\";
+            gate bell a, b { h a; cx a, b; }
+            qreg q[4];
+            h q;
+            bell q[0], q[1];
+            bell q[2], q[3];
+            barrier q;
+            x q[3];
+            "
+        ));
+        assert_eq!(stats.top_level_depth, 3);
+        assert_eq!(stats.depth, 4);
+    }
+
+    #[test]
+    fn test_conditionals_and_mid_circuit_measurements_are_dynamic() {
+        let conditional = stats_of(indoc!(
+            "
+            OPENQASM 2.0;
+            include \"qelib1.inc\";
+            qreg q[2];
+            creg c[1];
+            measure q[0] -> c[0];
+            if (c == 1) x q[1];
+            "
+        ));
+        assert!(conditional.dynamic);
+        assert_eq!(conditional.depth, 2);
+        assert_eq!(conditional.top_level_depth, 2);
+
+        let mid_circuit_measurement = stats_of(indoc!(
+            "
+            OPENQASM 2.0;
+            include \"qelib1.inc\";
+            qreg q[1];
+            creg c[1];
+            measure q[0] -> c[0];
+            h q[0];
+            "
+        ));
+        assert!(mid_circuit_measurement.dynamic);
+    }
+
+    #[test]
+    fn test_opaque_gates_are_counted_by_name() {
+        let stats = stats_of(indoc!(
+            "
+            OPENQASM 2.0;
+            opaque magic(theta) a, b;
+            qreg q[2];
+            magic(0.5) q[0], q[1];
+            "
+        ));
+        assert_eq!(stats.gate_counts.get("magic"), Some(&1));
+        assert_eq!(stats.two_qubit_gates, 1);
+    }
+}
@@ -0,0 +1,63 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::statevector::{Complex, StateVector};
+
+/// Represent the unitary matrix implemented by a measurement-free program.
+///
+/// The matrix is indexed by computational basis states following the same
+/// convention as the [`StateVector`]: qubit `i` corresponds to the bit `i` of
+/// the index. Column `j` is the state resulting from running the program on
+/// the basis state `j`.
+///
+/// # Examples:
+///
+/// See [`extract_unitary()`] for an example of generating a `Unitary`
+/// instance.
+///
+/// [`StateVector`]: ./statevector/struct.StateVector.html
+/// [`extract_unitary()`]: ./fn.extract_unitary.html
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Unitary {
+    qubit_width: usize,
+    rows: Vec<Vec<Complex>>,
+}
+
+impl Unitary {
+    /// Create a new unitary from its `columns`, this is, the images of each
+    /// of the computational basis states.
+    pub fn from_columns(columns: &[StateVector]) -> Self {
+        let dimension = columns.len();
+        let qubit_width = (dimension as f64).log2() as usize;
+        let rows = (0..dimension)
+            .map(|row| {
+                columns
+                    .iter()
+                    .map(|column| column.as_complex_bases()[row])
+                    .collect()
+            })
+            .collect();
+        Unitary { qubit_width, rows }
+    }
+
+    /// Return the number of qubits the unitary acts on.
+    pub fn qubit_width(&self) -> usize {
+        self.qubit_width
+    }
+
+    /// Return the number of rows (or columns) of the matrix.
+    pub fn dimension(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Return the rows of the matrix.
+    pub fn rows(&self) -> &[Vec<Complex>] {
+        &self.rows
+    }
+
+    /// Return the entry at `row` and `column`.
+    pub fn get(&self, row: usize, column: usize) -> Complex {
+        self.rows[row][column]
+    }
+}
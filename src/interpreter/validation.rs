@@ -0,0 +1,389 @@
+use std::collections::HashSet;
+
+use crate::grammar::{ast, lexer::Location, LanguageOptions};
+use crate::interpreter::compiler::{locate_operation, statement_errors};
+use crate::interpreter::opaque::OpaqueGates;
+use crate::interpreter::runtime::RuntimeError;
+use crate::semantics::{extract_semantics_and_errors, QasmType, Semantics};
+
+/// Check `program` without simulating it and return all the errors found.
+///
+/// Besides the semantic errors, the check covers every statement, including
+/// those inside conditionals whose branches would never run, and the bodies of
+/// all gate definitions, even if the gates are never called.
+pub(crate) fn validate(
+    program: &ast::OpenQasmProgram,
+    options: &LanguageOptions,
+    opaque_gates: Option<&OpaqueGates>,
+) -> Vec<RuntimeError> {
+    let (semantics, semantic_errors) = extract_semantics_and_errors(program, options);
+    let mut errors: Vec<RuntimeError> = semantic_errors
+        .into_iter()
+        .map(RuntimeError::from)
+        .collect();
+
+    let invalid_gates: HashSet<String> = program
+        .program
+        .iter()
+        .filter_map(|span| match &*span.node {
+            ast::Statement::GateDecl { signature, .. }
+                if !gate_errors(signature, span, &semantics).is_empty() =>
+            {
+                Some(signature.0.clone())
+            }
+            _ => None,
+        })
+        .collect();
+
+    for span in &program.program {
+        match &*span.node {
+            ast::Statement::GateDecl { signature, .. } => {
+                errors.extend(gate_errors(signature, span, &semantics))
+            }
+            _ => errors.extend(statement_errors(
+                span,
+                &semantics,
+                opaque_gates,
+                &invalid_gates,
+            )),
+        }
+    }
+    errors
+}
+
+/// Return the errors in the body of the gate definition in `span`. They are
+/// located at the operations of the body or, if their spans are unknown, at
+/// the definition.
+fn gate_errors(
+    signature: &(String, Vec<String>, Vec<String>, Vec<ast::GateOperation>),
+    span: &ast::Span<ast::Statement>,
+    semantics: &Semantics,
+) -> Vec<RuntimeError> {
+    let (_, real_params, quantum_params, body) = signature;
+    let mut errors = Vec::new();
+    for (index, operation) in body.iter().enumerate() {
+        let params = (&real_params[..], &quantum_params[..]);
+        match span.operations.get(index) {
+            None => errors.extend(operation_errors(
+                operation,
+                params,
+                span.boundaries.0,
+                semantics,
+            )),
+            Some(operation_span) => errors.extend(
+                operation_errors(operation, params, operation_span.boundaries.0, semantics)
+                    .into_iter()
+                    .map(|error| locate_operation(error, operation, operation_span)),
+            ),
+        }
+    }
+    errors
+}
+
+/// Return the errors in one `operation` of a gate body given the real and
+/// quantum parameters of the gate.
+fn operation_errors(
+    operation: &ast::GateOperation,
+    (real_params, quantum_params): (&[String], &[String]),
+    location: Location,
+    semantics: &Semantics,
+) -> Vec<RuntimeError> {
+    let mut errors = Vec::new();
+    let (modifiers, unitary) = match operation {
+        ast::GateOperation::Unitary(unitary) => (&[][..], unitary),
+        ast::GateOperation::Modified(modifiers, unitary) => (&modifiers[..], unitary),
+        ast::GateOperation::Barrier(ast::BarrierPragma(args)) => {
+            errors.extend(argument_errors(args, quantum_params, location));
+            return errors;
+        }
+    };
+    let ast::UnitaryOperation(name, real_args, args) = unitary;
+
+    let mut expressions: Vec<&ast::Expression> = real_args.iter().collect();
+    let mut control_count = 0;
+    for modifier in modifiers {
+        match modifier {
+            ast::GateModifier::Ctrl(count) => control_count += count,
+            ast::GateModifier::Pow(exponent) => expressions.push(exponent),
+            _ => (),
+        }
+    }
+    for expression in expressions {
+        errors.extend(expression_errors(expression, real_params, location));
+    }
+    errors.extend(argument_errors(args, quantum_params, location));
+    if let Some(repeated) = args
+        .iter()
+        .enumerate()
+        .find_map(|(index, argument)| args[..index].iter().find(|other| *other == argument))
+    {
+        errors.push(RuntimeError::RepeatedQubit {
+            location,
+            boundaries: None,
+            symbol_name: name.clone(),
+            qubit: repeated.to_string(),
+        });
+    }
+
+    let arity = match name.as_str() {
+        "U" => Some((3, 1)),
+        "CX" => Some((0, 2)),
+        _ => semantics
+            .macro_definitions
+            .get(name)
+            .map(|definition| (definition.1.len(), definition.2.len()))
+            .or_else(|| {
+                semantics
+                    .opaque_definitions
+                    .get(name)
+                    .map(|definition| (definition.1.len(), definition.2.len()))
+            }),
+    };
+    let (expected_real_args, expected_args) = match arity {
+        None => {
+            errors.push(RuntimeError::UndefinedGate {
+                location,
+                symbol_name: name.clone(),
+            });
+            return errors;
+        }
+        Some(arity) => arity,
+    };
+
+    if !modifiers.is_empty() && semantics.opaque_definitions.contains_key(name) {
+        errors.push(RuntimeError::ModifiedOpaqueGate {
+            location,
+            symbol_name: name.clone(),
+        });
+    }
+    if real_args.len() != expected_real_args {
+        errors.push(RuntimeError::WrongNumberOfParameters {
+            are_registers: false,
+            location,
+            boundaries: None,
+            symbol_name: name.clone(),
+            given: real_args.len(),
+            expected: expected_real_args,
+        });
+    }
+    if args.len() != control_count + expected_args {
+        errors.push(RuntimeError::WrongNumberOfParameters {
+            are_registers: true,
+            location,
+            boundaries: None,
+            symbol_name: name.clone(),
+            given: args.len(),
+            expected: control_count + expected_args,
+        });
+    }
+    errors
+}
+
+fn argument_errors(
+    args: &[ast::Argument],
+    quantum_params: &[String],
+    location: Location,
+) -> Vec<RuntimeError> {
+    args.iter()
+        .filter_map(|argument| match argument {
+            ast::Argument::Id(name) if quantum_params.contains(name) => None,
+            ast::Argument::Id(name) | ast::Argument::Item(name, _) => {
+                Some(RuntimeError::SymbolNotFound {
+                    location,
+                    boundaries: None,
+                    symbol_name: name.clone(),
+                    expected: QasmType::QuantumRegister,
+                })
+            }
+        })
+        .collect()
+}
+
+fn expression_errors(
+    expression: &ast::Expression,
+    real_params: &[String],
+    location: Location,
+) -> Vec<RuntimeError> {
+    match expression {
+        ast::Expression::Id(name) if !real_params.contains(name) => {
+            vec![RuntimeError::SymbolNotFound {
+                location,
+                boundaries: None,
+                symbol_name: name.clone(),
+                expected: QasmType::RealValue,
+            }]
+        }
+        ast::Expression::Op(_, left, right) | ast::Expression::BinaryFunction(_, left, right) => {
+            let mut errors = expression_errors(left, real_params, location);
+            errors.extend(expression_errors(right, real_params, location));
+            errors
+        }
+        ast::Expression::Function(_, operand) | ast::Expression::Minus(operand) => {
+            expression_errors(operand, real_params, location)
+        }
+        _ => vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use indoc::indoc;
+
+    use crate::parse_and_link;
+
+    fn validate_source(source: &str) -> Vec<RuntimeError> {
+        let program = parse_and_link(source).unwrap();
+        validate(&program, &LanguageOptions::new(), None)
+    }
+
+    #[test]
+    fn test_valid_programs_have_no_errors() {
+        let source = indoc!(
+            "
+        OPENQASM 2.0;
+        include \"qelib1.inc\";
+        qreg q[2];
+        creg c[2];
+        h q[0];
+        cx q[0], q[1];
+        measure q -> c;
+        if (c == 3) x q;
+        "
+        );
+        assert_eq!(validate_source(source), vec![]);
+    }
+
+    #[test]
+    fn test_collect_all_errors_including_untaken_branches() {
+        let source = indoc!(
+            "
+        OPENQASM 2.0;
+        qreg q[2];
+        qreg r[3];
+        creg c[2];
+        gate two a, b { CX a, b; }
+        two q[0];
+        if (c == 3) U(0, 0, 0) q[2];
+        CX q, r;
+        foo q;
+        "
+        );
+        assert_eq!(
+            validate_source(source),
+            vec![
+                RuntimeError::WrongNumberOfParameters {
+                    are_registers: true,
+                    location: Location(74),
+                    boundaries: Some((Location(78), Location(82))),
+                    symbol_name: "two".into(),
+                    given: 1,
+                    expected: 2
+                },
+                RuntimeError::IndexOutOfBounds {
+                    location: Location(84),
+                    boundaries: Some((Location(107), Location(111))),
+                    symbol_name: "q".into(),
+                    index: 2,
+                    size: 2
+                },
+                RuntimeError::RegisterSizeMismatch {
+                    location: Location(113),
+                    boundaries: Some((Location(116), Location(120))),
+                    symbol_name: "CX".into(),
+                    sizes: vec![2, 3]
+                },
+                RuntimeError::UndefinedGate {
+                    location: Location(122),
+                    symbol_name: "foo".into()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_repeated_qubits_are_rejected() {
+        let source = indoc!(
+            "
+        OPENQASM 2.0;
+        include \"qelib1.inc\";
+        qreg q[2];
+        gate twice a, b { cx a, a; }
+        cx q[0], q[0];
+        ctrl @ x q[1], q[1];
+        cx q, q[0];
+        cx q[0], q[1];
+        "
+        );
+        assert_eq!(
+            validate_source(source),
+            vec![
+                RuntimeError::RepeatedQubit {
+                    location: Location(65),
+                    boundaries: Some((Location(68), Location(72))),
+                    symbol_name: "cx".into(),
+                    qubit: "a".into()
+                },
+                RuntimeError::RepeatedQubit {
+                    location: Location(76),
+                    boundaries: Some((Location(79), Location(89))),
+                    symbol_name: "cx".into(),
+                    qubit: "q[0]".into()
+                },
+                RuntimeError::RepeatedQubit {
+                    location: Location(91),
+                    boundaries: Some((Location(100), Location(110))),
+                    symbol_name: "x".into(),
+                    qubit: "q[1]".into()
+                },
+                RuntimeError::RepeatedQubit {
+                    location: Location(112),
+                    boundaries: Some((Location(115), Location(122))),
+                    symbol_name: "cx".into(),
+                    qubit: "q[0]".into()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_gate_bodies_once() {
+        let source = indoc!(
+            "
+        OPENQASM 2.0;
+        qreg q[1];
+        gate never_called a { U(theta, 0, 0) b; }
+        gate two a, b { CX a, b; }
+        gate broken a { two a; }
+        broken q[0];
+        broken q[0];
+        "
+        );
+        assert_eq!(
+            validate_source(source),
+            vec![
+                RuntimeError::SymbolNotFound {
+                    location: Location(47),
+                    boundaries: Some((Location(49), Location(54))),
+                    symbol_name: "theta".into(),
+                    expected: QasmType::RealValue
+                },
+                RuntimeError::SymbolNotFound {
+                    location: Location(47),
+                    boundaries: Some((Location(62), Location(63))),
+                    symbol_name: "b".into(),
+                    expected: QasmType::QuantumRegister
+                },
+                RuntimeError::WrongNumberOfParameters {
+                    are_registers: true,
+                    location: Location(110),
+                    boundaries: Some((Location(114), Location(115))),
+                    symbol_name: "two".into(),
+                    given: 1,
+                    expected: 2
+                },
+            ]
+        );
+    }
+}
@@ -31,32 +31,68 @@
 //!
 //! ```sh
 //! $ qasmsim --help
-//! qasmsim 1.2.0
+//! qasmsim 1.3.1
 //! A QASM interpreter and quantum simulator in Rust.
 //!
 //! USAGE:
-//!     qasmsim [FLAGS] [OPTIONS] [source]
+//!     qasmsim [FLAGS] [OPTIONS] [source] [SUBCOMMAND]
 //!
 //! FLAGS:
 //!     -b, --binary           Prints the binary representation of the values
+//!         --check            Checks the program for errors without simulating it. Reports all the errors found, including
+//!                            those in branches and gates that never run
 //!     -h, --help             Prints help information
 //!     -x, --hexadecimal      Prints the hexadecimal representation of the values
 //!     -i, --integer          Prints the interger representation of the values. Default option
+//!         --json             Prints the output in JSON format
+//!         --ket              Prints the state vector of the simulation in ket notation, grouping the bits by quantum
+//!                            register. Ignored if shots is set
 //!         --probabilities    Prints the probabilities vector of the simulation. Ignored if shots is set
 //!         --statevector      Prints the state vector of the simulation. Ignored if shots is set
+//!         --stats            Prints metrics of the circuit without simulating it: number of qubits and bits, gates after
+//!                            expanding the gate calls, depth, two-qubit gates, T-count, measurements and whether the
+//!                            circuit is dynamic
+//!         --strict           Rejects anything outside the OPENQASM 2.0 specification. Extensions can be enabled one by one
+//!                            with --allow
 //!     -t, --times            Prints times measured for parsing and simulating
+//!         --unitary          Prints the unitary matrix implemented by the program instead of simulating it. The program
+//!                            cannot contain measurements, resets or conditionals
 //!     -V, --version          Prints version information
 //!     -v                     Verbosity of the output
 //!
 //! OPTIONS:
-//!         --info <info>      Show gate-related information
-//!         --out <out>        Output files prefix, print in the stdout if not present. The output format of each file is
-//!                            CSV. At most, three files are created with the names out.memory.csv, out.state.csv and
-//!                            out.times.csv
-//!         --shots <shots>    Specify the number of simulations
+//!         --allow <extension>...           Enables an extension to OPENQASM 2.0. Can be repeated [possible values:
+//!                                          extended-conditionals, gate-modifiers, embedded-libraries, forward-references,
+//!                                          openqasm3, extended-math]
+//!         --endianness <endianness>        Order of the bits and registers when labelling the states by quantum register,
+//!                                          either big (most significant bit and last register first) or little [default:
+//!                                          big]  [possible values: big, little]
+//!         --error-format <error-format>    Format of the errors, either human (with the context of the error in the
+//!                                          source) or json (one JSON object per line and error) [default: human]
+//!                                          [possible values: human, json]
+//!         --info <info>                    Show the documentation and definition of a gate
+//!         --opaque <opaque>                JSON file with the unitary matrices implementing the opaque gates of the
+//!                                          program. It maps each gate name to a list of rows, and each entry is either a
+//!                                          real number or a pair [real, imaginary]
+//!         --out <out>                      Output files prefix, print in the stdout if not present. The output format of
+//!                                          each file is CSV. At most, three files are created with the names
+//!                                          out.memory.csv, out.state.csv and out.times.csv
+//!         --shots <shots>                  Specify the number of simulations
 //!
 //! ARGS:
 //!     <source>    QASM program file, read from stdin if not present
+//!
+//! SUBCOMMANDS:
+//!     draw       Print a text circuit diagram of an OPENQASM 2.0 program, with one wire per qubit and classical bit.
+//!                Language options go before the subcommand
+//!     equiv      Check if two programs implement the same unitary up to a global phase. Exits with 7 if they do not
+//!     flatten    Print an OPENQASM 2.0 program with all its gates inlined and written in a basis, one statement per
+//!                qubit. Language options go before the subcommand
+//!     fmt        Print an OPENQASM 2.0 program formatted in the canonical layout, keeping its comments. Language
+//!                options go before the subcommand
+//!     help       Prints this message or the help of the given subcommand(s)
+//!     lint       Report warnings about suspicious constructions in a program. Language options such as --strict go
+//!                before the subcommand
 //! ```
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -69,17 +105,26 @@ pub mod grammar;
 #[cfg(not(target_arch = "wasm32"))]
 mod linker;
 
+#[cfg(not(target_arch = "wasm32"))]
+pub mod lint;
+
 #[cfg(not(target_arch = "wasm32"))]
 pub mod statevector;
 
 #[cfg(not(target_arch = "wasm32"))]
 pub use crate::{
     arch::native::{
-        get_gate_info, parse_and_link, run, simulate, simulate_with_shots, Execution,
-        ExecutionTimes,
+        check, check_equivalence, draw, extract_unitary, flatten, format_source,
+        get_gate_definition, get_gate_info, get_stats, get_symbols, lint, parse_and_link,
+        parse_and_link_with_options, run, run_with_opaque_gates, run_with_options, simulate,
+        simulate_with_shots, to_qasm, Execution, ExecutionTimes, Symbol, SymbolKind,
     },
     error::QasmSimError,
-    interpreter::{Computation, Histogram},
+    grammar::{Extension, LanguageOptions},
+    interpreter::{
+        Basis, Computation, Equivalence, Histogram, MatrixGate, OpaqueGate, OpaqueGates, Stats,
+        Unitary,
+    },
     semantics::QasmType,
 };
 
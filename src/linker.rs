@@ -9,6 +9,7 @@ use serde::{Deserialize, Serialize};
 use crate::grammar::ast;
 use crate::grammar::lexer::Location;
 use crate::grammar::parse_library;
+use crate::grammar::{is_open_qasm2, Extension, ExtensionError, LanguageOptions};
 
 /// Represent a filure during linkage.
 ///
@@ -43,6 +44,39 @@ pub enum LinkerError {
         /// Library path passed to the `include` directive.
         libpath: String,
     },
+    /// The `include` directive at `location` imports a library which
+    /// requires `extension`.
+    DisabledExtension {
+        /// Location of the `include` directive.
+        location: Location,
+        /// The extension required by the library.
+        extension: Extension,
+    },
+}
+
+impl From<ExtensionError> for LinkerError {
+    fn from(extension_error: ExtensionError) -> Self {
+        match extension_error {
+            ExtensionError::DisabledExtension {
+                location,
+                extension,
+            } => LinkerError::DisabledExtension {
+                location,
+                extension,
+            },
+        }
+    }
+}
+
+/// A gate from a library left out while linking because the program defines
+/// a gate with the same name.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ShadowedGate {
+    /// Name of the gate.
+    pub symbol_name: String,
+    /// Path of the library defining the shadowed gate.
+    pub libpath: String,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -60,7 +94,33 @@ impl Linker {
 
     /// Look into `tree` for `include` statements, parse the referred libraries,
     /// and integrate their ASTs into `tree`, effectively modifying `tree`.
-    pub fn link(&self, mut tree: ast::OpenQasmProgram) -> Result<ast::OpenQasmProgram> {
+    /// In OPENQASM 2 programs, libraries other than `qelib1.inc` are only
+    /// accepted if `options` allows the embedded libraries.
+    pub fn link(
+        &self,
+        tree: ast::OpenQasmProgram,
+        options: &LanguageOptions,
+    ) -> Result<ast::OpenQasmProgram> {
+        self.link_with_shadowing(tree, options)
+            .map(|(tree, _)| tree)
+    }
+
+    /// Like [`link()`] but also return the library gates left out because
+    /// the program defines gates with the same names. The definitions of the
+    /// program shadow those of the libraries.
+    ///
+    /// [`link()`]: #method.link
+    pub fn link_with_shadowing(
+        &self,
+        mut tree: ast::OpenQasmProgram,
+        options: &LanguageOptions,
+    ) -> Result<(ast::OpenQasmProgram, Vec<ShadowedGate>)> {
+        let program_gates: Vec<&str> = tree
+            .program
+            .iter()
+            .filter_map(|span| gate_name(&span.node))
+            .collect();
+        let mut shadowed = vec![];
         let mut to_embed = vec![];
         for (index, span) in tree.program.iter().enumerate() {
             if let ast::Statement::Include(libpath) = &*span.node {
@@ -70,22 +130,33 @@ impl Linker {
                         location: span.boundaries.0,
                         libpath: libpath.into(),
                     })?;
+                if is_open_qasm2(&tree) && libpath != "qelib1.inc" {
+                    options.assert_allows(Extension::EmbeddedLibraries, span.boundaries.0)?;
+                }
                 let library_tree = parse_library(&source).unwrap();
-                to_embed.push((index, span.boundaries, library_tree.definitions));
+                let mut definitions = library_tree.definitions;
+                definitions.retain(|definition| match gate_name(definition) {
+                    Some(name) if program_gates.contains(&name) => {
+                        shadowed.push(ShadowedGate {
+                            symbol_name: name.into(),
+                            libpath: libpath.into(),
+                        });
+                        false
+                    }
+                    _ => true,
+                });
+                to_embed.push((index, span.boundaries, definitions));
             }
         }
         to_embed.reverse();
         for (index, boundaries, statements) in to_embed {
             let mut inner_spans = vec![];
             for one_statement in statements {
-                inner_spans.push(ast::Span {
-                    boundaries,
-                    node: Box::new(one_statement),
-                })
+                inner_spans.push(ast::Span::new(boundaries, one_statement))
             }
             tree.program.splice(index..=index, inner_spans);
         }
-        Ok(tree)
+        Ok((tree, shadowed))
     }
 
     fn sources(&self, libpath: &str) -> std::result::Result<String, ()> {
@@ -96,6 +167,20 @@ impl Linker {
     }
 }
 
+fn gate_name(statement: &ast::Statement) -> Option<&str> {
+    match statement {
+        ast::Statement::GateDecl {
+            signature: (name, _, _, _),
+            ..
+        }
+        | ast::Statement::OpaqueGateDecl {
+            signature: (name, _, _),
+            ..
+        } => Some(name),
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::iter::FromIterator;
@@ -109,10 +194,7 @@ mod tests {
 
     macro_rules! span {
         ($left:expr, $node:expr, $right:expr) => {
-            Span {
-                boundaries: (Location($left), Location($right)),
-                node: Box::new($node),
-            }
+            Span::new((Location($left), Location($right)), $node)
         };
     }
 
@@ -129,7 +211,7 @@ mod tests {
             "gate test () q {}".to_owned(),
         )]));
         let tree = parse_program(source).unwrap();
-        let linked_tree = linker.link(tree).unwrap();
+        let linked_tree = linker.link(tree, &LanguageOptions::new()).unwrap();
         assert_eq!(
             linked_tree,
             ast::OpenQasmProgram {
@@ -145,4 +227,51 @@ mod tests {
             }
         )
     }
+
+    #[test]
+    fn test_program_gates_shadow_library_gates() {
+        let source = indoc!(
+            "
+    OPENQASM 2.0;
+    include \"test.inc\";
+    gate test q {}
+    "
+        );
+        let linker = Linker::with_embedded(HashMap::from_iter(vec![(
+            "test.inc".to_owned(),
+            "gate test () q {} gate other q {}".to_owned(),
+        )]));
+        let tree = parse_program(source).unwrap();
+        let (linked_tree, shadowed) = linker
+            .link_with_shadowing(tree, &LanguageOptions::new())
+            .unwrap();
+        assert_eq!(
+            shadowed,
+            vec![ShadowedGate {
+                symbol_name: "test".into(),
+                libpath: "test.inc".into()
+            }]
+        );
+        assert_eq!(
+            linked_tree.program,
+            vec![
+                span!(
+                    14,
+                    ast::Statement::GateDecl {
+                        signature: ("other".to_owned(), vec![], vec!["q".to_string()], vec![]),
+                        docstring: None
+                    },
+                    33
+                ),
+                span!(
+                    34,
+                    ast::Statement::GateDecl {
+                        signature: ("test".to_owned(), vec![], vec!["q".to_string()], vec![]),
+                        docstring: None
+                    },
+                    48
+                )
+            ]
+        )
+    }
 }
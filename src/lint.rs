@@ -0,0 +1,736 @@
+//! Contain the lints finding suspicious programs. Unlike errors, the warnings
+//! found by the lints do not prevent a program from running.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::error::humanize::{human_warning_description, humanize_warning};
+use crate::error::{extract_statement_lines, Diagnostic, Severity, SrcAndErr};
+use crate::grammar::ast;
+use crate::grammar::lexer::Location;
+use crate::linker::ShadowedGate;
+
+/// Suspicious constructions found by the linter.
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Lint {
+    /// A register is declared but never used.
+    UnusedRegister {
+        /// Name of the register.
+        symbol_name: String,
+    },
+    /// A gate is defined by the program but never called.
+    UnusedGate {
+        /// Name of the gate.
+        symbol_name: String,
+    },
+    /// A measurement is stored in a classical bit which is written again
+    /// before being read.
+    OverwrittenMeasurement {
+        /// Name of the classical register.
+        symbol_name: String,
+        /// Index of the overwritten bit.
+        index: usize,
+    },
+    /// A gate is applied to qubits after their last measurement. Conditional
+    /// gates are not reported since they feed the measurements forward.
+    GateAfterMeasurement {
+        /// Name of the gate.
+        symbol_name: String,
+    },
+    /// A barrier refers to a register which is not declared.
+    UndeclaredBarrierRegister {
+        /// Name of the register.
+        symbol_name: String,
+    },
+    /// A gate defined by the program shadows the gate with the same name of
+    /// an included library.
+    ShadowedGate {
+        /// Name of the gate.
+        symbol_name: String,
+        /// Path of the library defining the shadowed gate.
+        libpath: String,
+    },
+    /// A conditional tests a register which is never written before.
+    UnwrittenCondition {
+        /// Name of the register.
+        symbol_name: String,
+    },
+}
+
+impl Lint {
+    /// Return the name of the lint.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Lint::UnusedRegister { .. } => "UnusedRegister",
+            Lint::UnusedGate { .. } => "UnusedGate",
+            Lint::OverwrittenMeasurement { .. } => "OverwrittenMeasurement",
+            Lint::GateAfterMeasurement { .. } => "GateAfterMeasurement",
+            Lint::UndeclaredBarrierRegister { .. } => "UndeclaredBarrierRegister",
+            Lint::ShadowedGate { .. } => "ShadowedGate",
+            Lint::UnwrittenCondition { .. } => "UnwrittenCondition",
+        }
+    }
+}
+
+/// A lint found in the source code, with the information needed for
+/// pointing at it.
+///
+/// `Warning` instances can be printed like [`QasmSimError`] instances.
+///
+/// [`QasmSimError`]: ../error/enum.QasmSimError.html
+///
+/// # Examples
+///
+/// ```
+/// use qasmsim::{lint, LanguageOptions};
+/// use qasmsim::lint::Lint;
+///
+/// let warnings = lint(r#"OPENQASM 2.0;
+/// qreg q[2];
+/// qreg r[2];
+/// U(0, 0, 0) q;
+/// "#, &LanguageOptions::new())?;
+///
+/// assert_eq!(warnings.len(), 1);
+/// assert_eq!(warnings[0].lint, Lint::UnusedRegister { symbol_name: "r".into() });
+/// assert_eq!(format!("{}", warnings[0]), r#"warning: register `r` is never used
+///   |
+/// 3 | qreg r[2];
+///   | ^^^^^^^^^^ help: consider removing the declaration
+/// "#);
+/// # Ok::<(), qasmsim::QasmSimError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Warning<'src> {
+    /// Source of the lines from the start of the statement to the line of
+    /// the warning.
+    pub source: &'src str,
+    /// Line number.
+    pub lineno: usize,
+    /// Start position inside the line.
+    pub startpos: usize,
+    /// End position inside the line, if known.
+    pub endpos: Option<usize>,
+    /// The lint found.
+    pub lint: Lint,
+}
+
+impl Warning<'_> {
+    /// Return the diagnostic describing the warning.
+    pub fn diagnostic(&self) -> Diagnostic {
+        let description = human_warning_description(self);
+        Diagnostic {
+            kind: self.lint.kind().into(),
+            severity: Severity::Warning,
+            stage: None,
+            message: description.msg,
+            file: None,
+            line: Some(description.lineno),
+            column: Some(description.startpos + 1),
+            end_column: description.endpos.map(|endpos| endpos + 1),
+            expected: vec![],
+            hint: description.help,
+        }
+    }
+}
+
+impl fmt::Display for Warning<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut buffer = String::new();
+        humanize_warning(&mut buffer, self)?;
+        write!(f, "{}", buffer)
+    }
+}
+
+/// A lint found in the AST, located by the statement and, optionally, the
+/// boundaries of the offending part.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct LocatedLint {
+    pub lint: Lint,
+    pub location: Location,
+    pub boundaries: Option<(Location, Location)>,
+}
+
+impl<'src> From<SrcAndErr<'src, LocatedLint>> for Warning<'src> {
+    fn from(source_and_lint: SrcAndErr<'src, LocatedLint>) -> Self {
+        let (
+            input,
+            LocatedLint {
+                lint,
+                location,
+                boundaries,
+            },
+        ) = source_and_lint;
+        let (source, lineno, startpos, endpos) =
+            extract_statement_lines(location.0, boundaries, input);
+        Warning {
+            source,
+            lineno,
+            startpos,
+            endpos,
+            lint,
+        }
+    }
+}
+
+/// Return the lints found in `program`. The program is also needed linked in
+/// `linked`, along with the library gates it `shadowed` when linking.
+pub(crate) fn lint(
+    program: &ast::OpenQasmProgram,
+    linked: &ast::OpenQasmProgram,
+    shadowed: &[ShadowedGate],
+) -> Vec<LocatedLint> {
+    let mut linter = Linter::new(&program.program);
+    linter.check_statements(&program.program, false);
+    linter.check_gates_after_measurement();
+    linter.check_declarations(&program.program, &called_gates(linked), shadowed);
+    let mut lints = linter.lints;
+    lints.sort_by_key(|lint| (lint.location, lint.boundaries));
+    lints
+}
+
+type Bit = (String, usize);
+
+type Boundaries = Option<(Location, Location)>;
+
+#[derive(Debug, Clone, PartialEq, Default)]
+struct Linter {
+    lints: Vec<LocatedLint>,
+    register_sizes: HashMap<String, usize>,
+    used_registers: HashSet<String>,
+    written_registers: HashSet<String>,
+    /// Classical bits holding a measurement not read yet, along with where
+    /// the measurement happens.
+    unread_bits: HashMap<Bit, (Location, Boundaries)>,
+    /// Number of the last measurement of each qubit.
+    last_measurements: HashMap<Bit, usize>,
+    measurement_count: usize,
+    /// Gates applied once all their qubits are measured, along with the
+    /// number of the last measurement of each qubit at that moment.
+    gates_after_measurement: Vec<(LocatedLint, Vec<(Bit, usize)>)>,
+}
+
+impl Linter {
+    fn new(statements: &[ast::Span<ast::Statement>]) -> Self {
+        let register_sizes = statements
+            .iter()
+            .filter_map(|span| match &*span.node {
+                ast::Statement::QRegDecl(name, size) | ast::Statement::CRegDecl(name, size) => {
+                    Some((name.clone(), *size))
+                }
+                _ => None,
+            })
+            .collect();
+        Linter {
+            register_sizes,
+            ..Default::default()
+        }
+    }
+
+    fn check_statements(&mut self, statements: &[ast::Span<ast::Statement>], conditional: bool) {
+        for span in statements {
+            let location = span.boundaries.0;
+            match &*span.node {
+                ast::Statement::Barrier(ast::BarrierPragma(args)) => {
+                    for (argument, boundaries) in args.iter().zip(parts(&span.arguments)) {
+                        let symbol_name = register_name(argument);
+                        if self.register_sizes.contains_key(symbol_name) {
+                            self.used_registers.insert(symbol_name.into());
+                        } else {
+                            self.lints.push(LocatedLint {
+                                lint: Lint::UndeclaredBarrierRegister {
+                                    symbol_name: symbol_name.into(),
+                                },
+                                location,
+                                boundaries,
+                            });
+                        }
+                    }
+                }
+                ast::Statement::QuantumOperation(operation) => {
+                    self.check_operation(span, operation, 0, conditional)
+                }
+                ast::Statement::Conditional(register, _, operation) => {
+                    self.read(register, location, parts(&span.arguments).next().flatten());
+                    self.check_operation(span, operation, 1, true);
+                }
+                ast::Statement::IfElse {
+                    condition,
+                    then_branch,
+                    else_branch,
+                } => {
+                    for (ast::Comparison(register, _, _), boundaries) in
+                        condition.0.iter().zip(parts(&span.arguments))
+                    {
+                        self.read(register, location, boundaries);
+                    }
+                    self.check_statements(then_branch, true);
+                    self.check_statements(else_branch, true);
+                }
+                _ => (),
+            }
+        }
+    }
+
+    fn check_operation(
+        &mut self,
+        span: &ast::Span<ast::Statement>,
+        operation: &ast::QuantumOperation,
+        first_argument: usize,
+        conditional: bool,
+    ) {
+        let location = span.boundaries.0;
+        match operation {
+            ast::QuantumOperation::Unitary(ast::UnitaryOperation(name, _, args))
+            | ast::QuantumOperation::Modified(_, ast::UnitaryOperation(name, _, args)) => {
+                let qubits: Vec<Bit> = args.iter().flat_map(|arg| self.use_bits(arg)).collect();
+                if conditional {
+                    return;
+                }
+                let measurements: Option<Vec<(Bit, usize)>> = qubits
+                    .into_iter()
+                    .map(|qubit| {
+                        let measurement = *self.last_measurements.get(&qubit)?;
+                        Some((qubit, measurement))
+                    })
+                    .collect();
+                if let Some(measurements) = measurements.filter(|m| !m.is_empty()) {
+                    self.gates_after_measurement.push((
+                        LocatedLint {
+                            lint: Lint::GateAfterMeasurement {
+                                symbol_name: name.clone(),
+                            },
+                            location,
+                            boundaries: Some(span.boundaries),
+                        },
+                        measurements,
+                    ));
+                }
+            }
+            ast::QuantumOperation::Measure(source, target) => {
+                let target_boundaries = parts(&span.arguments).nth(first_argument + 1).flatten();
+                let qubits = self.use_bits(source);
+                let bits = self.use_bits(target);
+                self.written_registers.insert(register_name(target).into());
+                for (qubit, bit) in qubits.into_iter().zip(bits) {
+                    self.last_measurements.insert(qubit, self.measurement_count);
+                    self.measurement_count += 1;
+                    if conditional {
+                        self.unread_bits.remove(&bit);
+                        continue;
+                    }
+                    let measurement = (location, target_boundaries);
+                    if let Some((location, boundaries)) =
+                        self.unread_bits.insert(bit.clone(), measurement)
+                    {
+                        self.lints.push(LocatedLint {
+                            lint: Lint::OverwrittenMeasurement {
+                                symbol_name: bit.0,
+                                index: bit.1,
+                            },
+                            location,
+                            boundaries,
+                        });
+                    }
+                }
+            }
+            ast::QuantumOperation::Reset(target) => {
+                self.use_bits(target);
+            }
+        }
+    }
+
+    fn read(&mut self, register: &ast::Argument, location: Location, boundaries: Boundaries) {
+        let symbol_name = register_name(register);
+        if !self.written_registers.contains(symbol_name) {
+            self.lints.push(LocatedLint {
+                lint: Lint::UnwrittenCondition {
+                    symbol_name: symbol_name.into(),
+                },
+                location,
+                boundaries,
+            });
+        }
+        for bit in self.use_bits(register) {
+            self.unread_bits.remove(&bit);
+        }
+    }
+
+    /// Mark the register of `argument` as used and return its bits.
+    fn use_bits(&mut self, argument: &ast::Argument) -> Vec<Bit> {
+        let symbol_name = register_name(argument);
+        self.used_registers.insert(symbol_name.into());
+        match argument {
+            ast::Argument::Item(_, index) => vec![(symbol_name.into(), *index)],
+            ast::Argument::Id(_) => {
+                let size = self.register_sizes.get(symbol_name).copied().unwrap_or(0);
+                (0..size).map(|index| (symbol_name.into(), index)).collect()
+            }
+        }
+    }
+
+    fn check_gates_after_measurement(&mut self) {
+        for (lint, measurements) in std::mem::take(&mut self.gates_after_measurement) {
+            let measured_again = measurements
+                .iter()
+                .any(|(qubit, measurement)| self.last_measurements[qubit] != *measurement);
+            if !measured_again {
+                self.lints.push(lint);
+            }
+        }
+    }
+
+    fn check_declarations(
+        &mut self,
+        statements: &[ast::Span<ast::Statement>],
+        called_gates: &HashSet<String>,
+        shadowed: &[ShadowedGate],
+    ) {
+        for span in statements {
+            let lint = match &*span.node {
+                ast::Statement::QRegDecl(name, _) | ast::Statement::CRegDecl(name, _)
+                    if !self.used_registers.contains(name) =>
+                {
+                    Lint::UnusedRegister {
+                        symbol_name: name.clone(),
+                    }
+                }
+                ast::Statement::GateDecl {
+                    signature: (name, _, _, _),
+                    ..
+                }
+                | ast::Statement::OpaqueGateDecl {
+                    signature: (name, _, _),
+                    ..
+                } => {
+                    if let Some(shadowed_gate) = shadowed
+                        .iter()
+                        .find(|shadowed_gate| &shadowed_gate.symbol_name == name)
+                    {
+                        self.lints.push(LocatedLint {
+                            lint: Lint::ShadowedGate {
+                                symbol_name: name.clone(),
+                                libpath: shadowed_gate.libpath.clone(),
+                            },
+                            location: span.boundaries.0,
+                            boundaries: Some(span.boundaries),
+                        });
+                    }
+                    if called_gates.contains(name) {
+                        continue;
+                    }
+                    Lint::UnusedGate {
+                        symbol_name: name.clone(),
+                    }
+                }
+                _ => continue,
+            };
+            self.lints.push(LocatedLint {
+                lint,
+                location: span.boundaries.0,
+                boundaries: Some(span.boundaries),
+            });
+        }
+    }
+}
+
+/// Return the names of the gates called in the statements of `program`,
+/// including the calls inside gate definitions.
+fn called_gates(program: &ast::OpenQasmProgram) -> HashSet<String> {
+    fn collect(statements: &[ast::Span<ast::Statement>], called: &mut HashSet<String>) {
+        for span in statements {
+            match &*span.node {
+                ast::Statement::GateDecl {
+                    signature: (_, _, _, operations),
+                    ..
+                } => {
+                    for operation in operations {
+                        if let ast::GateOperation::Unitary(ast::UnitaryOperation(name, _, _))
+                        | ast::GateOperation::Modified(_, ast::UnitaryOperation(name, _, _)) =
+                            operation
+                        {
+                            called.insert(name.clone());
+                        }
+                    }
+                }
+                ast::Statement::QuantumOperation(operation)
+                | ast::Statement::Conditional(_, _, operation) => {
+                    if let ast::QuantumOperation::Unitary(ast::UnitaryOperation(name, _, _))
+                    | ast::QuantumOperation::Modified(_, ast::UnitaryOperation(name, _, _)) =
+                        operation
+                    {
+                        called.insert(name.clone());
+                    }
+                }
+                ast::Statement::IfElse {
+                    then_branch,
+                    else_branch,
+                    ..
+                } => {
+                    collect(then_branch, called);
+                    collect(else_branch, called);
+                }
+                _ => (),
+            }
+        }
+    }
+
+    let mut called = HashSet::new();
+    collect(&program.program, &mut called);
+    called
+}
+
+/// Iterate over the boundaries of the parts of a statement, yielding `None`
+/// forever once they are exhausted, as statements built programmatically
+/// may lack them.
+fn parts(boundaries: &[(Location, Location)]) -> impl Iterator<Item = Boundaries> + '_ {
+    boundaries
+        .iter()
+        .copied()
+        .map(Some)
+        .chain(std::iter::repeat(None))
+}
+
+fn register_name(argument: &ast::Argument) -> &str {
+    match argument {
+        ast::Argument::Id(name) | ast::Argument::Item(name, _) => name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::*;
+    use crate::api::default_linker;
+    use crate::grammar::parse_program;
+    use crate::grammar::LanguageOptions;
+
+    fn lint_source(source: &str) -> Vec<(Lint, usize)> {
+        let program = parse_program(source).unwrap();
+        let (linked, shadowed) = default_linker()
+            .link_with_shadowing(program.clone(), &LanguageOptions::new())
+            .unwrap();
+        lint(&program, &linked, &shadowed)
+            .into_iter()
+            .map(|located| {
+                let warning = Warning::from((source, located));
+                (warning.lint, warning.lineno)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_programs_have_no_warnings() {
+        let source = indoc!(
+            "
+        OPENQASM 2.0;
+        include \"qelib1.inc\";
+        qreg q[2];
+        creg c[2];
+        h q[0];
+        cx q[0], q[1];
+        measure q[0] -> c[0];
+        if (c == 1) x q[1];
+        measure q[1] -> c[1];
+        "
+        );
+        assert_eq!(lint_source(source), vec![]);
+    }
+
+    #[test]
+    fn test_unused_declarations() {
+        let source = indoc!(
+            "
+        OPENQASM 2.0;
+        qreg q[2];
+        qreg r[2];
+        creg c[2];
+        gate unused a { U(0, 0, 0) a; }
+        opaque magic a;
+        U(0, 0, 0) q;
+        "
+        );
+        assert_eq!(
+            lint_source(source),
+            vec![
+                (
+                    Lint::UnusedRegister {
+                        symbol_name: "r".into()
+                    },
+                    3
+                ),
+                (
+                    Lint::UnusedRegister {
+                        symbol_name: "c".into()
+                    },
+                    4
+                ),
+                (
+                    Lint::UnusedGate {
+                        symbol_name: "unused".into()
+                    },
+                    5
+                ),
+                (
+                    Lint::UnusedGate {
+                        symbol_name: "magic".into()
+                    },
+                    6
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_overwritten_measurements() {
+        let source = indoc!(
+            "
+        OPENQASM 2.0;
+        qreg q[2];
+        creg c[2];
+        measure q[0] -> c[0];
+        measure q[1] -> c[0];
+        if (c == 1) U(0, 0, 0) q[0];
+        measure q[0] -> c[0];
+        "
+        );
+        assert_eq!(
+            lint_source(source),
+            vec![(
+                Lint::OverwrittenMeasurement {
+                    symbol_name: "c".into(),
+                    index: 0
+                },
+                4
+            )]
+        );
+    }
+
+    #[test]
+    fn test_gates_after_the_final_measurement() {
+        let source = indoc!(
+            "
+        OPENQASM 2.0;
+        include \"qelib1.inc\";
+        qreg q[2];
+        creg c[2];
+        creg d[1];
+        measure q -> c;
+        x q[0];
+        cx q[0], q[1];
+        h q[1];
+        measure q[1] -> d[0];
+        "
+        );
+        assert_eq!(
+            lint_source(source),
+            vec![(
+                Lint::GateAfterMeasurement {
+                    symbol_name: "x".into()
+                },
+                7
+            )]
+        );
+    }
+
+    #[test]
+    fn test_conditional_gates_after_the_final_measurement() {
+        let source = indoc!(
+            "
+        OPENQASM 2.0;
+        include \"qelib1.inc\";
+        qreg q[2];
+        creg c[2];
+        creg d[1];
+        measure q -> c;
+        if (c == 1) x q[1];
+        if (d == 1) x q[1];
+        "
+        );
+        assert_eq!(
+            lint_source(source),
+            vec![(
+                Lint::UnwrittenCondition {
+                    symbol_name: "d".into()
+                },
+                8
+            )]
+        );
+    }
+
+    #[test]
+    fn test_barriers_on_undeclared_registers() {
+        let source = indoc!(
+            "
+        OPENQASM 2.0;
+        qreg q[2];
+        U(0, 0, 0) q;
+        barrier q, r;
+        "
+        );
+        assert_eq!(
+            lint_source(source),
+            vec![(
+                Lint::UndeclaredBarrierRegister {
+                    symbol_name: "r".into()
+                },
+                4
+            )]
+        );
+    }
+
+    #[test]
+    fn test_shadowed_library_gates() {
+        let source = indoc!(
+            "
+        OPENQASM 2.0;
+        include \"qelib1.inc\";
+        qreg q[1];
+        gate h a { U(pi/2, 0, pi) a; }
+        h q[0];
+        "
+        );
+        assert_eq!(
+            lint_source(source),
+            vec![(
+                Lint::ShadowedGate {
+                    symbol_name: "h".into(),
+                    libpath: "qelib1.inc".into()
+                },
+                4
+            )]
+        );
+    }
+
+    #[test]
+    fn test_conditionals_on_unwritten_registers() {
+        let source = indoc!(
+            "
+        OPENQASM 2.0;
+        qreg q[1];
+        creg c[1];
+        creg d[1];
+        if (c == 1) U(0, 0, 0) q[0];
+        measure q[0] -> d[0];
+        if (d == 1) U(0, 0, 0) q[0];
+        measure q[0] -> d[0];
+        "
+        );
+        assert_eq!(
+            lint_source(source),
+            vec![(
+                Lint::UnwrittenCondition {
+                    symbol_name: "c".into()
+                },
+                5
+            )]
+        );
+    }
+}
@@ -142,3 +142,130 @@ gate rzz(theta) a,b
   cx a,b;
 }
 "#;
+
+pub const STDGATES: &str = r#"
+// OpenQASM 3 standard gate library
+// file: stdgates.inc
+//
+// Gates are defined in terms of `U` and `CX`. Definitions relying on
+// `gphase` in the reference library differ from them in a global phase.
+
+// phase gate
+gate p(lambda) a { U(0,0,lambda) a; }
+// Pauli gate: bit-flip
+gate x a { U(pi,0,pi) a; }
+// Pauli gate: bit and phase flip
+gate y a { U(pi,pi/2,pi/2) a; }
+// Pauli gate: phase flip
+gate z a { p(pi) a; }
+// Clifford gate: Hadamard
+gate h a { U(pi/2,0,pi) a; }
+// Clifford gate: sqrt(Z) or S gate
+gate s a { p(pi/2) a; }
+// Clifford gate: inverse of sqrt(Z)
+gate sdg a { p(-pi/2) a; }
+// sqrt(S) or T gate
+gate t a { p(pi/4) a; }
+// inverse of sqrt(S)
+gate tdg a { p(-pi/4) a; }
+// sqrt(NOT) gate
+gate sx a { sdg a; h a; sdg a; }
+// Rotation around X-axis
+gate rx(theta) a { U(theta,-pi/2,pi/2) a; }
+// rotation around Y-axis
+gate ry(theta) a { U(theta,0,0) a; }
+// rotation around Z axis
+gate rz(lambda) a { p(lambda) a; }
+// controlled-NOT
+gate cx c,t { CX c,t; }
+// controlled-Y
+gate cy a,b { sdg b; cx a,b; s b; }
+// controlled-Z
+gate cz a,b { h b; cx a,b; h b; }
+// controlled-phase
+gate cp(lambda) a,b
+{
+  p(lambda/2) a;
+  cx a,b;
+  p(-lambda/2) b;
+  cx a,b;
+  p(lambda/2) b;
+}
+// controlled rx rotation
+gate crx(theta) a,b
+{
+  p(pi/2) b;
+  cx a,b;
+  U(-theta/2,0,0) b;
+  cx a,b;
+  U(theta/2,-pi/2,0) b;
+}
+// controlled ry rotation
+gate cry(theta) a,b
+{
+  ry(theta/2) b;
+  cx a,b;
+  ry(-theta/2) b;
+  cx a,b;
+}
+// controlled rz rotation
+gate crz(theta) a,b
+{
+  rz(theta/2) b;
+  cx a,b;
+  rz(-theta/2) b;
+  cx a,b;
+}
+// controlled-H
+gate ch a,b {
+h b; sdg b;
+cx a,b;
+h b; t b;
+cx a,b;
+t b; h b; s b; x b; s a;
+}
+// swap
+gate swap a,b { cx a,b; cx b,a; cx a,b; }
+// Toffoli
+gate ccx a,b,c
+{
+  h c;
+  cx b,c; tdg c;
+  cx a,c; t c;
+  cx b,c; tdg c;
+  cx a,c; t b; t c; h c;
+  cx a,b; t a; tdg b;
+  cx a,b;
+}
+// controlled-swap (Fredkin)
+gate cswap a,b,c
+{
+  cx c,b;
+  ccx a,b,c;
+  cx c,b;
+}
+// controlled-U with an extra phase on the control
+gate cu(theta,phi,lambda,gamma) c,t
+{
+  p(gamma) c;
+  p((lambda+phi)/2) c;
+  p((lambda-phi)/2) t;
+  cx c,t;
+  U(-theta/2,0,-(phi+lambda)/2) t;
+  cx c,t;
+  U(theta/2,phi,0) t;
+}
+
+// --- Gates for backwards compatibility ---
+
+// phase gate
+gate phase(lambda) q { U(0,0,lambda) q; }
+// controlled-phase
+gate cphase(lambda) a,b { cp(lambda) a,b; }
+// identity or idle gate
+gate id a { U(0,0,0) a; }
+// IBM Quantum Experience gates
+gate u1(lambda) q { U(0,0,lambda) q; }
+gate u2(phi,lambda) q { U(pi/2,phi,lambda) q; }
+gate u3(theta,phi,lambda) q { U(theta,phi,lambda) q; }
+"#;
@@ -7,6 +7,8 @@ use serde::{Deserialize, Serialize};
 
 use crate::grammar::ast;
 use crate::grammar::lexer::Location;
+use crate::grammar::{is_open_qasm2, Extension, ExtensionError, LanguageOptions};
+use crate::statevector::QuantumRegister;
 
 /// The different types for OPENQASM values.
 #[non_exhaustive]
@@ -59,12 +61,20 @@ pub enum SemanticError {
         /// Location of the original definition.
         previous_location: Location,
     },
+    /// Use of a construct which requires an extension not enabled.
+    DisabledExtension {
+        /// Location of the construct.
+        location: Location,
+        /// The extension required by the construct.
+        extension: Extension,
+    },
 }
 
 impl fmt::Display for SemanticError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let message = match lazy_humanize! {
             self,
+            SemanticError::DisabledExtension,
             SemanticError::RedefinitionError
         } {
             Some(message) => message,
@@ -76,6 +86,20 @@ impl fmt::Display for SemanticError {
 
 impl error::Error for SemanticError {}
 
+impl From<ExtensionError> for SemanticError {
+    fn from(extension_error: ExtensionError) -> Self {
+        match extension_error {
+            ExtensionError::DisabledExtension {
+                location,
+                extension,
+            } => SemanticError::DisabledExtension {
+                location,
+                extension,
+            },
+        }
+    }
+}
+
 type Result<T> = std::result::Result<T, SemanticError>;
 
 /// Register name, type, size and definition location.
@@ -86,7 +110,8 @@ pub struct RegisterEntry(pub String, pub RegisterType, pub usize, pub Location);
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct MemoryMapEntry(pub String, pub usize, pub usize);
 
-/// Macro name, real arguments, register arguments, list of statements and definition location.
+/// Macro name, real arguments, register arguments, list of statements,
+/// definition location and spans of the statements, if known.
 #[derive(Debug, Clone, PartialEq)]
 pub struct MacroDefinition(
     pub String,
@@ -94,11 +119,17 @@ pub struct MacroDefinition(
     pub Vec<String>,
     pub Vec<ast::GateOperation>,
     pub Location,
+    pub Vec<ast::Span<()>>,
 );
 
+/// Opaque gate name, real arguments, register arguments and definition location.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct OpaqueDefinition(pub String, pub Vec<String>, pub Vec<String>, pub Location);
+
 #[derive(Debug, Clone, PartialEq, Default)]
 pub struct Semantics {
     pub macro_definitions: HashMap<String, MacroDefinition>,
+    pub opaque_definitions: HashMap<String, OpaqueDefinition>,
     pub symbol_docstrings: HashMap<String, String>,
     pub register_table: HashMap<String, RegisterEntry>,
     /// Map quantum registers to a unique unified register while classical
@@ -108,11 +139,33 @@ pub struct Semantics {
     pub classical_memory_size: usize,
 }
 
+impl Semantics {
+    /// Return the quantum registers sorted by their position in the
+    /// state-vector.
+    pub fn quantum_registers(&self) -> Vec<QuantumRegister> {
+        let mut registers: Vec<QuantumRegister> = self
+            .register_table
+            .values()
+            .filter(|entry| entry.1 == RegisterType::Q)
+            .map(|entry| {
+                let mapping = self
+                    .memory_map
+                    .get(&entry.0)
+                    .expect("all registers are mapped");
+                QuantumRegister::new(entry.0.clone(), mapping.1, entry.2)
+            })
+            .collect();
+        registers.sort_by_key(|register| register.start());
+        registers
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Default)]
 struct SemanticsBuilder {
     semantics: Semantics,
     last_quantum_register: Option<String>,
     last_classical_register: Option<String>,
+    undeclared_calls: Vec<(String, Location)>,
 }
 
 impl SemanticsBuilder {
@@ -153,24 +206,76 @@ impl SemanticsBuilder {
         args: Vec<String>,
         body: Vec<ast::GateOperation>,
         location: Location,
+        operation_spans: Vec<ast::Span<()>>,
     ) -> Result<()> {
-        let entry = self.semantics.macro_definitions.get(&name);
-        if let Some(MacroDefinition(_, _, _, _, previous_location)) = entry {
-            return Err(SemanticError::RedefinitionError {
-                symbol_name: name,
-                location,
-                previous_location: *previous_location,
-            });
-        }
-
+        self.assert_gate_is_not_defined(&name, location)?;
+        // The body is expanded on every call, so fold its constant parameters
+        // only once.
+        let body = body.iter().map(ast::GateOperation::simplify).collect();
         self.semantics.macro_definitions.insert(
             name.clone(),
-            MacroDefinition(name, real_args, args, body, location),
+            MacroDefinition(name, real_args, args, body, location, operation_spans),
+        );
+
+        Ok(())
+    }
+
+    pub fn new_opaque_gate(
+        &mut self,
+        name: String,
+        real_args: Vec<String>,
+        args: Vec<String>,
+        location: Location,
+    ) -> Result<()> {
+        self.assert_gate_is_not_defined(&name, location)?;
+        self.semantics.opaque_definitions.insert(
+            name.clone(),
+            OpaqueDefinition(name, real_args, args, location),
         );
 
         Ok(())
     }
 
+    fn assert_gate_is_not_defined(&self, name: &str, location: Location) -> Result<()> {
+        let previous_location = match (
+            self.semantics.macro_definitions.get(name),
+            self.semantics.opaque_definitions.get(name),
+        ) {
+            (Some(MacroDefinition(_, _, _, _, previous_location, _)), _) => previous_location,
+            (_, Some(OpaqueDefinition(_, _, _, previous_location))) => previous_location,
+            _ => return Ok(()),
+        };
+        Err(SemanticError::RedefinitionError {
+            symbol_name: name.into(),
+            location,
+            previous_location: *previous_location,
+        })
+    }
+
+    /// Register a call to the gate `name`, remembering it if the gate is
+    /// not declared yet.
+    pub fn call_gate(&mut self, name: &str, location: Location) {
+        let is_declared = name == "U"
+            || name == "CX"
+            || self.semantics.macro_definitions.contains_key(name)
+            || self.semantics.opaque_definitions.contains_key(name);
+        if !is_declared {
+            self.undeclared_calls.push((name.into(), location));
+        }
+    }
+
+    /// Return the location of the first call to a gate declared after the
+    /// call.
+    pub fn first_forward_reference(&self) -> Option<Location> {
+        self.undeclared_calls
+            .iter()
+            .find(|(name, _)| {
+                self.semantics.macro_definitions.contains_key(name)
+                    || self.semantics.opaque_definitions.contains_key(name)
+            })
+            .map(|(_, location)| *location)
+    }
+
     pub fn update_docstring(&mut self, symbol_name: String, docstring: String) {
         self.semantics
             .symbol_docstrings
@@ -230,16 +335,50 @@ impl SemanticsBuilder {
     }
 }
 
-pub fn extract_semantics(tree: &ast::OpenQasmProgram) -> Result<Semantics> {
+pub fn extract_semantics(
+    tree: &ast::OpenQasmProgram,
+    options: &LanguageOptions,
+) -> Result<Semantics> {
+    let (semantics, mut errors) = extract_semantics_and_errors(tree, options);
+    if errors.is_empty() {
+        Ok(semantics)
+    } else {
+        Err(errors.remove(0))
+    }
+}
+
+/// Like [`extract_semantics()`] but, instead of stopping at the first error,
+/// collect all of them along with the semantics of the valid declarations.
+pub(crate) fn extract_semantics_and_errors(
+    tree: &ast::OpenQasmProgram,
+    options: &LanguageOptions,
+) -> (Semantics, Vec<SemanticError>) {
     let mut builder = SemanticsBuilder::new();
-    for span in &tree.program {
+    let mut errors = Vec::new();
+    extract_statements(&mut builder, &tree.program, &mut errors);
+    if is_open_qasm2(tree) {
+        if let Some(location) = builder.first_forward_reference() {
+            if let Err(error) = options.assert_allows(Extension::ForwardReferences, location) {
+                errors.push(error.into());
+            }
+        }
+    }
+    (builder.semantics, errors)
+}
+
+fn extract_statements(
+    builder: &mut SemanticsBuilder,
+    statements: &[ast::Span<ast::Statement>],
+    errors: &mut Vec<SemanticError>,
+) {
+    for span in statements {
         let location = span.boundaries.0;
-        match &*span.node {
+        let result = match &*span.node {
             ast::Statement::QRegDecl(name, size) => {
-                builder.new_quantum_register(name.clone(), *size, location)?
+                builder.new_quantum_register(name.clone(), *size, location)
             }
             ast::Statement::CRegDecl(name, size) => {
-                builder.new_classical_register(name.clone(), *size, location)?
+                builder.new_classical_register(name.clone(), *size, location)
             }
             ast::Statement::GateDecl {
                 signature: (name, real_args, args, operations),
@@ -248,19 +387,57 @@ pub fn extract_semantics(tree: &ast::OpenQasmProgram) -> Result<Semantics> {
                 if let Some(docstring_content) = docstring {
                     builder.update_docstring(name.clone(), docstring_content.clone());
                 }
+                for operation in operations {
+                    if let ast::GateOperation::Unitary(ast::UnitaryOperation(callee, _, _))
+                    | ast::GateOperation::Modified(_, ast::UnitaryOperation(callee, _, _)) =
+                        operation
+                    {
+                        builder.call_gate(callee, location);
+                    }
+                }
                 builder.new_gate(
                     name.clone(),
                     real_args.to_vec(),
                     args.to_vec(),
                     operations.to_vec(),
                     location,
-                )?
+                    span.operations.clone(),
+                )
+            }
+            ast::Statement::OpaqueGateDecl {
+                signature: (name, real_args, args),
+                docstring,
+            } => {
+                if let Some(docstring_content) = docstring {
+                    builder.update_docstring(name.clone(), docstring_content.clone());
+                }
+                builder.new_opaque_gate(name.clone(), real_args.to_vec(), args.to_vec(), location)
+            }
+            ast::Statement::QuantumOperation(operation)
+            | ast::Statement::Conditional(_, _, operation) => {
+                if let ast::QuantumOperation::Unitary(ast::UnitaryOperation(callee, _, _))
+                | ast::QuantumOperation::Modified(_, ast::UnitaryOperation(callee, _, _)) =
+                    operation
+                {
+                    builder.call_gate(callee, location);
+                }
+                Ok(())
+            }
+            ast::Statement::IfElse {
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                extract_statements(builder, then_branch, errors);
+                extract_statements(builder, else_branch, errors);
+                Ok(())
             }
-            // TODO: What to do with opaque gates?
-            _ => (),
+            _ => Ok(()),
+        };
+        if let Err(error) = result {
+            errors.push(error);
         }
     }
-    Ok(builder.semantics)
 }
 
 #[cfg(test)]
@@ -284,7 +461,7 @@ mod test {
     "
         );
         let tree = parse_program(source).unwrap();
-        let semantics_result = extract_semantics(&tree);
+        let semantics_result = extract_semantics(&tree, &LanguageOptions::new());
         assert!(semantics_result.is_ok());
 
         let expected_register_table = HashMap::from_iter(vec![
@@ -320,7 +497,7 @@ mod test {
     creg d[10];
     ";
         let tree = parse_program(source).unwrap();
-        let semantics_result = extract_semantics(&tree);
+        let semantics_result = extract_semantics(&tree, &LanguageOptions::new());
         assert!(semantics_result.is_ok());
         if let Ok(semantics) = semantics_result {
             assert_eq!(semantics.quantum_memory_size, 12);
@@ -337,7 +514,7 @@ mod test {
     creg d[10];
     ";
         let tree = parse_program(source).unwrap();
-        let semantics_result = extract_semantics(&tree);
+        let semantics_result = extract_semantics(&tree, &LanguageOptions::new());
         assert!(semantics_result.is_ok());
         if let Ok(semantics) = semantics_result {
             assert_eq!(semantics.classical_memory_size, 12);
@@ -346,7 +523,7 @@ mod test {
 
     #[test]
     fn test_cannot_redeclare_a_register() {
-        let sources = vec![
+        let sources = [
             indoc!(
                 "
       OPENQASM 2.0;
@@ -392,7 +569,8 @@ mod test {
         ];
         for (index, source) in sources.iter().enumerate() {
             let tree = parse_program(source).unwrap();
-            let error = extract_semantics(&tree).expect_err("should be a redeclaration error");
+            let error = extract_semantics(&tree, &LanguageOptions::new())
+                .expect_err("should be a redeclaration error");
             println!("Using source sample #{}", index);
             assert_eq!(
                 error,
@@ -415,7 +593,7 @@ mod test {
     creg d[10];
     ";
         let tree = parse_program(source).unwrap();
-        let semantics_result = extract_semantics(&tree);
+        let semantics_result = extract_semantics(&tree, &LanguageOptions::new());
         assert!(semantics_result.is_ok());
         let expected_memory_map = HashMap::from_iter(vec![
             ("q".to_owned(), MemoryMapEntry("q".to_owned(), 0, 1)),
@@ -444,7 +622,7 @@ mod test {
     "
         );
         let tree = parse_program(source).unwrap();
-        let semantics_result = extract_semantics(&tree);
+        let semantics_result = extract_semantics(&tree, &LanguageOptions::new());
         assert!(semantics_result.is_ok());
         let expected_definitions = HashMap::from_iter(vec![
             (
@@ -459,6 +637,13 @@ mod test {
                         vec![ast::Argument::Id("q".to_owned())],
                     ))],
                     Location(14),
+                    vec![ast::Span {
+                        boundaries: (Location(37), Location(41)),
+                        node: Box::new(()),
+                        parameters: vec![],
+                        arguments: vec![(Location(39), Location(40))],
+                        operations: vec![],
+                    }],
                 ),
             ),
             (
@@ -481,6 +666,17 @@ mod test {
                         vec![ast::Argument::Id("q".to_owned())],
                     ))],
                     Location(69),
+                    vec![ast::Span {
+                        boundaries: (Location(107), Location(122)),
+                        node: Box::new(()),
+                        parameters: vec![
+                            (Location(109), Location(112)),
+                            (Location(114), Location(115)),
+                            (Location(117), Location(118)),
+                        ],
+                        arguments: vec![(Location(120), Location(121))],
+                        operations: vec![],
+                    }],
                 ),
             ),
         ]);
@@ -488,4 +684,70 @@ mod test {
             assert_eq!(semantics.macro_definitions, expected_definitions);
         }
     }
+
+    #[test]
+    fn test_opaque_definitions() {
+        let source = indoc!(
+            "
+    OPENQASM 2.0;
+    opaque magic (a) q, r;
+    "
+        );
+        let tree = parse_program(source).unwrap();
+        let semantics = extract_semantics(&tree, &LanguageOptions::new()).unwrap();
+        let expected_definitions = HashMap::from_iter(vec![(
+            "magic".to_owned(),
+            OpaqueDefinition(
+                "magic".to_owned(),
+                vec!["a".to_owned()],
+                vec!["q".to_owned(), "r".to_owned()],
+                Location(14),
+            ),
+        )]);
+        assert_eq!(semantics.opaque_definitions, expected_definitions);
+    }
+
+    #[test]
+    fn test_cannot_redeclare_a_gate_as_opaque() {
+        let source = indoc!(
+            "
+    OPENQASM 2.0;
+    gate g q { U(0, 0, 0) q; }
+    opaque g q;
+    "
+        );
+        let tree = parse_program(source).unwrap();
+        let error = extract_semantics(&tree, &LanguageOptions::new())
+            .expect_err("should be a redeclaration error");
+        assert_eq!(
+            error,
+            SemanticError::RedefinitionError {
+                symbol_name: "g".into(),
+                location: Location(41),
+                previous_location: Location(14)
+            }
+        );
+    }
+
+    #[test]
+    fn test_forward_references_in_gate_bodies() {
+        let source = indoc!(
+            "
+    OPENQASM 2.0;
+    gate g q { h q; }
+    gate h q { U(pi/2, 0, pi) q; }
+    "
+        );
+        let tree = parse_program(source).unwrap();
+        assert!(extract_semantics(&tree, &LanguageOptions::new()).is_ok());
+        let error = extract_semantics(&tree, &LanguageOptions::strict())
+            .expect_err("should be a disabled extension error");
+        assert_eq!(
+            error,
+            SemanticError::DisabledExtension {
+                location: Location(14),
+                extension: Extension::ForwardReferences
+            }
+        );
+    }
 }
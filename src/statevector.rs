@@ -1,6 +1,8 @@
 //! Contain utilities for representing the internal state of a quantum system.
 use std::f64;
+use std::fmt;
 use std::iter::FromIterator;
+use std::str::FromStr;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
@@ -12,6 +14,95 @@ use crate::complex;
 pub use crate::complex::{Complex, ComplexMargin};
 use crate::random;
 
+/// Represent a quantum register as a range of consecutive qubits in the
+/// state-vector.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct QuantumRegister {
+    name: String,
+    start: usize,
+    size: usize,
+}
+
+impl QuantumRegister {
+    /// Create a new register called `name` spanning `size` qubits from the
+    /// qubit `start`.
+    pub fn new(name: String, start: usize, size: usize) -> Self {
+        QuantumRegister { name, start, size }
+    }
+
+    /// Return the name of the register.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Return the index of the first qubit of the register.
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Return the number of qubits in the register.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Return the bits of `index` corresponding to this register.
+    pub fn value_of(&self, index: usize) -> usize {
+        (index >> self.start) & ((1 << self.size) - 1)
+    }
+
+    /// Return the bits of `index` corresponding to this register as a
+    /// bitstring in the `endianness` order.
+    pub fn bitstring_of(&self, index: usize, endianness: Endianness) -> String {
+        let bitstring = format!("{:0width$b}", self.value_of(index), width = self.size);
+        match endianness {
+            Endianness::Big => bitstring,
+            Endianness::Little => bitstring.chars().rev().collect(),
+        }
+    }
+}
+
+/// Order in which the bits of a register are displayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Endianness {
+    /// The most significant bit goes first, as in regular binary notation.
+    #[default]
+    Big,
+    /// The least significant bit, the one of index 0, goes first.
+    Little,
+}
+
+impl Endianness {
+    /// Return `registers` in display order. With big-endianness, registers go
+    /// from the most significant one, the last declared, to the least
+    /// significant one, so the concatenated bits read as the index of the
+    /// basis state. With little-endianness, the order is reversed.
+    pub fn sort_registers<'a>(&self, registers: &'a [QuantumRegister]) -> Vec<&'a QuantumRegister> {
+        let mut registers: Vec<&QuantumRegister> = registers.iter().collect();
+        registers.sort_by_key(|register| register.start());
+        if *self == Endianness::Big {
+            registers.reverse();
+        }
+        registers
+    }
+}
+
+impl FromStr for Endianness {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "big" => Ok(Endianness::Big),
+            "little" => Ok(Endianness::Little),
+            _ => Err(format!(
+                "unknown endianness `{}`, expected `big` or `little`",
+                s
+            )),
+        }
+    }
+}
+
 /// Represent the state vector of a quantum system simulation.
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -30,6 +121,14 @@ impl StateVector {
         statevector
     }
 
+    /// Create a new state-vector of length 2 to the `qubit_width` power and
+    /// all the amplitude concentrated in the basis state `index`.
+    pub fn from_basis_state(qubit_width: usize, index: usize) -> Self {
+        let mut bases = vec![Complex::new(0.0, 0.0); exp2(qubit_width)];
+        bases[index] = Complex::new(1.0, 0.0);
+        StateVector { bases, qubit_width }
+    }
+
     /// Return the amplitudes corresponding to the bases of the system.
     pub fn as_complex_bases(&self) -> &[Complex] {
         &self.bases
@@ -79,6 +178,78 @@ impl StateVector {
         }
     }
 
+    /// Apply a general rotation on `target` qubit, as in [`u()`], only on the
+    /// bases for which all the `controls` qubits are set.
+    ///
+    /// [`u()`]: #method.u
+    pub fn controlled_u(
+        &mut self,
+        theta: f64,
+        phi: f64,
+        lambda: f64,
+        controls: &[usize],
+        target: usize,
+    ) {
+        let control_mask: usize = controls.iter().map(|control| exp2(*control)).sum();
+        let target_rows = find_target_rows(self.qubit_width, target);
+        let u_matrix = build_u(theta, phi, lambda);
+        for (index_0, index_1) in target_rows {
+            if index_0 & control_mask != control_mask {
+                continue;
+            }
+            let selected = (self.bases[index_0], self.bases[index_1]);
+            self.bases[index_0] = u_matrix.0 * selected.0 + u_matrix.1 * selected.1;
+            self.bases[index_1] = u_matrix.2 * selected.0 + u_matrix.3 * selected.1;
+        }
+    }
+
+    /// Apply the unitary `matrix` on the `targets` qubits. The matrix must be
+    /// a 2^k×2^k matrix, with k the number of targets, given as a list of
+    /// rows. Following the convention of the state-vector, the bit `j` of
+    /// the matrix indices corresponds to the qubit `targets[j]`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the matrix dimensions do not match the number of targets.
+    pub fn apply_matrix(&mut self, matrix: &[Vec<Complex>], targets: &[usize]) {
+        let dimension = exp2(targets.len());
+        assert!(
+            matrix.len() == dimension && matrix.iter().all(|row| row.len() == dimension),
+            "a gate on {} qubits requires a {}x{} matrix",
+            targets.len(),
+            dimension,
+            dimension
+        );
+
+        let target_mask: usize = targets.iter().map(|target| exp2(*target)).sum();
+        let offsets: Vec<usize> = (0..dimension)
+            .map(|local_index| {
+                targets
+                    .iter()
+                    .enumerate()
+                    .map(|(bit, target)| check_bit(local_index, bit) * exp2(*target))
+                    .sum()
+            })
+            .collect();
+
+        let mut selected = vec![Complex::new(0.0, 0.0); dimension];
+        for base_index in 0..self.bases.len() {
+            if base_index & target_mask != 0 {
+                continue;
+            }
+            for (local_index, offset) in offsets.iter().enumerate() {
+                selected[local_index] = self.bases[base_index + offset];
+            }
+            for (row, offset) in matrix.iter().zip(&offsets) {
+                self.bases[base_index + offset] = row
+                    .iter()
+                    .zip(&selected)
+                    .map(|(entry, amplitude)| entry * amplitude)
+                    .sum();
+            }
+        }
+    }
+
     /// Perform a measurement on the Z-axis of the quantum state on `target` qubit.
     pub fn measure(&mut self, target: usize) -> bool {
         let mut measurement = Measurement::new(&mut self.bases, target);
@@ -91,6 +262,76 @@ impl StateVector {
         self.bases.iter().map(|c| c.norm_sqr()).collect()
     }
 
+    /// Return a helper for displaying the state-vector in ket notation, such
+    /// as `0.707|00⟩ + 0.707|11⟩`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::f64::consts::FRAC_1_SQRT_2;
+    /// use qasmsim::statevector::{Complex, StateVector};
+    ///
+    /// let bell = StateVector::from_complex_bases(vec![
+    ///     Complex::from(FRAC_1_SQRT_2),
+    ///     Complex::from(0.0),
+    ///     Complex::from(0.0),
+    ///     Complex::new(0.0, FRAC_1_SQRT_2),
+    /// ]);
+    /// assert_eq!(format!("{}", bell.ket()), "0.707|00⟩ + 0.707∠1.571|11⟩");
+    /// ```
+    pub fn ket(&self) -> Ket<'_> {
+        Ket {
+            statevector: self,
+            registers: &[],
+            threshold: 1e-3,
+            endianness: Endianness::default(),
+        }
+    }
+
+    /// Check if the state-vector is equal to `other` up to a global phase,
+    /// comparing each of the complex components within `margin`.
+    pub fn equals_up_to_global_phase<T: Into<ComplexMargin>>(
+        &self,
+        other: &StateVector,
+        margin: T,
+    ) -> bool {
+        self.first_difference_up_to_global_phase(other, margin.into())
+            .is_none()
+    }
+
+    /// Return the first basis state whose amplitudes differ once the global
+    /// phase is removed, or `None` if the state-vectors are the same up to a
+    /// global phase.
+    fn first_difference_up_to_global_phase(
+        &self,
+        other: &StateVector,
+        margin: ComplexMargin,
+    ) -> Option<usize> {
+        if self.len() != other.len() {
+            return Some(std::cmp::min(self.len(), other.len()));
+        }
+
+        // The global phase is estimated from the largest amplitude to
+        // minimize the numerical error.
+        let reference = (0..other.len()).max_by(|a, b| {
+            other.bases[*a]
+                .norm_sqr()
+                .total_cmp(&other.bases[*b].norm_sqr())
+                .then(b.cmp(a))
+        })?;
+        let ratio = self.bases[reference] / other.bases[reference];
+        let phase = if ratio.norm() > 0.0 {
+            ratio / ratio.norm()
+        } else {
+            Complex::new(1.0, 0.0)
+        };
+
+        self.bases.iter().zip(&other.bases).position(|(c1, c2)| {
+            let c2 = phase * c2;
+            c1.re.approx_ne(c2.re, margin) || c1.im.approx_ne(c2.im, margin)
+        })
+    }
+
     /// Reset the state-vector to the state |0⟩.
     pub fn reset(&mut self) {
         for amplitude in self.bases.iter_mut() {
@@ -101,7 +342,7 @@ impl StateVector {
     }
 }
 
-impl<'a> ApproxEq for &'a StateVector {
+impl ApproxEq for &StateVector {
     type Margin = ComplexMargin;
 
     fn approx_eq<T: Into<Self::Margin>>(self, other: Self, margin: T) -> bool {
@@ -122,6 +363,105 @@ impl FromIterator<Complex> for StateVector {
     }
 }
 
+impl fmt::Display for StateVector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.ket())
+    }
+}
+
+/// Helper for displaying a state-vector in ket notation.
+///
+/// Amplitudes are printed in polar form, `magnitude∠angle` with the angle in
+/// radians, omitting the angle when it is 0. Amplitudes whose magnitude is
+/// below a threshold are hidden. The precision of the magnitudes and angles
+/// can be set with the formatting precision and defaults to 3 decimals.
+///
+/// This `struct` is created by the [`ket()`] method on [`StateVector`].
+///
+/// [`ket()`]: ./struct.StateVector.html#method.ket
+/// [`StateVector`]: ./struct.StateVector.html
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ket<'a> {
+    statevector: &'a StateVector,
+    registers: &'a [QuantumRegister],
+    threshold: f64,
+    endianness: Endianness,
+}
+
+impl<'a> Ket<'a> {
+    /// Group the bits of each basis state by quantum register, separated by
+    /// spaces.
+    pub fn with_registers(mut self, registers: &'a [QuantumRegister]) -> Self {
+        self.registers = registers;
+        self
+    }
+
+    /// Hide the amplitudes whose magnitude is below `threshold`.
+    pub fn with_threshold(mut self, threshold: f64) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Display the bits in `endianness` order. With big-endianness, the
+    /// default, registers and bits go from the most significant to the least
+    /// significant one. With little-endianness, the order is reversed. See
+    /// [`Endianness::sort_registers()`] for the order of the registers.
+    ///
+    /// [`Endianness::sort_registers()`]: ./enum.Endianness.html#method.sort_registers
+    pub fn with_endianness(mut self, endianness: Endianness) -> Self {
+        self.endianness = endianness;
+        self
+    }
+
+    fn basis_label(&self, index: usize) -> String {
+        let whole_register;
+        let registers = if self.registers.is_empty() {
+            whole_register = [QuantumRegister::new(
+                String::new(),
+                0,
+                self.statevector.qubit_width(),
+            )];
+            &whole_register[..]
+        } else {
+            self.registers
+        };
+
+        let groups: Vec<String> = self
+            .endianness
+            .sort_registers(registers)
+            .iter()
+            .map(|register| register.bitstring_of(index, self.endianness))
+            .collect();
+        groups.join(" ")
+    }
+}
+
+impl fmt::Display for Ket<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let precision = f.precision().unwrap_or(3);
+        let mut terms = Vec::new();
+        for (index, amplitude) in self.statevector.bases.iter().enumerate() {
+            let (magnitude, angle) = amplitude.to_polar();
+            if magnitude < self.threshold {
+                continue;
+            }
+            let mut term = format!("{:.precision$}", magnitude, precision = precision);
+            // Omit the angle when it would print as zero.
+            if angle.abs() >= 0.5 * 10_f64.powi(-(precision as i32)) {
+                term.push_str(&format!("∠{:.precision$}", angle, precision = precision));
+            }
+            term.push_str(&format!("|{}⟩", self.basis_label(index)));
+            terms.push(term);
+        }
+
+        if terms.is_empty() {
+            write!(f, "0")
+        } else {
+            write!(f, "{}", terms.join(" + "))
+        }
+    }
+}
+
 #[derive(Debug, PartialEq)]
 struct Measurement<'a> {
     bases: &'a mut Vec<Complex>,
@@ -174,6 +514,62 @@ pub fn assert_approx_eq(v1: &StateVector, v2: &StateVector) {
     }
 }
 
+/// Assert two state-vectors are approximately equal up to a global phase by
+/// an error no higher than the f64 margin for each of the complex components.
+/// On failure, the message shows the first differing basis state in ket
+/// notation.
+pub fn assert_equivalent_up_to_phase(v1: &StateVector, v2: &StateVector) {
+    if let Some(index) = v1.first_difference_up_to_global_phase(v2, ComplexMargin::default()) {
+        let width = std::cmp::max(v1.qubit_width(), v2.qubit_width());
+        panic!(
+            concat!(
+                "assertion failed `(left ~= right up to global phase)`\n",
+                "  first difference at |{:0width$b}⟩: left `{}`, right `{}`\n",
+                "  left: `{:?}`\n",
+                " right: `{:?}`"
+            ),
+            index,
+            amplitude_or_none(v1, index),
+            amplitude_or_none(v2, index),
+            v1,
+            v2,
+            width = width
+        );
+    }
+}
+
+fn amplitude_or_none(statevector: &StateVector, index: usize) -> String {
+    statevector.bases.get(index).map_or_else(
+        || String::from("none"),
+        |amplitude| format!("{}", amplitude),
+    )
+}
+
+/// Return the fidelity between the pure states `v1` and `v2`, this is the
+/// squared modulus of their inner product `|⟨v1|v2⟩|²`. The fidelity is 1
+/// for states equal up to a global phase, and 0 for orthogonal states.
+pub fn fidelity(v1: &StateVector, v2: &StateVector) -> f64 {
+    inner_product(v1, v2).norm_sqr()
+}
+
+/// Return the trace distance between the pure states `v1` and `v2`. For pure
+/// states, the trace distance is `sqrt(1 - F)` where `F` is their
+/// [`fidelity()`].
+///
+/// [`fidelity()`]: ./fn.fidelity.html
+pub fn trace_distance(v1: &StateVector, v2: &StateVector) -> f64 {
+    (1.0 - fidelity(v1, v2)).max(0.0).sqrt()
+}
+
+/// Return the inner product `⟨v1|v2⟩`.
+pub(crate) fn inner_product(v1: &StateVector, v2: &StateVector) -> Complex {
+    v1.bases
+        .iter()
+        .zip(&v2.bases)
+        .map(|(c1, c2)| c1.conj() * c2)
+        .sum()
+}
+
 #[inline]
 fn check_bit(value: usize, index: usize) -> usize {
     (value & (1 << index)) >> index
@@ -337,6 +733,72 @@ mod tests {
         assert_eq!(v, StateVector::from_complex_bases(vec!(p, a, p, b)));
     }
 
+    #[test]
+    fn test_apply_matrix_matches_cnot() {
+        let p = Default::default();
+        let a = Complex::new(1.0, 0.0);
+        let b = Complex::new(0.0, 1.0);
+        let l = Complex::new(1.0, 0.0);
+        let cnot = vec![
+            vec![l, p, p, p],
+            vec![p, p, p, l],
+            vec![p, p, l, p],
+            vec![p, l, p, p],
+        ];
+        let mut v = StateVector::from_complex_bases(vec![p, p, p, p, a, b, a, b]);
+        v.apply_matrix(&cnot, &[2, 0]);
+        let mut expected = StateVector::from_complex_bases(vec![p, p, p, p, a, b, a, b]);
+        expected.cnot(2, 0);
+        assert_eq!(v, expected);
+    }
+
+    #[test]
+    fn test_apply_matrix_matches_u() {
+        let h = Complex::from(FRAC_1_SQRT_2);
+        let mut v = StateVector::new(2);
+        v.apply_matrix(&[vec![h, h], vec![h, -h]], &[1]);
+        let mut expected = StateVector::new(2);
+        expected.u(PI / 2.0, 0.0, PI, 1);
+        assert_approx_eq(&v, &expected);
+    }
+
+    #[test]
+    fn test_controlled_u_matches_cnot() {
+        let p = Default::default();
+        let a = Complex::new(1.0, 0.0);
+        let b = Complex::new(0.0, 1.0);
+        let mut v = StateVector::from_complex_bases(vec![p, p, p, p, a, b, a, b]);
+        v.controlled_u(PI, 0.0, PI, &[2], 0);
+        let mut expected = StateVector::from_complex_bases(vec![p, p, p, p, a, b, a, b]);
+        expected.cnot(2, 0);
+        assert_approx_eq(&v, &expected);
+    }
+
+    #[test]
+    fn test_controlled_u_requires_all_the_controls() {
+        let p = Default::default();
+        let a = Complex::new(1.0, 0.0);
+        let mut v = StateVector::from_complex_bases(vec![p, a, p, p, p, p, p, p]);
+        v.controlled_u(PI, 0.0, PI, &[0, 1], 2);
+        assert_approx_eq(
+            &v,
+            &StateVector::from_complex_bases(vec![p, a, p, p, p, p, p, p]),
+        );
+        let mut v = StateVector::from_complex_bases(vec![p, p, p, a, p, p, p, p]);
+        v.controlled_u(PI, 0.0, PI, &[0, 1], 2);
+        assert_approx_eq(
+            &v,
+            &StateVector::from_complex_bases(vec![p, p, p, p, p, p, p, a]),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "a gate on 1 qubits requires a 2x2 matrix")]
+    fn test_apply_matrix_checks_dimensions() {
+        let mut v = StateVector::new(2);
+        v.apply_matrix(&[vec![Complex::from(1.0)]], &[0]);
+    }
+
     #[test]
     fn test_measurement() {
         let size = 1000;
@@ -353,7 +815,7 @@ mod tests {
             f64,
             (accum as f64) / (size as f64),
             0.5,
-            epsilon = std::f64::EPSILON
+            epsilon = f64::EPSILON
         );
     }
 
@@ -396,6 +858,199 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_equals_up_to_global_phase() {
+        let v1 = StateVector::from_complex_bases(vec![
+            Complex::from(FRAC_1_SQRT_2),
+            Complex::from(0.0),
+            Complex::from(0.0),
+            Complex::new(0.0, FRAC_1_SQRT_2),
+        ]);
+        let v2 = StateVector::from_complex_bases(vec![
+            Complex::from(-FRAC_1_SQRT_2),
+            Complex::from(0.0),
+            Complex::from(0.0),
+            Complex::new(0.0, -FRAC_1_SQRT_2),
+        ]);
+        assert!(v1.equals_up_to_global_phase(&v2, ComplexMargin::default()));
+        assert_equivalent_up_to_phase(&v1, &v2);
+    }
+
+    #[test]
+    fn test_relative_phase_is_not_a_global_phase() {
+        let v1 = StateVector::from_complex_bases(vec![
+            Complex::from(FRAC_1_SQRT_2),
+            Complex::from(FRAC_1_SQRT_2),
+        ]);
+        let v2 = StateVector::from_complex_bases(vec![
+            Complex::from(FRAC_1_SQRT_2),
+            Complex::from(-FRAC_1_SQRT_2),
+        ]);
+        assert!(!v1.equals_up_to_global_phase(&v2, ComplexMargin::default()));
+    }
+
+    #[test]
+    fn test_equals_up_to_global_phase_respects_the_margin() {
+        let v1 = StateVector::from_complex_bases(vec![Complex::from(1.0), Complex::from(0.0)]);
+        let v2 = StateVector::from_complex_bases(vec![Complex::new(0.0, 1.0), Complex::from(1e-6)]);
+        assert!(!v1.equals_up_to_global_phase(&v2, ComplexMargin::default()));
+        assert!(v1.equals_up_to_global_phase(&v2, ComplexMargin::default().epsilon(1e-5)));
+    }
+
+    #[test]
+    #[should_panic(expected = "first difference at |10⟩")]
+    fn test_assert_equivalent_up_to_phase_shows_the_first_difference() {
+        let v1 = StateVector::from_complex_bases(vec![
+            Complex::from(FRAC_1_SQRT_2),
+            Complex::from(0.0),
+            Complex::from(FRAC_1_SQRT_2),
+            Complex::from(0.0),
+        ]);
+        let v2 = StateVector::from_complex_bases(vec![
+            Complex::from(FRAC_1_SQRT_2),
+            Complex::from(0.0),
+            Complex::from(-FRAC_1_SQRT_2),
+            Complex::from(0.0),
+        ]);
+        assert_equivalent_up_to_phase(&v1, &v2);
+    }
+
+    #[test]
+    fn test_ket_hides_small_amplitudes() {
+        let v = StateVector::from_complex_bases(vec![
+            Complex::from(FRAC_1_SQRT_2),
+            Complex::from(1e-9),
+            Complex::from(0.0),
+            Complex::from(-FRAC_1_SQRT_2),
+        ]);
+        assert_eq!(format!("{}", v.ket()), "0.707|00⟩ + 0.707∠3.142|11⟩");
+        assert_eq!(
+            format!("{}", v.ket().with_threshold(0.0)),
+            "0.707|00⟩ + 0.000|01⟩ + 0.000|10⟩ + 0.707∠3.142|11⟩"
+        );
+    }
+
+    #[test]
+    fn test_ket_precision() {
+        let v = StateVector::from_complex_bases(vec![
+            Complex::from(FRAC_1_SQRT_2),
+            Complex::new(0.0, -FRAC_1_SQRT_2),
+        ]);
+        assert_eq!(format!("{:.2}", v.ket()), "0.71|0⟩ + 0.71∠-1.57|1⟩");
+    }
+
+    #[test]
+    fn test_ket_groups_bits_by_register() {
+        let mut bases = vec![Complex::from(0.0); 8];
+        bases[0b110] = Complex::from(1.0);
+        let v = StateVector::from_complex_bases(bases);
+        let registers = vec![
+            QuantumRegister::new("q".into(), 0, 2),
+            QuantumRegister::new("r".into(), 2, 1),
+        ];
+        assert_eq!(
+            format!("{}", v.ket().with_registers(&registers)),
+            "1.000|1 10⟩"
+        );
+    }
+
+    #[test]
+    fn test_little_endian_ket() {
+        let mut bases = vec![Complex::from(0.0); 8];
+        bases[0b110] = Complex::from(1.0);
+        let v = StateVector::from_complex_bases(bases);
+        let registers = vec![
+            QuantumRegister::new("q".into(), 0, 2),
+            QuantumRegister::new("r".into(), 2, 1),
+        ];
+        assert_eq!(
+            format!("{}", v.ket().with_endianness(Endianness::Little)),
+            "1.000|011⟩"
+        );
+        assert_eq!(
+            format!(
+                "{}",
+                v.ket()
+                    .with_registers(&registers)
+                    .with_endianness(Endianness::Little)
+            ),
+            "1.000|01 1⟩"
+        );
+    }
+
+    #[test]
+    fn test_register_order() {
+        let registers = vec![
+            QuantumRegister::new("q".into(), 0, 2),
+            QuantumRegister::new("r".into(), 2, 1),
+        ];
+        let names = |endianness: Endianness| -> Vec<&str> {
+            endianness
+                .sort_registers(&registers)
+                .iter()
+                .map(|register| register.name())
+                .collect()
+        };
+        assert_eq!(names(Endianness::Big), vec!["r", "q"]);
+        assert_eq!(names(Endianness::Little), vec!["q", "r"]);
+    }
+
+    #[test]
+    fn test_register_bitstring() {
+        let register = QuantumRegister::new("r".into(), 1, 3);
+        assert_eq!(register.bitstring_of(0b1100, Endianness::Big), "110");
+        assert_eq!(register.bitstring_of(0b1100, Endianness::Little), "011");
+    }
+
+    #[test]
+    fn test_empty_ket() {
+        let v = StateVector::from_complex_bases(vec![Complex::from(0.0), Complex::from(0.0)]);
+        assert_eq!(format!("{}", v), "0");
+    }
+
+    #[test]
+    fn test_fidelity_ignores_global_phase() {
+        let v1 = StateVector::from_complex_bases(vec![
+            Complex::from(FRAC_1_SQRT_2),
+            Complex::from(FRAC_1_SQRT_2),
+        ]);
+        let v2 = StateVector::from_complex_bases(vec![
+            Complex::new(0.0, FRAC_1_SQRT_2),
+            Complex::new(0.0, FRAC_1_SQRT_2),
+        ]);
+        assert!(approx_eq!(f64, fidelity(&v1, &v2), 1.0, ulps = 2));
+        assert!(approx_eq!(
+            f64,
+            trace_distance(&v1, &v2),
+            0.0,
+            epsilon = 1e-7
+        ));
+    }
+
+    #[test]
+    fn test_fidelity_of_orthogonal_states() {
+        let v1 = StateVector::from_complex_bases(vec![Complex::from(1.0), Complex::from(0.0)]);
+        let v2 = StateVector::from_complex_bases(vec![Complex::from(0.0), Complex::from(1.0)]);
+        assert!(approx_eq!(f64, fidelity(&v1, &v2), 0.0));
+        assert!(approx_eq!(f64, trace_distance(&v1, &v2), 1.0));
+    }
+
+    #[test]
+    fn test_fidelity_of_partially_overlapping_states() {
+        let v1 = StateVector::from_complex_bases(vec![Complex::from(1.0), Complex::from(0.0)]);
+        let v2 = StateVector::from_complex_bases(vec![
+            Complex::from(FRAC_1_SQRT_2),
+            Complex::from(FRAC_1_SQRT_2),
+        ]);
+        assert!(approx_eq!(f64, fidelity(&v1, &v2), 0.5, ulps = 2));
+        assert!(approx_eq!(
+            f64,
+            trace_distance(&v1, &v2),
+            FRAC_1_SQRT_2,
+            ulps = 2
+        ));
+    }
+
     #[test]
     fn test_state_vector_measurement_2_qubit_superposition() {
         let mut v = StateVector::from_complex_bases(vec![
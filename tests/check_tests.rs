@@ -0,0 +1,153 @@
+#![cfg(test)]
+
+extern crate qasmsim;
+
+use indoc::indoc;
+
+use qasmsim::{check, LanguageOptions, OpaqueGates, QasmSimError, QasmType};
+
+#[test]
+fn test_check_valid_program() {
+    let source = indoc!(
+        "
+  OPENQASM 2.0;
+  include \"qelib1.inc\";
+  qreg q[2];
+  creg c[2];
+  h q[0];
+  cx q[0], q[1];
+  measure q -> c;
+  "
+    );
+    let errors = check(source, &LanguageOptions::new(), &OpaqueGates::new());
+    assert_eq!(errors, vec![]);
+}
+
+#[test]
+fn test_check_reports_all_errors() {
+    let source = indoc!(
+        "
+  OPENQASM 2.0;
+  include \"qelib1.inc\";
+  qreg q[2];
+  creg c[2];
+  gate rot a { rz(theta) a; }
+  h q[2];
+  if (c == 1) xxx q;
+  "
+    );
+    let errors = check(source, &LanguageOptions::new(), &OpaqueGates::new());
+    assert_eq!(
+        errors,
+        vec![
+            QasmSimError::SymbolNotFound {
+                source: "gate rot a { rz(theta) a; }\n",
+                symbol_name: "theta".into(),
+                lineno: 5,
+                startpos: 16,
+                endpos: Some(21),
+                expected: QasmType::RealValue
+            },
+            QasmSimError::IndexOutOfBounds {
+                source: "h q[2];\n",
+                symbol_name: "q".into(),
+                lineno: 6,
+                startpos: 2,
+                endpos: Some(6),
+                index: 2,
+                size: 2
+            },
+            QasmSimError::UndefinedGate {
+                source: "if (c == 1) xxx q;\n",
+                symbol_name: "xxx".into(),
+                lineno: 7
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_check_points_at_the_operations_of_gate_bodies() {
+    let source = indoc!(
+        "
+  OPENQASM 2.0;
+  include \"qelib1.inc\";
+  qreg q[2];
+  gate bad a { foo a; cx a; }
+  bad q[0];
+  "
+    );
+    let errors = check(source, &LanguageOptions::new(), &OpaqueGates::new());
+    assert_eq!(
+        errors,
+        vec![
+            QasmSimError::UndefinedGate {
+                source: "gate bad a { foo a; cx a; }\n",
+                symbol_name: "foo".into(),
+                lineno: 4
+            },
+            QasmSimError::WrongNumberOfParameters {
+                source: "gate bad a { foo a; cx a; }\n",
+                symbol_name: "cx".into(),
+                lineno: 4,
+                startpos: 23,
+                endpos: Some(24),
+                are_registers: true,
+                given: 1,
+                expected: 2
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_check_reports_only_the_syntax_error() {
+    let source = indoc!(
+        "
+  OPENQASM 2.0;
+  qreg q[2]
+  xxx q;
+  "
+    );
+    let errors = check(source, &LanguageOptions::new(), &OpaqueGates::new());
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(errors[0], QasmSimError::UnexpectedToken { .. }));
+}
+
+#[test]
+fn test_check_points_at_the_qubits_of_conditional_operations() {
+    let source = indoc!(
+        "
+  OPENQASM 2.0;
+  include \"qelib1.inc\";
+  qreg q[2];
+  creg c[1];
+  if (c==1) cx q[0], q[0];
+  if (c==1) cx q[0];
+  "
+    );
+    let errors = check(source, &LanguageOptions::new(), &OpaqueGates::new());
+    assert_eq!(
+        errors,
+        vec![
+            QasmSimError::RepeatedQubit {
+                source: "if (c==1) cx q[0], q[0];\n",
+                lineno: 5,
+                startpos: 13,
+                endpos: Some(23),
+                symbol_name: "cx".into(),
+                qubit: "q[0]".into()
+            },
+            QasmSimError::WrongNumberOfParameters {
+                source: "if (c==1) cx q[0];\n",
+                lineno: 6,
+                startpos: 13,
+                endpos: Some(17),
+                symbol_name: "cx".into(),
+                are_registers: true,
+                given: 1,
+                expected: 2
+            },
+        ]
+    );
+}
@@ -0,0 +1,215 @@
+#![cfg(all(test, feature = "cli"))]
+
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+use indoc::indoc;
+use serde_json::{json, Value};
+
+/// Run the `qasmsim` command with `args` on `source` and return its output.
+fn qasmsim(source: &str, args: &[&str]) -> String {
+    let output = run_qasmsim(source, args);
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+fn run_qasmsim(source: &str, args: &[&str]) -> Output {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_qasmsim"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("start qasmsim");
+    command
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(source.as_bytes())
+        .unwrap();
+    command.wait_with_output().unwrap()
+}
+
+const TWO_REGISTERS: &str = indoc!(
+    "
+    OPENQASM 2.0;
+    include \"qelib1.inc\";
+    qreg a[1];
+    qreg b[2];
+    x a[0];
+    x b[1];
+    "
+);
+
+#[test]
+fn test_register_labels_and_ket_share_the_order_of_registers() {
+    let output = qasmsim(TWO_REGISTERS, &["--probabilities", "--ket"]);
+    assert!(output.contains("| 5    | b=10 a=1  | 1.000000    |"));
+    assert!(output.contains("1.000|10 1⟩"));
+}
+
+#[test]
+fn test_little_endian_register_labels_and_ket() {
+    let output = qasmsim(
+        TWO_REGISTERS,
+        &["--probabilities", "--ket", "--endianness", "little"],
+    );
+    assert!(output.contains("| 5    | a=1 b=01  | 1.000000    |"));
+    assert!(output.contains("1.000|1 01⟩"));
+}
+
+const INVALID_CALLS: &str = indoc!(
+    "
+    OPENQASM 2.0;
+    include \"qelib1.inc\";
+    qreg q[2];
+    foo q[0];
+    h q[2];
+    cx q[0];
+    "
+);
+
+#[test]
+fn test_errors_found_by_check_are_semantic() {
+    let output = run_qasmsim(INVALID_CALLS, &["--check", "--error-format", "json"]);
+    assert_eq!(output.status.code(), Some(4));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert_eq!(stderr.lines().count(), 3);
+    assert!(stderr
+        .lines()
+        .all(|line| line.contains("\"stage\":\"semantic\"")));
+}
+
+#[test]
+fn test_semantic_errors_keep_their_stage_when_running() {
+    let output = run_qasmsim(INVALID_CALLS, &["--error-format", "json"]);
+    assert_eq!(output.status.code(), Some(4));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("\"stage\":\"semantic\""));
+}
+
+#[test]
+fn test_invalid_values_are_runtime_errors() {
+    let source = indoc!(
+        "
+        OPENQASM 2.0;
+        qreg q[1];
+        U(ln(0), 0, 0) q[0];
+        "
+    );
+    for args in &[
+        &["--error-format", "json"][..],
+        &["--check", "--error-format", "json"],
+    ] {
+        let output = run_qasmsim(source, args);
+        assert_eq!(output.status.code(), Some(5));
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains("\"stage\":\"runtime\""));
+    }
+}
+
+#[test]
+fn test_unformatted_programs_have_their_own_exit_code() {
+    let output = run_qasmsim("OPENQASM 2.0; qreg q[1];", &["fmt", "--check"]);
+    assert_eq!(output.status.code(), Some(8));
+}
+
+#[test]
+fn test_non_equivalent_programs_have_their_own_exit_code() {
+    let directory = std::env::temp_dir();
+    let left = directory.join("qasmsim_cli_tests_left.qasm");
+    let right = directory.join("qasmsim_cli_tests_right.qasm");
+    std::fs::write(&left, "OPENQASM 2.0;\nqreg q[1];\n").unwrap();
+    std::fs::write(&right, "OPENQASM 2.0;\nqreg q[1];\nU(pi, 0, pi) q[0];\n").unwrap();
+    let output = run_qasmsim(
+        "",
+        &["equiv", left.to_str().unwrap(), right.to_str().unwrap()],
+    );
+    assert_eq!(output.status.code(), Some(7));
+}
+
+#[test]
+fn test_missing_opaque_gates_have_their_own_exit_code() {
+    let output = run_qasmsim(TWO_REGISTERS, &["--opaque", "missing-opaque-gates.json"]);
+    assert_eq!(output.status.code(), Some(6));
+}
+
+#[test]
+fn test_errors_in_gate_bodies_underline_the_operation() {
+    let source = indoc!(
+        "
+  OPENQASM 2.0;
+  include \"qelib1.inc\";
+  qreg q[2];
+  gate bad a { foo a; cx a; }
+  bad q[0];
+  "
+    );
+    let output = run_qasmsim(source, &["--check"]);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains(indoc!(
+        "
+  4 | gate bad a { foo a; cx a; }
+    |              ^^^ help: cannot find gate `foo` in this scope
+  "
+    )));
+}
+
+#[test]
+fn test_stats_in_json() {
+    let source = indoc!(
+        "
+  OPENQASM 2.0;
+  include \"qelib1.inc\";
+  qreg q[3];
+  creg c[3];
+  h q[0];
+  cx q[0], q[1];
+  ccx q[0], q[1], q[2];
+  measure q[0] -> c[0];
+  if (c == 1) x q[2];
+  h q[0];
+  measure q -> c;
+  "
+    );
+    let stats: Value = serde_json::from_str(&qasmsim(source, &["--stats", "--json"])).unwrap();
+    assert_eq!(
+        stats,
+        json!({
+            "qubits": 3,
+            "clbits": 3,
+            "gate_counts": { "CX": 7, "U": 12 },
+            "depth": 15,
+            "top_level_depth": 6,
+            "two_qubit_gates": 7,
+            "t_count": 7,
+            "measurements": 4,
+            "dynamic": true
+        })
+    );
+}
+
+#[test]
+fn test_info_shows_the_simplified_definition() {
+    let source = indoc!(
+        "
+        OPENQASM 2.0;
+        // Half a turn,
+        // plus one.
+        gate flip q { U(pi, 0, 3 * pi + 1 - 1) q; }
+        "
+    );
+    let output = qasmsim(source, &["--info", "flip"]);
+    assert_eq!(
+        output,
+        indoc!(
+            "
+            // Half a turn,
+            // plus one.
+            gate flip q {
+              U(pi, 0, pi) q;
+            }
+            "
+        )
+    );
+}
@@ -1,10 +1,16 @@
 #![cfg(test)]
+#![allow(clippy::useless_vec)]
 
 extern crate qasmsim;
 
 use std::f64::consts::FRAC_1_SQRT_2;
 
-use qasmsim::statevector::{assert_approx_eq, Complex, StateVector};
+use float_cmp::ApproxEq;
+
+use qasmsim::statevector::{
+    assert_approx_eq, assert_equivalent_up_to_phase, Complex, ComplexMargin, QuantumRegister,
+    StateVector,
+};
 
 #[test]
 fn endianess() {
@@ -229,6 +235,24 @@ fn test_measurements() {
     }
 }
 
+#[test]
+fn test_measurements_overwrite_previous_values() {
+    let source = "
+  OPENQASM 2.0;
+  include \"qelib1.inc\";
+  qreg q[2];
+  creg c[2];
+  x q;
+  measure q -> c;
+  x q[0];
+  measure q -> c;
+  x q;
+  measure q[1] -> c[1];
+  ";
+    let result = &qasmsim::run(source, None).unwrap();
+    assert_eq!(*result.memory().get("c").unwrap(), 0b00);
+}
+
 #[test]
 fn test_all_classical_memory_is_displayed() {
     let source = "
@@ -266,3 +290,75 @@ fn test_conditional() {
     assert_eq!(*result.memory().get("c").unwrap(), 0b10);
     assert_eq!(*result.memory().get("d").unwrap(), 0b01);
 }
+
+#[test]
+fn test_rz_matches_the_rotation_matrix_up_to_a_global_phase() {
+    let source = "
+  OPENQASM 2.0;
+  include \"qelib1.inc\";
+  qreg q[1];
+  h q[0];
+  rz(pi/2) q[0];
+  ";
+    // `rz` is defined as `u1`, so the state lacks the `exp(-i*pi/4)` phase
+    // of the rotation matrix `diag(exp(-i*pi/4), exp(i*pi/4))`.
+    let statevector = qasmsim::run(source, None).unwrap().statevector().clone();
+    let rotated =
+        StateVector::from_complex_bases(vec![Complex::new(0.5, -0.5), Complex::new(0.5, 0.5)]);
+    assert!(!statevector.approx_eq(&rotated, ComplexMargin::default()));
+    assert_equivalent_up_to_phase(&statevector, &rotated);
+}
+
+#[test]
+fn test_quantum_registers_layout() {
+    let source = "
+  OPENQASM 2.0;
+  qreg q[2];
+  creg c[2];
+  qreg r[3];
+  ";
+    let result = qasmsim::run(source, None).unwrap();
+    assert_eq!(
+        result.quantum_registers(),
+        &[
+            QuantumRegister::new("q".into(), 0, 2),
+            QuantumRegister::new("r".into(), 2, 3),
+        ]
+    );
+}
+
+#[test]
+fn test_ket_notation_groups_bits_by_register() {
+    let source = "
+  OPENQASM 2.0;
+  include \"qelib1.inc\";
+  qreg q[1];
+  qreg r[2];
+  h q[0];
+  cx q[0], r[1];
+  ";
+    let result = qasmsim::run(source, None).unwrap();
+    let ket = result
+        .statevector()
+        .ket()
+        .with_registers(result.quantum_registers());
+    assert_eq!(format!("{}", ket), "0.707|00 0⟩ + 0.707|10 1⟩");
+}
+
+#[test]
+fn extended_math_functions_and_constants() {
+    let source = "
+  OPENQASM 2.0;
+  qreg q[1];
+  U(acos(-1) + mod(tau, pi) + floor(euler) - 2 * ceil(atan(1)) - atan2(0, 1), 0, pi) q[0];
+  ";
+    let expected = "
+  OPENQASM 2.0;
+  qreg q[1];
+  U(pi, 0, pi) q[0];
+  ";
+    assert_approx_eq(
+        qasmsim::run(source, None).unwrap().statevector(),
+        qasmsim::run(expected, None).unwrap().statevector(),
+    )
+}
@@ -0,0 +1,112 @@
+#![cfg(test)]
+
+extern crate qasmsim;
+
+use indoc::indoc;
+use qasmsim::{Extension, LanguageOptions, OpaqueGates, QasmSimError};
+
+fn extended() -> LanguageOptions {
+    LanguageOptions::new().allow(Extension::ExtendedConditionals)
+}
+
+#[test]
+fn test_teleportation_with_bit_conditionals() {
+    let source = indoc!(
+        "
+        OPENQASM 2.0;
+        include \"qelib1.inc\";
+        qreg q[3];
+        creg c0[1];
+        creg c1[1];
+        creg result[1];
+        x q[0];
+        h q[1];
+        cx q[1], q[2];
+        cx q[0], q[1];
+        h q[0];
+        measure q[0] -> c0[0];
+        measure q[1] -> c1[0];
+        if (c1[0]==1) x q[2];
+        if (c0[0]==1) z q[2];
+        measure q[2] -> result[0];
+        "
+    );
+    let result =
+        qasmsim::run_with_options(source, Some(100), &extended(), &OpaqueGates::new()).unwrap();
+    let histogram = result.histogram().as_ref().unwrap();
+    assert!(histogram["result"].iter().all(|(value, _)| *value == 1));
+}
+
+#[test]
+fn test_comparison_operators_and_conjunctions() {
+    let source = indoc!(
+        "
+        OPENQASM 2.0;
+        include \"qelib1.inc\";
+        qreg q[2];
+        qreg r[5];
+        creg c[2];
+        creg d[5];
+        x q[1];
+        measure q -> c;
+        if (c!=2) x r[0];
+        if (c<3) x r[1];
+        if (c>=2 && c[0]==0) x r[2];
+        if (c>2) x r[3];
+        if (c<=1 && c[1]==1) x r[4];
+        measure r -> d;
+        "
+    );
+    let result = qasmsim::run_with_options(source, None, &extended(), &OpaqueGates::new()).unwrap();
+    assert_eq!(result.memory()["d"], 0b00110);
+}
+
+#[test]
+fn test_extended_conditionals_are_disabled_by_default() {
+    let source = indoc!(
+        "
+        OPENQASM 2.0;
+        include \"qelib1.inc\";
+        qreg q[1];
+        creg c[1];
+        if (c[0]==0) x q[0];
+        "
+    );
+    let error = qasmsim::run(source, None).expect_err("should fail");
+    assert_eq!(
+        error,
+        QasmSimError::DisabledExtension {
+            source: "if (c[0]==0) x q[0];\n",
+            lineno: 5,
+            startpos: 0,
+            extension: Extension::ExtendedConditionals
+        }
+    );
+    assert_eq!(
+        format!("{}", error),
+        indoc!(
+            "
+            error: extended conditionals are not part of OPENQASM 2.0
+              |
+            5 | if (c[0]==0) x q[0];
+              | ^ help: enable the `extended-conditionals` extension to allow them
+            "
+        )
+    );
+}
+
+#[test]
+fn test_whole_register_equality_is_standard() {
+    let source = indoc!(
+        "
+        OPENQASM 2.0;
+        include \"qelib1.inc\";
+        qreg q[1];
+        creg c[1];
+        if (c==0) x q[0];
+        measure q -> c;
+        "
+    );
+    let result = qasmsim::run(source, None).unwrap();
+    assert_eq!(result.memory()["c"], 1);
+}
@@ -0,0 +1,78 @@
+#![cfg(test)]
+
+extern crate qasmsim;
+
+use indoc::indoc;
+
+use qasmsim::{draw, LanguageOptions, QasmSimError};
+
+#[test]
+fn test_draw_opaque_gates() {
+    let source = indoc!(
+        "
+        OPENQASM 2.0;
+        opaque magic(theta) a, b;
+        qreg q[2];
+        magic(pi / 3) q[1], q[0];
+        "
+    );
+    let expected = indoc!(
+        "
+        q[0]: --[magic(pi/3):1]--
+                       |
+        q[1]: --[magic(pi/3):0]--
+        "
+    );
+    for expand in &[false, true] {
+        assert_eq!(
+            draw(source, *expand, None, &LanguageOptions::new()).unwrap(),
+            expected
+        );
+    }
+}
+
+#[test]
+fn test_draw_registers_in_order_of_declaration() {
+    let source = indoc!(
+        "
+        OPENQASM 2.0;
+        creg c[1];
+        qreg r[1];
+        qreg q[1];
+        CX q[0], r[0];
+        measure r[0] -> c[0];
+        "
+    );
+    assert_eq!(
+        draw(source, false, None, &LanguageOptions::new()).unwrap(),
+        indoc!(
+            "
+            r[0]: --(+)--[M]--
+                     |    |
+            q[0]: ---*----|---
+                          |
+            c[0]: ========v===
+            "
+        )
+    );
+}
+
+#[test]
+fn test_cannot_draw_programs_with_errors() {
+    let source = indoc!(
+        "
+        OPENQASM 2.0;
+        qreg q[1];
+        magic q[0];
+        "
+    );
+    let error = draw(source, false, None, &LanguageOptions::new()).unwrap_err();
+    assert!(matches!(error, QasmSimError::UndefinedGate { .. }));
+}
+
+#[test]
+fn test_cannot_draw_open_qasm3_programs() {
+    let source = "OPENQASM 3;\nqubit q;\n";
+    let error = draw(source, false, None, &LanguageOptions::new()).unwrap_err();
+    assert!(matches!(error, QasmSimError::UnsupportedFeature { .. }));
+}
@@ -0,0 +1,198 @@
+#![cfg(test)]
+
+extern crate qasmsim;
+
+use indoc::indoc;
+
+use qasmsim::statevector::fidelity;
+use qasmsim::{Equivalence, LanguageOptions, QasmSimError};
+
+#[test]
+fn test_swap_decompositions_are_equivalent() {
+    let left = indoc!(
+        "
+  OPENQASM 2.0;
+  include \"qelib1.inc\";
+  qreg q[2];
+  cx q[0], q[1];
+  cx q[1], q[0];
+  cx q[0], q[1];
+  "
+    );
+    let right = indoc!(
+        "
+  OPENQASM 2.0;
+  include \"qelib1.inc\";
+  qreg q[2];
+  cx q[1], q[0];
+  cx q[0], q[1];
+  cx q[1], q[0];
+  "
+    );
+    let equivalence =
+        qasmsim::check_equivalence(left, right, &LanguageOptions::new()).expect("can compare");
+    assert_eq!(equivalence, Equivalence::Equivalent);
+}
+
+#[test]
+fn test_programs_differing_in_global_phase_are_equivalent() {
+    let left = indoc!(
+        "
+  OPENQASM 2.0;
+  include \"qelib1.inc\";
+  qreg q[1];
+  z q[0];
+  "
+    );
+    let right = indoc!(
+        "
+  OPENQASM 2.0;
+  include \"qelib1.inc\";
+  qreg q[1];
+  rz(pi) q[0];
+  "
+    );
+    let equivalence =
+        qasmsim::check_equivalence(left, right, &LanguageOptions::new()).expect("can compare");
+    assert_eq!(equivalence, Equivalence::Equivalent);
+}
+
+#[test]
+fn test_programs_differing_in_relative_phase_are_not_equivalent() {
+    let left = indoc!(
+        "
+  OPENQASM 2.0;
+  include \"qelib1.inc\";
+  qreg q[2];
+  cz q[0], q[1];
+  "
+    );
+    let right = indoc!(
+        "
+  OPENQASM 2.0;
+  include \"qelib1.inc\";
+  qreg q[2];
+  id q;
+  "
+    );
+    let equivalence =
+        qasmsim::check_equivalence(left, right, &LanguageOptions::new()).expect("can compare");
+    match equivalence {
+        Equivalence::NotEquivalent { witness, fidelity } => {
+            assert!(fidelity < 1.0);
+            assert_eq!(witness.qubit_width(), 2);
+        }
+        other => panic!("expected non equivalent programs, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_witness_reproduces_the_reported_fidelity() {
+    let left = indoc!(
+        "
+  OPENQASM 2.0;
+  include \"qelib1.inc\";
+  qreg q[1];
+  h q[0];
+  "
+    );
+    let right = indoc!(
+        "
+  OPENQASM 2.0;
+  include \"qelib1.inc\";
+  qreg q[1];
+  x q[0];
+  "
+    );
+    let equivalence =
+        qasmsim::check_equivalence(left, right, &LanguageOptions::new()).expect("can compare");
+    match equivalence {
+        Equivalence::NotEquivalent {
+            witness,
+            fidelity: reported,
+        } => {
+            let mut h_output = witness.clone();
+            h_output.u(std::f64::consts::FRAC_PI_2, 0.0, std::f64::consts::PI, 0);
+            let mut x_output = witness;
+            x_output.u(std::f64::consts::PI, 0.0, std::f64::consts::PI, 0);
+            assert!((fidelity(&h_output, &x_output) - reported).abs() < 1e-9);
+        }
+        other => panic!("expected non equivalent programs, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_programs_of_different_widths_are_not_equivalent() {
+    let left = indoc!(
+        "
+  OPENQASM 2.0;
+  qreg q[1];
+  "
+    );
+    let right = indoc!(
+        "
+  OPENQASM 2.0;
+  qreg q[2];
+  "
+    );
+    let equivalence =
+        qasmsim::check_equivalence(left, right, &LanguageOptions::new()).expect("can compare");
+    assert_eq!(equivalence, Equivalence::DifferentWidths(1, 2));
+}
+
+#[test]
+fn test_cannot_compare_programs_with_measurements() {
+    let left = indoc!(
+        "
+  OPENQASM 2.0;
+  qreg q[1];
+  "
+    );
+    let right = indoc!(
+        "
+  OPENQASM 2.0;
+  qreg q[1];
+  creg c[1];
+  measure q -> c;
+  "
+    );
+    let error =
+        qasmsim::check_equivalence(left, right, &LanguageOptions::new()).expect_err("should fail");
+    assert_eq!(
+        error,
+        QasmSimError::NonUnitaryOperation {
+            source: "measure q -> c;\n",
+            lineno: 4,
+            symbol_name: "measure".into()
+        }
+    );
+}
+
+#[test]
+fn test_folding_constants_in_gate_bodies_keeps_the_circuit() {
+    let folded = indoc!(
+        "
+  OPENQASM 2.0;
+  gate g(x) a {
+    U(x * (tau / 4 - 0) + 2 * 3, -(3 * pi / 6), 1 * x / 1) a;
+    U(acos(-1) * 5, 0, 0) a;
+  }
+  gate big a { U(2 * 500000000000 + 0.5, 0, 2 * 123456789012 + 1) a; }
+  qreg q[2];
+  g(0.3) q[0];
+  big q[1];
+  "
+    );
+    let unfolded = indoc!(
+        "
+  OPENQASM 2.0;
+  qreg q[2];
+  U(0.3 * (tau / 4 - 0) + 2 * 3, -(3 * pi / 6), 1 * 0.3 / 1) q[0];
+  U(acos(-1) * 5, 0, 0) q[0];
+  U(2 * 500000000000 + 0.5, 0, 2 * 123456789012 + 1) q[1];
+  "
+    );
+    let equivalence =
+        qasmsim::check_equivalence(folded, unfolded, &LanguageOptions::new()).expect("can compare");
+    assert!(equivalence.is_equivalent());
+}
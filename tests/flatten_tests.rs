@@ -0,0 +1,210 @@
+#![cfg(test)]
+
+extern crate qasmsim;
+
+use indoc::indoc;
+
+use qasmsim::grammar::ast::{QuantumOperation, Statement, UnitaryOperation};
+use qasmsim::{flatten, parse_and_link, Basis, LanguageOptions, QasmSimError};
+
+/// Unitary programs using gate declarations, broadcasting and modifiers.
+const UNITARY_PROGRAMS: &[&str] = &[
+    indoc!(
+        "
+        OPENQASM 2.0;
+        include \"qelib1.inc\";
+        gate rot(theta, phi) a, b {
+          u3(theta, phi, -phi) a;
+          crz(theta / 2) a, b;
+          cy b, a;
+        }
+        qreg q[2];
+        qreg r[2];
+        h q;
+        rot(pi / 3, 0.7) q[0], r[1];
+        cx q, r;
+        swap r[0], q[1];
+        ccx q[0], q[1], r[0];
+        "
+    ),
+    indoc!(
+        "
+        OPENQASM 2.0;
+        include \"stdgates.inc\";
+        qreg q[4];
+        h q;
+        ctrl @ U(0.3, 1.1, -0.2) q[0], q[1];
+        ctrl(2) @ rx(0.5) q[0], q[2], q[1];
+        ctrl(3) @ p(pi / 5) q[3], q[1], q[2], q[0];
+        inv @ ctrl @ sx q[2], q[3];
+        pow(3) @ t q[1];
+        "
+    ),
+];
+
+fn flatten_in(source: &str, basis: Basis) -> String {
+    flatten(source, basis, &LanguageOptions::new()).unwrap()
+}
+
+/// Return the names of the gates called in `source`.
+fn gate_names(source: &str) -> Vec<String> {
+    parse_and_link(source)
+        .unwrap()
+        .program
+        .iter()
+        .filter_map(|span| match &*span.node {
+            Statement::QuantumOperation(QuantumOperation::Unitary(UnitaryOperation(
+                name,
+                _,
+                _,
+            ))) => Some(name.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_flattened_programs_are_equivalent() {
+    for source in UNITARY_PROGRAMS {
+        for basis in Basis::all() {
+            let flattened = flatten_in(source, *basis);
+            let equivalence =
+                qasmsim::check_equivalence(source, &flattened, &LanguageOptions::new()).unwrap();
+            assert!(equivalence.is_equivalent(), "{}:\n{}", basis, flattened);
+        }
+    }
+}
+
+#[test]
+fn test_flattened_programs_use_the_gates_of_the_basis() {
+    for source in UNITARY_PROGRAMS {
+        for basis in Basis::all() {
+            let flattened = flatten_in(source, *basis);
+            let gates: Vec<&str> = basis.name().split(',').collect();
+            for name in gate_names(&flattened) {
+                assert!(gates.contains(&name.as_str()), "{} in {}", name, basis);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_flattening_keeps_the_results_of_the_simulation() {
+    let source = indoc!(
+        "
+        OPENQASM 2.0;
+        include \"qelib1.inc\";
+        gate prepare(theta) a, b { ry(theta) a; cx a, b; }
+        qreg q[3];
+        creg c[2];
+        creg d[3];
+        x q[0];
+        measure q[0] -> c[0];
+        if (c == 1) prepare(pi) q[1], q[2];
+        barrier q;
+        if (c == 1) measure q -> d;
+        if (d == 7) measure q[0] -> c[1];
+        if (c == 3) reset q;
+        if (d == 7) measure q -> d;
+        "
+    );
+    let expected = qasmsim::run(source, None).unwrap();
+    for basis in Basis::all() {
+        let flattened = flatten_in(source, *basis);
+        let result = qasmsim::run(&flattened, None).unwrap();
+        assert_eq!(result.memory(), expected.memory(), "{}", flattened);
+        assert!(
+            qasmsim::statevector::fidelity(result.statevector(), expected.statevector())
+                > 1.0 - 1e-9,
+            "{}",
+            flattened
+        );
+    }
+}
+
+#[test]
+fn test_flatten_program() {
+    let source = indoc!(
+        "
+        OPENQASM 2.0;
+        include \"qelib1.inc\";
+        // A Bell pair.
+        gate bell a, b { h a; cx a, b; }
+        qreg q[2];
+        creg c[2];
+        bell q[0], q[1];
+        barrier q;
+        measure q -> c;
+        if (c == 1) x q;
+        if (c == 2) measure q -> c;
+        "
+    );
+    assert_eq!(
+        flatten_in(source, Basis::UCx),
+        indoc!(
+            "
+            OPENQASM 2.0;
+            qreg q[2];
+            creg c[2];
+            U(pi / 2, 0, pi) q[0];
+            CX q[0], q[1];
+            barrier q;
+            measure q[0] -> c[0];
+            measure q[1] -> c[1];
+            if (c == 1) U(pi, 0, pi) q[0];
+            if (c == 1) U(pi, 0, pi) q[1];
+            if (c == 2) measure q -> c;
+            "
+        )
+    );
+    assert_eq!(
+        flatten_in(source, Basis::RzSxXCx),
+        indoc!(
+            "
+            OPENQASM 2.0;
+            include \"qelib1.inc\";
+            // Square root of X.
+            gate sx a {
+              h a;
+              s a;
+              h a;
+            }
+            qreg q[2];
+            creg c[2];
+            rz(pi) q[0];
+            sx q[0];
+            rz(-pi / 2) q[0];
+            sx q[0];
+            rz(pi) q[0];
+            cx q[0], q[1];
+            barrier q;
+            measure q[0] -> c[0];
+            measure q[1] -> c[1];
+            if (c == 1) x q[0];
+            if (c == 1) x q[1];
+            if (c == 2) measure q -> c;
+            "
+        )
+    );
+}
+
+#[test]
+fn test_cannot_flatten_opaque_gates() {
+    let source = indoc!(
+        "
+        OPENQASM 2.0;
+        opaque magic a;
+        qreg q[1];
+        magic q[0];
+        "
+    );
+    let error = flatten(source, Basis::UCx, &LanguageOptions::new()).unwrap_err();
+    assert!(matches!(error, QasmSimError::UnboundOpaqueGate { .. }));
+}
+
+#[test]
+fn test_cannot_flatten_open_qasm3_programs() {
+    let source = "OPENQASM 3;\nqubit q;\n";
+    let error = flatten(source, Basis::UCx, &LanguageOptions::new()).unwrap_err();
+    assert!(matches!(error, QasmSimError::UnsupportedFeature { .. }));
+}
@@ -0,0 +1,260 @@
+#![cfg(test)]
+
+extern crate qasmsim;
+
+use std::fs;
+use std::path::Path;
+
+use indoc::indoc;
+
+use qasmsim::grammar::ast::{OpenQasmProgram, Span, Statement};
+use qasmsim::grammar::lexer::Location;
+use qasmsim::grammar::parse_program_with_options;
+use qasmsim::{format_source, to_qasm, Extension, LanguageOptions, QasmSimError};
+
+const TELEPORTATION: &str = indoc!(
+    "
+        // Teleportation.
+        OPENQASM 2.0;
+        include \"qelib1.inc\";
+
+        qreg q[3];
+        creg c0[1];   creg c1[1];
+        // Prepare the state to teleport.
+        u3(0.3,0.2,0.1) q[0];
+        h q[1]; cx q[1],q[2];
+        barrier q;
+        cx q[0],q[1]; h q[0];
+        measure q[0]->c0[0];
+        measure q[1] -> c1[0]; // Classical communication.
+        if(c0==1) z q[2];
+        if (c1==1) x q[2];
+        "
+);
+
+/// Gates with docstrings, comments in odd places and blank lines.
+const GATES: &str = indoc!(
+    "
+        OPENQASM 2.0;
+        // The identity.
+        gate id a { }
+        // A rotation
+        //   with two angles.
+        gate rot(theta,phi) a {
+          U(theta, phi, -phi + 2*(pi - theta)/3) a;
+        }
+
+
+        // Not a docstring.
+
+        gate pair a, b
+        // In the header.
+        {
+          // First.
+          rot(-(pi^2), ln(2)) a; barrier a, b;
+          CX a, b; // Last.
+        }
+        opaque magic(lambda) a;
+        qreg q[2]; creg c[2];
+        pair q[0],
+          // Interleaved.
+          q[1];
+        reset q[1];
+        measure q -> c;
+        "
+);
+
+/// Programs using the extensions to OPENQASM 2.0.
+const EXTENSIONS: &str = indoc!(
+    "
+        OPENQASM 2.0;
+        include \"stdgates.inc\";
+        gate g a { inv @ pow(2) @ s a; }
+        qreg q[3];
+        creg c[2];
+        ctrl(2) @ x q[0], q[1], q[2];
+        ctrl @ inv @ g q[0], q[1];
+        if (c[0] == 1 && c[1] != 0) U(atan2(1, 2), mod(tau, 3), euler) q[0];
+        if (c >= 2) CX q[0], q[1];
+        "
+);
+
+/// Return the programs of the `samples` directory followed by the programs
+/// above.
+fn corpus() -> Vec<String> {
+    let samples = Path::new(env!("CARGO_MANIFEST_DIR")).join("samples");
+    let mut corpus: Vec<String> = fs::read_dir(samples)
+        .unwrap()
+        .map(|entry| fs::read_to_string(entry.unwrap().path()).unwrap())
+        .collect();
+    corpus.extend(
+        vec![TELEPORTATION, GATES, EXTENSIONS]
+            .into_iter()
+            .map(String::from),
+    );
+    corpus
+}
+
+fn options() -> LanguageOptions {
+    Extension::all()
+        .iter()
+        .fold(LanguageOptions::new(), |options, extension| {
+            options.allow(*extension)
+        })
+}
+
+fn format(source: &str) -> String {
+    format_source(source, &options()).unwrap()
+}
+
+fn parse(source: &str) -> OpenQasmProgram {
+    parse_program_with_options(source, &options()).unwrap()
+}
+
+/// Return the version and the statements of `program`, ignoring the
+/// locations of the statements.
+fn nodes(program: &OpenQasmProgram) -> (&str, Vec<Statement>) {
+    fn without_locations(span: &Span<Statement>) -> Statement {
+        match &*span.node {
+            Statement::IfElse {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                let branch = |statements: &[Span<Statement>]| {
+                    statements
+                        .iter()
+                        .map(|span| Span::new((Location(0), Location(0)), without_locations(span)))
+                        .collect()
+                };
+                Statement::IfElse {
+                    condition: condition.clone(),
+                    then_branch: branch(then_branch),
+                    else_branch: branch(else_branch),
+                }
+            }
+            statement => statement.clone(),
+        }
+    }
+
+    (
+        &program.version,
+        program.program.iter().map(without_locations).collect(),
+    )
+}
+
+#[test]
+fn test_formatting_preserves_the_simplified_ast() {
+    for source in corpus() {
+        let formatted = format(&source);
+        assert_eq!(
+            nodes(&parse(&formatted).simplify()),
+            nodes(&parse(&source).simplify()),
+            "\n{}",
+            formatted
+        );
+    }
+}
+
+#[test]
+fn test_formatting_is_idempotent() {
+    for source in corpus() {
+        let formatted = format(&source);
+        assert_eq!(format(&formatted), formatted);
+    }
+}
+
+#[test]
+fn test_formatting_keeps_every_comment() {
+    for source in corpus() {
+        let formatted = format(&source);
+        let comments = |text: &str| -> Vec<String> {
+            let mut comments: Vec<String> = text
+                .lines()
+                .filter_map(|line| line.find("//").map(|start| line[start..].into()))
+                .collect();
+            comments.sort();
+            comments
+        };
+        assert_eq!(comments(&formatted), comments(&source));
+    }
+}
+
+#[test]
+fn test_format_gates() {
+    let formatted = format(GATES);
+    assert_eq!(
+        formatted,
+        indoc!(
+            "
+            OPENQASM 2.0;
+            // The identity.
+            gate id a {}
+            // A rotation
+            //   with two angles.
+            gate rot(theta, phi) a {
+              U(theta, phi, -phi + 2 * (pi - theta) / 3) a;
+            }
+
+            // Not a docstring.
+
+            gate pair a, b {
+              // In the header.
+              // First.
+              rot(-9.869604401089358, 0.6931471805599453) a;
+              barrier a, b;
+              CX a, b; // Last.
+            }
+            opaque magic(lambda) a;
+            qreg q[2];
+            creg c[2];
+            // Interleaved.
+            pair q[0], q[1];
+            reset q[1];
+            measure q -> c;
+            "
+        )
+    );
+}
+
+#[test]
+fn test_format_simplifies_parameters() {
+    let formatted = format(indoc!(
+        "
+        OPENQASM 2.0;
+        qreg q[1];
+        U(tau / 4, 3 * pi, 1 - 1) q[0]; // Normalized.
+        "
+    ));
+    assert_eq!(
+        formatted,
+        indoc!(
+            "
+            OPENQASM 2.0;
+            qreg q[1];
+            U(pi / 2, pi, 0) q[0]; // Normalized.
+            "
+        )
+    );
+}
+
+#[test]
+fn test_cannot_format_open_qasm3_programs() {
+    let source = "OPENQASM 3;\nqubit q;\n";
+    let error = format_source(source, &LanguageOptions::new()).unwrap_err();
+    assert!(matches!(error, QasmSimError::UnsupportedFeature { .. }));
+}
+
+#[test]
+fn test_to_qasm_preserves_the_simplified_ast() {
+    for source in corpus() {
+        let program = parse(&source);
+        let emitted = to_qasm(&program);
+        assert_eq!(
+            nodes(&parse(&emitted).simplify()),
+            nodes(&program.simplify()),
+            "\n{}",
+            emitted
+        );
+    }
+}
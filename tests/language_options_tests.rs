@@ -0,0 +1,214 @@
+#![cfg(test)]
+
+extern crate qasmsim;
+
+use indoc::indoc;
+use qasmsim::{Extension, LanguageOptions, OpaqueGates, QasmSimError};
+
+fn run_strict(source: &str) -> Result<qasmsim::Execution, QasmSimError<'_>> {
+    qasmsim::run_with_options(
+        source,
+        None,
+        &LanguageOptions::strict(),
+        &OpaqueGates::new(),
+    )
+}
+
+fn required_extension(error: QasmSimError<'_>) -> Extension {
+    match error {
+        QasmSimError::DisabledExtension { extension, .. } => extension,
+        other => panic!("expected a disabled extension error, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_strict_mode_accepts_standard_programs() {
+    let source = indoc!(
+        "
+        OPENQASM 2.0;
+        include \"qelib1.inc\";
+        gate bell a, b {
+          h a;
+          cx a, b;
+        }
+        qreg q[2];
+        creg c[2];
+        bell q[0], q[1];
+        measure q -> c;
+        if (c==3) x q[0];
+        "
+    );
+    assert!(run_strict(source).is_ok());
+}
+
+#[test]
+fn test_strict_mode_rejects_gate_modifiers() {
+    let sources = vec![
+        indoc!(
+            "
+            OPENQASM 2.0;
+            include \"qelib1.inc\";
+            qreg q[2];
+            ctrl @ h q[0], q[1];
+            "
+        ),
+        indoc!(
+            "
+            OPENQASM 2.0;
+            include \"qelib1.inc\";
+            gate ch2 a, b { ctrl @ h a, b; }
+            qreg q[2];
+            ch2 q[0], q[1];
+            "
+        ),
+    ];
+    for source in sources {
+        let error = run_strict(source).expect_err("should fail");
+        assert_eq!(required_extension(error), Extension::GateModifiers);
+    }
+}
+
+#[test]
+fn test_strict_mode_rejects_other_libraries() {
+    let source = indoc!(
+        "
+        OPENQASM 2.0;
+        include \"stdgates.inc\";
+        qreg q[1];
+        "
+    );
+    let error = run_strict(source).expect_err("should fail");
+    assert_eq!(
+        error,
+        QasmSimError::DisabledExtension {
+            source: "include \"stdgates.inc\";\n",
+            lineno: 2,
+            startpos: 0,
+            extension: Extension::EmbeddedLibraries
+        }
+    );
+    assert_eq!(
+        format!("{}", error),
+        indoc!(
+            "
+            error: libraries other than `qelib1.inc` are not part of OPENQASM 2.0
+              |
+            2 | include \"stdgates.inc\";
+              | ^ help: enable the `embedded-libraries` extension to allow them
+            "
+        )
+    );
+}
+
+#[test]
+fn test_strict_mode_rejects_forward_references() {
+    let source = indoc!(
+        "
+        OPENQASM 2.0;
+        qreg q[1];
+        flip q[0];
+        gate flip a { U(pi, 0, pi) a; }
+        "
+    );
+    let error = run_strict(source).expect_err("should fail");
+    assert_eq!(
+        error,
+        QasmSimError::DisabledExtension {
+            source: "flip q[0];\n",
+            lineno: 3,
+            startpos: 0,
+            extension: Extension::ForwardReferences
+        }
+    );
+}
+
+#[test]
+fn test_strict_mode_does_not_hide_undefined_gates() {
+    let source = indoc!(
+        "
+        OPENQASM 2.0;
+        qreg q[1];
+        flip q[0];
+        "
+    );
+    assert!(matches!(
+        run_strict(source),
+        Err(QasmSimError::UndefinedGate { .. })
+    ));
+}
+
+#[test]
+fn test_strict_mode_rejects_open_qasm3() {
+    let source = indoc!(
+        "
+        OPENQASM 3;
+        qubit q;
+        "
+    );
+    let error = run_strict(source).expect_err("should fail");
+    assert_eq!(required_extension(error), Extension::OpenQasm3);
+}
+
+#[test]
+fn test_extensions_can_be_enabled_one_by_one() {
+    let source = indoc!(
+        "
+        OPENQASM 2.0;
+        include \"qelib1.inc\";
+        qreg q[2];
+        creg c[1];
+        ctrl @ x q[0], q[1];
+        if (c[0]==0) x q[0];
+        "
+    );
+    let options = LanguageOptions::strict().allow(Extension::GateModifiers);
+    let error = qasmsim::run_with_options(source, None, &options, &OpaqueGates::new())
+        .expect_err("should fail");
+    assert_eq!(required_extension(error), Extension::ExtendedConditionals);
+
+    let options = options.allow(Extension::ExtendedConditionals);
+    assert!(qasmsim::run_with_options(source, None, &options, &OpaqueGates::new()).is_ok());
+}
+
+#[test]
+fn test_strict_mode_rejects_extended_math() {
+    let source = indoc!(
+        "
+        OPENQASM 2.0;
+        gate r a { U(tau, 0, 0) a; }
+        "
+    );
+    let error = run_strict(source).expect_err("should fail");
+    assert_eq!(required_extension(error), Extension::ExtendedMath);
+}
+
+#[test]
+fn test_strict_mode_applies_to_gate_info_unitaries_and_equivalence() {
+    let source = indoc!(
+        "
+        OPENQASM 2.0;
+        include \"qelib1.inc\";
+        // controlled-NOT
+        gate cnot a, b { ctrl @ x a, b; }
+        qreg q[2];
+        cnot q[0], q[1];
+        "
+    );
+    let strict = LanguageOptions::strict();
+
+    let error = qasmsim::get_gate_info(source, "cnot", &strict).expect_err("should fail");
+    assert_eq!(required_extension(error), Extension::GateModifiers);
+
+    let error = qasmsim::extract_unitary(source, &strict).expect_err("should fail");
+    assert_eq!(required_extension(error), Extension::GateModifiers);
+
+    let error = qasmsim::check_equivalence(source, source, &strict).expect_err("should fail");
+    assert_eq!(required_extension(error), Extension::GateModifiers);
+
+    let options = strict.allow(Extension::GateModifiers);
+    assert!(qasmsim::get_gate_info(source, "cnot", &options).is_ok());
+    assert!(qasmsim::extract_unitary(source, &options).is_ok());
+    assert!(qasmsim::check_equivalence(source, source, &options)
+        .expect("can compare")
+        .is_equivalent());
+}
@@ -0,0 +1,102 @@
+#![cfg(test)]
+
+extern crate qasmsim;
+
+use indoc::indoc;
+
+use qasmsim::lint::{Lint, Warning};
+use qasmsim::{lint, LanguageOptions};
+
+#[test]
+fn test_lint_valid_program() {
+    let source = indoc!(
+        "
+  OPENQASM 2.0;
+  include \"qelib1.inc\";
+  qreg q[2];
+  creg c[2];
+  h q[0];
+  cx q[0], q[1];
+  measure q -> c;
+  "
+    );
+    let warnings = lint(source, &LanguageOptions::new()).unwrap();
+    assert_eq!(warnings, vec![]);
+}
+
+#[test]
+fn test_lint_underlines_the_offending_argument() {
+    let source = indoc!(
+        "
+  OPENQASM 2.0;
+  include \"qelib1.inc\";
+  qreg q[2];
+  creg c[2];
+  measure q[0] -> c[0];
+  measure q[1] -> c[0];
+  if (c == 1) x q[0];
+  "
+    );
+    let warnings = lint(source, &LanguageOptions::new()).unwrap();
+    assert_eq!(
+        warnings[0],
+        Warning {
+            source: "measure q[0] -> c[0];\n",
+            lineno: 5,
+            startpos: 16,
+            endpos: Some(20),
+            lint: Lint::OverwrittenMeasurement {
+                symbol_name: "c".into(),
+                index: 0
+            }
+        }
+    );
+    assert_eq!(
+        format!("{}", warnings[0]),
+        indoc!(
+            "
+            warning: the measurement stored in `c[0]` is overwritten
+              |
+            5 | measure q[0] -> c[0];
+              |                 ^^^^ help: the bit is written again before being read
+            "
+        )
+    );
+}
+
+#[test]
+fn test_lint_fails_on_syntax_errors() {
+    let source = indoc!(
+        "
+  OPENQASM 2.0;
+  qreg q[2]
+  "
+    );
+    assert!(lint(source, &LanguageOptions::new()).is_err());
+}
+
+#[test]
+fn test_program_gates_shadow_library_gates() {
+    let source = indoc!(
+        "
+  OPENQASM 2.0;
+  include \"qelib1.inc\";
+  gate x a { U(0, 0, 0) a; }
+  qreg q[1];
+  x q[0];
+  "
+    );
+    let warnings = lint(source, &LanguageOptions::new()).unwrap();
+    assert_eq!(
+        warnings
+            .iter()
+            .map(|warning| &warning.lint)
+            .collect::<Vec<_>>(),
+        vec![&Lint::ShadowedGate {
+            symbol_name: "x".into(),
+            libpath: "qelib1.inc".into()
+        }]
+    );
+    let statevector = qasmsim::run(source, None).unwrap().statevector().clone();
+    assert!(statevector.as_complex_bases()[0].norm() > 0.99);
+}
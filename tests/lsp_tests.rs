@@ -0,0 +1,253 @@
+#![cfg(all(test, feature = "cli"))]
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+use indoc::indoc;
+use serde_json::{json, Value};
+
+/// A minimal JSON-RPC client talking to the language server over stdio.
+struct Client {
+    server: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    next_id: u64,
+}
+
+impl Client {
+    fn start() -> Self {
+        let mut server = Command::new(env!("CARGO_BIN_EXE_qasmsim-lsp"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("start the server");
+        let stdin = server.stdin.take().unwrap();
+        let stdout = BufReader::new(server.stdout.take().unwrap());
+        Client {
+            server,
+            stdin,
+            stdout,
+            next_id: 1,
+        }
+    }
+
+    fn send(&mut self, message: Value) {
+        let content = message.to_string();
+        write!(
+            self.stdin,
+            "Content-Length: {}\r\n\r\n{}",
+            content.len(),
+            content
+        )
+        .unwrap();
+        self.stdin.flush().unwrap();
+    }
+
+    fn receive(&mut self) -> Value {
+        let mut content_length = 0;
+        loop {
+            let mut header = String::new();
+            self.stdout.read_line(&mut header).unwrap();
+            let header = header.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            if let Some(length) = header.strip_prefix("Content-Length: ") {
+                content_length = length.parse().unwrap();
+            }
+        }
+        let mut content = vec![0; content_length];
+        self.stdout.read_exact(&mut content).unwrap();
+        serde_json::from_slice(&content).unwrap()
+    }
+
+    fn request(&mut self, method: &str, params: Value) -> Value {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.send(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }));
+        let response = self.receive();
+        assert_eq!(response["id"], id);
+        response["result"].clone()
+    }
+
+    fn notify(&mut self, method: &str, params: Value) {
+        self.send(json!({ "jsonrpc": "2.0", "method": method, "params": params }));
+    }
+}
+
+const URI: &str = "file:///program.qasm";
+
+fn source() -> &'static str {
+    indoc!(
+        "
+    OPENQASM 2.0;
+    include \"qelib1.inc\";
+    // Flip a qubit.
+    gate flip a { x a; }
+    qreg q[2];
+    qreg r[1];
+    flip q[0];
+    h q[2];
+    "
+    )
+}
+
+fn position(line: u64, character: u64) -> Value {
+    json!({
+        "textDocument": { "uri": URI },
+        "position": { "line": line, "character": character }
+    })
+}
+
+fn open(client: &mut Client, text: &str) -> Value {
+    client.request("initialize", json!({ "capabilities": {} }));
+    client.notify("initialized", json!({}));
+    client.notify(
+        "textDocument/didOpen",
+        json!({
+            "textDocument": { "uri": URI, "languageId": "qasm", "version": 1, "text": text }
+        }),
+    );
+    client.receive()
+}
+
+fn shut_down(mut client: Client) {
+    assert_eq!(client.request("shutdown", Value::Null), Value::Null);
+    client.notify("exit", Value::Null);
+    assert!(client.server.wait().unwrap().success());
+}
+
+#[test]
+fn test_initialize_announces_the_capabilities() {
+    let mut client = Client::start();
+    let result = client.request("initialize", json!({ "capabilities": {} }));
+    assert_eq!(result["capabilities"]["hoverProvider"], true);
+    assert_eq!(result["capabilities"]["definitionProvider"], true);
+    assert_eq!(result["capabilities"]["documentSymbolProvider"], true);
+    assert_eq!(result["serverInfo"]["name"], "qasmsim-lsp");
+    shut_down(client);
+}
+
+#[test]
+fn test_publish_diagnostics_on_open_and_change() {
+    let mut client = Client::start();
+    let notification = open(&mut client, source());
+    assert_eq!(notification["method"], "textDocument/publishDiagnostics");
+    assert_eq!(
+        notification["params"]["diagnostics"],
+        json!([
+            {
+                "range": {
+                    "start": { "line": 7, "character": 2 },
+                    "end": { "line": 7, "character": 6 }
+                },
+                "severity": 1,
+                "code": "IndexOutOfBounds",
+                "source": "qasmsim",
+                "message": "index out of bounds\nhelp: indices of register `q` range from 0 to 1 but the index is 2"
+            },
+            {
+                "range": {
+                    "start": { "line": 5, "character": 0 },
+                    "end": { "line": 5, "character": 10 }
+                },
+                "severity": 2,
+                "code": "UnusedRegister",
+                "source": "qasmsim",
+                "message": "register `r` is never used\nhelp: consider removing the declaration"
+            }
+        ])
+    );
+
+    client.notify(
+        "textDocument/didChange",
+        json!({
+            "textDocument": { "uri": URI, "version": 2 },
+            "contentChanges": [{ "text": "OPENQASM 2.0;\nqreg q[2]" }]
+        }),
+    );
+    let notification = client.receive();
+    let diagnostics = notification["params"]["diagnostics"].as_array().unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0]["code"], "UnexpectedEOF");
+    shut_down(client);
+}
+
+#[test]
+fn test_hover_shows_the_gate_documentation() {
+    let mut client = Client::start();
+    open(&mut client, source());
+    let hover = client.request("textDocument/hover", position(6, 2));
+    assert_eq!(
+        hover["contents"]["value"],
+        "```qasm\ngate flip a\n```\n\nFlip a qubit."
+    );
+    let hover = client.request("textDocument/hover", position(7, 0));
+    assert!(hover["contents"]["value"]
+        .as_str()
+        .unwrap()
+        .starts_with("```qasm\ngate h a\n```"));
+    assert_eq!(
+        client.request("textDocument/hover", position(0, 0)),
+        Value::Null
+    );
+    shut_down(client);
+}
+
+#[test]
+fn test_go_to_definition_of_gates_and_registers() {
+    let mut client = Client::start();
+    open(&mut client, source());
+    let definition = client.request("textDocument/definition", position(7, 2));
+    assert_eq!(
+        definition,
+        json!({
+            "uri": URI,
+            "range": {
+                "start": { "line": 4, "character": 0 },
+                "end": { "line": 4, "character": 10 }
+            }
+        })
+    );
+    let definition = client.request("textDocument/definition", position(6, 0));
+    // The definition of a gate starts at its documentation comment.
+    assert_eq!(
+        definition["range"]["start"],
+        json!({ "line": 2, "character": 0 })
+    );
+    shut_down(client);
+}
+
+#[test]
+fn test_completion_and_document_symbols() {
+    let mut client = Client::start();
+    open(&mut client, source());
+    let completion = client.request("textDocument/completion", position(7, 0));
+    let labels: Vec<&str> = completion
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["label"].as_str().unwrap())
+        .collect();
+    assert!(labels.contains(&"flip"));
+    assert!(labels.contains(&"h"));
+    assert!(labels.contains(&"q"));
+
+    let symbols = client.request(
+        "textDocument/documentSymbol",
+        json!({ "textDocument": { "uri": URI } }),
+    );
+    let names: Vec<(&str, u64)> = symbols
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|symbol| {
+            (
+                symbol["name"].as_str().unwrap(),
+                symbol["kind"].as_u64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(names, vec![("flip", 12), ("q", 13), ("r", 13)]);
+    shut_down(client);
+}
@@ -0,0 +1,259 @@
+#![cfg(test)]
+
+extern crate qasmsim;
+
+use indoc::indoc;
+use qasmsim::statevector::{assert_approx_eq, StateVector};
+use qasmsim::QasmSimError;
+
+#[test]
+fn test_ctrl_modifier_on_a_macro() {
+    let source = indoc!(
+        "
+        OPENQASM 2.0;
+        include \"qelib1.inc\";
+        qreg q[3];
+        h q[0];
+        h q[1];
+        ctrl(2) @ x q[0], q[1], q[2];
+        "
+    );
+    let expected = indoc!(
+        "
+        OPENQASM 2.0;
+        include \"qelib1.inc\";
+        qreg q[3];
+        h q[0];
+        h q[1];
+        ccx q[0], q[1], q[2];
+        "
+    );
+    assert_approx_eq(
+        qasmsim::run(source, None).unwrap().statevector(),
+        qasmsim::run(expected, None).unwrap().statevector(),
+    )
+}
+
+#[test]
+fn test_ctrl_modifier_replaces_hand_written_controlled_gates() {
+    let source = indoc!(
+        "
+        OPENQASM 2.0;
+        include \"qelib1.inc\";
+        qreg q[2];
+        h q;
+        ctrl @ u3(0.3, 0.2, 0.1) q[0], q[1];
+        "
+    );
+    let expected = indoc!(
+        "
+        OPENQASM 2.0;
+        include \"qelib1.inc\";
+        qreg q[2];
+        h q;
+        cu3(0.3, 0.2, 0.1) q[0], q[1];
+        "
+    );
+    assert_approx_eq(
+        qasmsim::run(source, None).unwrap().statevector(),
+        qasmsim::run(expected, None).unwrap().statevector(),
+    )
+}
+
+#[test]
+fn test_inv_modifier_reverses_the_body() {
+    let source = indoc!(
+        "
+        OPENQASM 2.0;
+        include \"qelib1.inc\";
+        gate circuit a, b {
+          h a;
+          cx a, b;
+          rz(0.4) b;
+          ry(0.7) a;
+        }
+        qreg q[2];
+        circuit q[0], q[1];
+        inv @ circuit q[0], q[1];
+        "
+    );
+    assert_approx_eq(
+        qasmsim::run(source, None).unwrap().statevector(),
+        &StateVector::new(2),
+    )
+}
+
+#[test]
+fn test_pow_modifier() {
+    let source = indoc!(
+        "
+        OPENQASM 2.0;
+        include \"qelib1.inc\";
+        qreg q[1];
+        h q;
+        pow(4) @ t q;
+        pow(-2) @ s q;
+        h q;
+        "
+    );
+    assert_approx_eq(
+        qasmsim::run(source, None).unwrap().statevector(),
+        &StateVector::new(1),
+    )
+}
+
+#[test]
+fn test_modifiers_inside_gate_definitions() {
+    let source = indoc!(
+        "
+        OPENQASM 2.0;
+        include \"qelib1.inc\";
+        gate my_cz a, b { ctrl @ z a, b; }
+        qreg q[2];
+        h q;
+        my_cz q[0], q[1];
+        "
+    );
+    let expected = indoc!(
+        "
+        OPENQASM 2.0;
+        include \"qelib1.inc\";
+        qreg q[2];
+        h q;
+        cz q[0], q[1];
+        "
+    );
+    assert_approx_eq(
+        qasmsim::run(source, None).unwrap().statevector(),
+        qasmsim::run(expected, None).unwrap().statevector(),
+    )
+}
+
+#[test]
+fn test_modifiers_expand_over_registers() {
+    let source = indoc!(
+        "
+        OPENQASM 2.0;
+        include \"qelib1.inc\";
+        qreg c[2];
+        qreg t[2];
+        x c;
+        ctrl @ x c, t;
+        "
+    );
+    let statevector = qasmsim::run(source, None).unwrap().statevector().clone();
+    assert!(statevector.as_complex_bases()[0b1111].norm() > 0.99);
+}
+
+#[test]
+fn test_ctrl_modifier_with_wrong_number_of_arguments() {
+    let source = indoc!(
+        "
+        OPENQASM 2.0;
+        include \"qelib1.inc\";
+        qreg q[2];
+        ctrl(2) @ x q[0], q[1];
+        "
+    );
+    let error = qasmsim::run(source, None).expect_err("should fail");
+    assert_eq!(
+        error,
+        QasmSimError::WrongNumberOfParameters {
+            source: "ctrl(2) @ x q[0], q[1];\n",
+            lineno: 4,
+            startpos: 12,
+            endpos: Some(22),
+            symbol_name: "x".into(),
+            are_registers: true,
+            given: 2,
+            expected: 3
+        }
+    );
+}
+
+#[test]
+fn test_modifiers_on_opaque_gates_are_rejected() {
+    let source = indoc!(
+        "
+        OPENQASM 2.0;
+        opaque magic q;
+        qreg q[1];
+        inv @ magic q[0];
+        "
+    );
+    let error = qasmsim::run(source, None).expect_err("should fail");
+    assert_eq!(
+        error,
+        QasmSimError::ModifiedOpaqueGate {
+            source: "inv @ magic q[0];\n",
+            lineno: 4,
+            symbol_name: "magic".into()
+        }
+    );
+}
+
+#[test]
+fn test_pow_modifier_with_a_huge_exponent_is_rejected() {
+    let source = indoc!(
+        "
+        OPENQASM 2.0;
+        include \"qelib1.inc\";
+        qreg q[1];
+        pow(1000000000) @ x q[0];
+        "
+    );
+    let error = qasmsim::run(source, None).expect_err("should fail");
+    assert_eq!(
+        error,
+        QasmSimError::PowerTooLarge {
+            source: "pow(1000000000) @ x q[0];\n",
+            lineno: 4,
+            symbol_name: "x".into(),
+            exponent: "1000000000".into()
+        }
+    );
+}
+
+#[test]
+fn test_unknown_modifiers_are_rejected() {
+    let source = indoc!(
+        "
+        OPENQASM 2.0;
+        include \"qelib1.inc\";
+        qreg q[1];
+        foo @ x q[0];
+        "
+    );
+    let error = qasmsim::run(source, None).expect_err("should fail");
+    assert_eq!(
+        error,
+        QasmSimError::InvalidModifier {
+            source: "foo @ x q[0];\n",
+            lineno: 4,
+            startpos: 0,
+            modifier: "foo".into()
+        }
+    );
+}
+
+#[test]
+fn test_ctrl_modifier_without_controls_is_rejected() {
+    let source = indoc!(
+        "
+        OPENQASM 2.0;
+        include \"qelib1.inc\";
+        qreg q[1];
+        ctrl(0) @ x q[0];
+        "
+    );
+    let error = qasmsim::run(source, None).expect_err("should fail");
+    assert_eq!(
+        error,
+        QasmSimError::InvalidModifier {
+            source: "ctrl(0) @ x q[0];\n",
+            lineno: 4,
+            startpos: 0,
+            modifier: "ctrl(0)".into()
+        }
+    );
+}
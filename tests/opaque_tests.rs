@@ -0,0 +1,158 @@
+#![cfg(test)]
+
+extern crate qasmsim;
+
+use std::f64::consts::FRAC_1_SQRT_2;
+
+use indoc::indoc;
+
+use qasmsim::statevector::{assert_approx_eq, Complex, StateVector};
+use qasmsim::{MatrixGate, OpaqueGates, QasmSimError};
+
+fn hadamard() -> MatrixGate {
+    let amplitude = Complex::from(FRAC_1_SQRT_2);
+    MatrixGate::new(vec![
+        vec![amplitude, amplitude],
+        vec![amplitude, -amplitude],
+    ])
+    .unwrap()
+}
+
+#[test]
+fn test_opaque_gate_implemented_by_a_matrix() {
+    let source = indoc!(
+        "
+  OPENQASM 2.0;
+  opaque hadamard q;
+  qreg q[2];
+  hadamard q;
+  "
+    );
+    let mut opaque_gates = OpaqueGates::new();
+    opaque_gates.bind("hadamard", hadamard());
+    let execution = qasmsim::run_with_opaque_gates(source, None, &opaque_gates).unwrap();
+    assert_approx_eq(
+        execution.statevector(),
+        &StateVector::from_complex_bases(vec![Complex::from(0.5); 4]),
+    );
+}
+
+#[test]
+fn test_matrix_qubits_follow_the_order_of_the_arguments() {
+    let source = indoc!(
+        "
+  OPENQASM 2.0;
+  opaque cnot c, t;
+  qreg q[2];
+  U(pi, 0, pi) q[1];
+  cnot q[1], q[0];
+  "
+    );
+    let zero = Complex::from(0.0);
+    let one = Complex::from(1.0);
+    let mut opaque_gates = OpaqueGates::new();
+    opaque_gates.bind(
+        "cnot",
+        MatrixGate::new(vec![
+            vec![one, zero, zero, zero],
+            vec![zero, zero, zero, one],
+            vec![zero, zero, one, zero],
+            vec![zero, one, zero, zero],
+        ])
+        .unwrap(),
+    );
+    let execution = qasmsim::run_with_opaque_gates(source, None, &opaque_gates).unwrap();
+    assert_approx_eq(
+        execution.statevector(),
+        &StateVector::from_complex_bases(vec![zero, zero, zero, one]),
+    );
+}
+
+#[test]
+fn test_opaque_gate_implemented_by_a_closure() {
+    let source = indoc!(
+        "
+  OPENQASM 2.0;
+  opaque rx(theta) q;
+  gate flip q { rx(pi) q; }
+  qreg q[1];
+  creg c[1];
+  flip q[0];
+  measure q -> c;
+  "
+    );
+    let mut opaque_gates = OpaqueGates::new();
+    opaque_gates.bind(
+        "rx",
+        |statevector: &mut StateVector, real_args: &[f64], qubits: &[usize]| {
+            let theta = real_args[0];
+            statevector.u(
+                theta,
+                -std::f64::consts::FRAC_PI_2,
+                std::f64::consts::FRAC_PI_2,
+                qubits[0],
+            );
+        },
+    );
+    let execution = qasmsim::run_with_opaque_gates(source, Some(10), &opaque_gates).unwrap();
+    let histogram = execution.histogram().as_ref().unwrap();
+    assert_eq!(histogram["c"], vec![(1, 10)]);
+}
+
+#[test]
+fn test_matrix_width_must_match_the_call() {
+    let source = indoc!(
+        "
+  OPENQASM 2.0;
+  opaque hadamard a, b;
+  qreg q[2];
+  hadamard q[0], q[1];
+  "
+    );
+    let mut opaque_gates = OpaqueGates::new();
+    opaque_gates.bind("hadamard", hadamard());
+    let error =
+        qasmsim::run_with_opaque_gates(source, None, &opaque_gates).expect_err("should fail");
+    assert_eq!(
+        error,
+        QasmSimError::WrongNumberOfParameters {
+            source: "hadamard q[0], q[1];\n",
+            symbol_name: "hadamard".into(),
+            lineno: 4,
+            startpos: 9,
+            endpos: Some(19),
+            are_registers: true,
+            given: 2,
+            expected: 1
+        }
+    );
+}
+
+#[test]
+fn test_calls_must_match_the_opaque_declaration() {
+    let source = indoc!(
+        "
+  OPENQASM 2.0;
+  opaque hadamard q;
+  qreg q[2];
+  hadamard(pi) q[0];
+  "
+    );
+    let mut opaque_gates = OpaqueGates::new();
+    opaque_gates.bind("hadamard", hadamard());
+    let error =
+        qasmsim::run_with_opaque_gates(source, None, &opaque_gates).expect_err("should fail");
+    assert_eq!(
+        error,
+        QasmSimError::WrongNumberOfParameters {
+            source: "hadamard(pi) q[0];\n",
+            symbol_name: "hadamard".into(),
+            lineno: 4,
+            startpos: 9,
+            endpos: Some(11),
+            are_registers: false,
+            given: 1,
+            expected: 0
+        }
+    );
+}
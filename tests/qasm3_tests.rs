@@ -0,0 +1,303 @@
+#![cfg(test)]
+
+extern crate qasmsim;
+
+use std::f64::consts::FRAC_1_SQRT_2;
+
+use indoc::indoc;
+use qasmsim::statevector::{assert_approx_eq, Complex, StateVector};
+use qasmsim::QasmSimError;
+
+#[test]
+fn test_bell_circuit() {
+    let source = indoc!(
+        "
+        OPENQASM 3;
+        include \"stdgates.inc\";
+        qubit[2] q;
+        h q[0];
+        cx q[0], q[1];
+        "
+    );
+    assert_approx_eq(
+        qasmsim::run(source, None).unwrap().statevector(),
+        &StateVector::from_complex_bases(vec![
+            Complex::from(FRAC_1_SQRT_2),
+            Complex::from(0.0),
+            Complex::from(0.0),
+            Complex::from(FRAC_1_SQRT_2),
+        ]),
+    )
+}
+
+#[test]
+fn test_ctrl_modifier_is_equivalent_to_cx() {
+    let source = indoc!(
+        "
+        OPENQASM 3;
+        include \"stdgates.inc\";
+        qubit[2] q;
+        h q[0];
+        ctrl @ x q[0], q[1];
+        "
+    );
+    let expected = indoc!(
+        "
+        OPENQASM 3;
+        include \"stdgates.inc\";
+        qubit[2] q;
+        h q[0];
+        cx q[0], q[1];
+        "
+    );
+    assert_approx_eq(
+        qasmsim::run(source, None).unwrap().statevector(),
+        qasmsim::run(expected, None).unwrap().statevector(),
+    )
+}
+
+#[test]
+fn test_ctrl_modifier_with_several_controls() {
+    let source = indoc!(
+        "
+        OPENQASM 3;
+        include \"stdgates.inc\";
+        qubit[3] q;
+        x q[0];
+        x q[1];
+        ctrl(2) @ x q[0], q[1], q[2];
+        "
+    );
+    assert_approx_eq(
+        qasmsim::run(source, None).unwrap().statevector(),
+        &StateVector::from_complex_bases(
+            (0..8)
+                .map(|index| Complex::from(if index == 0b111 { 1.0 } else { 0.0 }))
+                .collect(),
+        ),
+    )
+}
+
+#[test]
+fn test_inv_modifier_undoes_the_gate() {
+    let source = indoc!(
+        "
+        OPENQASM 3;
+        include \"stdgates.inc\";
+        qubit q;
+        h q;
+        s q;
+        t q;
+        inv @ t q;
+        inv @ s q;
+        h q;
+        "
+    );
+    assert_approx_eq(
+        qasmsim::run(source, None).unwrap().statevector(),
+        &StateVector::new(1),
+    )
+}
+
+#[test]
+fn test_pow_modifier_repeats_the_gate() {
+    let source = indoc!(
+        "
+        OPENQASM 3;
+        include \"stdgates.inc\";
+        qubit q;
+        h q;
+        pow(2) @ s q;
+        "
+    );
+    let expected = indoc!(
+        "
+        OPENQASM 3;
+        include \"stdgates.inc\";
+        qubit q;
+        h q;
+        z q;
+        "
+    );
+    assert_approx_eq(
+        qasmsim::run(source, None).unwrap().statevector(),
+        qasmsim::run(expected, None).unwrap().statevector(),
+    )
+}
+
+#[test]
+fn test_non_integer_powers_are_not_supported() {
+    let source = indoc!(
+        "
+        OPENQASM 3;
+        include \"stdgates.inc\";
+        qubit q;
+        pow(0.5) @ x q;
+        "
+    );
+    let error = qasmsim::run(source, None).expect_err("should fail");
+    assert_eq!(
+        error,
+        QasmSimError::NonIntegerPower {
+            source: "pow(0.5) @ x q;\n",
+            lineno: 4,
+            symbol_name: "x".into(),
+            exponent: "0.5".into()
+        }
+    );
+}
+
+#[test]
+fn test_for_loop() {
+    let source = indoc!(
+        "
+        OPENQASM 3;
+        include \"stdgates.inc\";
+        qubit[3] q;
+        bit[3] c;
+        for int i in [0:2] {
+          x q[i];
+        }
+        c = measure q;
+        "
+    );
+    let result = qasmsim::run(source, None).unwrap();
+    assert_eq!(*result.memory().get("c").unwrap(), 0b111);
+}
+
+#[test]
+fn test_if_else_on_a_measured_bit() {
+    let source = indoc!(
+        "
+        OPENQASM 3;
+        include \"stdgates.inc\";
+        qubit[2] q;
+        bit[2] c;
+        x q[0];
+        c[0] = measure q[0];
+        if (c[0]) {
+          x q[1];
+        } else {
+          h q[1];
+        }
+        c[1] = measure q[1];
+        "
+    );
+    let result = qasmsim::run(source, None).unwrap();
+    assert_eq!(*result.memory().get("c").unwrap(), 0b11);
+}
+
+#[test]
+fn test_reset() {
+    let source = indoc!(
+        "
+        OPENQASM 3;
+        include \"stdgates.inc\";
+        qubit q;
+        x q;
+        reset q;
+        "
+    );
+    assert_approx_eq(
+        qasmsim::run(source, None).unwrap().statevector(),
+        &StateVector::new(1),
+    )
+}
+
+#[test]
+fn test_constants() {
+    let source = indoc!(
+        "
+        OPENQASM 3.0;
+        include \"stdgates.inc\";
+        const int size = 2;
+        angle half_turn = pi;
+        qubit[size] q;
+        bit[size] c;
+        rx(half_turn) q[size - 1];
+        c = measure q;
+        "
+    );
+    let result = qasmsim::run(source, None).unwrap();
+    assert_eq!(*result.memory().get("c").unwrap(), 0b10);
+}
+
+#[test]
+fn test_unsupported_keywords_are_rejected() {
+    let source = indoc!(
+        "
+        OPENQASM 3;
+        qubit q;
+        while (true) { }
+        "
+    );
+    let error = qasmsim::run(source, None).expect_err("should fail");
+    assert_eq!(
+        error,
+        QasmSimError::UnsupportedFeature {
+            source: "while (true) { }\n",
+            lineno: 3,
+            startpos: 0,
+            feature: "`while`".into()
+        }
+    );
+}
+
+#[test]
+fn test_classical_assignments_are_rejected() {
+    let source = indoc!(
+        "
+        OPENQASM 3;
+        int i = 0;
+        i = 1;
+        "
+    );
+    let error = qasmsim::run(source, None).expect_err("should fail");
+    assert_eq!(
+        error,
+        QasmSimError::UnsupportedFeature {
+            source: "i = 1;\n",
+            lineno: 3,
+            startpos: 0,
+            feature: "classical assignments".into()
+        }
+    );
+}
+
+#[test]
+fn test_unsupported_feature_message() {
+    let source = indoc!(
+        "
+        OPENQASM 3;
+        qubit q;
+        measure q;
+        "
+    );
+    let error = qasmsim::run(source, None).expect_err("should fail");
+    assert_eq!(
+        format!("{}", error),
+        indoc!(
+            "
+            error: unsupported feature: measurements without a target
+              |
+            3 | measure q;
+              | ^ help: qasmsim only supports a core subset of OPENQASM 3
+            "
+        )
+    );
+}
+
+#[test]
+fn test_open_qasm2_programs_keep_the_old_grammar() {
+    let source = indoc!(
+        "
+        OPENQASM 2.0;
+        qreg q[1];
+        qubit[1] r;
+        "
+    );
+    assert!(matches!(
+        qasmsim::run(source, None),
+        Err(QasmSimError::UnexpectedToken { .. })
+    ));
+}
@@ -42,6 +42,8 @@ fn test_using_a_quantum_register_while_expecting_classical() {
         QasmSimError::TypeMismatch {
             source: "measure q -> q;\n",
             lineno: 4,
+            startpos: 13,
+            endpos: Some(14),
             symbol_name: "q".into(),
             expected: QasmType::ClassicalRegister
         }
@@ -64,6 +66,8 @@ fn test_using_a_classical_register_when_expecting_quantum() {
         QasmSimError::TypeMismatch {
             source: "measure c -> c;\n",
             lineno: 4,
+            startpos: 8,
+            endpos: Some(9),
             symbol_name: "c".into(),
             expected: QasmType::QuantumRegister
         }
@@ -86,6 +90,8 @@ fn test_passing_a_classical_register_when_expecting_quantum() {
         QasmSimError::TypeMismatch {
             source: "h c;\n",
             lineno: 4,
+            startpos: 2,
+            endpos: Some(3),
             symbol_name: "c".into(),
             expected: QasmType::QuantumRegister
         }
@@ -108,6 +114,8 @@ fn test_passing_an_unexistent_register() {
         QasmSimError::SymbolNotFound {
             source: "h t;\n",
             lineno: 4,
+            startpos: 2,
+            endpos: Some(3),
             symbol_name: "t".into(),
             expected: QasmType::QuantumRegister
         }
@@ -131,6 +139,8 @@ fn test_passing_an_unexistent_real_parameter() {
             symbol_name: "xxx".into(),
             source: "u1(xxx) q;\n",
             lineno: 4,
+            startpos: 3,
+            endpos: Some(6),
             expected: QasmType::RealValue
         }
     );
@@ -153,6 +163,8 @@ fn test_passing_a_register_instead_of_real_parameter() {
             symbol_name: "q".into(),
             source: "u1(q) q;\n",
             lineno: 4,
+            startpos: 3,
+            endpos: Some(4),
             expected: QasmType::RealValue
         }
     );
@@ -176,6 +188,8 @@ fn test_pass_more_real_arguments_than_expected() {
             source: "u1(pi, pi, pi) q;\n",
             symbol_name: "u1".into(),
             lineno: 4,
+            startpos: 3,
+            endpos: Some(13),
             expected: 1,
             given: 3
         }
@@ -200,6 +214,8 @@ fn test_pass_more_registers_than_expected() {
             source: "u1(pi) q, q, q;\n",
             symbol_name: "u1".into(),
             lineno: 4,
+            startpos: 7,
+            endpos: Some(14),
             expected: 1,
             given: 3
         }
@@ -223,6 +239,8 @@ fn test_index_out_of_bounds() {
             source: "h q[3];\n",
             symbol_name: "q".into(),
             lineno: 4,
+            startpos: 2,
+            endpos: Some(6),
             size: 2,
             index: 3
         }
@@ -246,6 +264,8 @@ fn test_argument_expansion_with_different_size_registers() {
         QasmSimError::RegisterSizeMismatch {
             source: "cx q, r;\n",
             lineno: 5,
+            startpos: 3,
+            endpos: Some(7),
             symbol_name: "cx".into(),
             sizes: vec![1, 2]
         }
@@ -269,12 +289,38 @@ fn test_argument_expansion_in_measurement_with_different_size_registers() {
         QasmSimError::RegisterSizeMismatch {
             source: "measure q -> c;\n",
             lineno: 5,
+            startpos: 8,
+            endpos: Some(14),
             symbol_name: "measure".into(),
             sizes: vec![1, 2]
         }
     );
 }
 
+#[test]
+fn test_repeated_qubit_in_a_gate_call() {
+    let source = indoc!(
+        r#"
+  OPENQASM 2.0;
+  include "qelib1.inc";
+  qreg q[2];
+  ctrl @ x q, q[0];
+  "#
+    );
+    let error = qasmsim::run(source, None).expect_err("should fail");
+    assert_eq!(
+        error,
+        QasmSimError::RepeatedQubit {
+            source: "ctrl @ x q, q[0];\n",
+            lineno: 4,
+            startpos: 9,
+            endpos: Some(16),
+            symbol_name: "x".into(),
+            qubit: "q[0]".into()
+        }
+    );
+}
+
 #[test]
 fn test_quantum_register_in_conditional() {
     let source = indoc!(
@@ -292,6 +338,8 @@ fn test_quantum_register_in_conditional() {
         QasmSimError::TypeMismatch {
             source: "if (q==3) h q;\n",
             lineno: 5,
+            startpos: 4,
+            endpos: Some(5),
             symbol_name: "q".into(),
             expected: QasmType::ClassicalRegister
         }
@@ -315,6 +363,8 @@ fn test_non_existent_register_in_conditional() {
         QasmSimError::SymbolNotFound {
             source: "if (d==3) h q;\n",
             lineno: 5,
+            startpos: 4,
+            endpos: Some(5),
             symbol_name: "d".into(),
             expected: QasmType::ClassicalRegister
         }
@@ -339,3 +389,142 @@ fn test_include_non_existent_lib() {
         }
     );
 }
+
+#[test]
+fn test_calling_an_unbound_opaque_gate() {
+    let source = indoc!(
+        "
+  OPENQASM 2.0;
+  opaque magic q;
+  qreg q[2];
+  magic q[0];
+  "
+    );
+    let error = qasmsim::run(source, None).expect_err("should fail");
+    assert_eq!(
+        error,
+        QasmSimError::UnboundOpaqueGate {
+            source: "magic q[0];\n",
+            symbol_name: "magic".into(),
+            lineno: 4
+        }
+    );
+}
+
+#[test]
+fn test_logarithm_of_a_negative_number() {
+    let source = indoc!(
+        "
+  OPENQASM 2.0;
+  qreg q[1];
+  U(0, ln(-1), 0) q[0];
+  "
+    );
+    let error = qasmsim::run(source, None).expect_err("should fail");
+    assert_eq!(
+        error,
+        QasmSimError::InvalidParameter {
+            source: "U(0, ln(-1), 0) q[0];\n",
+            lineno: 3,
+            startpos: 5,
+            endpos: Some(11),
+            expression: "ln(-1)".into(),
+            value: "NaN".into()
+        }
+    );
+    assert_eq!(
+        format!("{}", error),
+        indoc!(
+            "
+            error: invalid parameter `ln(-1)`
+              |
+            3 | U(0, ln(-1), 0) q[0];
+              |      ^^^^^^ help: evaluating the parameter results in `NaN`, parameters must be finite numbers
+            "
+        )
+    );
+}
+
+#[test]
+fn test_division_by_zero_inside_a_gate() {
+    let source = indoc!(
+        "
+  OPENQASM 2.0;
+  gate rot(theta) a { U(pi / theta, 0, 0) a; }
+  qreg q[1];
+  rot(0) q[0];
+  "
+    );
+    let error = qasmsim::run(source, None).expect_err("should fail");
+    assert_eq!(
+        error,
+        QasmSimError::InvalidParameter {
+            source: "gate rot(theta) a { U(pi / theta, 0, 0) a; }\n",
+            lineno: 2,
+            startpos: 22,
+            endpos: Some(32),
+            expression: "pi / theta".into(),
+            value: "inf".into()
+        }
+    );
+}
+
+#[test]
+fn test_underline_the_offending_argument() {
+    let source = indoc!(
+        "
+  OPENQASM 2.0;
+  qreg q[2];
+  CX q[1], q[2];
+  "
+    );
+    let error = qasmsim::run(source, None).expect_err("should fail");
+    assert_eq!(
+        format!("{}", error),
+        indoc!(
+            "
+            error: index out of bounds
+              |
+            3 | CX q[1], q[2];
+              |          ^^^^ help: indices of register `q` range from 0 to 1 but the index is 2
+            "
+        )
+    );
+}
+
+#[test]
+fn test_show_all_the_lines_of_a_multiline_statement() {
+    let source = indoc!(
+        "
+  OPENQASM 2.0;
+  qreg q[2];
+  CX q[1],
+     q[2];
+  "
+    );
+    let error = qasmsim::run(source, None).expect_err("should fail");
+    assert_eq!(
+        error,
+        QasmSimError::IndexOutOfBounds {
+            source: "CX q[1],\n   q[2];\n",
+            lineno: 4,
+            startpos: 3,
+            endpos: Some(7),
+            symbol_name: "q".into(),
+            index: 2,
+            size: 2
+        }
+    );
+    assert_eq!(
+        format!("{}", error),
+        indoc!(
+            "
+            error: index out of bounds
+              |
+            3 | CX q[1],
+            4 |    q[2];
+              |    ^^^^ help: indices of register `q` range from 0 to 1 but the index is 2
+            "
+        )
+    );
+}
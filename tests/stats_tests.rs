@@ -0,0 +1,47 @@
+#![cfg(test)]
+
+extern crate qasmsim;
+
+use indoc::indoc;
+
+use qasmsim::{get_stats, LanguageOptions};
+
+#[test]
+fn test_stats_of_a_dynamic_circuit() {
+    let source = indoc!(
+        "
+  OPENQASM 2.0;
+  include \"qelib1.inc\";
+  qreg q[3];
+  creg c[3];
+  h q[0];
+  cx q[0], q[1];
+  ccx q[0], q[1], q[2];
+  measure q[0] -> c[0];
+  if (c == 1) x q[2];
+  h q[0];
+  measure q -> c;
+  "
+    );
+    let stats = get_stats(source, &LanguageOptions::new()).unwrap();
+    assert_eq!(stats.qubits, 3);
+    assert_eq!(stats.clbits, 3);
+    let counts: Vec<(&str, usize)> = stats
+        .gate_counts
+        .iter()
+        .map(|(name, count)| (name.as_str(), *count))
+        .collect();
+    // Besides the 6 `CX`, 2 `h` and 7 `t` or `tdg` of `ccx`, there are the
+    // `cx`, the 2 top-level `h` and the conditional `x`.
+    assert_eq!(counts, vec![("CX", 7), ("U", 12)]);
+    assert_eq!(stats.two_qubit_gates, 7);
+    assert_eq!(stats.t_count, 7);
+    assert_eq!(stats.measurements, 4);
+    // `h` and `cx` take 2 layers, `ccx` 10 more, and the measurement of
+    // `q[0]`, the conditional `x` and the final measurements one each.
+    assert_eq!(stats.depth, 15);
+    // Without expanding `ccx`, every statement takes one layer but the
+    // second `h`, which shares the layer of the conditional.
+    assert_eq!(stats.top_level_depth, 6);
+    assert!(stats.dynamic);
+}
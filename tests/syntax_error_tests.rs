@@ -186,3 +186,117 @@ fn test_missing_arrow() {
         }
     );
 }
+
+#[test]
+fn test_report_all_the_syntax_errors() {
+    let source = indoc!(
+        "
+    OPENQASM 2.0;
+    qreg q[10]
+    qreg r[10];
+    creg c[10]
+    creg d[10];
+  "
+    );
+    let err = qasmsim::run(source, None).unwrap_err();
+    assert_eq!(
+        err,
+        QasmSimError::MultipleErrors(vec![
+            QasmSimError::UnexpectedToken {
+                source: "qreg r[10];\n",
+                lineno: 3,
+                startpos: 0,
+                endpos: Some(4),
+                token: Some(Tok::QReg),
+                expected: vec!["\";\"".into()]
+            },
+            QasmSimError::UnexpectedToken {
+                source: "creg d[10];\n",
+                lineno: 5,
+                startpos: 0,
+                endpos: Some(4),
+                token: Some(Tok::CReg),
+                expected: vec!["\";\"".into()]
+            },
+        ])
+    );
+}
+
+#[test]
+fn test_comparison_operators_are_only_expected_in_conditions() {
+    let source = indoc!(
+        "
+    OPENQASM 2.0;
+    qreg q[2];
+    gate g a, b { CX a b; }
+    CX q[0] q[1];
+  "
+    );
+    let err = qasmsim::run(source, None).unwrap_err();
+    assert_eq!(
+        err,
+        QasmSimError::MultipleErrors(vec![
+            QasmSimError::UnexpectedToken {
+                source: "gate g a, b { CX a b; }\n",
+                lineno: 3,
+                startpos: 19,
+                endpos: Some(20),
+                token: Some(Tok::Id { repr: "b".into() }),
+                expected: vec![
+                    "\",\"".into(),
+                    "\"->\"".into(),
+                    "\";\"".into(),
+                    "\"[\"".into()
+                ]
+            },
+            QasmSimError::UnexpectedToken {
+                source: "CX q[0] q[1];\n",
+                lineno: 4,
+                startpos: 8,
+                endpos: Some(9),
+                token: Some(Tok::Id { repr: "q".into() }),
+                expected: vec!["\",\"".into(), "\"->\"".into(), "\";\"".into()]
+            }
+        ])
+    );
+}
+
+#[test]
+fn test_missing_semicolon_at_the_end_of_a_gate_body() {
+    let source = indoc!(
+        "
+    OPENQASM 2.0;
+    qreg q[1];
+    gate g a { U(0, 0, 0) a; U(0, 0, 0) a }
+    g q[0];
+    qreg r[1]
+  "
+    );
+    let err = qasmsim::run(source, None).unwrap_err();
+    let errors = match err {
+        QasmSimError::MultipleErrors(errors) => errors,
+        other => panic!("expected several errors, found {:?}", other),
+    };
+    assert_eq!(errors.len(), 2);
+    assert!(matches!(
+        &errors[0],
+        QasmSimError::UnexpectedToken {
+            source: "gate g a { U(0, 0, 0) a; U(0, 0, 0) a }\n",
+            lineno: 3,
+            startpos: 38,
+            token: Some(Tok::RBrace),
+            ..
+        }
+    ));
+    assert_eq!(
+        errors[1],
+        QasmSimError::UnexpectedEOF {
+            source: "qreg r[1]\n",
+            lineno: 5,
+            startpos: 9,
+            endpos: None,
+            token: None,
+            expected: vec!["\";\"".into()]
+        }
+    );
+}
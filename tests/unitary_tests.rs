@@ -0,0 +1,128 @@
+#![cfg(test)]
+
+extern crate qasmsim;
+
+use std::f64::consts::FRAC_1_SQRT_2;
+
+use indoc::indoc;
+
+use qasmsim::statevector::Complex;
+use qasmsim::{LanguageOptions, QasmSimError};
+
+fn assert_matrix_approx_eq(unitary: &qasmsim::Unitary, expected: &[Vec<Complex>]) {
+    assert_eq!(unitary.dimension(), expected.len());
+    for (row, expected_row) in expected.iter().enumerate() {
+        for (column, expected_entry) in expected_row.iter().enumerate() {
+            let entry = unitary.get(row, column);
+            assert!(
+                (entry - expected_entry).norm() < 1e-10,
+                "entry ({}, {}) differs: {} != {}",
+                row,
+                column,
+                entry,
+                expected_entry
+            );
+        }
+    }
+}
+
+#[test]
+fn test_unitary_of_hadamard() {
+    let source = indoc!(
+        "
+  OPENQASM 2.0;
+  include \"qelib1.inc\";
+  qreg q[1];
+  h q[0];
+  "
+    );
+    let unitary =
+        qasmsim::extract_unitary(source, &LanguageOptions::new()).expect("can extract the unitary");
+    let h = Complex::from(FRAC_1_SQRT_2);
+    assert_eq!(unitary.qubit_width(), 1);
+    assert_matrix_approx_eq(&unitary, &[vec![h, h], vec![h, -h]]);
+}
+
+#[test]
+fn test_unitary_follows_statevector_endianess() {
+    let source = indoc!(
+        "
+  OPENQASM 2.0;
+  include \"qelib1.inc\";
+  qreg q[2];
+  cx q[0], q[1];
+  "
+    );
+    let unitary =
+        qasmsim::extract_unitary(source, &LanguageOptions::new()).expect("can extract the unitary");
+    let o = Complex::from(0.0);
+    let l = Complex::from(1.0);
+    assert_matrix_approx_eq(
+        &unitary,
+        &[
+            vec![l, o, o, o],
+            vec![o, o, o, l],
+            vec![o, o, l, o],
+            vec![o, l, o, o],
+        ],
+    );
+}
+
+#[test]
+fn test_unitary_of_empty_program_is_identity() {
+    let source = indoc!(
+        "
+  OPENQASM 2.0;
+  qreg q[2];
+  "
+    );
+    let unitary =
+        qasmsim::extract_unitary(source, &LanguageOptions::new()).expect("can extract the unitary");
+    for row in 0..4 {
+        for column in 0..4 {
+            let expected = if row == column { 1.0 } else { 0.0 };
+            assert_eq!(unitary.get(row, column), Complex::from(expected));
+        }
+    }
+}
+
+#[test]
+fn test_cannot_extract_the_unitary_of_programs_with_resets() {
+    let source = indoc!(
+        "
+  OPENQASM 2.0;
+  qreg q[1];
+  reset q;
+  "
+    );
+    let error = qasmsim::extract_unitary(source, &LanguageOptions::new()).expect_err("should fail");
+    assert_eq!(
+        error,
+        QasmSimError::NonUnitaryOperation {
+            source: "reset q;\n",
+            lineno: 3,
+            symbol_name: "reset".into()
+        }
+    );
+}
+
+#[test]
+fn test_cannot_extract_the_unitary_of_programs_with_conditionals() {
+    let source = indoc!(
+        "
+  OPENQASM 2.0;
+  qreg q[1];
+  creg c[1];
+  if (c==1) U(pi, 0, pi) q[0];
+  "
+    );
+    let error = qasmsim::extract_unitary(source, &LanguageOptions::new()).expect_err("should fail");
+    assert_eq!(
+        error,
+        QasmSimError::NonUnitaryOperation {
+            source: "if (c==1) U(pi, 0, pi) q[0];\n",
+            lineno: 4,
+            symbol_name: "if".into()
+        }
+    );
+}